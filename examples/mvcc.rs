@@ -8,7 +8,7 @@ use tempfile::TempDir;
 #[tokio::main]
 async fn main() -> Result<(), KernelError> {
     let temp_dir = TempDir::new().expect("unable to create temporary working directory");
-    let config = Config::new(temp_dir.into_path()).enable_level_0_memorization();
+    let config = Config::new(temp_dir.keep()).enable_level_0_memorization();
     let kip_storage = KipStorage::open_with_config(config).await?;
 
     println!("New Transaction");
@@ -0,0 +1,64 @@
+#[allow(dead_code)]
+mod util;
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use crate::util::counter;
+use kip_db::kernel::lsm::storage::KipStorage;
+use kip_db::kernel::Storage;
+
+fn sorted_batch(batch_size: usize) -> Vec<(Bytes, Bytes)> {
+    let start = counter() * batch_size;
+
+    (start..start + batch_size)
+        .map(|i| {
+            let key = Bytes::from((i as u64).to_be_bytes().to_vec());
+            (key.clone(), key)
+        })
+        .collect()
+}
+
+fn sorted_insert(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(8)
+        .enable_all()
+        .build()
+        .unwrap();
+    let db_path = "batch_sorted_insert";
+    let _ = std::fs::remove_dir_all(db_path);
+    let db = rt.block_on(async { KipStorage::open(db_path).await.unwrap() });
+    let mut group = c.benchmark_group("Store: KipStorage, sorted insert");
+
+    for batch_size in [16_usize, 256, 4096] {
+        let _ = group.throughput(Throughput::Elements(batch_size as u64));
+        group.bench_with_input(
+            BenchmarkId::new("per key", batch_size),
+            &batch_size,
+            |b, &batch_size| {
+                b.to_async(&rt).iter(|| async {
+                    for (key, value) in sorted_batch(batch_size) {
+                        db.set(key, value).await.unwrap();
+                    }
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("batch sorted", batch_size),
+            &batch_size,
+            |b, &batch_size| {
+                b.to_async(&rt).iter(|| async {
+                    db.set_batch_sorted(sorted_batch(batch_size)).await.unwrap();
+                })
+            },
+        );
+    }
+    group.finish();
+
+    drop(db);
+    let _ = std::fs::remove_dir_all(db_path);
+}
+
+criterion_group!(batch_benches, sorted_insert);
+
+criterion_main!(batch_benches);
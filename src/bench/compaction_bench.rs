@@ -0,0 +1,89 @@
+#[allow(dead_code)]
+mod util;
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::time::{Duration, Instant};
+
+use crate::util::random_bytes;
+use kip_db::kernel::lsm::storage::{Config, KipStorage};
+use kip_db::kernel::Storage;
+
+const TABLE_COUNT: usize = 4;
+const KEYS_PER_TABLE: usize = 20000;
+
+/// 写入`TABLE_COUNT - 1`个Level 0的Table后，计时最后一次Flush所触发的Major压缩
+async fn compaction_once(readahead_size: usize, round: u64) -> Duration {
+    let dir_path = format!("compaction_readahead_{readahead_size}_{round}");
+    let _ = std::fs::remove_dir_all(&dir_path);
+    let config = Config::new(&dir_path)
+        .major_threshold_with_sst_size(TABLE_COUNT)
+        .compaction_readahead_size(readahead_size)
+        // 避免Block缓存命中掩盖压缩时的磁盘读取
+        .block_cache_size(16)
+        .prefetch_index(false);
+    let db = KipStorage::open_with_config(config).await.unwrap();
+
+    for _ in 0..TABLE_COUNT - 1 {
+        for _ in 0..KEYS_PER_TABLE {
+            db.set(
+                Bytes::from(random_bytes(16usize..17usize)),
+                Bytes::from(random_bytes(128usize..257usize)),
+            )
+            .await
+            .unwrap();
+        }
+        db.flush().await.unwrap();
+    }
+    for _ in 0..KEYS_PER_TABLE {
+        db.set(
+            Bytes::from(random_bytes(16usize..17usize)),
+            Bytes::from(random_bytes(128usize..257usize)),
+        )
+        .await
+        .unwrap();
+    }
+
+    let start = Instant::now();
+    db.flush().await.unwrap();
+    let elapsed = start.elapsed();
+
+    drop(db);
+    let _ = std::fs::remove_dir_all(&dir_path);
+
+    elapsed
+}
+
+fn compaction_readahead(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(8)
+        .enable_all()
+        .build()
+        .unwrap();
+    let mut group = c.benchmark_group("Store: KipStorage, compaction readahead");
+
+    for readahead_size in [0_usize, 64 * 1024, 512 * 1024, 2 * 1024 * 1024] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(readahead_size),
+            &readahead_size,
+            |b, &readahead_size| {
+                b.to_async(&rt).iter_custom(|iters| async move {
+                    let mut total = Duration::ZERO;
+                    for round in 0..iters {
+                        total += compaction_once(readahead_size, round).await;
+                    }
+                    total
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    name = compaction_benches;
+    config = Criterion::default().sample_size(10);
+    targets = compaction_readahead,
+);
+
+criterion_main!(compaction_benches);
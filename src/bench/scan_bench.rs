@@ -0,0 +1,86 @@
+#[allow(dead_code)]
+mod util;
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::collections::Bound;
+use std::time::{Duration, Instant};
+
+use crate::util::random_bytes;
+use kip_db::kernel::lsm::compactor::CompactionOptions;
+use kip_db::kernel::lsm::iterator::Iter;
+use kip_db::kernel::lsm::mvcc::CheckType;
+use kip_db::kernel::lsm::storage::{Config, KipStorage};
+use kip_db::kernel::Storage;
+
+const KEY_COUNT: usize = 100000;
+
+/// 写入数据并压缩至磁盘后，计时一次全量范围扫描
+async fn scan_once(prefetch_blocks: usize, round: u64) -> Duration {
+    let dir_path = format!("scan_prefetch_{prefetch_blocks}_{round}");
+    let _ = std::fs::remove_dir_all(&dir_path);
+    let config = Config::new(&dir_path)
+        .scan_prefetch_blocks(prefetch_blocks)
+        // 避免Block缓存命中掩盖扫描时的磁盘读取
+        .block_cache_size(16)
+        .prefetch_index(false);
+    let db = KipStorage::open_with_config(config).await.unwrap();
+
+    for _ in 0..KEY_COUNT {
+        db.set(
+            Bytes::from(random_bytes(16usize..17usize)),
+            Bytes::from(random_bytes(128usize..257usize)),
+        )
+        .await
+        .unwrap();
+    }
+    db.flush().await.unwrap();
+    db.compact_all(CompactionOptions::default()).await.unwrap();
+
+    let start = Instant::now();
+    {
+        let tx = db.new_transaction(CheckType::Optimistic).await;
+        let mut iter = tx.iter(Bound::Unbounded, Bound::Unbounded).unwrap();
+        while iter.try_next().unwrap().is_some() {}
+    }
+    let elapsed = start.elapsed();
+
+    drop(db);
+    let _ = std::fs::remove_dir_all(&dir_path);
+
+    elapsed
+}
+
+fn scan_prefetch(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(8)
+        .enable_all()
+        .build()
+        .unwrap();
+    let mut group = c.benchmark_group("Store: KipStorage, scan prefetch");
+
+    for prefetch_blocks in [0_usize, 2, 8] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(prefetch_blocks),
+            &prefetch_blocks,
+            |b, &prefetch_blocks| {
+                b.to_async(&rt).iter_custom(|iters| async move {
+                    let mut total = Duration::ZERO;
+                    for round in 0..iters {
+                        total += scan_once(prefetch_blocks, round).await;
+                    }
+                    total
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    name = scan_benches;
+    config = Criterion::default().sample_size(10);
+    targets = scan_prefetch,
+);
+
+criterion_main!(scan_benches);
@@ -54,7 +54,7 @@ pub fn random(n: u32) -> u32 {
     use std::num::Wrapping;
 
     thread_local! {
-        static RNG: Cell<Wrapping<u32>> = Cell::new(Wrapping(1406868647));
+        static RNG: Cell<Wrapping<u32>> = const { Cell::new(Wrapping(1406868647)) };
     }
 
     RNG.with(|rng| {
@@ -89,6 +89,7 @@ async fn main() -> ConnectionResult<()> {
         }
         Command::SizeOfDisk => client.size_of_disk().await?.to_string(),
         Command::Len => client.len().await?.to_string(),
+        Command::Stats => format!("\n{}", client.stats().await?),
         Command::Flush => {
             client.flush().await?;
             DONE.to_string()
@@ -135,4 +136,5 @@ pub enum Command {
     },
     SizeOfDisk,
     Len,
+    Stats,
 }
@@ -14,6 +14,11 @@ pub async fn main() -> ConnectionResult<()> {
     let ip = cli.ip.unwrap_or(LOCAL_IP.to_string());
     let port = cli.port.unwrap_or(DEFAULT_PORT);
 
+    #[cfg(unix)]
+    if let Some(uds) = cli.uds {
+        kip_db::server::server::serve_with_uds(&ip, port, uds).await?;
+        return Ok(());
+    }
     serve(&ip, port).await?;
 
     Ok(())
@@ -26,4 +31,8 @@ struct Cli {
     ip: Option<String>,
     #[clap(long)]
     port: Option<u16>,
+    /// 额外监听的Unix domain socket路径
+    #[cfg(unix)]
+    #[clap(long)]
+    uds: Option<std::path::PathBuf>,
 }
@@ -1,6 +1,9 @@
+use crate::kernel::io::IoOp;
 use crate::kernel::lsm::compactor::CompactTask;
 use crate::kernel::lsm::version::cleaner::CleanTag;
+use bytes::Bytes;
 use std::io;
+use std::path::PathBuf;
 use thiserror::Error;
 use tokio::sync::mpsc::error::SendError;
 use tokio::sync::oneshot::error::RecvError;
@@ -13,6 +16,15 @@ pub enum KernelError {
     #[error(transparent)]
     Io(#[from] io::Error),
 
+    /// 附带文件路径与操作的IO error
+    #[error("Failed to {op} `{}`: {source}", path.display())]
+    IoWithPath {
+        op: IoOp,
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
     #[error(transparent)]
     RecvError(#[from] RecvError),
 
@@ -33,6 +45,13 @@ pub enum KernelError {
     #[error("Data is empty")]
     DataEmpty,
 
+    /// 输入数据未严格按Key升序排列，附带首个乱序或重复处的相邻两个Key
+    #[error("Input data is not sorted: {prev:?} >= {next:?}")]
+    UnsortedInput { prev: Bytes, next: Bytes },
+
+    #[error("The requested seq has been compacted away")]
+    SeqCompacted,
+
     #[error("Level Over")]
     LevelOver,
 
@@ -56,8 +75,9 @@ pub enum KernelError {
     #[error("Cache sharding and size overflow")]
     CacheShardingNotAlign,
 
-    #[error("File not found")]
-    FileNotFound,
+    /// 以只读方式打开不存在的文件
+    #[error("File not found: `{}`", .0.display())]
+    FileNotFound(PathBuf),
 
     /// 正常情况wal在内存中存在索引则表示硬盘中存在有对应的数据
     /// 而错误则是内存存在索引却在硬盘中不存在这个数据
@@ -83,6 +103,24 @@ pub enum KernelError {
 
     #[error("Same write in different transactions")]
     RepeatedWrite,
+
+    #[error("Backup archive is corrupted")]
+    BackupCorrupted,
+
+    #[error("Compaction was cancelled")]
+    CompactionCancelled,
+
+    /// 数据目录的格式版本不受当前版本支持
+    #[error("Unsupported format version {found}, supported up to {supported}")]
+    UnsupportedTableVersion { found: u32, supported: u32 },
+
+    /// 新建的Table与已存在的Table文件gen相同，写入将覆盖原有数据
+    #[error("Table file of gen {0} already exists")]
+    TableExists(i64),
+
+    /// 后台压缩失败，Level 0超出`Config::l0_stop_trigger`而被阻塞的写入无法继续
+    #[error("Background compaction failed: {0}")]
+    BackgroundCompaction(String),
 }
 
 #[derive(Error, Debug)]
@@ -1,8 +1,11 @@
-use crate::kernel::io::{FileExtension, IoReader, IoType, IoWriter};
+use crate::kernel::io::{
+    file_read_at, FileExtension, IoOp, IoReader, IoResultExt, IoType, IoWriter,
+};
 use crate::kernel::KernelResult;
+use itertools::Itertools;
 use std::fs::{File, OpenOptions};
 use std::io;
-use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::io::{BufReader, BufWriter, IoSlice, Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -28,9 +31,11 @@ impl BufIoReader {
         let reader = BufReaderWithPos::new(
             OpenOptions::new()
                 .create(true)
+                .truncate(false)
                 .write(true)
                 .read(true)
-                .open(path)?,
+                .open(&path)
+                .with_path(IoOp::Open, path)?,
         )?;
 
         Ok(BufIoReader {
@@ -54,11 +59,14 @@ impl BufIoWriter {
         extension: Arc<FileExtension>,
     ) -> KernelResult<Self> {
         // 通过路径构造写入器
+        let path = extension.path_with_gen(&dir_path, gen);
         let file = OpenOptions::new()
             .create(true)
+            .truncate(false)
             .write(true)
             .read(true)
-            .open(extension.path_with_gen(&dir_path, gen))?;
+            .open(&path)
+            .with_path(IoOp::Open, path)?;
 
         Ok(BufIoWriter {
             writer: BufWriterWithPos::new(file)?,
@@ -90,6 +98,11 @@ impl IoReader for BufIoReader {
     fn get_type(&self) -> IoType {
         IoType::Buf
     }
+
+    /// 直接定位读取文件，不经过也不影响顺序读取时的缓冲
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> KernelResult<usize> {
+        Ok(file_read_at(self.reader.get_ref(), buf, offset)?)
+    }
 }
 
 impl Write for BufIoWriter {
@@ -112,6 +125,53 @@ impl IoWriter for BufIoWriter {
     fn current_pos(&mut self) -> KernelResult<u64> {
         Ok(self.writer.pos)
     }
+
+    fn sync_data(&mut self) -> KernelResult<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_data()?;
+
+        Ok(())
+    }
+
+    fn sync_all(&mut self) -> KernelResult<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_all()?;
+
+        Ok(())
+    }
+
+    /// 通过`write_vectored`写入，数据超出缓冲区时直接交由writev一次写入多段
+    fn write_all_vectored(&mut self, bufs: &[&[u8]]) -> KernelResult<()> {
+        let mut bufs = bufs.to_vec();
+        // 首个尚未写完的段
+        let mut start = 0;
+        let mut written = 0;
+
+        loop {
+            // 跳过已完整写入的段，并截去部分写入的段中已写入的部分
+            while start < bufs.len() && written >= bufs[start].len() {
+                written -= bufs[start].len();
+                start += 1;
+            }
+            if start == bufs.len() {
+                break;
+            }
+            bufs[start] = &bufs[start][written..];
+
+            let slices = bufs[start..]
+                .iter()
+                .map(|buf| IoSlice::new(buf))
+                .collect_vec();
+            written = match self.writer.write_vectored(&slices) {
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::WriteZero).into()),
+                Ok(len) => len,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => 0,
+                Err(err) => return Err(err.into()),
+            };
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -130,6 +190,12 @@ impl<R: Read + Seek> BufReaderWithPos<R> {
     }
 }
 
+impl<R: Read + Seek> BufReaderWithPos<R> {
+    fn get_ref(&self) -> &R {
+        self.reader.get_ref()
+    }
+}
+
 impl<R: Read + Seek> Read for BufReaderWithPos<R> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let len = self.reader.read(buf)?;
@@ -159,6 +225,10 @@ impl<W: Write + Seek> BufWriterWithPos<W> {
             pos,
         })
     }
+
+    fn get_ref(&self) -> &W {
+        self.writer.get_ref()
+    }
 }
 
 impl<W: Write + Seek> Write for BufWriterWithPos<W> {
@@ -168,6 +238,12 @@ impl<W: Write + Seek> Write for BufWriterWithPos<W> {
         Ok(len)
     }
 
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        let len = self.writer.write_vectored(bufs)?;
+        self.pos += len as u64;
+        Ok(len)
+    }
+
     fn flush(&mut self) -> io::Result<()> {
         self.writer.flush()
     }
@@ -1,21 +1,115 @@
-use crate::kernel::io::{FileExtension, IoReader, IoType, IoWriter};
+use crate::kernel::io::mem::seek_pos;
+use crate::kernel::io::{
+    file_read_at, file_write_all_at, FileExtension, IoOp, IoReader, IoResultExt, IoType, IoWriter,
+};
 use crate::kernel::KernelResult;
 use std::fs::{File, OpenOptions};
+use std::io;
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 use std::sync::Arc;
 
+/// Direct IO读写的偏移量、长度与内存地址所需的对齐长度，同时满足512字节与4096字节扇区的设备
+pub(crate) const DIRECT_IO_ALIGN: usize = 4096;
+
+/// 以`O_DIRECT`打开文件，读写绕过OS的页缓存直接进行系统调用的IOHandler
+///
+/// `O_DIRECT`要求读写按扇区对齐，因此读写均经由对齐的缓冲区进行，文件末尾不足一个扇区的数据填充后写入，
+/// 再将文件截断至实际的数据长度，使文件大小始终为写入的数据长度；
+/// 非Linux平台或文件系统不支持`O_DIRECT`(如tmpfs)时退化为普通的读写
 #[derive(Debug)]
 pub(crate) struct DirectIoReader {
     gen: i64,
     dir_path: Arc<PathBuf>,
     fs: File,
     extension: Arc<FileExtension>,
+    pos: u64,
 }
 
+/// 写入的数据先缓存于`tail`中，凑满对齐块时才写入其中完整的块；
+/// 不足一个对齐块的末尾仅在`flush`、`sync`、`seek`以及Drop时填充后写入并截断文件，
+/// 因此每次`flush`的代价为一次对齐块的写入(覆盖写入时还需先读取该块)与一次`set_len`，
+/// 频繁`flush`的小写入(如逐条同步的WAL)仍会重复写入同一个末尾块
 #[derive(Debug)]
 pub(crate) struct DirectIoWriter {
     fs: File,
+    /// `tail`在文件中的起始位置，按`DIRECT_IO_ALIGN`对齐
+    tail_offset: u64,
+    /// 当前位置所在的对齐块中位于当前位置之前的数据，`flush`时连同填充一并重新写入
+    tail: Vec<u8>,
+    /// `tail`中是否存在尚未写入文件的数据
+    dirty: bool,
+    /// 已写入文件的实际数据长度
+    len: u64,
+}
+
+/// 起始地址按`DIRECT_IO_ALIGN`对齐的缓冲区
+struct AlignedBuf {
+    buf: Vec<u8>,
+    offset: usize,
+    len: usize,
+}
+
+impl AlignedBuf {
+    /// `len`需为`DIRECT_IO_ALIGN`的整数倍
+    fn new(len: usize) -> Self {
+        let buf = vec![0; len + DIRECT_IO_ALIGN];
+        let offset = buf.as_ptr().align_offset(DIRECT_IO_ALIGN);
+
+        AlignedBuf { buf, offset, len }
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        &self.buf[self.offset..self.offset + self.len]
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.buf[self.offset..self.offset + self.len]
+    }
+}
+
+fn align_down(offset: u64) -> u64 {
+    offset - offset % DIRECT_IO_ALIGN as u64
+}
+
+fn align_up(len: usize) -> usize {
+    len.div_ceil(DIRECT_IO_ALIGN) * DIRECT_IO_ALIGN
+}
+
+fn open_direct(path: PathBuf) -> KernelResult<File> {
+    let mut options = OpenOptions::new();
+    let _ = options.create(true).truncate(false).write(true).read(true);
+
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let mut direct_options = options.clone();
+        match direct_options.custom_flags(libc::O_DIRECT).open(&path) {
+            Ok(fs) => return Ok(fs),
+            // 文件系统不支持O_DIRECT
+            Err(err) if err.raw_os_error() == Some(libc::EINVAL) => (),
+            Err(err) => return Err(err).with_path(IoOp::Open, path),
+        }
+    }
+
+    options.open(&path).with_path(IoOp::Open, path)
+}
+
+/// 将`offset`所在的对齐范围读取至对齐的缓冲区后，复制其中所需的部分至`buf`
+fn direct_read_at(fs: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    if buf.is_empty() {
+        return Ok(0);
+    }
+    let start = align_down(offset);
+    let head = (offset - start) as usize;
+    let mut aligned = AlignedBuf::new(align_up(head + buf.len()));
+
+    let read_len = file_read_at(fs, aligned.as_mut_slice(), start)?;
+    let len = read_len.saturating_sub(head).min(buf.len());
+    buf[..len].copy_from_slice(&aligned.as_slice()[head..head + len]);
+
+    Ok(len)
 }
 
 impl DirectIoReader {
@@ -24,18 +118,14 @@ impl DirectIoReader {
         gen: i64,
         extension: Arc<FileExtension>,
     ) -> KernelResult<Self> {
-        let path = extension.path_with_gen(&dir_path, gen);
-        let fs = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .read(true)
-            .open(path)?;
+        let fs = open_direct(extension.path_with_gen(&dir_path, gen))?;
 
         Ok(DirectIoReader {
             gen,
             dir_path,
             fs,
             extension,
+            pos: 0,
         })
     }
 }
@@ -46,26 +136,71 @@ impl DirectIoWriter {
         gen: i64,
         extension: Arc<FileExtension>,
     ) -> KernelResult<Self> {
-        let path = extension.path_with_gen(&dir_path, gen);
-        let fs = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .read(true)
-            .open(path)?;
+        let fs = open_direct(extension.path_with_gen(&dir_path, gen))?;
+        let len = fs.metadata()?.len();
 
-        Ok(DirectIoWriter { fs })
+        Ok(DirectIoWriter {
+            fs,
+            tail_offset: 0,
+            tail: Vec::new(),
+            dirty: false,
+            len,
+        })
+    }
+
+    /// 写入`tail`中完整的对齐块，`with_partial`为true时末尾不足一个对齐块的部分也填充后写入，
+    /// 不足一个对齐块的部分保留于`tail`中
+    fn write_tail(&mut self, with_partial: bool) -> io::Result<()> {
+        let full_len = self.tail.len() / DIRECT_IO_ALIGN * DIRECT_IO_ALIGN;
+        let (aligned_len, data_len) = if with_partial {
+            (align_up(self.tail.len()), self.tail.len())
+        } else {
+            (full_len, full_len)
+        };
+        if aligned_len == 0 {
+            return Ok(());
+        }
+        let end = self.tail_offset + data_len as u64;
+        let mut aligned = AlignedBuf::new(aligned_len);
+
+        // 填充的部分保留文件中原有的数据
+        if aligned_len > full_len && self.len > end {
+            let block_offset = self.tail_offset + full_len as u64;
+            let _ = file_read_at(
+                &self.fs,
+                &mut aligned.as_mut_slice()[full_len..],
+                block_offset,
+            )?;
+        }
+        aligned.as_mut_slice()[..data_len].copy_from_slice(&self.tail[..data_len]);
+        file_write_all_at(&self.fs, aligned.as_slice(), self.tail_offset)?;
+
+        self.len = self.len.max(end);
+        if self.tail_offset + aligned_len as u64 > self.len {
+            self.fs.set_len(self.len)?;
+        }
+        let _ = self.tail.drain(..full_len);
+        self.tail_offset += full_len as u64;
+        self.dirty = !with_partial && !self.tail.is_empty();
+
+        Ok(())
     }
 }
 
 impl Read for DirectIoReader {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        self.fs.read(buf)
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = direct_read_at(&self.fs, buf, self.pos)?;
+        self.pos += len as u64;
+
+        Ok(len)
     }
 }
 
 impl Seek for DirectIoReader {
-    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
-        self.fs.seek(pos)
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.pos = seek_pos(self.pos, self.fs.metadata()?.len(), pos)?;
+
+        Ok(self.pos)
     }
 }
 
@@ -81,26 +216,70 @@ impl IoReader for DirectIoReader {
     fn get_type(&self) -> IoType {
         IoType::Direct
     }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> KernelResult<usize> {
+        Ok(direct_read_at(&self.fs, buf, offset)?)
+    }
 }
 
 impl Write for DirectIoWriter {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.fs.write(buf)
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.tail.extend_from_slice(buf);
+        self.dirty = true;
+        if self.tail.len() >= DIRECT_IO_ALIGN {
+            self.write_tail(false)?;
+        }
+
+        Ok(buf.len())
     }
 
-    fn flush(&mut self) -> std::io::Result<()> {
-        self.fs.flush()
+    fn flush(&mut self) -> io::Result<()> {
+        if self.dirty {
+            self.write_tail(true)?;
+        }
+
+        Ok(())
     }
 }
 
 impl Seek for DirectIoWriter {
-    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
-        self.fs.seek(pos)
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.flush()?;
+        let pos = seek_pos(self.tail_offset + self.tail.len() as u64, self.len, pos)?;
+        let tail_offset = align_down(pos);
+        let mut tail = vec![0; (pos - tail_offset) as usize];
+
+        // 超出文件末尾的部分以0填充
+        let _ = direct_read_at(&self.fs, &mut tail, tail_offset)?;
+        self.tail_offset = tail_offset;
+        self.tail = tail;
+
+        Ok(pos)
     }
 }
 
 impl IoWriter for DirectIoWriter {
     fn current_pos(&mut self) -> KernelResult<u64> {
-        Ok(self.fs.stream_position()?)
+        Ok(self.tail_offset + self.tail.len() as u64)
+    }
+
+    fn sync_data(&mut self) -> KernelResult<()> {
+        self.flush()?;
+        self.fs.sync_data()?;
+
+        Ok(())
+    }
+
+    fn sync_all(&mut self) -> KernelResult<()> {
+        self.flush()?;
+        self.fs.sync_all()?;
+
+        Ok(())
+    }
+}
+
+impl Drop for DirectIoWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
     }
 }
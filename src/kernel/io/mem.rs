@@ -0,0 +1,190 @@
+use crate::kernel::io::{checksum, FileExtension, IoReader, IoType, IoWriter};
+use crate::kernel::KernelResult;
+use bytes::{Bytes, BytesMut};
+use parking_lot::Mutex;
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::mem;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// 内存中的文件内容，由同一Gen的Reader与Writer共享
+///
+/// Reader通过`read_range`直接共享其切片而无需复制，
+/// Writer写入时若不存在被共享的切片则原地追加，否则复制一次后再写入
+pub(crate) type MemFile = Arc<Mutex<Bytes>>;
+
+/// 计算Seek后的位置
+pub(crate) fn seek_pos(pos: u64, len: u64, seek: SeekFrom) -> io::Result<u64> {
+    let new_pos = match seek {
+        SeekFrom::Start(offset) => Some(offset),
+        SeekFrom::End(offset) => len.checked_add_signed(offset),
+        SeekFrom::Current(offset) => pos.checked_add_signed(offset),
+    };
+
+    new_pos.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "invalid seek to a negative or overflowing position",
+        )
+    })
+}
+
+/// 自`offset`处将数据读取至`buf`，`offset`超出数据末尾时与文件一致地读取0个字节
+pub(crate) fn read_from(file: &[u8], buf: &mut [u8], offset: u64) -> usize {
+    let start = usize::try_from(offset).map_or(file.len(), |offset| offset.min(file.len()));
+    let len = buf.len().min(file.len() - start);
+
+    buf[..len].copy_from_slice(&file[start..start + len]);
+    len
+}
+
+/// 获取`[offset, offset + len)`对应的切片，超出数据末尾时与文件一致地返回`UnexpectedEof`
+pub(crate) fn range_of(file: &[u8], offset: u64, len: usize) -> io::Result<&[u8]> {
+    usize::try_from(offset)
+        .ok()
+        .and_then(|start| file.get(start..start.checked_add(len)?))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "failed to fill whole buffer"))
+}
+
+/// 基于内存实现的IOHandler
+/// 数据仅存在于内存中，可通过`IoFactory::snapshot_mem_to_disk`或`IoFactory::persist_mem_to`落盘
+#[derive(Debug)]
+pub(crate) struct MemIoReader {
+    gen: i64,
+    dir_path: Arc<PathBuf>,
+    file: MemFile,
+    pos: u64,
+    extension: Arc<FileExtension>,
+}
+
+#[derive(Debug)]
+pub(crate) struct MemIoWriter {
+    file: MemFile,
+    pos: u64,
+}
+
+impl MemIoReader {
+    pub(crate) fn new(
+        dir_path: Arc<PathBuf>,
+        gen: i64,
+        extension: Arc<FileExtension>,
+        file: MemFile,
+    ) -> Self {
+        MemIoReader {
+            gen,
+            dir_path,
+            file,
+            pos: 0,
+            extension,
+        }
+    }
+}
+
+impl MemIoWriter {
+    pub(crate) fn new(file: MemFile) -> Self {
+        MemIoWriter { file, pos: 0 }
+    }
+}
+
+impl Read for MemIoReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = read_from(&self.file.lock(), buf, self.pos);
+
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl Seek for MemIoReader {
+    fn seek(&mut self, seek: SeekFrom) -> io::Result<u64> {
+        self.pos = seek_pos(self.pos, self.file.lock().len() as u64, seek)?;
+        Ok(self.pos)
+    }
+}
+
+impl IoReader for MemIoReader {
+    fn get_gen(&self) -> i64 {
+        self.gen
+    }
+
+    fn get_path(&self) -> PathBuf {
+        self.extension.path_with_gen(&self.dir_path, self.gen)
+    }
+
+    fn file_size(&self) -> KernelResult<u64> {
+        Ok(self.file.lock().len() as u64)
+    }
+
+    fn get_type(&self) -> IoType {
+        IoType::Mem
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> KernelResult<usize> {
+        Ok(read_from(&self.file.lock(), buf, offset))
+    }
+
+    /// 返回共享内存文件的切片，不进行复制
+    fn read_range(&self, offset: u64, len: usize) -> KernelResult<Bytes> {
+        let file = self.file.lock();
+
+        Ok(file.slice_ref(range_of(&file, offset, len)?))
+    }
+
+    fn checksum(&self, len: u64) -> KernelResult<u64> {
+        let file = self.file.lock();
+        let len = (len as usize).min(file.len());
+
+        Ok(checksum(&file[..len]))
+    }
+}
+
+impl Write for MemIoWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut guard = self.file.lock();
+        let mut file = BytesMut::from(mem::take(&mut *guard));
+        // 与文件一致，允许Seek至末尾之后写入，空缺部分以0填充
+        let (start, end) = usize::try_from(self.pos)
+            .ok()
+            .and_then(|start| Some((start, start.checked_add(buf.len())?)))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "write position exceeds the addressable memory",
+                )
+            })?;
+
+        if end > file.len() {
+            file.resize(end, 0);
+        }
+        file[start..end].copy_from_slice(buf);
+        *guard = file.freeze();
+        self.pos = end as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for MemIoWriter {
+    fn seek(&mut self, seek: SeekFrom) -> io::Result<u64> {
+        self.pos = seek_pos(self.pos, self.file.lock().len() as u64, seek)?;
+        Ok(self.pos)
+    }
+}
+
+impl IoWriter for MemIoWriter {
+    fn current_pos(&mut self) -> KernelResult<u64> {
+        Ok(self.pos)
+    }
+
+    fn sync_data(&mut self) -> KernelResult<()> {
+        Ok(())
+    }
+
+    fn sync_all(&mut self) -> KernelResult<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use crate::kernel::io::mem::{range_of, read_from, seek_pos};
+use crate::kernel::io::{checksum, FileExtension, IoOp, IoReader, IoResultExt, IoType};
+use crate::kernel::KernelResult;
+use bytes::Bytes;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// 基于内存映射实现的IOHandler
+///
+/// 读取时直接由OS的页缓存提供数据，不需要每次读取都进行seek与read的系统调用，
+/// 适用于被频繁读取的SSTable；仅支持读取，映射期间文件内容不应被修改
+#[derive(Debug)]
+pub(crate) struct MmapIoReader {
+    gen: i64,
+    dir_path: Arc<PathBuf>,
+    /// 空文件无法被映射，此时为None
+    mmap: Option<Mmap>,
+    pos: u64,
+    extension: Arc<FileExtension>,
+}
+
+impl MmapIoReader {
+    pub(crate) fn new(
+        dir_path: Arc<PathBuf>,
+        gen: i64,
+        extension: Arc<FileExtension>,
+    ) -> KernelResult<Self> {
+        let path = extension.path_with_gen(&dir_path, gen);
+        let file = File::open(&path).with_path(IoOp::Open, &path)?;
+        let mmap = if file.metadata().with_path(IoOp::Open, &path)?.len() > 0 {
+            // SAFETY: SSTable等文件写入完成后不再被修改，仅会在不再被引用后删除
+            Some(unsafe { Mmap::map(&file) }.with_path(IoOp::Open, path)?)
+        } else {
+            None
+        };
+
+        Ok(MmapIoReader {
+            gen,
+            dir_path,
+            mmap,
+            pos: 0,
+            extension,
+        })
+    }
+
+    fn data(&self) -> &[u8] {
+        self.mmap.as_deref().unwrap_or_default()
+    }
+}
+
+impl Read for MmapIoReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = read_from(self.data(), buf, self.pos);
+
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl Seek for MmapIoReader {
+    fn seek(&mut self, seek: SeekFrom) -> io::Result<u64> {
+        self.pos = seek_pos(self.pos, self.data().len() as u64, seek)?;
+        Ok(self.pos)
+    }
+}
+
+impl IoReader for MmapIoReader {
+    fn get_gen(&self) -> i64 {
+        self.gen
+    }
+
+    fn get_path(&self) -> PathBuf {
+        self.extension.path_with_gen(&self.dir_path, self.gen)
+    }
+
+    fn file_size(&self) -> KernelResult<u64> {
+        Ok(self.data().len() as u64)
+    }
+
+    fn get_type(&self) -> IoType {
+        IoType::Mmap
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> KernelResult<usize> {
+        Ok(read_from(self.data(), buf, offset))
+    }
+
+    fn read_range(&self, offset: u64, len: usize) -> KernelResult<Bytes> {
+        Ok(Bytes::copy_from_slice(range_of(self.data(), offset, len)?))
+    }
+
+    fn checksum(&self, len: u64) -> KernelResult<u64> {
+        let bytes = self.data();
+        let len = usize::try_from(len).map_or(bytes.len(), |len| len.min(bytes.len()));
+
+        Ok(checksum(&bytes[..len]))
+    }
+}
@@ -1,19 +1,33 @@
 pub(crate) mod buf;
 pub(crate) mod direct;
+pub(crate) mod mem;
+pub(crate) mod mmap;
+pub mod priority;
+pub(crate) mod readahead;
 
 use crate::kernel::io::buf::{BufIoReader, BufIoWriter};
 use crate::kernel::io::direct::{DirectIoReader, DirectIoWriter};
-use crate::kernel::KernelResult;
-use std::fs;
-use std::io::{Read, Seek, Write};
+use crate::kernel::io::mem::{seek_pos, MemFile, MemIoReader, MemIoWriter};
+use crate::kernel::io::mmap::MmapIoReader;
+use crate::kernel::utils::lru_cache::LruCache;
+use crate::kernel::{sorted_gen_list, KernelResult};
+use crate::KernelError;
+use bytes::Bytes;
+use itertools::Itertools;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::{fmt, fs, io};
 
 #[derive(Debug, Copy, Clone)]
 pub enum FileExtension {
     Log,
     SSTable,
     Manifest,
+    /// 与SSTable分离存储的大Value
+    ValueLog,
 }
 
 impl FileExtension {
@@ -22,6 +36,7 @@ impl FileExtension {
             FileExtension::Log => "log",
             FileExtension::SSTable => "sst",
             FileExtension::Manifest => "manifest",
+            FileExtension::ValueLog => "vlog",
         }
     }
 
@@ -31,29 +46,121 @@ impl FileExtension {
     }
 }
 
+const CHECKSUM_BUF_SIZE: usize = 64 * 1024;
+/// 文件末尾所存放的自身校验和的长度，见`IoReader::checksum_file`
+pub(crate) const FILE_CHECKSUM_SIZE: usize = 8;
+
+/// 发生IO错误时所进行的文件操作
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum IoOp {
+    Open,
+    Read,
+    Write,
+    Seek,
+    CreateDir,
+    Remove,
+}
+
+impl fmt::Display for IoOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IoOp::Open => "open",
+            IoOp::Read => "read",
+            IoOp::Write => "write",
+            IoOp::Seek => "seek",
+            IoOp::CreateDir => "create dir",
+            IoOp::Remove => "remove",
+        })
+    }
+}
+
+/// 为`io::Result`附加发生错误时的文件路径与操作
+pub(crate) trait IoResultExt<T> {
+    fn with_path(self, op: IoOp, path: impl Into<PathBuf>) -> KernelResult<T>;
+}
+
+impl<T> IoResultExt<T> for io::Result<T> {
+    fn with_path(self, op: IoOp, path: impl Into<PathBuf>) -> KernelResult<T> {
+        self.map_err(|source| KernelError::IoWithPath {
+            op,
+            path: path.into(),
+            source,
+        })
+    }
+}
+
+/// 通过`shared_reader`打开的读取器，以文件路径区分
+type ReaderCache = Arc<Mutex<LruCache<PathBuf, Arc<dyn IoReader>>>>;
+
 pub struct IoFactory {
     dir_path: Arc<PathBuf>,
     extension: Arc<FileExtension>,
+    // IoType::Mem的文件
+    mem_files: Mutex<HashMap<i64, MemFile>>,
+    // 通过`shared_reader`打开的读取器，以文件路径区分，可由多个IoFactory共享；为None时不进行缓存
+    reader_cache: Option<ReaderCache>,
 }
 
 #[derive(PartialEq, Copy, Clone, Debug)]
 pub enum IoType {
     Buf,
+    /// 以`O_DIRECT`绕过页缓存读写文件，读写经由按扇区对齐的缓冲区进行，见`DirectIoWriter`
     Direct,
+    /// 数据仅保存于内存中，适用于测试与临时缓存
+    Mem,
+    /// 以内存映射的方式读取文件，由OS的页缓存提供数据，仅支持读取
+    Mmap,
 }
 
 impl IoFactory {
+    /// 读取已存在的文件，文件不存在时返回`KernelError::FileNotFound`
     #[inline]
     pub fn reader(&self, gen: i64, io_type: IoType) -> KernelResult<Box<dyn IoReader>> {
+        if !self.exists(gen)? {
+            return Err(KernelError::FileNotFound(
+                self.extension.path_with_gen(&self.dir_path, gen),
+            ));
+        }
         let dir_path = Arc::clone(&self.dir_path);
         let extension = Arc::clone(&self.extension);
 
         Ok(match io_type {
             IoType::Buf => Box::new(BufIoReader::new(dir_path, gen, extension)?),
             IoType::Direct => Box::new(DirectIoReader::new(dir_path, gen, extension)?),
+            IoType::Mem => Box::new(MemIoReader::new(
+                dir_path,
+                gen,
+                extension,
+                self.mem_file(gen),
+            )),
+            IoType::Mmap => Box::new(MmapIoReader::new(dir_path, gen, extension)?),
         })
     }
 
+    /// 读取文件，文件不存在时先创建空文件
+    ///
+    /// 返回的bool表示该文件是否为此次新建
+    #[inline]
+    pub fn read_or_create(
+        &self,
+        gen: i64,
+        io_type: IoType,
+    ) -> KernelResult<(Box<dyn IoReader>, bool)> {
+        let is_created = !self.exists(gen)?;
+        if is_created {
+            // Mmap不支持写入，以Direct的方式创建空文件
+            let create_type = if io_type == IoType::Mmap {
+                IoType::Direct
+            } else {
+                io_type
+            };
+            let _ = self.writer(gen, create_type)?;
+        }
+
+        Ok((self.reader(gen, io_type)?, is_created))
+    }
+
+    /// 创建文件的写入器，`IoType::Mmap`仅支持读取因此返回`KernelError::NotSupport`
     #[inline]
     pub fn writer(&self, gen: i64, io_type: IoType) -> KernelResult<Box<dyn IoWriter>> {
         let dir_path = Arc::clone(&self.dir_path);
@@ -62,6 +169,10 @@ impl IoFactory {
         Ok(match io_type {
             IoType::Buf => Box::new(BufIoWriter::new(dir_path, gen, extension)?),
             IoType::Direct => Box::new(DirectIoWriter::new(dir_path, gen, extension)?),
+            IoType::Mem => Box::new(MemIoWriter::new(self.mem_file(gen))),
+            IoType::Mmap => {
+                return Err(KernelError::NotSupport("mmap io does not support writing"))
+            }
         })
     }
 
@@ -74,27 +185,205 @@ impl IoFactory {
     pub fn new(dir_path: impl Into<PathBuf>, extension: FileExtension) -> KernelResult<Self> {
         let path_buf = dir_path.into();
         // 创建文件夹（如果他们缺失）
-        fs::create_dir_all(&path_buf)?;
+        fs::create_dir_all(&path_buf).with_path(IoOp::CreateDir, &path_buf)?;
         let dir_path = Arc::new(path_buf);
         let extension = Arc::new(extension);
 
         Ok(Self {
             dir_path,
             extension,
+            mem_files: Mutex::new(HashMap::new()),
+            reader_cache: None,
         })
     }
 
+    /// 限制通过`shared_reader`同时打开的文件数量，为0时不作限制
+    ///
+    /// 超出数量时关闭最久未使用的文件，其下次被读取时重新打开
+    #[inline]
+    pub fn max_open_files(mut self, max_open_files: usize) -> KernelResult<Self> {
+        self.reader_cache = if max_open_files > 0 {
+            Some(Arc::new(Mutex::new(LruCache::new(max_open_files)?)))
+        } else {
+            None
+        };
+
+        Ok(self)
+    }
+
+    /// 与`other`共享`max_open_files`的限制，使两者同时打开的文件数量之和不超过该限制
+    ///
+    /// 用于不同目录(如快速与慢速存储层)的IoFactory
+    pub(crate) fn share_open_files(mut self, other: &IoFactory) -> Self {
+        self.reader_cache.clone_from(&other.reader_cache);
+        self
+    }
+
+    /// 同一目录下另一种扩展名的文件的IoFactory，与其共享`max_open_files`的限制
+    pub(crate) fn with_extension(&self, extension: FileExtension) -> Self {
+        IoFactory {
+            dir_path: Arc::clone(&self.dir_path),
+            extension: Arc::new(extension),
+            mem_files: Mutex::new(HashMap::new()),
+            reader_cache: self.reader_cache.clone(),
+        }
+    }
+
+    /// 获取可在多处共享的文件读取器
+    ///
+    /// 设置了`max_open_files`时读取器会被缓存并复用，否则每次均打开新的读取器
+    #[inline]
+    pub fn shared_reader(&self, gen: i64, io_type: IoType) -> KernelResult<Arc<dyn IoReader>> {
+        let Some(reader_cache) = &self.reader_cache else {
+            return Ok(Arc::from(self.reader(gen, io_type)?));
+        };
+        let path = self.path_with_gen(gen);
+        let mut reader_cache = reader_cache.lock();
+        let is_cached = match reader_cache.get(&path) {
+            Some(reader) if reader.get_type() == io_type => return Ok(Arc::clone(reader)),
+            cached => cached.is_some(),
+        };
+        // LruCache因容量不足而驱逐时并不释放元素，因此需先移除最久未使用的读取器以关闭其文件
+        if !is_cached && reader_cache.is_full() {
+            let _ = reader_cache.pop_lru();
+        }
+        let reader: Arc<dyn IoReader> = Arc::from(self.reader(gen, io_type)?);
+        let _ = reader_cache.put(path, Arc::clone(&reader));
+
+        Ok(reader)
+    }
+
+    /// 文件对应的路径
+    pub(crate) fn path_with_gen(&self, gen: i64) -> PathBuf {
+        self.extension.path_with_gen(&self.dir_path, gen)
+    }
+
+    /// 移除文件，IoType::Mem的文件若已通过`snapshot_mem_to_disk`落盘则同时移除其磁盘文件
     #[inline]
     pub fn clean(&self, gen: i64) -> KernelResult<()> {
-        fs::remove_file(self.extension.path_with_gen(&self.dir_path, gen))?;
+        if let Some(reader_cache) = &self.reader_cache {
+            let _ = reader_cache.lock().remove(&self.path_with_gen(gen));
+        }
+        let is_mem = self.mem_files.lock().remove(&gen).is_some();
+        let path = self.extension.path_with_gen(&self.dir_path, gen);
+
+        if !is_mem || fs::try_exists(&path)? {
+            fs::remove_file(&path).with_path(IoOp::Remove, path)?;
+        }
         Ok(())
     }
 
     #[inline]
     pub fn exists(&self, gen: i64) -> KernelResult<bool> {
+        if self.mem_files.lock().contains_key(&gen) {
+            return Ok(true);
+        }
         let path = self.extension.path_with_gen(&self.dir_path, gen);
         Ok(fs::try_exists(path)?)
     }
+
+    /// 获取文件占用的磁盘空间，IoType::Mem的文件以其在内存中的数据长度计算
+    ///
+    /// 文件不存在时返回`KernelError::FileNotFound`
+    #[inline]
+    pub fn disk_usage(&self, gen: i64) -> KernelResult<u64> {
+        if let Some(file) = self.mem_files.lock().get(&gen) {
+            return Ok(file.lock().len() as u64);
+        }
+        let path = self.extension.path_with_gen(&self.dir_path, gen);
+
+        match fs::metadata(&path) {
+            Ok(metadata) => Ok(metadata.len()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                Err(KernelError::FileNotFound(path))
+            }
+            Err(err) => Err(err).with_path(IoOp::Open, path),
+        }
+    }
+
+    /// 获取该IoFactory中所有文件占用的磁盘空间之和
+    ///
+    /// 同时存在于内存与磁盘中的文件(如`snapshot_mem_to_disk`后)仅计算其内存中的数据，
+    /// 统计期间被移除的文件不计入其中
+    #[inline]
+    pub fn total_disk_usage(&self) -> KernelResult<u64> {
+        let mut gens = sorted_gen_list(&self.dir_path, *self.extension)?;
+        gens.extend(self.mem_files.lock().keys());
+
+        gens.into_iter()
+            .unique()
+            .map(|gen| match self.disk_usage(gen) {
+                Err(KernelError::FileNotFound(_)) => Ok(0),
+                result => result,
+            })
+            .sum()
+    }
+
+    /// 将所有IoType::Mem的文件以`{gen}.{extension}`的形式写入指定文件夹
+    ///
+    /// 写入后可通过以该文件夹构建的IoFactory以Buf或Direct的方式读取
+    #[inline]
+    pub fn persist_mem_to(&self, dir: impl AsRef<Path>) -> KernelResult<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+
+        for (gen, file) in self.mem_files.lock().iter() {
+            let path = self.extension.path_with_gen(dir, *gen);
+            // 先写入临时文件再替换，避免中途停机时留下不完整的文件
+            let tmp_path = path.with_extension(format!("{}.tmp", self.extension.extension_str()));
+            fs::write(&tmp_path, file.lock().as_ref()).with_path(IoOp::Write, &tmp_path)?;
+            fs::File::open(&tmp_path)?.sync_all()?;
+            fs::rename(&tmp_path, &path).with_path(IoOp::Write, path)?;
+        }
+        // 持久化重命名操作，Windows不支持打开文件夹因此跳过
+        #[cfg(unix)]
+        fs::File::open(dir)?.sync_all()?;
+
+        Ok(())
+    }
+
+    /// 将所有IoType::Mem的文件写入该IoFactory自身的文件夹中
+    ///
+    /// 写入后即使进程重启，也可通过`IoFactory::load_mem_from_disk`重新以内存模式打开，
+    /// 或由同一路径的其他IoFactory以Buf或Direct的方式读取
+    #[inline]
+    pub fn snapshot_mem_to_disk(&self) -> KernelResult<()> {
+        self.persist_mem_to(self.dir_path.as_path())
+    }
+
+    /// 创建IoFactory，并将文件夹中已存在的文件载入为IoType::Mem的文件
+    ///
+    /// 与`snapshot_mem_to_disk`对应
+    #[inline]
+    pub fn load_mem_from_disk(
+        dir_path: impl Into<PathBuf>,
+        extension: FileExtension,
+    ) -> KernelResult<Self> {
+        let factory = Self::new(dir_path, extension)?;
+        factory.load_mem_from(factory.dir_path.as_path())?;
+
+        Ok(factory)
+    }
+
+    /// 将文件夹中所有对应后缀的文件载入为IoType::Mem的文件
+    ///
+    /// 与`persist_mem_to`对应，用于将已落盘的数据重新以内存模式打开
+    #[inline]
+    pub fn load_mem_from(&self, dir: impl AsRef<Path>) -> KernelResult<()> {
+        let dir = dir.as_ref();
+        let mut mem_files = self.mem_files.lock();
+
+        for gen in sorted_gen_list(dir, *self.extension)? {
+            let bytes = fs::read(self.extension.path_with_gen(dir, gen))?;
+            let _ = mem_files.insert(gen, Arc::new(Mutex::new(Bytes::from(bytes))));
+        }
+
+        Ok(())
+    }
+
+    fn mem_file(&self, gen: i64) -> MemFile {
+        Arc::clone(self.mem_files.lock().entry(gen).or_default())
+    }
 }
 
 pub trait IoReader: Send + Sync + 'static + Read + Seek {
@@ -109,8 +398,778 @@ pub trait IoReader: Send + Sync + 'static + Read + Seek {
     }
 
     fn get_type(&self) -> IoType;
+
+    /// 从`offset`处读取数据至`buf`，不改变也不依赖Reader当前的位置
+    ///
+    /// 对应Unix的`pread`与Windows的`seek_read`，因此可通过`&self`进行无锁的并发读取
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> KernelResult<usize>;
+
+    /// 从`offset`处读满`buf`，数据不足时返回`UnexpectedEof`
+    #[inline]
+    fn read_exact_at(&self, mut buf: &mut [u8], mut offset: u64) -> KernelResult<()> {
+        while !buf.is_empty() {
+            match self.read_at(buf, offset)? {
+                0 => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "failed to fill whole buffer",
+                    )
+                    .into())
+                }
+                len => {
+                    buf = &mut buf[len..];
+                    offset += len as u64;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 读取`[offset, offset + len)`的数据，不改变也不依赖Reader当前的位置，数据不足时返回`UnexpectedEof`
+    ///
+    /// 用于替代手动分配缓冲区后的`seek`与`read_exact`
+    #[inline]
+    fn read_range(&self, offset: u64, len: usize) -> KernelResult<Bytes> {
+        let mut buf = vec![0; len];
+        self.read_exact_at(&mut buf, offset)?;
+
+        Ok(Bytes::from(buf))
+    }
+
+    /// 流式读取文件前`len`字节并计算其校验和
+    #[inline]
+    fn checksum(&self, len: u64) -> KernelResult<u64> {
+        checksum_reader(fs::File::open(self.get_path())?.take(len))
+    }
+
+    /// 流式读取文件中除末尾`FILE_CHECKSUM_SIZE`字节外的数据并计算其校验和，无需解码文件内容
+    ///
+    /// 与存放于文件末尾的校验和(如SSTable Footer中的`checksum`)覆盖相同的范围，可直接与之比较
+    #[inline]
+    fn checksum_file(&self) -> KernelResult<u64> {
+        self.checksum(self.file_size()?.saturating_sub(FILE_CHECKSUM_SIZE as u64))
+    }
+}
+
+/// 基于定位读取为共享的IoReader提供独立的读写位置
+///
+/// 多处顺序读取同一文件时(如压缩时的预读)复用已打开的IoReader，而不再各自打开新的文件句柄
+pub(crate) struct PositionedReader {
+    reader: Arc<dyn IoReader>,
+    pos: u64,
+}
+
+impl PositionedReader {
+    pub(crate) fn new(reader: Arc<dyn IoReader>) -> Self {
+        PositionedReader { reader, pos: 0 }
+    }
+
+    fn io_error(err: KernelError) -> io::Error {
+        match err {
+            KernelError::Io(err) => err,
+            err => io::Error::other(err),
+        }
+    }
+}
+
+impl Read for PositionedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.reader.read_at(buf, self.pos).map_err(Self::io_error)?;
+
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl Seek for PositionedReader {
+    fn seek(&mut self, seek: SeekFrom) -> io::Result<u64> {
+        let len = match seek {
+            SeekFrom::End(_) => self.reader.file_size().map_err(Self::io_error)?,
+            _ => 0,
+        };
+        self.pos = seek_pos(self.pos, len, seek)?;
+
+        Ok(self.pos)
+    }
+}
+
+/// 非unix平台的定位读写会移动文件句柄的读写位置(如Windows的`seek_read`)，
+/// 以此锁串行化定位读写，并在完成后恢复原有的读写位置
+#[cfg(not(unix))]
+static POSITIONAL_IO_LOCK: Mutex<()> = Mutex::new(());
+
+#[cfg(not(unix))]
+fn with_restored_pos<T>(
+    mut file: &fs::File,
+    f: impl FnOnce(&mut &fs::File) -> io::Result<T>,
+) -> io::Result<T> {
+    let _guard = POSITIONAL_IO_LOCK.lock();
+    let pos = file.stream_position()?;
+    let result = f(&mut file);
+    let _ = file.seek(SeekFrom::Start(pos))?;
+
+    result
+}
+
+/// 以定位读取的方式读取文件，不影响文件句柄的读写位置
+pub(crate) fn file_read_at(file: &fs::File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::FileExt::read_at(file, buf, offset)
+    }
+    #[cfg(windows)]
+    {
+        with_restored_pos(file, |file| {
+            std::os::windows::fs::FileExt::seek_read(*file, buf, offset)
+        })
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        with_restored_pos(file, |file| {
+            let _ = file.seek(SeekFrom::Start(offset))?;
+            file.read(buf)
+        })
+    }
+}
+
+/// 以定位写入的方式写入全部数据，不影响文件句柄的读写位置
+pub(crate) fn file_write_all_at(file: &fs::File, buf: &[u8], offset: u64) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::FileExt::write_all_at(file, buf, offset)
+    }
+    #[cfg(windows)]
+    {
+        with_restored_pos(file, |file| {
+            let (mut buf, mut offset) = (buf, offset);
+            while !buf.is_empty() {
+                match std::os::windows::fs::FileExt::seek_write(*file, buf, offset) {
+                    Ok(0) => return Err(io::Error::from(io::ErrorKind::WriteZero)),
+                    Ok(len) => {
+                        buf = &buf[len..];
+                        offset += len as u64;
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                    Err(err) => return Err(err),
+                }
+            }
+
+            Ok(())
+        })
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        with_restored_pos(file, |file| {
+            let _ = file.seek(SeekFrom::Start(offset))?;
+            file.write_all(buf)
+        })
+    }
+}
+
+/// 计算数据的校验和
+pub(crate) fn checksum(bytes: &[u8]) -> u64 {
+    u64::from(crc32fast::hash(bytes))
+}
+
+/// 计算多段数据拼接后的校验和，结果与对拼接后的数据调用`checksum`一致
+pub(crate) fn checksum_vectored(bufs: &[&[u8]]) -> u64 {
+    let mut hasher = crc32fast::Hasher::new();
+    for buf in bufs {
+        hasher.update(buf);
+    }
+
+    u64::from(hasher.finalize())
+}
+
+/// 以固定大小的缓冲区流式计算Reader中所有数据的校验和
+pub(crate) fn checksum_reader(mut reader: impl Read) -> KernelResult<u64> {
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0; CHECKSUM_BUF_SIZE];
+
+    loop {
+        match reader.read(&mut buf)? {
+            0 => break,
+            len => hasher.update(&buf[..len]),
+        }
+    }
+
+    Ok(u64::from(hasher.finalize()))
 }
 
 pub trait IoWriter: Send + Sync + 'static + Write + Seek {
     fn current_pos(&mut self) -> KernelResult<u64>;
+
+    /// 将已写入的数据同步至硬盘(不保证文件元数据同步)
+    fn sync_data(&mut self) -> KernelResult<()>;
+
+    /// 将已写入的数据与文件元数据一同同步至硬盘
+    fn sync_all(&mut self) -> KernelResult<()>;
+
+    /// 依据`use_fsync`选择`sync_all`或`sync_data`进行同步
+    fn sync(&mut self, use_fsync: bool) -> KernelResult<()> {
+        if use_fsync {
+            self.sync_all()
+        } else {
+            self.sync_data()
+        }
+    }
+
+    /// 依次写入多段数据，使调用方无需为写入而将其拼接为一份连续的内存
+    fn write_all_vectored(&mut self, bufs: &[&[u8]]) -> KernelResult<()> {
+        for buf in bufs {
+            self.write_all(buf)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kernel::io::{
+        checksum, checksum_vectored, FileExtension, IoFactory, IoOp, IoType, IoWriter,
+    };
+    use crate::kernel::KernelResult;
+    use crate::KernelError;
+    use std::io;
+    use std::io::{Read, Seek, SeekFrom, Write};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_io_error_with_path() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let dir_path = temp_dir.path().join("ss_table");
+        let factory = IoFactory::new(&dir_path, FileExtension::SSTable)?;
+        // 移除文件夹使其后的打开操作失败
+        std::fs::remove_dir(&dir_path)?;
+
+        let path = FileExtension::SSTable.path_with_gen(&dir_path, 1);
+        for io_type in [IoType::Buf, IoType::Direct] {
+            let err = factory.reader(1, io_type).err().unwrap();
+            assert!(matches!(&err, KernelError::FileNotFound(err_path) if err_path == &path));
+            assert!(err.to_string().contains(&path.display().to_string()));
+
+            let err = factory.writer(1, io_type).err().unwrap();
+            assert!(matches!(
+                &err,
+                KernelError::IoWithPath { op: IoOp::Open, path: err_path, .. } if err_path == &path
+            ));
+            assert!(err.to_string().contains(&path.display().to_string()));
+        }
+        let err = factory.clean(1).err().unwrap();
+        assert!(err.to_string().contains(&path.display().to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_at() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let factory = IoFactory::new(temp_dir.path(), FileExtension::SSTable)?;
+        let data = (0..=255_u8).cycle().take(64 * 1024).collect::<Vec<_>>();
+
+        for (gen, io_type) in [(1, IoType::Buf), (2, IoType::Direct), (3, IoType::Mem)] {
+            let mut writer = factory.writer(gen, io_type)?;
+            writer.write_all(&data)?;
+            writer.flush()?;
+
+            let mut reader = factory.reader(gen, io_type)?;
+            // 多个线程通过共享引用并发地定位读取
+            std::thread::scope(|scope| {
+                for i in 0..8 {
+                    let (reader, data) = (&reader, &data);
+                    let _ = scope.spawn(move || {
+                        let offset = i * 4096 + 7;
+                        let mut buf = vec![0; 1000];
+                        reader.read_exact_at(&mut buf, offset as u64).unwrap();
+                        assert_eq!(buf, data[offset..offset + 1000]);
+                    });
+                }
+            });
+            // 定位读取不影响顺序读取的位置
+            let mut buf = [0; 4];
+            reader.read_exact(&mut buf)?;
+            assert_eq!(buf, data[..4]);
+
+            let mut buf = [0; 8];
+            assert_eq!(reader.read_at(&mut buf, data.len() as u64 - 4)?, 4);
+            assert!(reader
+                .read_exact_at(&mut buf, data.len() as u64 - 4)
+                .is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_seek_and_read_out_of_bounds() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let factory = IoFactory::new(temp_dir.path(), FileExtension::SSTable)?;
+        let data = (0..100_u8).collect::<Vec<_>>();
+
+        // 各IoType在越界时的行为应与文件一致
+        for (gen, io_type) in [(1, IoType::Buf), (2, IoType::Direct), (3, IoType::Mem)] {
+            let mut writer = factory.writer(gen, io_type)?;
+            writer.write_all(&data)?;
+            writer.flush()?;
+
+            let mut reader = factory.reader(gen, io_type)?;
+            // Seek至负数位置时返回错误且不改变当前位置
+            assert_eq!(reader.seek(SeekFrom::Start(10))?, 10);
+            assert!(reader.seek(SeekFrom::End(-200)).is_err());
+            assert!(reader.seek(SeekFrom::Current(-11)).is_err());
+            assert_eq!(reader.stream_position()?, 10);
+
+            // 跨越末尾的读取仅返回剩余的数据
+            assert_eq!(reader.seek(SeekFrom::End(-10))?, 90);
+            let mut buf = Vec::new();
+            assert_eq!(reader.read_to_end(&mut buf)?, 10);
+            assert_eq!(buf, data[90..]);
+
+            // Seek至末尾之后是合法的，但读取时为EOF
+            assert_eq!(reader.seek(SeekFrom::End(50))?, 150);
+            let mut buf = [0; 8];
+            assert_eq!(reader.read(&mut buf)?, 0);
+            assert!(reader.read_exact(&mut buf).is_err());
+            assert_eq!(reader.read_at(&mut buf, 1000)?, 0);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mem_write_after_seek_past_end() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let factory = IoFactory::new(temp_dir.path(), FileExtension::SSTable)?;
+
+        for (gen, io_type) in [(1, IoType::Buf), (2, IoType::Mem)] {
+            let mut writer = factory.writer(gen, io_type)?;
+            writer.write_all(&[1; 4])?;
+            let _ = writer.seek(SeekFrom::Start(8))?;
+            writer.write_all(&[2; 4])?;
+            writer.flush()?;
+
+            let mut buf = Vec::new();
+            let _ = factory.reader(gen, io_type)?.read_to_end(&mut buf)?;
+            assert_eq!(buf, [1, 1, 1, 1, 0, 0, 0, 0, 2, 2, 2, 2]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mmap_reader() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let factory = IoFactory::new(temp_dir.path(), FileExtension::SSTable)?;
+        let data = (0..=255_u8).cycle().take(10_000).collect::<Vec<_>>();
+
+        let mut writer = factory.writer(1, IoType::Direct)?;
+        writer.write_all(&data)?;
+        writer.flush()?;
+        drop(writer);
+
+        let mut reader = factory.reader(1, IoType::Mmap)?;
+        assert_eq!(reader.get_type(), IoType::Mmap);
+        assert_eq!(reader.file_size()?, data.len() as u64);
+        assert_eq!(
+            reader.checksum_file()?,
+            factory.reader(1, IoType::Direct)?.checksum_file()?
+        );
+
+        let mut buf = Vec::new();
+        assert_eq!(reader.read_to_end(&mut buf)?, data.len());
+        assert_eq!(buf, data);
+
+        let mut buf = vec![0; 100];
+        reader.read_exact_at(&mut buf, 4000)?;
+        assert_eq!(buf, data[4000..4100]);
+        assert_eq!(reader.read_at(&mut buf, data.len() as u64 - 10)?, 10);
+        assert_eq!(reader.seek(SeekFrom::End(10))?, data.len() as u64 + 10);
+        assert_eq!(reader.read(&mut buf)?, 0);
+
+        // Mmap仅支持读取
+        assert!(matches!(
+            factory.writer(2, IoType::Mmap),
+            Err(KernelError::NotSupport(_))
+        ));
+        // 空文件同样可以被读取
+        let (mut reader, is_created) = factory.read_or_create(3, IoType::Mmap)?;
+        assert!(is_created);
+        assert_eq!(reader.file_size()?, 0);
+        assert_eq!(reader.read(&mut buf)?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_range() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let factory = IoFactory::new(temp_dir.path(), FileExtension::SSTable)?;
+        let data = (0..=255_u8).cycle().take(10_000).collect::<Vec<_>>();
+
+        for (gen, io_type) in [(1, IoType::Direct), (2, IoType::Mem)] {
+            let mut writer = factory.writer(gen, io_type)?;
+            writer.write_all(&data)?;
+            writer.flush()?;
+        }
+
+        for (gen, io_type) in [
+            (1, IoType::Buf),
+            (1, IoType::Direct),
+            (1, IoType::Mmap),
+            (2, IoType::Mem),
+        ] {
+            let mut reader = factory.reader(gen, io_type)?;
+            assert_eq!(reader.read_range(4000, 100)?, data[4000..4100]);
+            assert_eq!(reader.read_range(9990, 10)?, data[9990..]);
+            assert!(reader.read_range(10_000, 0)?.is_empty());
+            // 不依赖也不改变Reader当前的位置
+            assert_eq!(reader.seek(SeekFrom::Start(7))?, 7);
+            assert_eq!(reader.read_range(0, 3)?, data[..3]);
+            assert_eq!(reader.stream_position()?, 7);
+
+            assert!(matches!(
+                reader.read_range(9990, 11),
+                Err(KernelError::Io(err)) if err.kind() == io::ErrorKind::UnexpectedEof
+            ));
+            assert!(reader.read_range(u64::MAX, 1).is_err());
+        }
+
+        // Mem的切片直接共享文件内容，持有切片时的写入不影响已读取的数据
+        let reader = factory.reader(2, IoType::Mem)?;
+        let range = reader.read_range(0, 100)?;
+        assert_eq!(range.as_ptr(), reader.read_range(0, 100)?.as_ptr());
+
+        let mut writer = factory.writer(2, IoType::Mem)?;
+        writer.write_all(&[0; 100])?;
+        assert_eq!(range, data[..100]);
+        assert_eq!(reader.read_range(0, 100)?, [0; 100][..]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_direct_io_unaligned() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let factory = IoFactory::new(temp_dir.path(), FileExtension::SSTable)?;
+        // 长度与偏移均不按扇区对齐
+        let data = (0..130_u8).collect::<Vec<_>>();
+
+        let mut writer = factory.writer(1, IoType::Direct)?;
+        writer.write_all(&data[..7])?;
+        writer.write_all(&data[7..])?;
+        writer.flush()?;
+        assert_eq!(writer.current_pos()?, 130);
+
+        let mut reader = factory.reader(1, IoType::Direct)?;
+        assert_eq!(reader.file_size()?, 130);
+        let mut buf = Vec::new();
+        assert_eq!(reader.read_to_end(&mut buf)?, 130);
+        assert_eq!(buf, data);
+
+        let mut buf = [0; 3];
+        reader.read_exact_at(&mut buf, 127)?;
+        assert_eq!(buf, data[127..]);
+
+        // 追加跨越对齐块的数据，并覆盖写入位于对齐块中间的数据
+        let mut data = data.into_iter().cycle().take(5000).collect::<Vec<_>>();
+        let mut writer = factory.writer(1, IoType::Direct)?;
+        assert_eq!(writer.seek(SeekFrom::End(0))?, 130);
+        writer.write_all(&data[130..])?;
+        assert_eq!(writer.seek(SeekFrom::Start(4090))?, 4090);
+        writer.write_all(&[u8::MAX; 10])?;
+        data[4090..4100].fill(u8::MAX);
+        assert_eq!(writer.current_pos()?, 4100);
+        writer.flush()?;
+
+        let mut reader = factory.reader(1, IoType::Direct)?;
+        assert_eq!(reader.file_size()?, 5000);
+        let mut buf = Vec::new();
+        assert_eq!(reader.read_to_end(&mut buf)?, 5000);
+        assert_eq!(buf, data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_direct_io_buffered() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let factory = IoFactory::new(temp_dir.path(), FileExtension::SSTable)?;
+        let data = (0..=u8::MAX).cycle().take(5000).collect::<Vec<_>>();
+
+        // 不足一个对齐块的数据在flush前不会写入文件
+        let mut writer = factory.writer(1, IoType::Direct)?;
+        let reader = factory.reader(1, IoType::Direct)?;
+        writer.write_all(&data[..7])?;
+        assert_eq!(reader.file_size()?, 0);
+        writer.flush()?;
+        assert_eq!(reader.file_size()?, 7);
+
+        // 凑满的对齐块直接写入，末尾的部分在Drop时写入
+        writer.write_all(&data[7..])?;
+        assert_eq!(reader.file_size()?, 4096);
+        drop(writer);
+        assert_eq!(reader.file_size()?, 5000);
+
+        let mut buf = vec![0; 5000];
+        reader.read_exact_at(&mut buf, 0)?;
+        assert_eq!(buf, data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_all_vectored() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let factory = IoFactory::new(temp_dir.path(), FileExtension::SSTable)?;
+        // 包含空段以及超出BufWriter缓冲区大小的段
+        let large = vec![7_u8; 20 * 1024];
+        let segments: [&[u8]; 4] = [b"data", &[], &large, b"footer"];
+        let data = segments.concat();
+        assert_eq!(checksum_vectored(&segments), checksum(&data));
+
+        for (gen, io_type) in [(1, IoType::Buf), (2, IoType::Direct), (3, IoType::Mem)] {
+            let mut writer = factory.writer(gen, io_type)?;
+            writer.write_all(b"head")?;
+            IoWriter::write_all_vectored(writer.as_mut(), &segments)?;
+            writer.flush()?;
+            assert_eq!(writer.current_pos()?, 4 + data.len() as u64);
+
+            let mut buf = Vec::new();
+            let _ = factory.reader(gen, io_type)?.read_to_end(&mut buf)?;
+            assert_eq!(&buf[..4], b"head");
+            assert_eq!(buf[4..], data);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_open_files() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let factory =
+            IoFactory::new(temp_dir.path(), FileExtension::SSTable)?.max_open_files(16)?;
+        let open_readers = |factory: &IoFactory| {
+            factory
+                .reader_cache
+                .as_ref()
+                .map_or(0, |reader_cache| reader_cache.lock().len())
+        };
+
+        for gen in 0..32 {
+            let mut writer = factory.writer(gen, IoType::Direct)?;
+            writer.write_all(&gen.to_be_bytes())?;
+            writer.flush()?;
+        }
+        for _ in 0..2 {
+            for gen in 0..32 {
+                let reader = factory.shared_reader(gen, IoType::Direct)?;
+                let mut buf = [0; 8];
+                reader.read_exact_at(&mut buf, 0)?;
+                assert_eq!(i64::from_be_bytes(buf), gen);
+                assert!(open_readers(&factory) <= 16);
+            }
+        }
+        // 被移出缓存的读取器会被释放以关闭其文件
+        let evicted = factory.shared_reader(0, IoType::Direct)?;
+        for gen in 1..=16 {
+            let _ = factory.shared_reader(gen, IoType::Direct)?;
+        }
+        assert_eq!(Arc::strong_count(&evicted), 1);
+
+        // 相同gen的读取器会被复用
+        let reader = factory.shared_reader(31, IoType::Direct)?;
+        assert!(Arc::ptr_eq(
+            &reader,
+            &factory.shared_reader(31, IoType::Direct)?
+        ));
+        assert_eq!(
+            factory.shared_reader(31, IoType::Buf)?.get_type(),
+            IoType::Buf
+        );
+
+        factory.clean(31)?;
+        assert!(factory.shared_reader(31, IoType::Direct).is_err());
+
+        // 共享限制的IoFactory打开的文件计入同一限制
+        let other_dir = TempDir::new().expect("unable to create temporary working directory");
+        let other_factory =
+            IoFactory::new(other_dir.path(), FileExtension::SSTable)?.share_open_files(&factory);
+        for gen in 0..32 {
+            let mut writer = other_factory.writer(gen, IoType::Direct)?;
+            writer.write_all(&gen.to_be_bytes())?;
+            writer.flush()?;
+        }
+        for gen in 0..32 {
+            let _ = factory.shared_reader(gen % 31, IoType::Direct)?;
+            let _ = other_factory.shared_reader(gen, IoType::Direct)?;
+            assert!(open_readers(&factory) <= 16);
+            assert_eq!(open_readers(&factory), open_readers(&other_factory));
+        }
+
+        // 未限制时不缓存读取器
+        let factory = factory.max_open_files(0)?;
+        let _ = factory.shared_reader(0, IoType::Direct)?;
+        assert_eq!(open_readers(&factory), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_disk_usage() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let factory = IoFactory::new(temp_dir.path(), FileExtension::SSTable)?;
+
+        assert_eq!(factory.total_disk_usage()?, 0);
+        assert!(matches!(
+            factory.disk_usage(1),
+            Err(KernelError::FileNotFound(_))
+        ));
+
+        let mut writer = factory.writer(1, IoType::Direct)?;
+        writer.write_all(&[0; 100])?;
+        writer.flush()?;
+        let mut writer = factory.writer(2, IoType::Mem)?;
+        writer.write_all(&[0; 30])?;
+        writer.flush()?;
+
+        assert_eq!(factory.disk_usage(1)?, 100);
+        assert_eq!(factory.disk_usage(2)?, 30);
+        assert_eq!(factory.total_disk_usage()?, 130);
+
+        // 落盘后的Mem文件不会被重复计算
+        factory.snapshot_mem_to_disk()?;
+        assert_eq!(factory.total_disk_usage()?, 130);
+
+        factory.clean(1)?;
+        assert_eq!(factory.total_disk_usage()?, 30);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_or_create() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let factory = IoFactory::new(temp_dir.path(), FileExtension::Log)?;
+
+        for (gen, io_type) in [(1, IoType::Buf), (2, IoType::Direct), (3, IoType::Mem)] {
+            let path = FileExtension::Log.path_with_gen(temp_dir.path(), gen);
+            assert!(matches!(
+                factory.reader(gen, io_type),
+                Err(KernelError::FileNotFound(err_path)) if err_path == path
+            ));
+
+            // 不存在时创建空文件
+            let (mut reader, is_created) = factory.read_or_create(gen, io_type)?;
+            assert!(is_created);
+            let mut buf = Vec::new();
+            assert_eq!(reader.read_to_end(&mut buf)?, 0);
+            assert!(factory.exists(gen)?);
+
+            let mut writer = factory.writer(gen, io_type)?;
+            writer.write_all(b"KipDB")?;
+            writer.flush()?;
+
+            // 已存在时直接读取其中的数据
+            let (mut reader, is_created) = factory.read_or_create(gen, io_type)?;
+            assert!(!is_created);
+            let mut buf = String::new();
+            let _ = reader.read_to_string(&mut buf)?;
+            assert_eq!(buf, "KipDB");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_mem_to_disk() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let factory = IoFactory::new(temp_dir.path(), FileExtension::SSTable)?;
+
+        for gen in 1..=3 {
+            let mut writer = factory.writer(gen, IoType::Mem)?;
+            writer.write_all(format!("KipDB-{gen}").as_bytes())?;
+            writer.flush()?;
+        }
+        factory.snapshot_mem_to_disk()?;
+        // 落盘后继续写入的数据需要再次落盘才能被恢复
+        factory.writer(3, IoType::Mem)?.write_all(b"-unsaved")?;
+
+        // 同一路径的其他IoFactory可以直接读取落盘的文件
+        let mut buf = String::new();
+        let _ = IoFactory::new(temp_dir.path(), FileExtension::SSTable)?
+            .reader(1, IoType::Buf)?
+            .read_to_string(&mut buf)?;
+        assert_eq!(buf, "KipDB-1");
+
+        // 模拟进程重启
+        drop(factory);
+        let factory = IoFactory::load_mem_from_disk(temp_dir.path(), FileExtension::SSTable)?;
+        for gen in 1..=3 {
+            let mut buf = String::new();
+            let _ = factory.reader(gen, IoType::Mem)?.read_to_string(&mut buf)?;
+            assert_eq!(buf, format!("KipDB-{gen}"));
+        }
+
+        // 移除内存文件时同时移除已落盘的文件，避免其在恢复时重新出现
+        factory.clean(1)?;
+        assert!(!factory.exists(1)?);
+        let factory = IoFactory::load_mem_from_disk(temp_dir.path(), FileExtension::SSTable)?;
+        assert!(!factory.exists(1)?);
+        assert!(factory.exists(2)?);
+        // 未落盘的内存文件移除时不会产生错误
+        let _ = factory.writer(4, IoType::Mem)?;
+        factory.clean(4)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_persist_mem_to_disk() -> KernelResult<()> {
+        let mem_dir = TempDir::new().expect("unable to create temporary working directory");
+        let persist_dir = TempDir::new().expect("unable to create temporary working directory");
+        let mem_factory = IoFactory::new(mem_dir.path(), FileExtension::SSTable)?;
+
+        for gen in 1..=3 {
+            let mut writer = mem_factory.writer(gen, IoType::Mem)?;
+            writer.write_all(format!("KipDB-{gen}").as_bytes())?;
+            writer.flush()?;
+        }
+        let mut reader = mem_factory.reader(2, IoType::Mem)?;
+        let _ = reader.seek(SeekFrom::Start(6))?;
+        let mut buf = String::new();
+        let _ = reader.read_to_string(&mut buf)?;
+        assert_eq!(buf, "2");
+        assert_eq!(reader.file_size()?, 7);
+        // 内存模式不应在文件夹中产生文件
+        assert!(std::fs::read_dir(mem_dir.path())?.next().is_none());
+
+        mem_factory.persist_mem_to(persist_dir.path())?;
+
+        let buf_factory = IoFactory::new(persist_dir.path(), FileExtension::SSTable)?;
+        let mem_reloaded = IoFactory::new(mem_dir.path(), FileExtension::SSTable)?;
+        mem_reloaded.load_mem_from(persist_dir.path())?;
+
+        for gen in 1..=3 {
+            let mut buf = String::new();
+            let _ = buf_factory
+                .reader(gen, IoType::Buf)?
+                .read_to_string(&mut buf)?;
+            assert_eq!(buf, format!("KipDB-{gen}"));
+
+            let mut buf = String::new();
+            let _ = mem_reloaded
+                .reader(gen, IoType::Mem)?
+                .read_to_string(&mut buf)?;
+            assert_eq!(buf, format!("KipDB-{gen}"));
+        }
+
+        mem_reloaded.clean(1)?;
+        assert!(!mem_reloaded.exists(1)?);
+        assert!(mem_reloaded.exists(2)?);
+
+        Ok(())
+    }
 }
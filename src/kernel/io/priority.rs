@@ -0,0 +1,69 @@
+use crate::kernel::KernelResult;
+
+/// 线程的I/O调度优先级
+///
+/// 仅在Linux下通过`ioprio_set`生效，其他平台上设置为空操作
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum IoPriority {
+    /// 尽力而为调度，等级为0~7，数值越小优先级越高
+    BestEffort(u8),
+    /// 仅当磁盘空闲时才进行I/O
+    Idle,
+}
+
+#[cfg(target_os = "linux")]
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+#[cfg(target_os = "linux")]
+const IOPRIO_CLASS_SHIFT: i32 = 13;
+#[cfg(target_os = "linux")]
+const IOPRIO_CLASS_BE: i32 = 2;
+#[cfg(target_os = "linux")]
+const IOPRIO_CLASS_IDLE: i32 = 3;
+
+impl IoPriority {
+    /// 编码为内核所使用的ioprio值
+    #[cfg(target_os = "linux")]
+    pub(crate) fn to_ioprio(self) -> i32 {
+        match self {
+            IoPriority::BestEffort(level) => {
+                (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | i32::from(level.min(7))
+            }
+            IoPriority::Idle => IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+        }
+    }
+
+    /// 将该优先级设置至当前线程
+    #[cfg(target_os = "linux")]
+    pub(crate) fn apply_to_current_thread(self) -> KernelResult<()> {
+        // who为0时作用于调用线程
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_ioprio_set,
+                IOPRIO_WHO_PROCESS,
+                0,
+                self.to_ioprio(),
+            )
+        };
+        if ret < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn apply_to_current_thread(self) -> KernelResult<()> {
+        Ok(())
+    }
+}
+
+/// 获取指定线程的ioprio值
+#[cfg(all(test, target_os = "linux"))]
+pub(crate) fn thread_ioprio(tid: i32) -> KernelResult<i32> {
+    let ret = unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, tid) };
+    if ret < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    Ok(ret as i32)
+}
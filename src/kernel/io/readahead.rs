@@ -0,0 +1,167 @@
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+
+/// 预读Reader
+///
+/// 每次缓冲未命中时从内部Reader一次性读取`readahead_size`字节，
+/// 使顺序读取(如压缩时的全量迭代)以大块的形式读取而非逐Block读取
+///
+/// Tips: 与Block缓存不同，预读缓冲仅服务于单次顺序读取，不参与随机读的缓存
+#[derive(Debug)]
+pub(crate) struct ReadAheadReader<R> {
+    inner: R,
+    buf: Vec<u8>,
+    // 缓冲区首字节所对应的文件位置
+    buf_start: u64,
+    buf_len: usize,
+    // 当前读取的文件位置
+    pos: u64,
+}
+
+impl<R: Read + Seek> ReadAheadReader<R> {
+    pub(crate) fn new(inner: R, readahead_size: usize) -> Self {
+        ReadAheadReader {
+            inner,
+            buf: vec![0; readahead_size.max(1)],
+            buf_start: 0,
+            buf_len: 0,
+            pos: 0,
+        }
+    }
+
+    /// 从当前位置开始填充缓冲区，直至缓冲区填满或到达文件末尾
+    fn fill_buf(&mut self) -> io::Result<()> {
+        let _ = self.inner.seek(SeekFrom::Start(self.pos))?;
+        let mut len = 0;
+
+        while len < self.buf.len() {
+            match self.inner.read(&mut self.buf[len..]) {
+                Ok(0) => break,
+                Ok(n) => len += n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        self.buf_start = self.pos;
+        self.buf_len = len;
+
+        Ok(())
+    }
+
+    fn buf_end(&self) -> u64 {
+        self.buf_start + self.buf_len as u64
+    }
+}
+
+impl<R: Read + Seek> Read for ReadAheadReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.pos < self.buf_start || self.pos >= self.buf_end() {
+            // 读取长度超出缓冲区时直接读取，避免额外的拷贝
+            if buf.len() >= self.buf.len() {
+                let _ = self.inner.seek(SeekFrom::Start(self.pos))?;
+                let len = self.inner.read(buf)?;
+                self.pos += len as u64;
+
+                return Ok(len);
+            }
+            self.fill_buf()?;
+        }
+        let start = (self.pos - self.buf_start) as usize;
+        let len = buf.len().min(self.buf_len - start);
+
+        buf[..len].copy_from_slice(&self.buf[start..start + len]);
+        self.pos += len as u64;
+
+        Ok(len)
+    }
+}
+
+impl<R: Read + Seek> Seek for ReadAheadReader<R> {
+    fn seek(&mut self, seek: SeekFrom) -> io::Result<u64> {
+        self.pos = match seek {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::End(_) => self.inner.seek(seek)?,
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "invalid seek to a negative or overflowing position",
+                )
+            })?,
+        };
+
+        Ok(self.pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kernel::io::readahead::ReadAheadReader;
+    use std::io;
+    use std::io::{Cursor, Read, Seek, SeekFrom};
+
+    /// 记录内部Reader被调用read的次数
+    struct CountingReader {
+        inner: Cursor<Vec<u8>>,
+        reads: usize,
+    }
+
+    impl Read for CountingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.reads += 1;
+            self.inner.read(buf)
+        }
+    }
+
+    impl Seek for CountingReader {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_read_ahead() -> io::Result<()> {
+        let data = (0..=255_u8).cycle().take(4096).collect::<Vec<_>>();
+        let mut reader = ReadAheadReader::new(
+            CountingReader {
+                inner: Cursor::new(data.clone()),
+                reads: 0,
+            },
+            1024,
+        );
+
+        // 顺序读取时每1024字节才会读取一次内部Reader
+        let mut buf = [0; 16];
+        for offset in (0..2048).step_by(16) {
+            reader.read_exact(&mut buf)?;
+            assert_eq!(&buf[..], &data[offset..offset + 16]);
+        }
+        assert_eq!(reader.inner.reads, 2);
+
+        // 在缓冲区内Seek不会触发读取
+        let _ = reader.seek(SeekFrom::Start(1030))?;
+        reader.read_exact(&mut buf)?;
+        assert_eq!(&buf[..], &data[1030..1046]);
+        assert_eq!(reader.inner.reads, 2);
+
+        // 跨越缓冲区边界与文件末尾的读取
+        let _ = reader.seek(SeekFrom::End(-8))?;
+        let mut tail = Vec::new();
+        let _ = reader.read_to_end(&mut tail)?;
+        assert_eq!(&tail[..], &data[4088..]);
+
+        let _ = reader.seek(SeekFrom::Start(2040))?;
+        reader.read_exact(&mut buf)?;
+        assert_eq!(&buf[..], &data[2040..2056]);
+
+        // 超出缓冲区大小的读取直接读取内部Reader
+        let mut large = vec![0; 2048];
+        let _ = reader.seek(SeekFrom::Start(0))?;
+        reader.read_exact(&mut large)?;
+        assert_eq!(large, data[..2048]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+use crate::kernel::io::FileExtension;
+use crate::kernel::lsm::format;
+use crate::kernel::lsm::log::LogWriter;
+use crate::kernel::lsm::mem_table::DEFAULT_WAL_PATH;
+use crate::kernel::lsm::version::{DEFAULT_SS_TABLE_PATH, DEFAULT_VERSION_PATH};
+use crate::kernel::KernelResult;
+use crate::KernelError;
+use integer_encoding::{FixedIntReader, FixedIntWriter};
+use std::fs;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// 备份归档的魔数
+const ARCHIVE_MAGIC: &[u8; 8] = b"KIPDBBAK";
+
+/// 归档中每个文件帧的类型
+///
+/// 帧格式: | type(u8) | gen(i64) | len(u64) | crc(u32) | bytes |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EntryType {
+    /// Version快照，内容为序列化后的Vec<VersionEdit>
+    Manifest = 1,
+    SSTable = 2,
+    /// 不存在SSTable文件的Table(如Level 0的BTreeTable)所对应的WAL
+    Log = 3,
+    /// SSTable中分离存储的大Value，恢复至对应SSTable的同一目录
+    ValueLog = 4,
+    /// 备份时的格式版本，内容为u32(小端序)
+    ///
+    /// 不存在该帧的旧归档还原后不带格式标记，开启时视为版本0
+    Format = 5,
+}
+
+impl EntryType {
+    fn from_u8(value: u8) -> KernelResult<Self> {
+        match value {
+            1 => Ok(EntryType::Manifest),
+            2 => Ok(EntryType::SSTable),
+            3 => Ok(EntryType::Log),
+            4 => Ok(EntryType::ValueLog),
+            5 => Ok(EntryType::Format),
+            _ => Err(KernelError::BackupCorrupted),
+        }
+    }
+}
+
+/// 备份归档写入器
+pub(crate) struct ArchiveWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    pub(crate) fn new(mut writer: W) -> KernelResult<Self> {
+        writer.write_all(ARCHIVE_MAGIC)?;
+
+        Ok(ArchiveWriter { writer })
+    }
+
+    /// 追加一个文件帧，同时附带其CRC用于恢复时校验
+    pub(crate) fn add(
+        &mut self,
+        entry_type: EntryType,
+        gen: i64,
+        bytes: &[u8],
+    ) -> KernelResult<()> {
+        self.write_header(entry_type, gen, bytes.len() as u64, crc32fast::hash(bytes))?;
+        self.writer.write_all(bytes)?;
+
+        Ok(())
+    }
+
+    /// 以`path`处的文件追加一个文件帧，分块拷贝而不将整个文件读入内存
+    ///
+    /// 帧头中的CRC位于内容之前，因此文件会被读取两次：先计算长度与CRC，再拷贝其内容
+    pub(crate) fn add_file(
+        &mut self,
+        entry_type: EntryType,
+        gen: i64,
+        path: &Path,
+    ) -> KernelResult<()> {
+        let mut file = File::open(path)?;
+        let mut crc_writer = CrcWriter::new(io::sink());
+        let len = io::copy(&mut file, &mut crc_writer)?;
+
+        self.write_header(entry_type, gen, len, crc_writer.crc())?;
+        let _ = file.seek(SeekFrom::Start(0))?;
+        if io::copy(&mut file.take(len), &mut self.writer)? != len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        Ok(())
+    }
+
+    fn write_header(
+        &mut self,
+        entry_type: EntryType,
+        gen: i64,
+        len: u64,
+        crc: u32,
+    ) -> KernelResult<()> {
+        self.writer.write_fixedint(entry_type as u8)?;
+        self.writer.write_fixedint(gen)?;
+        self.writer.write_fixedint(len)?;
+        self.writer.write_fixedint(crc)?;
+
+        Ok(())
+    }
+
+    pub(crate) fn finish(mut self) -> KernelResult<()> {
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// 在写入的同时计算所写入内容的CRC
+struct CrcWriter<W: Write> {
+    inner: W,
+    hasher: crc32fast::Hasher,
+}
+
+impl<W: Write> CrcWriter<W> {
+    fn new(inner: W) -> Self {
+        CrcWriter {
+            inner,
+            hasher: crc32fast::Hasher::new(),
+        }
+    }
+
+    fn crc(self) -> u32 {
+        self.hasher.finalize()
+    }
+}
+
+impl<W: Write> Write for CrcWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.hasher.update(&buf[..len]);
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 将文件帧中`len`字节的内容分块拷贝至`writer`并校验其CRC
+///
+/// 读取的长度受归档剩余的大小限制，损坏的`len`不会导致按其大小分配内存；
+/// 归档在帧结束前耗尽时返回`KernelError::BackupCorrupted`
+fn copy_entry<R: Read, W: Write>(
+    reader: &mut R,
+    len: u64,
+    crc: u32,
+    writer: W,
+) -> KernelResult<()> {
+    let mut crc_writer = CrcWriter::new(writer);
+    if io::copy(&mut reader.by_ref().take(len), &mut crc_writer)? != len {
+        return Err(KernelError::BackupCorrupted);
+    }
+    crc_writer.flush()?;
+    if crc_writer.crc() != crc {
+        return Err(KernelError::CrcMisMatch);
+    }
+
+    Ok(())
+}
+
+/// 读取Manifest等较小的文件帧的内容并校验其CRC
+fn read_entry<R: Read>(reader: &mut R, len: u64, crc: u32) -> KernelResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    copy_entry(reader, len, crc, &mut bytes)?;
+
+    Ok(bytes)
+}
+
+/// 将归档还原至指定的数据目录
+///
+/// 目标目录需要为空(或不存在)，任何一个文件帧的CRC不匹配时返回`KernelError::CrcMisMatch`
+pub(crate) fn restore<R: Read>(mut reader: R, dir: &Path) -> KernelResult<()> {
+    fs::create_dir_all(dir)?;
+    if fs::read_dir(dir)?.next().is_some() {
+        return Err(KernelError::NotSupport(
+            "the directory to restore must be empty",
+        ));
+    }
+    let mut magic = [0u8; ARCHIVE_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if &magic != ARCHIVE_MAGIC {
+        return Err(KernelError::BackupCorrupted);
+    }
+
+    let sst_path = dir.join(DEFAULT_SS_TABLE_PATH);
+    let wal_path = dir.join(DEFAULT_WAL_PATH);
+    let version_path = dir.join(DEFAULT_VERSION_PATH);
+    for path in [&sst_path, &wal_path, &version_path] {
+        fs::create_dir_all(path)?;
+    }
+    let mut max_gen = None;
+    let mut type_buf = [0u8; 1];
+
+    while reader.read(&mut type_buf)? > 0 {
+        let entry_type = EntryType::from_u8(type_buf[0])?;
+        let gen: i64 = reader.read_fixedint()?;
+        let len: u64 = reader.read_fixedint()?;
+        let crc: u32 = reader.read_fixedint()?;
+
+        match entry_type {
+            EntryType::Manifest => {
+                let bytes = read_entry(&mut reader, len, crc)?;
+                let file = File::create(FileExtension::Log.path_with_gen(&version_path, gen))?;
+                let mut log_writer = LogWriter::new(file);
+                let _ = log_writer.add_record(&bytes)?;
+                log_writer.flush()?;
+            }
+            EntryType::SSTable => {
+                let file = File::create(FileExtension::SSTable.path_with_gen(&sst_path, gen))?;
+                copy_entry(&mut reader, len, crc, file)?;
+                max_gen = max_gen.max(Some(gen));
+            }
+            EntryType::ValueLog => {
+                let file = File::create(FileExtension::ValueLog.path_with_gen(&sst_path, gen))?;
+                copy_entry(&mut reader, len, crc, file)?;
+            }
+            EntryType::Log => {
+                let file = File::create(FileExtension::Log.path_with_gen(&wal_path, gen))?;
+                copy_entry(&mut reader, len, crc, file)?;
+                max_gen = max_gen.max(Some(gen));
+            }
+            EntryType::Format => {
+                let version: [u8; 4] = read_entry(&mut reader, len, crc)?
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| KernelError::BackupCorrupted)?;
+                format::write_format_version(dir, u32::from_le_bytes(version))?;
+            }
+        }
+    }
+    // MemTable会使用最新的WAL作为当前的日志，因此需要创建一个空的WAL
+    // 避免还原的Table所对应的WAL被追加写入
+    if let Some(gen) = max_gen {
+        let _ = File::create(FileExtension::Log.path_with_gen(&wal_path, gen + 1))?;
+    }
+
+    Ok(())
+}
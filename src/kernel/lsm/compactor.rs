@@ -1,38 +1,305 @@
-use crate::kernel::lsm::mem_table::{KeyValue, MemTable};
-use crate::kernel::lsm::storage::{Config, StoreInner};
+use crate::kernel::lsm::iterator::merging_iter::MergingIter;
+use crate::kernel::lsm::iterator::{Iter, SeekIter};
+use crate::kernel::lsm::mem_table::{key_value_bytes_len, KeyValue, MemTable};
+use crate::kernel::lsm::stats::CompactionStats;
+use crate::kernel::lsm::storage::{Config, Gen, StoreInner};
 use crate::kernel::lsm::table::meta::TableMeta;
 use crate::kernel::lsm::table::scope::Scope;
 use crate::kernel::lsm::table::{collect_gen, Table};
 use crate::kernel::lsm::version::edit::VersionEdit;
 use crate::kernel::lsm::version::status::VersionStatus;
-use crate::kernel::lsm::{data_sharding, MAX_LEVEL};
+use crate::kernel::lsm::{check_sorted, CompressType, MAX_LEVEL};
+use crate::kernel::utils::rate_limiter::RateLimiter;
 use crate::kernel::KernelResult;
 use crate::KernelError;
 use bytes::Bytes;
 use futures::future;
 use itertools::Itertools;
-use std::collections::HashSet;
-use std::mem;
+use std::cmp::Reverse;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
+use std::{fmt, mem, vec};
 use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
 use tracing::info;
 
 pub(crate) const LEVEL_0: usize = 0;
 
-/// 数据分片集
-/// 包含对应分片的Gen与数据
-pub(crate) type MergeShardingVec = Vec<(i64, Vec<KeyValue>)>;
 pub(crate) type DelNode = (Vec<i64>, TableMeta);
-/// Major压缩时的待删除Gen封装(N为此次Major所压缩的Level)，第一个为Level N级，第二个为Level N+1级
-pub(crate) type DelNodeTuple = (DelNode, DelNode);
 pub type SeekScope = (Scope, usize);
 
 /// Store与Compactor的交互信息
 #[derive(Debug)]
 pub enum CompactTask {
     Seek(SeekScope),
-    Flush(Option<oneshot::Sender<()>>),
+    Manual(SeekScope),
+    /// 依次压缩`levels`中的每一个Level，`scope`为None时压缩该Level的全部Table
+    Range {
+        levels: Range<usize>,
+        scope: Option<Scope>,
+        options: CompactionOptions,
+        tx: oneshot::Sender<KernelResult<()>>,
+    },
+    /// 持久化Immut Table，完成后响应此次所新建的Table的Gen
+    Flush(Option<oneshot::Sender<Vec<i64>>>),
+}
+
+/// 自动压缩时Level 0之下的Level超出阈值后，挑选优先向下一级压缩的Table的策略
+///
+/// 多个相邻的Level同时超出阈值时，同样以各Level所挑选的Table依此比较而决定先压缩的Level；
+/// 不同策略在空间放大与写放大之间有所取舍；Level 0的Table间范围可能重叠，
+/// 因此总是压缩与触发压缩的范围相交的所有Table
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum CompactionPriority {
+    /// 沿用触发压缩的Key范围，逐级压缩与其相交的Table
+    #[default]
+    Cascade,
+    /// 优先压缩磁盘占用最大的Table，使该Level尽快回落至阈值之下
+    LargestFirst,
+    /// 优先压缩最旧(Gen最小)的Table，使长时间未被更新的数据尽早下沉
+    OldestSmallestSeqFirst,
+    /// 优先压缩与下一级重叠的数据量相对自身大小最小的Table，以降低压缩的写放大
+    MinOverlappingRatio,
+    /// 综合删除标记密度与下一级中重叠的Table数量评分，优先压缩删除标记多且与下一级重叠少的Table
+    ///
+    /// 删除标记随压缩下沉并在最底层被丢弃，以此尽早回收大量删除后所占用的空间
+    TombstoneDensity,
+    /// 以Key顺序轮流压缩各Table，每次由上一次压缩的最大Key之后开始
+    ///
+    /// 压缩点通过`VersionEdit::CompactPoint`持久化，重启后仍由此继续，避免较大Key的范围长期得不到压缩
+    RoundRobin,
+}
+
+/// 自动压缩的策略，仅能在启动时指定
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum CompactionStrategy {
+    /// 各Level的Table数量超出阈值(`major_threshold_with_sst_size`与`level_sst_magnification`)时向下一级压缩
+    #[default]
+    Leveled,
+    /// 某Level中磁盘占用相近(处于同一2的幂次区间)的Table达到`min_merge_width`个时，将其一同向下一级压缩
+    ///
+    /// Level 0的Table各自为一个Run，数量达到`min_merge_width`时即触发压缩；
+    /// 延迟压缩直至积累足够的同等大小的Table，以空间放大换取较低的写放大，适用于写入密集的场景。
+    /// 由于Level 0之下的Level内Table之间范围不重叠，合并时仍会一同重写下一级与其重叠的Table
+    Tiered { min_merge_width: usize },
+}
+
+/// 手动压缩的进度
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct CompactionProgress {
+    /// 已归并的输入Table的磁盘大小总和
+    pub bytes_processed: u64,
+    /// 已归并的输入Table数量
+    pub tables_processed: usize,
+}
+
+/// 手动压缩的控制选项
+///
+/// 通过`CancellationToken`中止压缩，并可通过回调获取压缩进度
+#[derive(Clone, Default)]
+pub struct CompactionOptions {
+    cancel: CancellationToken,
+    on_progress: Option<Arc<dyn Fn(CompactionProgress) + Send + Sync>>,
+}
+
+impl CompactionOptions {
+    #[inline]
+    pub fn new(cancel: CancellationToken) -> Self {
+        CompactionOptions {
+            cancel,
+            on_progress: None,
+        }
+    }
+
+    /// 每当一组Table归并完成(新Table尚未写入Version)时回调
+    #[inline]
+    pub fn on_progress(
+        mut self,
+        on_progress: impl Fn(CompactionProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_progress = Some(Arc::new(on_progress));
+        self
+    }
+}
+
+impl fmt::Debug for CompactionOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompactionOptions")
+            .field("cancel", &self.cancel)
+            .field("on_progress", &self.on_progress.is_some())
+            .finish()
+    }
+}
+
+/// 手动压缩过程中的控制状态
+pub(crate) struct CompactionCtl<'a> {
+    options: &'a CompactionOptions,
+    progress: CompactionProgress,
+}
+
+impl CompactionCtl<'_> {
+    fn check_cancelled(&self) -> KernelResult<()> {
+        if self.options.cancel.is_cancelled() {
+            return Err(KernelError::CompactionCancelled);
+        }
+        Ok(())
+    }
+
+    fn report(&mut self, (gens_l, meta_l): &DelNode, (gens_ll, meta_ll): &DelNode) {
+        self.progress.bytes_processed += meta_l.size_of_disk + meta_ll.size_of_disk;
+        self.progress.tables_processed += gens_l.len() + gens_ll.len();
+
+        if let Some(on_progress) = &self.options.on_progress {
+            on_progress(self.progress);
+        }
+    }
+}
+
+/// 后台IO统计，由Compactor累计
+///
+/// 通过`KipStorage::compaction_stats`获取压缩次数与读写量，
+/// 开启`Config::report_bg_io_stats`时`KipStorage::stats`中另给出写放大：(Flush写入字节数 + 压缩写入字节数) / Flush写入字节数
+#[derive(Debug, Default)]
+pub(crate) struct BgIoStats {
+    user_bytes_flushed: AtomicU64,
+    compaction_bytes_read: AtomicU64,
+    compaction_bytes_written: AtomicU64,
+    minor_count: AtomicU64,
+    major_count: AtomicU64,
+    tables_created: AtomicU64,
+    tables_deleted: AtomicU64,
+}
+
+impl BgIoStats {
+    fn record_flush(&self, bytes: u64) {
+        let _ = self.minor_count.fetch_add(1, Ordering::Relaxed);
+        let _ = self.user_bytes_flushed.fetch_add(bytes, Ordering::Relaxed);
+        let _ = self.tables_created.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_compaction(
+        &self,
+        bytes_read: u64,
+        bytes_written: u64,
+        tables_created: usize,
+        tables_deleted: usize,
+    ) {
+        let _ = self.major_count.fetch_add(1, Ordering::Relaxed);
+        let _ = self
+            .compaction_bytes_read
+            .fetch_add(bytes_read, Ordering::Relaxed);
+        let _ = self
+            .compaction_bytes_written
+            .fetch_add(bytes_written, Ordering::Relaxed);
+        let _ = self
+            .tables_created
+            .fetch_add(tables_created as u64, Ordering::Relaxed);
+        let _ = self
+            .tables_deleted
+            .fetch_add(tables_deleted as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn user_bytes_flushed(&self) -> u64 {
+        self.user_bytes_flushed.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn compaction_bytes_read(&self) -> u64 {
+        self.compaction_bytes_read.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn compaction_bytes_written(&self) -> u64 {
+        self.compaction_bytes_written.load(Ordering::Relaxed)
+    }
+
+    /// 写放大系数，尚未Flush过数据时为0
+    pub(crate) fn write_amplification(&self) -> f64 {
+        let flushed = self.user_bytes_flushed();
+        if flushed == 0 {
+            return 0.0;
+        }
+
+        (flushed + self.compaction_bytes_written()) as f64 / flushed as f64
+    }
+
+    pub(crate) fn compaction_stats(&self) -> CompactionStats {
+        CompactionStats {
+            minor_count: self.minor_count.load(Ordering::Relaxed),
+            major_count: self.major_count.load(Ordering::Relaxed),
+            bytes_read: self.compaction_bytes_read(),
+            bytes_written: self.user_bytes_flushed() + self.compaction_bytes_written(),
+            tables_created: self.tables_created.load(Ordering::Relaxed),
+            tables_deleted: self.tables_deleted.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// 单个Level向下一级压缩所生成的Table及待删除的Table，尚未写入Version
+struct LevelCompaction {
+    /// 新Table插入下一级的位置
+    index: usize,
+    del_node_l: DelNode,
+    del_node_ll: DelNode,
+    new_scopes: Vec<Scope>,
+    new_metas: Vec<TableMeta>,
+    is_truncated: bool,
+    /// 此次压缩后该Level的压缩点，见`VersionEdit::CompactPoint`
+    compact_point: Option<Bytes>,
+}
+
+/// 压缩时依次读取一组Table数据的迭代器
+///
+/// 要求Table之间有序且不重叠；未设置预读时通过Table的迭代器惰性读取，
+/// 设置预读时同一时刻仅将其中一个Table的数据载入内存
+struct TableConcatIter<'a> {
+    tables: vec::IntoIter<&'a dyn Table>,
+    child_iter: Option<Box<dyn SeekIter<'a, Item = KeyValue> + 'a + Sync + Send>>,
+    buf: vec::IntoIter<KeyValue>,
+    readahead_size: usize,
+}
+
+impl<'a> TableConcatIter<'a> {
+    fn new(tables: Vec<&'a dyn Table>, readahead_size: usize) -> Self {
+        TableConcatIter {
+            tables: tables.into_iter(),
+            child_iter: None,
+            buf: Vec::new().into_iter(),
+            readahead_size,
+        }
+    }
+}
+
+impl<'a> Iter<'a> for TableConcatIter<'a> {
+    type Item = KeyValue;
+
+    fn try_next(&mut self) -> KernelResult<Option<Self::Item>> {
+        loop {
+            if let Some(child_iter) = self.child_iter.as_mut() {
+                if let Some(key_value) = child_iter.try_next()? {
+                    return Ok(Some(key_value));
+                }
+                self.child_iter = None;
+            }
+            if let Some(key_value) = self.buf.next() {
+                return Ok(Some(key_value));
+            }
+            let Some(table) = self.tables.next() else {
+                return Ok(None);
+            };
+            if self.readahead_size == 0 {
+                self.child_iter = Some(table.iter()?);
+            } else {
+                self.buf = table
+                    .load_for_compaction(self.readahead_size, &|_| true)?
+                    .into_iter();
+            }
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        true
+    }
 }
 
 /// 压缩器
@@ -40,11 +307,18 @@ pub enum CompactTask {
 /// 负责Minor和Major压缩
 pub(crate) struct Compactor {
     store_inner: Arc<StoreInner>,
+    /// 由各并行创建Table的Major压缩任务共享的写入限速器，速率于每次Major压缩时依据`Config::compaction_bytes_per_sec`更新
+    rate_limiter: RateLimiter,
 }
 
 impl Compactor {
     pub(crate) fn new(store_inner: Arc<StoreInner>) -> Self {
-        Compactor { store_inner }
+        let rate_limiter = RateLimiter::new(store_inner.config().compaction_bytes_per_sec);
+
+        Compactor {
+            store_inner,
+            rate_limiter,
+        }
     }
 
     /// 检查并进行压缩 （默认为 异步、被动 的Lazy压缩）
@@ -55,20 +329,36 @@ impl Compactor {
     /// 减少Level 0热数据的SSTable的冗余数据
     pub(crate) async fn check_then_compaction(
         &mut self,
-        option_tx: Option<oneshot::Sender<()>>,
+        option_tx: Option<oneshot::Sender<Vec<i64>>>,
     ) -> KernelResult<()> {
-        if let Some((gen, values)) = self.mem_table().swap()? {
+        let mem_table = self.mem_table();
+        let mut new_gens = Vec::new();
+
+        // 写入时已尝试封存，此处仅在手动Flush或因事务未能封存时进行封存
+        if option_tx.is_some() || mem_table.is_exceeded() {
+            let _ = mem_table.seal()?;
+        }
+        // 由旧至新依次持久化Immut Table
+        while let Some((gen, values)) = mem_table.next_unflushed() {
             if !values.is_empty() {
                 let start = Instant::now();
                 // 目前minor触发major时是同步进行的，所以此处对live_tag是在此方法体保持存活
-                self.minor_compaction(gen, values).await?;
+                match self.minor_compaction(gen, values).await {
+                    Ok(mut gens) => new_gens.append(&mut gens),
+                    Err(err) => {
+                        // 唤醒等待中的写入，避免其因持久化失败而永久阻塞
+                        mem_table.notify_flushed();
+                        return Err(err);
+                    }
+                }
                 info!("[Compactor][Compaction Drop][Time: {:?}]", start.elapsed());
             }
+            mem_table.mark_flushed(gen);
         }
 
         // 压缩请求响应
         if let Some(tx) = option_tx {
-            tx.send(()).map_err(|_| KernelError::ChannelClose)?
+            tx.send(new_gens).map_err(|_| KernelError::ChannelClose)?
         }
 
         Ok(())
@@ -76,34 +366,59 @@ impl Compactor {
 
     /// 持久化immutable_table为SSTable
     ///
+    /// 返回此次新建的Level 0 Table与随之触发的压缩所新建的Table的Gen
+    ///
     /// 请注意：vec_values必须是依照key值有序的
     pub(crate) async fn minor_compaction(
         &self,
         gen: i64,
         values: Vec<KeyValue>,
-    ) -> KernelResult<()> {
+    ) -> KernelResult<Vec<i64>> {
+        let mut new_gens = Vec::new();
+
         if !values.is_empty() {
-            let (scope, meta) = self
-                .ver_status()
-                .loader()
-                .create(
-                    gen,
-                    values,
-                    LEVEL_0,
-                    self.config().level_table_type[LEVEL_0],
-                )
+            let config = self.config();
+            if config.paranoid_memtable_order_check {
+                check_sorted(&values)?;
+            }
+            let loader = self.ver_status().loader();
+
+            // 同名的Table文件为上次持久化失败(如压缩出错或停机)时所残留的，并未写入Version
+            if loader.is_table_file_exist(gen)? {
+                let version = self.ver_status().current().await;
+                if version
+                    .level_slice
+                    .iter()
+                    .flatten()
+                    .any(|scope| scope.gen() == gen)
+                {
+                    return Ok(new_gens);
+                }
+                loader.discard(gen)?;
+            }
+            let (scope, meta) = loader
+                .create(gen, values, LEVEL_0, config.level_table_type[LEVEL_0])
                 .await?;
+            self.store_inner.bg_io_stats.record_flush(meta.size_of_disk);
+            new_gens.push(gen);
 
-            // `Compactor::data_loading_with_level`中会检测是否达到压缩阈值，因此此处直接调用Major压缩
-            self.major_compaction(
-                LEVEL_0,
-                scope.clone(),
-                vec![VersionEdit::NewFile((vec![scope], 0), 0, meta)],
-                false,
-            )
-            .await?;
+            // 先将新Table写入Version，使压缩阈值的检测计入该Table
+            self.ver_status()
+                .log_and_apply(
+                    vec![VersionEdit::NewFile((vec![scope.clone()], 0), 0, meta)],
+                    config.ver_log_snapshot_threshold,
+                )
+                .await?;
+            if !config.disable_auto_compaction {
+                // `Compactor::data_loading_with_level`中会检测是否达到压缩阈值，因此此处直接调用Major压缩
+                new_gens.append(
+                    &mut self
+                        .major_compaction(LEVEL_0, scope, vec![], false, None)
+                        .await?,
+                );
+            }
         }
-        Ok(())
+        Ok(new_gens)
     }
 
     /// Major压缩，负责将不同Level之间的数据向下层压缩转移
@@ -120,61 +435,118 @@ impl Compactor {
     /// Level0的Key基本是无序的，容易生成大量的SSTable至Level1
     /// 而Level1-MAX_LEVEL的Key排布有序，故转移至下一层的SSTable数量较小
     /// 因此大量数据压缩的情况下Level 1的SSTable数量会较多
+    ///
+    /// 返回压缩所新建的Table的Gen
+    ///
+    /// 压缩按轮次进行：每轮挑选互不相邻的Level(即`level`与`level + 1`所组成的压缩范围互不相交)并发压缩，
+    /// 所产生的VersionEdit在该轮结束时一同log_and_apply；除被`Config::max_compaction_bytes`截断外，
+    /// 每个Level在单次Major压缩中至多压缩一次
     pub(crate) async fn major_compaction(
         &self,
-        mut level: usize,
+        level: usize,
         scope: Scope,
         mut vec_ver_edit: Vec<VersionEdit>,
-        mut is_skip_sized: bool,
-    ) -> KernelResult<()> {
+        is_skip_sized: bool,
+        mut ctl: Option<&mut CompactionCtl<'_>>,
+    ) -> KernelResult<Vec<i64>> {
         let config = self.config();
-        let mut is_over = false;
+        let mut new_gens = Vec::new();
 
         if level > MAX_LEVEL - 1 {
             return Err(KernelError::LevelOver);
         }
+        let mut is_finished = [false; MAX_LEVEL];
+        // Tips: is_skip_sized选项仅仅对起始Level生效
+        let mut pending = vec![(level, is_skip_sized)];
 
-        while level < MAX_LEVEL && !is_over {
-            let next_level = level + 1;
-
-            // Tips: is_skip_sized选项仅仅允许跳过一次
-            if let Some((
-                index,
-                ((del_gens_l, del_meta_l), (del_gens_ll, del_meta_ll)),
-                vec_sharding,
-            )) = self
-                .data_loading_with_level(level, &scope, mem::replace(&mut is_skip_sized, false))
-                .await?
+        loop {
+            let mut round = mem::take(&mut pending);
             {
-                let start = Instant::now();
-                // 并行创建SSTable
-                let table_futures = vec_sharding.into_iter().map(|(gen, sharding)| {
-                    self.ver_status().loader().create(
-                        gen,
-                        sharding,
-                        next_level,
-                        config.level_table_type[next_level],
-                    )
-                });
-                let vec_table_and_scope: Vec<(Scope, TableMeta)> =
-                    future::try_join_all(table_futures).await?;
-                let (new_scopes, new_metas): (Vec<Scope>, Vec<TableMeta>) =
-                    vec_table_and_scope.into_iter().unzip();
+                let version = self.ver_status().current().await;
+                let mut candidates = is_finished
+                    .iter()
+                    .enumerate()
+                    .take(MAX_LEVEL - 1)
+                    .skip(level)
+                    .filter(|(next, is_finished)| {
+                        !**is_finished && version.is_threshold_exceeded_major(&config, *next)
+                    })
+                    .map(|(next, _)| next)
+                    .collect_vec();
+                // 相邻的Level无法在同一轮中压缩，因此依据压缩优先级决定先压缩哪一个
+                version.sort_levels_by_priority(&mut candidates, config.compaction_priority);
+
+                for next in candidates {
+                    if round.iter().all(|(level, _)| level.abs_diff(next) > 1) {
+                        round.push((next, false));
+                    }
+                }
+            }
+            if round.is_empty() {
+                break;
+            }
+            if let Some(ctl) = &ctl {
+                ctl.check_cancelled()?;
+            }
+            let start = Instant::now();
+            let outputs =
+                future::try_join_all(round.iter().map(|(level, is_skip)| {
+                    self.data_loading_with_level(*level, &scope, *is_skip)
+                }))
+                .await?;
+
+            if let Some(ctl) = &mut ctl {
+                for output in outputs.iter().flatten() {
+                    ctl.report(&output.del_node_l, &output.del_node_ll);
+                }
+                // 中止时新建的Table尚未写入Version，直接删除即可保持Version一致
+                if let Err(err) = ctl.check_cancelled() {
+                    for output in outputs.iter().flatten() {
+                        for scope in &output.new_scopes {
+                            self.ver_status().loader().discard(scope.gen())?;
+                        }
+                    }
+                    return Err(err);
+                }
+            }
+            for ((level, is_skip), output) in round.iter().copied().zip(outputs) {
+                let Some(LevelCompaction {
+                    index,
+                    del_node_l: (del_gens_l, del_meta_l),
+                    del_node_ll: (del_gens_ll, del_meta_ll),
+                    new_scopes,
+                    new_metas,
+                    is_truncated,
+                    compact_point,
+                }) = output
+                else {
+                    is_finished[level] = true;
+                    continue;
+                };
+                let next_level = level + 1;
                 let fusion_meta = TableMeta::fusion(&new_metas);
+                new_gens.extend(new_scopes.iter().map(Scope::gen));
+                self.store_inner.bg_io_stats.record_compaction(
+                    del_meta_l.size_of_disk + del_meta_ll.size_of_disk,
+                    fusion_meta.size_of_disk,
+                    new_scopes.len(),
+                    del_gens_l.len() + del_gens_ll.len(),
+                );
 
                 vec_ver_edit.append(&mut vec![
                     VersionEdit::NewFile((new_scopes, next_level), index, fusion_meta),
                     VersionEdit::DeleteFile((del_gens_l, level), del_meta_l),
                     VersionEdit::DeleteFile((del_gens_ll, next_level), del_meta_ll),
                 ]);
-                info!(
-                    "[LsmStore][Major Compaction][recreate_sst][Level: {}][Time: {:?}]",
-                    level,
-                    start.elapsed()
-                );
-                level += 1;
-            } else {
-                is_over = true;
+                if let Some(key) = compact_point {
+                    vec_ver_edit.push(VersionEdit::CompactPoint(level, key));
+                }
+                // 被`Config::max_compaction_bytes`截断时继续压缩该Level的剩余部分
+                if is_truncated {
+                    pending.push((level, is_skip));
+                } else {
+                    is_finished[level] = true;
+                }
             }
             if !vec_ver_edit.is_empty() {
                 self.ver_status()
@@ -184,24 +556,70 @@ impl Compactor {
                     )
                     .await?;
             }
+            info!(
+                "[LsmStore][Major Compaction][Levels: {:?}][Time: {:?}]",
+                round.iter().map(|(level, _)| level).collect_vec(),
+                start.elapsed()
+            );
         }
+        if !vec_ver_edit.is_empty() {
+            self.ver_status()
+                .log_and_apply(vec_ver_edit, config.ver_log_snapshot_threshold)
+                .await?;
+        }
+        Ok(new_gens)
+    }
+
+    /// 依次将`levels`中每一个Level与`scope`相交的Table向下一级压缩
+    ///
+    /// `scope`为None时压缩该Level的全部Table，中止时返回`KernelError::CompactionCancelled`
+    pub(crate) async fn range_compaction(
+        &self,
+        levels: Range<usize>,
+        scope: Option<Scope>,
+        options: &CompactionOptions,
+    ) -> KernelResult<()> {
+        let mut ctl = CompactionCtl {
+            options,
+            progress: CompactionProgress::default(),
+        };
+
+        for level in levels {
+            ctl.check_cancelled()?;
+
+            let scope = match &scope {
+                Some(scope) => scope.clone(),
+                None => {
+                    match Scope::fusion(&self.ver_status().current().await.level_slice[level]) {
+                        Some(scope) => scope,
+                        None => continue,
+                    }
+                }
+            };
+            let _ = self
+                .major_compaction(level, scope, vec![], true, Some(&mut ctl))
+                .await?;
+        }
+
         Ok(())
     }
 
-    /// 通过Level进行归并数据加载
+    /// 通过Level进行归并数据加载，并将归并结果写入下一级的新Table中
+    ///
+    /// 返回尚未写入Version的压缩结果，未达到压缩条件时返回None
     async fn data_loading_with_level(
         &self,
         level: usize,
         target: &Scope,
         is_skip_sized: bool,
-    ) -> KernelResult<Option<(usize, DelNodeTuple, MergeShardingVec)>> {
+    ) -> KernelResult<Option<LevelCompaction>> {
         let version = self.ver_status().current().await;
         let config = self.config();
         let next_level = level + 1;
 
         // 如果该Level的SSTables数量尚未越出阈值则提取返回空
         if level > MAX_LEVEL - 2
-            || !(is_skip_sized || version.is_threshold_exceeded_major(config, level))
+            || !(is_skip_sized || version.is_threshold_exceeded_major(&config, level))
         {
             return Ok(None);
         }
@@ -210,11 +628,38 @@ impl Compactor {
         // 类似罗马数字
         let start = Instant::now();
 
-        // 获取此级中有重复键值范围的SSTable
-        let (tables_l, scopes_l, _) = version.tables_by_scopes(level, target);
+        let (tables_l, scopes_l) = match config.compaction_strategy {
+            // Level 0之下的Level直接压缩磁盘占用相近的一组Table
+            CompactionStrategy::Tiered { min_merge_width } if level > LEVEL_0 && !is_skip_sized => {
+                version
+                    .pick_tiered_run(level, min_merge_width)
+                    .unwrap_or_default()
+            }
+            CompactionStrategy::Tiered { .. } => {
+                let (tables_l, scopes_l, _) = version.tables_by_scopes(level, target);
+                (tables_l, scopes_l)
+            }
+            CompactionStrategy::Leveled => {
+                // Level 0之下的Level依据`Config::compaction_priority`挑选压缩的Table
+                let picked_scope = (level > LEVEL_0 && !is_skip_sized)
+                    .then(|| version.pick_compaction_scope(level, config.compaction_priority))
+                    .flatten();
+                // 获取此级中有重复键值范围的SSTable
+                let (tables_l, scopes_l, _) =
+                    version.tables_by_scopes(level, picked_scope.as_ref().unwrap_or(target));
+                (tables_l, scopes_l)
+            }
+        };
         if scopes_l.is_empty() {
             return Ok(None);
         }
+        // 输入数据量超出`Config::max_compaction_bytes`时仅压缩其中一部分，其余留待下一轮压缩
+        let (tables_l, scopes_l, is_truncated) = version.truncate_compaction_input(
+            level,
+            tables_l,
+            scopes_l,
+            config.max_compaction_bytes,
+        );
 
         // 因此使用tables_l向下检测冲突时获取的集合应当含有tables_ll的元素
         let fusion_scope_l = Scope::fusion(&scopes_l).unwrap_or(target.clone());
@@ -222,82 +667,141 @@ impl Compactor {
         let (tables_ll, _, index) = version.tables_by_scopes(next_level, &fusion_scope_l);
 
         // 收集需要清除的SSTable
-        let del_gen_l = collect_gen(&tables_l)?;
-        let del_gen_ll = collect_gen(&tables_ll)?;
+        let del_node_l = collect_gen(&tables_l)?;
+        let del_node_ll = collect_gen(&tables_ll)?;
+        // 记录此次压缩的最大Key，使`CompactionPriority::RoundRobin`的下一次压缩由其后开始
+        let compact_point = (level > LEVEL_0
+            && !is_skip_sized
+            && config.compaction_strategy == CompactionStrategy::Leveled
+            && config.compaction_priority == CompactionPriority::RoundRobin)
+            .then(|| fusion_scope_l.end.clone());
 
         // 数据合并并切片
-        let vec_merge_sharding =
-            Self::data_merge_and_sharding(tables_l, tables_ll, config.sst_file_size).await?;
+        // 下一级之下已没有数据时，删除标记已无可遮蔽的旧数据
+        let is_bottommost = version.level_slice[next_level + 1..]
+            .iter()
+            .all(Vec::is_empty);
+        // 生成位于最深的有数据的Level的Table时使用`Config::bottommost_compression`
+        let compress_type = config
+            .bottommost_compression
+            .clone()
+            .filter(|_| is_bottommost);
+        let merging_iter = Self::merging_iter(
+            level,
+            tables_l,
+            tables_ll,
+            config.compaction_readahead_size,
+            is_bottommost,
+        )?;
+        let vec_table_and_scope = self
+            .data_merge_and_sharding(merging_iter, next_level, compress_type)
+            .await?;
         info!(
             "[LsmStore][Major Compaction][data_loading_with_level][Time: {:?}]",
             start.elapsed()
         );
 
-        Ok(Some((index, (del_gen_l, del_gen_ll), vec_merge_sharding)))
-    }
+        let (new_scopes, new_metas) = vec_table_and_scope.into_iter().unzip();
 
-    /// 以SSTables的数据归并再排序后切片，获取以KeyValue的Key值由小到大的切片排序
-    /// 1. 并行获取Level l(当前等级)的待合并SSTables_l的全量数据
-    /// 2. 基于SSTables_l获取唯一KeySet用于迭代过滤
-    /// 3. 并行对Level ll的SSTables_ll通过KeySet进行迭代同时过滤数据
-    /// 4. 组合SSTables_l和SSTables_ll的数据合并并进行唯一，排序处理
-    #[allow(clippy::mutable_key_type)]
-    async fn data_merge_and_sharding(
-        tables_l: Vec<&dyn Table>,
-        tables_ll: Vec<&dyn Table>,
-        file_size: usize,
-    ) -> KernelResult<MergeShardingVec> {
-        // SSTables的Gen会基于时间有序生成,所有以此作为SSTables的排序依据
-        let map_futures_l = tables_l
-            .iter()
-            .sorted_unstable_by_key(|table| table.gen())
-            .map(|table| async { Self::table_load_data(table, |_| true) });
+        Ok(Some(LevelCompaction {
+            index,
+            del_node_l,
+            del_node_ll,
+            new_scopes,
+            new_metas,
+            is_truncated,
+            compact_point,
+        }))
+    }
 
-        let sharding_l = future::try_join_all(map_futures_l).await?;
+    /// 以Level l与Level ll的待合并Table构建由新至旧排列的归并迭代器，产出以Key由小到大排序且唯一的数据
+    ///
+    /// - Level 0的Table之间可能存在重叠，因此每个Table单独作为一路输入，并以Gen倒序排列使较新的数据优先
+    /// - Level 0之下的Level中Table之间有序且不重叠，因此拼接为一路输入，依次读取其中的Table
+    /// - `is_drop_tombstone`为true时在去重后丢弃删除标记
+    fn merging_iter<'a>(
+        level: usize,
+        tables_l: Vec<&'a dyn Table>,
+        tables_ll: Vec<&'a dyn Table>,
+        readahead_size: usize,
+        is_drop_tombstone: bool,
+    ) -> KernelResult<MergingIter<'a>> {
+        let mut vec_iter: Vec<Box<dyn Iter<'a, Item = KeyValue> + 'a + Send + Sync>> =
+            Vec::with_capacity(tables_l.len() + 1);
 
-        // 获取Level l的唯一KeySet用于Level ll的迭代过滤数据
-        let filter_set_l: HashSet<&Bytes> = sharding_l
-            .iter()
-            .flatten()
-            .map(|key_value| &key_value.0)
-            .collect();
-
-        // 通过KeySet过滤出Level l中需要补充的数据
-        // 并行: 因为即使l为0时，此时的ll(Level 1)仍然保证SSTable数据之间排列有序且不冲突，因此并行迭代不会导致数据冲突
-        // 过滤: 基于l进行数据过滤避免冗余的数据迭代导致占用大量内存占用
-        let sharding_ll = future::try_join_all(tables_ll.iter().map(|table| async {
-            Self::table_load_data(table, |key| !filter_set_l.contains(key))
-        }))
-        .await?;
+        if level == LEVEL_0 {
+            // SSTables的Gen会基于时间有序生成,所有以此作为SSTables的排序依据
+            for table in tables_l
+                .into_iter()
+                .sorted_unstable_by_key(|table| Reverse(table.gen()))
+            {
+                vec_iter.push(Box::new(TableConcatIter::new(vec![table], readahead_size)));
+            }
+        } else {
+            vec_iter.push(Box::new(TableConcatIter::new(tables_l, readahead_size)));
+        }
+        vec_iter.push(Box::new(TableConcatIter::new(tables_ll, readahead_size)));
 
-        // 使用sharding_ll来链接sharding_l以保持数据倒序的顺序是由新->旧
-        let vec_cmd_data = sharding_ll
-            .into_iter()
-            .chain(sharding_l)
-            .flatten()
-            .rev()
-            .unique_by(|(key, _)| key.clone())
-            .sorted_unstable_by_key(|(key, _)| key.clone())
-            .collect();
-        Ok(data_sharding(vec_cmd_data, file_size))
+        Ok(MergingIter::new(vec_iter)?.skip_tombstones(is_drop_tombstone))
     }
 
-    fn table_load_data<F>(table: &&dyn Table, fn_is_filter: F) -> KernelResult<Vec<KeyValue>>
-    where
-        F: Fn(&Bytes) -> bool,
-    {
-        let mut iter = table.iter()?;
-        let mut vec_cmd = Vec::with_capacity(table.len());
-        while let Some(item) = iter.try_next()? {
-            if fn_is_filter(&item.0) {
-                vec_cmd.push(item)
+    /// 流式地消费归并迭代器，每当数据填满一个分片时即创建对应的Table
+    ///
+    /// 分片的数据大小达到`Config::sst_file_size`或条数达到`Config::max_entries_per_sst`时即视为填满
+    ///
+    /// 峰值内存仅为一个分片与各路输入当前载入的Table，而非压缩输入的全量数据
+    ///
+    /// 新Table仍在全部创建完成后于同一个VersionEdit中一并生效：
+    /// 若在归并中途提交，Level N+1中已写入的新Table会与尚未删除的旧Table发生重叠，
+    /// 而单次压缩的输入规模已由`Config::max_compaction_bytes`限制
+    async fn data_merge_and_sharding(
+        &self,
+        mut merging_iter: MergingIter<'_>,
+        next_level: usize,
+        compress_type: Option<CompressType>,
+    ) -> KernelResult<Vec<(Scope, TableMeta)>> {
+        let config = self.config();
+        let loader = self.ver_status().loader();
+        let mut vec_table_and_scope = Vec::new();
+        let mut sharding = Vec::new();
+        let mut sharding_len = 0;
+        // `Config::compaction_bytes_per_sec`可在运行时修改
+        self.rate_limiter
+            .set_bytes_per_sec(config.compaction_bytes_per_sec);
+
+        loop {
+            let item = merging_iter.try_next()?;
+            let is_end = item.is_none();
+
+            if let Some(key_value) = item {
+                sharding_len += key_value_bytes_len(&key_value);
+                sharding.push(key_value);
+            }
+            let is_full = sharding_len >= config.sst_file_size
+                || (config.max_entries_per_sst > 0 && sharding.len() >= config.max_entries_per_sst);
+            if (is_end && !sharding.is_empty()) || is_full {
+                sharding_len = 0;
+                vec_table_and_scope.push(
+                    loader
+                        .create_with_compress_type(
+                            Gen::create(),
+                            mem::take(&mut sharding),
+                            next_level,
+                            config.level_table_type[next_level],
+                            compress_type.clone(),
+                            Some(&self.rate_limiter),
+                        )
+                        .await?,
+                );
+            }
+            if is_end {
+                return Ok(vec_table_and_scope);
             }
         }
-        Ok(vec_cmd)
     }
 
-    pub(crate) fn config(&self) -> &Config {
-        &self.store_inner.config
+    pub(crate) fn config(&self) -> Arc<Config> {
+        self.store_inner.config()
     }
 
     pub(crate) fn mem_table(&self) -> &MemTable {
@@ -312,23 +816,29 @@ impl Compactor {
 #[cfg(test)]
 mod tests {
     use crate::kernel::io::{FileExtension, IoFactory, IoType};
-    use crate::kernel::lsm::compactor::{Compactor, LEVEL_0};
-    use crate::kernel::lsm::storage::{Config, KipStorage, StoreInner};
+    use crate::kernel::lsm::compactor::{
+        CompactionOptions, CompactionPriority, CompactionProgress, CompactionStrategy, Compactor,
+        LEVEL_0,
+    };
+    use crate::kernel::lsm::iterator::Iter;
+    use crate::kernel::lsm::storage::{Config, Gen, KipStorage, StoreInner};
     use crate::kernel::lsm::table::meta::TableMeta;
     use crate::kernel::lsm::table::scope::Scope;
     use crate::kernel::lsm::table::ss_table::SSTable;
     use crate::kernel::lsm::table::TableType;
     use crate::kernel::lsm::trigger::TriggerType;
     use crate::kernel::lsm::version::edit::VersionEdit;
-    use crate::kernel::lsm::version::DEFAULT_SS_TABLE_PATH;
+    use crate::kernel::lsm::version::{Version, DEFAULT_SS_TABLE_PATH};
     use crate::kernel::utils::lru_cache::ShardingLruCache;
     use crate::kernel::{KernelResult, Storage};
+    use crate::KernelError;
     use bytes::Bytes;
     use itertools::Itertools;
+    use parking_lot::Mutex;
     use std::collections::hash_map::RandomState;
     use std::sync::atomic::Ordering::Relaxed;
     use std::sync::Arc;
-    use std::time::Instant;
+    use std::time::{Duration, Instant};
     use tempfile::TempDir;
 
     #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
@@ -414,7 +924,7 @@ mod tests {
     async fn test_data_merge() -> KernelResult<()> {
         let temp_dir = TempDir::new().expect("unable to create temporary working directory");
 
-        let config = Config::new(temp_dir.into_path());
+        let config = Config::new(temp_dir.keep());
         let sst_factory = IoFactory::new(
             config.dir_path.join(DEFAULT_SS_TABLE_PATH),
             FileExtension::SSTable,
@@ -479,23 +989,31 @@ mod tests {
         )
         .await?;
 
-        let (_, vec_data) = &Compactor::data_merge_and_sharding(
-            vec![&ss_table_1, &ss_table_2],
-            vec![&ss_table_3, &ss_table_4],
-            config.sst_file_size,
-        )
-        .await?[0];
+        // 分别以无预读与有预读的方式进行合并
+        for readahead_size in [0, 4096] {
+            let mut merging_iter = Compactor::merging_iter(
+                LEVEL_0,
+                vec![&ss_table_1, &ss_table_2],
+                vec![&ss_table_3, &ss_table_4],
+                readahead_size,
+                false,
+            )?;
+            let mut vec_data = Vec::new();
+            while let Some(key_value) = merging_iter.try_next()? {
+                vec_data.push(key_value);
+            }
 
-        assert_eq!(
-            vec_data,
-            &vec![
-                (Bytes::from_static(b"1"), Some(Bytes::from_static(b"1"))),
-                (Bytes::from_static(b"2"), Some(Bytes::from_static(b"2"))),
-                (Bytes::from_static(b"3"), Some(Bytes::from_static(b"3"))),
-                (Bytes::from_static(b"4"), Some(Bytes::from_static(b"4"))),
-                (Bytes::from_static(b"5"), Some(Bytes::from_static(b"5")))
-            ]
-        );
+            assert_eq!(
+                vec_data,
+                vec![
+                    (Bytes::from_static(b"1"), Some(Bytes::from_static(b"1"))),
+                    (Bytes::from_static(b"2"), Some(Bytes::from_static(b"2"))),
+                    (Bytes::from_static(b"3"), Some(Bytes::from_static(b"3"))),
+                    (Bytes::from_static(b"4"), Some(Bytes::from_static(b"4"))),
+                    (Bytes::from_static(b"5"), Some(Bytes::from_static(b"5")))
+                ]
+            );
+        }
         Ok(())
     }
 
@@ -509,7 +1027,7 @@ mod tests {
     #[test]
     fn test_seek_compaction() -> KernelResult<()> {
         let temp_dir = TempDir::new().expect("unable to create temporary working directory");
-        let config = Config::new(temp_dir.into_path());
+        let config = Config::new(temp_dir.keep());
 
         tokio_test::block_on(async move {
             let inner = StoreInner::new(config).await?;
@@ -521,8 +1039,8 @@ mod tests {
                 .create(
                     1,
                     vec![
-                        (Bytes::from_static(b"1"), None),
-                        (Bytes::from_static(b"2"), None),
+                        (Bytes::from_static(b"1"), Some(Bytes::from_static(b"KipDB"))),
+                        (Bytes::from_static(b"2"), Some(Bytes::from_static(b"KipDB"))),
                     ],
                     1,
                     TableType::BTree,
@@ -532,9 +1050,9 @@ mod tests {
                 .create(
                     2,
                     vec![
-                        (Bytes::from_static(b"3"), None),
-                        (Bytes::from_static(b"5"), None),
-                        (Bytes::from_static(b"6"), None),
+                        (Bytes::from_static(b"3"), Some(Bytes::from_static(b"KipDB"))),
+                        (Bytes::from_static(b"5"), Some(Bytes::from_static(b"KipDB"))),
+                        (Bytes::from_static(b"6"), Some(Bytes::from_static(b"KipDB"))),
                     ],
                     1,
                     TableType::BTree,
@@ -544,8 +1062,8 @@ mod tests {
                 .create(
                     3,
                     vec![
-                        (Bytes::from_static(b"1"), None),
-                        (Bytes::from_static(b"2"), None),
+                        (Bytes::from_static(b"1"), Some(Bytes::from_static(b"KipDB"))),
+                        (Bytes::from_static(b"2"), Some(Bytes::from_static(b"KipDB"))),
                     ],
                     2,
                     TableType::BTree,
@@ -555,8 +1073,8 @@ mod tests {
                 .create(
                     4,
                     vec![
-                        (Bytes::from_static(b"3"), None),
-                        (Bytes::from_static(b"4"), None),
+                        (Bytes::from_static(b"3"), Some(Bytes::from_static(b"KipDB"))),
+                        (Bytes::from_static(b"4"), Some(Bytes::from_static(b"KipDB"))),
                     ],
                     2,
                     TableType::BTree,
@@ -566,8 +1084,8 @@ mod tests {
                 .create(
                     5,
                     vec![
-                        (Bytes::from_static(b"5"), None),
-                        (Bytes::from_static(b"6"), None),
+                        (Bytes::from_static(b"5"), Some(Bytes::from_static(b"KipDB"))),
+                        (Bytes::from_static(b"6"), Some(Bytes::from_static(b"KipDB"))),
                     ],
                     2,
                     TableType::BTree,
@@ -597,8 +1115,8 @@ mod tests {
             loop {
                 failure_count += 1;
                 if let (_, Some((scope, level))) = version_1.query(b"4")? {
-                    compactor
-                        .major_compaction(level, scope, vec![], true)
+                    let _ = compactor
+                        .major_compaction(level, scope, vec![], true, None)
                         .await?;
                     break;
                 }
@@ -632,4 +1150,713 @@ mod tests {
             Ok(())
         })
     }
+
+    #[tokio::test]
+    async fn test_paranoid_memtable_order_check() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path()).paranoid_memtable_order_check(true);
+        let compactor = Compactor::new(Arc::new(StoreInner::new(config).await?));
+
+        // 模拟乱序的MemTable数据
+        let values = vec![
+            (Bytes::from_static(b"1"), None),
+            (Bytes::from_static(b"3"), None),
+            (Bytes::from_static(b"2"), None),
+        ];
+        match compactor.minor_compaction(1, values).await {
+            Err(KernelError::UnsortedInput { prev, next }) => {
+                assert_eq!(prev, Bytes::from_static(b"3"));
+                assert_eq!(next, Bytes::from_static(b"2"));
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+        assert!(!compactor.ver_status().loader().is_table_file_exist(1)?);
+        assert_eq!(compactor.ver_status().current().await.level_len(LEVEL_0), 0);
+
+        Ok(())
+    }
+
+    /// 构造Level 1中两个Table仅有一个与Level 2重叠的布局，返回压缩Level 1所读取的数据量
+    async fn compaction_bytes_read_with_priority(
+        priority: CompactionPriority,
+    ) -> KernelResult<u64> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        // Level 1与Level 2的阈值分别为2与4个Table
+        let config = Config::new(temp_dir.path())
+            .major_threshold_with_sst_size(1)
+            .level_sst_magnification(2)
+            .report_bg_io_stats(true)
+            .compaction_priority(priority);
+        let store_inner = Arc::new(StoreInner::new(config.clone()).await?);
+        let compactor = Compactor::new(Arc::clone(&store_inner));
+        let loader = compactor.ver_status().loader();
+        let key_values = |prefix: u8, len: u32, value_len: usize| {
+            (0..len)
+                .map(|i| {
+                    let key = Bytes::from([&[prefix][..], &i.to_be_bytes()].concat());
+                    (key, Some(Bytes::from(vec![prefix; value_len])))
+                })
+                .collect_vec()
+        };
+
+        let (scope_a, meta_a) = loader
+            .create(
+                Gen::create(),
+                key_values(b'a', 10, 8),
+                1,
+                TableType::SortedString,
+            )
+            .await?;
+        let (scope_b, meta_b) = loader
+            .create(
+                Gen::create(),
+                key_values(b'b', 10, 8),
+                1,
+                TableType::SortedString,
+            )
+            .await?;
+        // 仅与Level 1中的scope_a重叠的大Table
+        let (scope_c, meta_c) = loader
+            .create(
+                Gen::create(),
+                key_values(b'a', 1000, 256),
+                2,
+                TableType::SortedString,
+            )
+            .await?;
+        compactor
+            .ver_status()
+            .log_and_apply(
+                vec![
+                    VersionEdit::NewFile(
+                        (vec![scope_a.clone(), scope_b], 1),
+                        0,
+                        TableMeta::fusion(&[meta_a, meta_b]),
+                    ),
+                    VersionEdit::NewFile((vec![scope_c], 2), 0, meta_c),
+                ],
+                config.ver_log_snapshot_threshold,
+            )
+            .await?;
+
+        let _ = compactor
+            .major_compaction(1, scope_a, vec![], false, None)
+            .await?;
+        assert_eq!(compactor.ver_status().current().await.level_len(1), 1);
+
+        Ok(store_inner.bg_io_stats.compaction_bytes_read())
+    }
+
+    #[tokio::test]
+    async fn test_compaction_priority() -> KernelResult<()> {
+        let cascade_bytes =
+            compaction_bytes_read_with_priority(CompactionPriority::Cascade).await?;
+        let min_overlapping_bytes =
+            compaction_bytes_read_with_priority(CompactionPriority::MinOverlappingRatio).await?;
+
+        // Cascade沿用触发压缩的范围而需重写Level 2中重叠的大Table，MinOverlappingRatio则选择无重叠的Table
+        assert!(
+            min_overlapping_bytes < cascade_bytes,
+            "{min_overlapping_bytes} >= {cascade_bytes}"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_compaction_priority_tombstone_density() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path());
+        let store_inner = Arc::new(StoreInner::new(config.clone()).await?);
+        let compactor = Compactor::new(Arc::clone(&store_inner));
+        let loader = compactor.ver_status().loader();
+        let key_values = |prefix: u8, len: u32, is_tombstone: &dyn Fn(u32) -> bool| {
+            (0..len)
+                .map(|i| {
+                    let key = Bytes::from([&[prefix][..], &i.to_be_bytes()].concat());
+                    (
+                        key,
+                        (!is_tombstone(i)).then(|| Bytes::from(vec![prefix; 64])),
+                    )
+                })
+                .collect_vec()
+        };
+
+        // Level 1中'a'的一半为删除标记且与Level 2重叠，'b'不含删除标记且与Level 2无重叠
+        let (scope_ll, meta_ll) = loader
+            .create(
+                Gen::create(),
+                key_values(b'a', 100, &|_| false),
+                2,
+                TableType::SortedString,
+            )
+            .await?;
+        let (scope_a, meta_a) = loader
+            .create(
+                Gen::create(),
+                key_values(b'a', 100, &|i| i % 2 == 0),
+                1,
+                TableType::SortedString,
+            )
+            .await?;
+        let (scope_b, meta_b) = loader
+            .create(
+                Gen::create(),
+                key_values(b'b', 100, &|_| false),
+                1,
+                TableType::SortedString,
+            )
+            .await?;
+        let gen_a = scope_a.gen();
+        let gen_b = scope_b.gen();
+        compactor
+            .ver_status()
+            .log_and_apply(
+                vec![
+                    VersionEdit::NewFile((vec![scope_ll], 2), 0, meta_ll),
+                    VersionEdit::NewFile((vec![scope_a], 1), 0, meta_a),
+                    VersionEdit::NewFile((vec![scope_b], 1), 1, meta_b),
+                ],
+                config.ver_log_snapshot_threshold,
+            )
+            .await?;
+
+        let version = compactor.ver_status().current().await;
+        assert_eq!(version.table(1, 0).unwrap().tombstone_count(), 50);
+        assert_eq!(version.table(1, 1).unwrap().tombstone_count(), 0);
+        assert_eq!(
+            version
+                .pick_compaction_scope(1, CompactionPriority::MinOverlappingRatio)
+                .map(|scope| scope.gen()),
+            Some(gen_b)
+        );
+        assert_eq!(
+            version
+                .pick_compaction_scope(1, CompactionPriority::TombstoneDensity)
+                .map(|scope| scope.gen()),
+            Some(gen_a)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_compaction_priority_level_order() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path());
+        let store_inner = Arc::new(StoreInner::new(config.clone()).await?);
+        let compactor = Compactor::new(Arc::clone(&store_inner));
+        let loader = compactor.ver_status().loader();
+        let key_values = |prefix: u8, len: u32, value_len: usize| {
+            (0..len)
+                .map(|i| {
+                    let key = Bytes::from([&[prefix][..], &i.to_be_bytes()].concat());
+                    (key, Some(Bytes::from(vec![prefix; value_len])))
+                })
+                .collect_vec()
+        };
+
+        // Level 1的Table与Level 2中的大Table重叠，Level 2中的另一个Table与Level 3无重叠
+        let mut vec_ver_edit = Vec::new();
+        for (prefix, len, value_len, level, index) in [
+            (b'a', 10, 8, 1, 0),
+            (b'a', 1000, 256, 2, 0),
+            (b'b', 100, 8, 2, 1),
+            (b'c', 10, 8, 3, 0),
+        ] {
+            let (scope, meta) = loader
+                .create(
+                    Gen::create(),
+                    key_values(prefix, len, value_len),
+                    level,
+                    TableType::SortedString,
+                )
+                .await?;
+            vec_ver_edit.push(VersionEdit::NewFile((vec![scope], level), index, meta));
+        }
+        compactor
+            .ver_status()
+            .log_and_apply(vec_ver_edit, config.ver_log_snapshot_threshold)
+            .await?;
+        let version = compactor.ver_status().current().await;
+        let sorted_levels = |priority| {
+            let mut levels = vec![1, 2];
+            version.sort_levels_by_priority(&mut levels, priority);
+            levels
+        };
+
+        assert_eq!(sorted_levels(CompactionPriority::Cascade), vec![1, 2]);
+        assert_eq!(sorted_levels(CompactionPriority::RoundRobin), vec![1, 2]);
+        // Level 2的Table与下一级无重叠
+        assert_eq!(
+            sorted_levels(CompactionPriority::MinOverlappingRatio),
+            vec![2, 1]
+        );
+        // Level 2挑选的大Table大于Level 1中的Table
+        assert_eq!(sorted_levels(CompactionPriority::LargestFirst), vec![2, 1]);
+        // Level 1中的Table最先创建
+        assert_eq!(
+            sorted_levels(CompactionPriority::OldestSmallestSeqFirst),
+            vec![1, 2]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_compaction_priority_round_robin() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        // Level 1的阈值为2个Table
+        let config = Config::new(temp_dir.path())
+            .major_threshold_with_sst_size(1)
+            .level_sst_magnification(2)
+            .compaction_priority(CompactionPriority::RoundRobin);
+        let key_values = |prefix: u8| {
+            (0..10_u32)
+                .map(|i| {
+                    let key = Bytes::from([&[prefix][..], &i.to_be_bytes()].concat());
+                    (key, Some(Bytes::from(vec![prefix; 8])))
+                })
+                .collect_vec()
+        };
+        let level_1_prefixes = |version: &Version| {
+            version.level_slice[1]
+                .iter()
+                .map(|scope| scope.start[0])
+                .collect_vec()
+        };
+
+        let store_inner = Arc::new(StoreInner::new(config.clone()).await?);
+        let compactor = Compactor::new(Arc::clone(&store_inner));
+        let mut vec_scope = Vec::new();
+        let mut vec_meta = Vec::new();
+        for prefix in [b'a', b'b', b'c'] {
+            let (scope, meta) = compactor
+                .ver_status()
+                .loader()
+                .create(
+                    Gen::create(),
+                    key_values(prefix),
+                    1,
+                    TableType::SortedString,
+                )
+                .await?;
+            vec_scope.push(scope);
+            vec_meta.push(meta);
+        }
+        let trigger_scope = vec_scope[0].clone();
+        compactor
+            .ver_status()
+            .log_and_apply(
+                vec![VersionEdit::NewFile(
+                    (vec_scope, 1),
+                    0,
+                    TableMeta::fusion(&vec_meta),
+                )],
+                config.ver_log_snapshot_threshold,
+            )
+            .await?;
+
+        let _ = compactor
+            .major_compaction(1, trigger_scope.clone(), vec![], false, None)
+            .await?;
+        assert_eq!(
+            level_1_prefixes(&*compactor.ver_status().current().await),
+            vec![b'b', b'c']
+        );
+        drop(compactor);
+        drop(store_inner);
+
+        // 重启后由持久化的压缩点继续，而非再次挑选Key最小的Table
+        let store_inner = Arc::new(StoreInner::new(config).await?);
+        let compactor = Compactor::new(Arc::clone(&store_inner));
+        assert!(compactor.ver_status().current().await.compact_points[1].is_some());
+        let _ = compactor
+            .major_compaction(1, trigger_scope, vec![], false, None)
+            .await?;
+        let version = compactor.ver_status().current().await;
+        assert_eq!(level_1_prefixes(&version), vec![b'c']);
+        for prefix in [b'a', b'b', b'c'] {
+            for (key, value) in key_values(prefix) {
+                assert_eq!(version.query(&key)?.0, Some((key, value)));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_max_compaction_bytes() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path());
+        let store_inner = Arc::new(StoreInner::new(config.clone()).await?);
+        let compactor = Compactor::new(Arc::clone(&store_inner));
+        let loader = compactor.ver_status().loader();
+        let key_values = |prefix: u8, len: u32, value: u8| {
+            (0..len)
+                .map(|i| {
+                    let key = Bytes::from([&[prefix][..], &i.to_be_bytes()].concat());
+                    (key, Some(Bytes::from(vec![value; 64])))
+                })
+                .collect_vec()
+        };
+
+        // Level 1中每个小Table都与Level 2中的一个大Table重叠
+        let mut vec_edit = Vec::new();
+        let mut max_pair_bytes = 0;
+        for (index, prefix) in [b'a', b'b', b'c', b'd'].into_iter().enumerate() {
+            let (scope_ll, meta_ll) = loader
+                .create(
+                    Gen::create(),
+                    key_values(prefix, 200, 0),
+                    2,
+                    TableType::SortedString,
+                )
+                .await?;
+            let (scope_l, meta_l) = loader
+                .create(
+                    Gen::create(),
+                    key_values(prefix, 10, 1),
+                    1,
+                    TableType::SortedString,
+                )
+                .await?;
+            max_pair_bytes = max_pair_bytes.max(meta_l.size_of_disk + meta_ll.size_of_disk);
+            vec_edit.push(VersionEdit::NewFile((vec![scope_ll], 2), index, meta_ll));
+            vec_edit.push(VersionEdit::NewFile((vec![scope_l], 1), index, meta_l));
+        }
+        compactor
+            .ver_status()
+            .log_and_apply(vec_edit, config.ver_log_snapshot_threshold)
+            .await?;
+
+        // 上限仅能容纳两组重叠的Table，因此压缩会被拆分
+        let max_compaction_bytes = max_pair_bytes * 2;
+        store_inner.update_config(|config| config.max_compaction_bytes(max_compaction_bytes))?;
+        let vec_progress = Arc::new(Mutex::new(vec![CompactionProgress::default()]));
+        let options = CompactionOptions::default().on_progress({
+            let vec_progress = Arc::clone(&vec_progress);
+            move |progress| vec_progress.lock().push(progress)
+        });
+        compactor.range_compaction(1..2, None, &options).await?;
+
+        {
+            let vec_progress = vec_progress.lock();
+            assert!(vec_progress.len() > 2, "{vec_progress:?}");
+            for (last, progress) in vec_progress.iter().tuple_windows() {
+                assert!(progress.bytes_processed - last.bytes_processed <= max_compaction_bytes);
+            }
+            assert_eq!(vec_progress.last().unwrap().tables_processed, 8);
+        }
+
+        let version = compactor.ver_status().current().await;
+        assert_eq!(version.level_len(1), 0);
+        for prefix in [b'a', b'b', b'c', b'd'] {
+            for (i, (key, _)) in key_values(prefix, 200, 0).into_iter().enumerate() {
+                let value = if i < 10 { 1 } else { 0 };
+                assert_eq!(
+                    version.query(&key)?.0,
+                    Some((key, Some(Bytes::from(vec![value; 64]))))
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_max_entries_per_sst() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path()).max_entries_per_sst(100);
+        let store_inner = Arc::new(StoreInner::new(config.clone()).await?);
+        let compactor = Compactor::new(Arc::clone(&store_inner));
+        // Key与Value极小，远不足以达到`Config::sst_file_size`
+        let key_values = (0..1050_u32)
+            .map(|i| {
+                (
+                    Bytes::from(i.to_be_bytes().to_vec()),
+                    Some(Bytes::from_static(b"v")),
+                )
+            })
+            .collect_vec();
+        let (scope, meta) = compactor
+            .ver_status()
+            .loader()
+            .create(
+                Gen::create(),
+                key_values.clone(),
+                1,
+                TableType::SortedString,
+            )
+            .await?;
+        compactor
+            .ver_status()
+            .log_and_apply(
+                vec![VersionEdit::NewFile((vec![scope], 1), 0, meta)],
+                config.ver_log_snapshot_threshold,
+            )
+            .await?;
+
+        compactor
+            .range_compaction(1..2, None, &CompactionOptions::default())
+            .await?;
+
+        // 按数据条数切分出的SSTable，最后一个为剩余的数据
+        let version = compactor.ver_status().current().await;
+        assert_eq!(version.level_len(2), 11);
+        for offset in 0..10 {
+            assert_eq!(version.table(2, offset).unwrap().len(), 100);
+        }
+        assert_eq!(version.table(2, 10).unwrap().len(), 50);
+        for (key, value) in key_values {
+            assert_eq!(version.query(&key)?.0, Some((key, value)));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_compaction_bytes_per_sec() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path()).compaction_bytes_per_sec(1 << 30);
+        let store_inner = Arc::new(StoreInner::new(config.clone()).await?);
+        let compactor = Compactor::new(Arc::clone(&store_inner));
+        // 将一个以`prefix`为前缀的Table写入Level 1后压缩至Level 2，返回压缩的耗时
+        let compaction = |prefix: u8| {
+            let compactor = &compactor;
+            let key_values = (0..500_u32)
+                .map(|i| {
+                    let key = Bytes::from([&[prefix][..], &i.to_be_bytes()].concat());
+                    (key, Some(Bytes::from(vec![prefix; 64])))
+                })
+                .collect_vec();
+
+            async move {
+                let (scope, meta) = compactor
+                    .ver_status()
+                    .loader()
+                    .create(Gen::create(), key_values, 1, TableType::SortedString)
+                    .await?;
+                compactor
+                    .ver_status()
+                    .log_and_apply(
+                        vec![VersionEdit::NewFile((vec![scope], 1), 0, meta)],
+                        config.ver_log_snapshot_threshold,
+                    )
+                    .await?;
+
+                let start = Instant::now();
+                compactor
+                    .range_compaction(1..2, None, &CompactionOptions::default())
+                    .await?;
+
+                KernelResult::Ok(start.elapsed())
+            }
+        };
+
+        let _ = compaction(b'a').await?;
+        let bytes_written = store_inner.bg_io_stats.compaction_bytes_written();
+        assert!(bytes_written > 0);
+
+        // 运行时降低限速，使下一次压缩写入同样大小的Table时需等待约半秒
+        store_inner
+            .update_config(|config| config.compaction_bytes_per_sec(bytes_written * 2 / 3))?;
+        let elapsed = compaction(b'b').await?;
+        assert_eq!(
+            store_inner.bg_io_stats.compaction_bytes_written(),
+            bytes_written * 2
+        );
+        assert_eq!(
+            compactor.rate_limiter.bytes_per_sec(),
+            bytes_written * 2 / 3
+        );
+        assert!(elapsed >= Duration::from_millis(400), "{elapsed:?}");
+
+        let version = compactor.ver_status().current().await;
+        assert_eq!(version.level_len(1), 0);
+        assert_eq!(version.level_len(2), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tiered_compaction() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path())
+            .compaction_strategy(CompactionStrategy::Tiered { min_merge_width: 3 });
+        let store_inner = Arc::new(StoreInner::new(config.clone()).await?);
+        let compactor = Compactor::new(Arc::clone(&store_inner));
+        let loader = compactor.ver_status().loader();
+        let key_values = |prefix: u8, len: u32| {
+            (0..len)
+                .map(|i| {
+                    let key = Bytes::from([&[prefix][..], &i.to_be_bytes()].concat());
+                    (key, Some(Bytes::from(vec![prefix; 64])))
+                })
+                .collect_vec()
+        };
+
+        // Level 1中有三个大小相近的小Table与一个大Table
+        let mut vec_edit = Vec::new();
+        let mut large_gen = 0;
+        for (index, (prefix, len)) in [(b'a', 10), (b'b', 10), (b'c', 10), (b'd', 1000)]
+            .into_iter()
+            .enumerate()
+        {
+            let (scope, meta) = loader
+                .create(
+                    Gen::create(),
+                    key_values(prefix, len),
+                    1,
+                    TableType::SortedString,
+                )
+                .await?;
+            if prefix == b'd' {
+                large_gen = scope.gen();
+            }
+            vec_edit.push(VersionEdit::NewFile((vec![scope], 1), index, meta));
+        }
+        compactor
+            .ver_status()
+            .log_and_apply(vec_edit, config.ver_log_snapshot_threshold)
+            .await?;
+
+        let version = compactor.ver_status().current().await;
+        assert!(version.is_threshold_exceeded_major(&config, 1));
+        let (_, scopes) = version.pick_tiered_run(1, 3).unwrap();
+        assert_eq!(scopes.len(), 3);
+        assert!(scopes.iter().all(|scope| scope.gen() != large_gen));
+        assert!(version.pick_tiered_run(1, 4).is_none());
+        let target = scopes[0].clone();
+        drop(version);
+
+        let _ = compactor
+            .major_compaction(1, target, vec![], false, None)
+            .await?;
+
+        // 仅大小相近的三个Table被压缩至Level 2，大Table仍留在Level 1
+        let version = compactor.ver_status().current().await;
+        assert_eq!(version.level_len(1), 1);
+        assert_eq!(version.level_slice[1][0].gen(), large_gen);
+        assert!(version.level_len(2) > 0);
+        assert!(!version.is_threshold_exceeded_major(&config, 1));
+        for (prefix, len) in [(b'a', 10), (b'b', 10), (b'c', 10), (b'd', 1000)] {
+            for (key, value) in key_values(prefix, len) {
+                assert_eq!(version.query(&key)?.0, Some((key, value)));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_parallel_major_compaction() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path())
+            .major_threshold_with_sst_size(2)
+            .level_sst_magnification(1);
+        let store_inner = Arc::new(StoreInner::new(config.clone()).await?);
+        let compactor = Compactor::new(Arc::clone(&store_inner));
+        let loader = compactor.ver_status().loader();
+        let key_values = |prefix: u8, len: u32, value: u8| {
+            (0..len)
+                .map(|i| {
+                    let key = Bytes::from([&[prefix][..], &i.to_be_bytes()].concat());
+                    (key, Some(Bytes::from(vec![value; 64])))
+                })
+                .collect_vec()
+        };
+
+        // Level 0中的Table未达到阈值，而Level 2的Table已超出阈值
+        let mut vec_edit = Vec::new();
+        for (index, prefix) in [b'a', b'b'].into_iter().enumerate() {
+            let (scope, meta) = loader
+                .create(
+                    Gen::create(),
+                    key_values(prefix, 100, 0),
+                    2,
+                    TableType::SortedString,
+                )
+                .await?;
+            vec_edit.push(VersionEdit::NewFile((vec![scope], 2), index, meta));
+        }
+        let level_0_data = key_values(b'a', 10, 1)
+            .into_iter()
+            .chain(key_values(b'b', 10, 1))
+            .collect_vec();
+        let (scope_0, meta_0) = loader
+            .create(Gen::create(), level_0_data, 0, TableType::SortedString)
+            .await?;
+        vec_edit.push(VersionEdit::NewFile((vec![scope_0.clone()], 0), 0, meta_0));
+        compactor
+            .ver_status()
+            .log_and_apply(vec_edit, config.ver_log_snapshot_threshold)
+            .await?;
+
+        // Level 0 -> 1与Level 2 -> 3的压缩范围互不相交，于同一轮中一并完成
+        let _ = compactor
+            .major_compaction(LEVEL_0, scope_0, vec![], true, None)
+            .await?;
+
+        let version = compactor.ver_status().current().await;
+        assert_eq!(version.level_len(0), 0);
+        assert_eq!(version.level_len(1), 1);
+        assert_eq!(version.level_len(2), 0);
+        assert!(version.level_len(3) > 0);
+        for prefix in [b'a', b'b'] {
+            for (i, (key, _)) in key_values(prefix, 100, 0).into_iter().enumerate() {
+                let value = if i < 10 { 1 } else { 0 };
+                assert_eq!(
+                    version.query(&key)?.0,
+                    Some((key, Some(Bytes::from(vec![value; 64]))))
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn test_lsm_major_compactor_with_parallel_levels() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+
+        let times = 100_000;
+        let value = b"Stray birds of summer come to my window to sing and fly away.";
+
+        // 阈值较小时多个Level会同时超出阈值，从而触发多个Level的并发压缩
+        let config = Config::new(temp_dir.path().to_str().unwrap())
+            .major_threshold_with_sst_size(2)
+            .level_sst_magnification(2)
+            .minor_trigger_with_threshold(TriggerType::Count, 1000);
+        let kv_store = KipStorage::open_with_config(config).await?;
+        let vec_kv = (0..times)
+            .map(|i: u32| {
+                // 打乱写入顺序，使各Level之间的范围相互重叠
+                let key = Bytes::from(i.wrapping_mul(2_654_435_761).to_be_bytes().to_vec());
+                (key.clone(), Bytes::from([&key[..], value].concat()))
+            })
+            .collect_vec();
+
+        for chunk in vec_kv.chunks(1000) {
+            for (key, value) in chunk {
+                kv_store.set(key.clone(), value.clone()).await?;
+            }
+            kv_store.flush().await?;
+        }
+
+        let version = kv_store.current_version().await;
+        for slice in version.level_slice.iter().skip(1) {
+            for (last_scope, scope) in slice.iter().tuple_windows() {
+                assert!(last_scope.end < scope.start);
+            }
+        }
+        drop(version);
+
+        assert_eq!(kv_store.len().await?, times as usize);
+        for (key, value) in &vec_kv {
+            assert_eq!(kv_store.get(key).await?, Some(value.clone()));
+        }
+
+        Ok(())
+    }
 }
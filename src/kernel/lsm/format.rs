@@ -0,0 +1,183 @@
+use crate::kernel::lsm::version::DEFAULT_VERSION_PATH;
+use crate::kernel::KernelResult;
+use crate::KernelError;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+/// 当前所写入的数据库格式版本
+///
+/// SSTable、Footer或VersionEdit等持久化格式每发生一次变化都需要递增，
+/// 使旧版本拒绝开启由新版本所写入的数据库；
+/// SSTable的Footer中记录其写入时的格式版本，读取时依此选择对应的解析方式
+///
+/// - 版本1: 引入格式标记
+/// - 版本2: Version日志记录附带版本标签，见`VersionEdit::encode_vec`
+/// - 版本3: MetaBlock存放DataBlock的压缩类型
+/// - 版本4: Block头部存放校验类型
+/// - 版本5: IndexBlock与MetaBlock之间存放PrefixIndex
+/// - 版本6: 大Value分离存储于ValueLog，DataBlock中以`u32::MAX`标记ValuePointer
+/// - 版本7: Footer中的偏移量与长度扩展为u64，ValuePointer的标记改为`u64::MAX`
+/// - 版本8: Footer存放IndexBlock的Restart间隔与DataBlock数量
+/// - 版本9: MetaBlock存放前缀布隆过滤器
+/// - 版本10: MetaBlock存放删除标记数量
+/// - 版本11: Version日志新增`VersionEdit::CompactPoint`
+/// - 版本12: Footer记录SSTable的格式版本，并使用独立的标记
+/// - 版本13: ValueLog中的每个Value附带CRC32校验码
+/// - 版本14: Version日志中的Scope记录Table的磁盘占用
+pub(crate) const FORMAT_VERSION: u32 = 14;
+
+/// MetaBlock以标记区分是否存放DataBlock的压缩类型的最低版本
+pub(crate) const COMPRESS_TYPE_VERSION: u32 = 3;
+/// Block带有校验类型头部的最低版本
+pub(crate) const BLOCK_HEADER_VERSION: u32 = 4;
+/// SSTable可能存放PrefixIndex的最低版本
+pub(crate) const PREFIX_INDEX_VERSION: u32 = 5;
+/// DataBlock中以`u32::MAX`标记ValuePointer的版本
+pub(crate) const VALUE_POINTER_VERSION: u32 = 6;
+/// DataBlock中以`u64::MAX`标记ValuePointer、Footer偏移量为u64的最低版本
+pub(crate) const WIDE_OFFSET_VERSION: u32 = 7;
+/// MetaBlock可能存放前缀布隆过滤器的最低版本
+pub(crate) const PREFIX_FILTER_VERSION: u32 = 9;
+/// MetaBlock可能存放删除标记数量的最低版本
+pub(crate) const TOMBSTONES_VERSION: u32 = 10;
+/// ValueLog中的Value附带校验码的最低版本
+pub(crate) const VALUE_LOG_CRC_VERSION: u32 = 13;
+
+/// 可通过原地迁移开启的最低格式版本
+///
+/// 版本0为引入格式标记前所创建的数据库，其中可能存在不带校验和与ScopeBlock的Footer及旧的MetaBlock，
+/// 无法与之后的格式区分，因此拒绝开启
+pub(crate) const MIN_SUPPORTED_FORMAT_VERSION: u32 = 1;
+
+/// 格式版本标记与Version日志(Manifest)一同存放
+pub(crate) const DEFAULT_FORMAT_FILE: &str = "FORMAT";
+
+/// 检测数据目录的格式版本，并在必要时原地迁移至`supported`
+///
+/// - 新建的数据库直接写入`supported`
+/// - 低于`supported`但不低于`MIN_SUPPORTED_FORMAT_VERSION`时进行迁移并更新标记
+/// - 高于`supported`(由更新的版本所写入)或过旧(包括未带格式标记的已有数据库)时返回`KernelError::UnsupportedTableVersion`
+pub(crate) fn check_and_migrate(dir_path: &Path, supported: u32) -> KernelResult<()> {
+    let manifest_path = dir_path.join(DEFAULT_VERSION_PATH);
+    let format_path = manifest_path.join(DEFAULT_FORMAT_FILE);
+
+    let found = if format_path.exists() {
+        let bytes = fs::read(&format_path)?;
+        let bytes: [u8; 4] = bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| KernelError::NotSupport("the format version marker is corrupted"))?;
+        u32::from_le_bytes(bytes)
+    } else if is_initialized(dir_path)? {
+        0
+    } else {
+        supported
+    };
+
+    if !(MIN_SUPPORTED_FORMAT_VERSION..=supported).contains(&found) {
+        return Err(KernelError::UnsupportedTableVersion { found, supported });
+    }
+    if found < supported {
+        info!("[Format][migrate]: {found} -> {supported}");
+    }
+    if found < supported || !format_path.exists() {
+        // SSTable依据其Footer中记录的格式版本解析，因此迁移仅需更新标记
+        write_format_version(dir_path, supported)?;
+    }
+
+    Ok(())
+}
+
+/// 以原子替换的方式写入数据目录的格式版本标记
+pub(crate) fn write_format_version(dir_path: &Path, version: u32) -> KernelResult<()> {
+    let manifest_path = dir_path.join(DEFAULT_VERSION_PATH);
+    fs::create_dir_all(&manifest_path)?;
+    let tmp_path = manifest_path.join(format!("{DEFAULT_FORMAT_FILE}.tmp"));
+    fs::write(&tmp_path, version.to_le_bytes())?;
+    fs::rename(tmp_path, manifest_path.join(DEFAULT_FORMAT_FILE))?;
+
+    Ok(())
+}
+
+/// 数据目录中是否已存在Version日志，即是否为已有的数据库
+fn is_initialized(dir_path: &Path) -> KernelResult<bool> {
+    let manifest_path = dir_path.join(DEFAULT_VERSION_PATH);
+
+    Ok(manifest_path.is_dir() && fs::read_dir(manifest_path)?.next().is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kernel::lsm::format::{
+        check_and_migrate, DEFAULT_FORMAT_FILE, FORMAT_VERSION, MIN_SUPPORTED_FORMAT_VERSION,
+    };
+    use crate::kernel::lsm::storage::{Config, KipStorage};
+    use crate::kernel::lsm::version::DEFAULT_VERSION_PATH;
+    use crate::kernel::{KernelResult, Storage};
+    use crate::KernelError;
+    use bytes::Bytes;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_format_version() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let format_path = temp_dir
+            .path()
+            .join(DEFAULT_VERSION_PATH)
+            .join(DEFAULT_FORMAT_FILE);
+        let config = Config::new(temp_dir.path());
+
+        let kv_store = KipStorage::open_with_config(config.clone()).await?;
+        kv_store
+            .set(Bytes::from_static(b"KipDB"), Bytes::from_static(b"format"))
+            .await?;
+        kv_store.flush().await?;
+        drop(kv_store);
+        assert_eq!(fs::read(&format_path)?, FORMAT_VERSION.to_le_bytes());
+
+        // 支持的版本提升后，旧格式的数据库被原地迁移
+        check_and_migrate(temp_dir.path(), FORMAT_VERSION + 1)?;
+        assert_eq!(fs::read(&format_path)?, (FORMAT_VERSION + 1).to_le_bytes());
+
+        // 由更新的版本所写入的数据库拒绝开启
+        let err = KipStorage::open_with_config(config.clone())
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            KernelError::UnsupportedTableVersion { found, supported }
+                if found == FORMAT_VERSION + 1 && supported == FORMAT_VERSION
+        ));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Unsupported format version {}, supported up to {}",
+                FORMAT_VERSION + 1,
+                FORMAT_VERSION
+            )
+        );
+
+        // 支持范围内的旧版本被原地迁移
+        fs::write(&format_path, MIN_SUPPORTED_FORMAT_VERSION.to_le_bytes())?;
+        let kv_store = KipStorage::open_with_config(config.clone()).await?;
+        assert_eq!(
+            kv_store.get(b"KipDB").await?,
+            Some(Bytes::from_static(b"format"))
+        );
+        drop(kv_store);
+        assert_eq!(fs::read(&format_path)?, FORMAT_VERSION.to_le_bytes());
+
+        // 引入格式标记之前创建的数据库视为版本0，拒绝开启
+        fs::remove_file(&format_path)?;
+        assert!(matches!(
+            KipStorage::open_with_config(config).await,
+            Err(KernelError::UnsupportedTableVersion { found: 0, supported })
+                if supported == FORMAT_VERSION
+        ));
+
+        Ok(())
+    }
+}
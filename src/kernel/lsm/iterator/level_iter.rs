@@ -106,7 +106,7 @@ mod tests {
         let temp_dir = TempDir::new().expect("unable to create temporary working directory");
 
         tokio_test::block_on(async move {
-            let config = Config::new(temp_dir.into_path());
+            let config = Config::new(temp_dir.keep());
 
             let (wal, _) = LogLoader::reload(
                 config.path(),
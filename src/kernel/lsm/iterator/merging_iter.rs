@@ -1,202 +1,215 @@
-use crate::kernel::lsm::iterator::{Iter, Seek, SeekIter};
+use crate::kernel::lsm::iterator::{
+    Cursor, CursorIter, Iter, IterCursor, Seek, SeekCursor, SeekIter,
+};
 use crate::kernel::lsm::mem_table::KeyValue;
 use crate::kernel::KernelResult;
 use bytes::Bytes;
-use std::cmp::Ordering;
-use std::collections::BTreeMap;
-
-/// 用于取值以及对应的Iter下标
-/// 通过序号进行同值优先获取
-#[derive(Eq, PartialEq, Debug)]
-struct IterKey {
-    num: usize,
-    key: Bytes,
+use itertools::Itertools;
+use std::cmp::Reverse;
+use std::collections::binary_heap::PeekMut;
+use std::collections::BinaryHeap;
+
+type BoxIter<'a> = Box<dyn Iter<'a, Item = KeyValue> + 'a + Send + Sync>;
+
+type BoxSeekIter<'a> = Box<dyn SeekIter<'a, Item = KeyValue> + 'a + Send + Sync>;
+
+/// 以小顶堆对各游标当前的Key进行k路归并的游标
+///
+/// 堆中存放各游标当前的Key及其序号，以Key升序排列，Key相同时通过序号进行同值优先获取(序号较小的游标数据较新)；
+/// 当前元素即堆顶的游标所指向的元素，直接通过该游标查看而无需将其取出，
+/// 堆中每个游标仅有一项，因此内存占用与游标的数量相关而与数据量无关
+pub(crate) struct MergingCursor<C> {
+    cursors: Vec<C>,
+    heap: BinaryHeap<Reverse<(Bytes, usize)>>,
 }
 
-impl PartialOrd<Self> for IterKey {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl<'a, C: Cursor<'a>> MergingCursor<C> {
+    pub(crate) fn new(cursors: Vec<C>) -> Self {
+        let mut merging_cursor = MergingCursor {
+            heap: BinaryHeap::with_capacity(cursors.len()),
+            cursors,
+        };
+        merging_cursor.rebuild_heap();
+
+        merging_cursor
+    }
+
+    pub(crate) fn cursors(&self) -> &[C] {
+        &self.cursors
+    }
+
+    fn rebuild_heap(&mut self) {
+        self.heap.clear();
+
+        for (num, cursor) in self.cursors.iter().enumerate() {
+            if cursor.valid() {
+                self.heap.push(Reverse((cursor.key().clone(), num)));
+            }
+        }
+    }
+
+    fn current(&self) -> &C {
+        let Reverse((_, num)) = self.heap.peek().expect("cursor is not valid");
+
+        &self.cursors[*num]
     }
 }
 
-impl Ord for IterKey {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.key
-            .cmp(&other.key)
-            .then_with(|| self.num.cmp(&other.num))
+impl<'a, C: Cursor<'a>> Cursor<'a> for MergingCursor<C> {
+    fn valid(&self) -> bool {
+        !self.heap.is_empty()
+    }
+
+    fn key(&self) -> &Bytes {
+        self.current().key()
+    }
+
+    fn value(&self) -> &Option<Bytes> {
+        self.current().value()
+    }
+
+    /// 同时越过各游标中与当前Key相同的较旧数据
+    fn next(&mut self) -> KernelResult<()> {
+        let Some(Reverse((key, _))) = self.heap.peek().cloned() else {
+            return Ok(());
+        };
+
+        while let Some(Reverse((_, num))) = self
+            .heap
+            .peek_mut()
+            .filter(|top| top.0 .0 == key)
+            .map(PeekMut::pop)
+        {
+            let cursor = &mut self.cursors[num];
+
+            cursor.next()?;
+            if cursor.valid() {
+                self.heap.push(Reverse((cursor.key().clone(), num)));
+            }
+        }
+
+        Ok(())
     }
 }
 
-struct InnerIter {
-    map_buf: BTreeMap<IterKey, Option<Bytes>>,
-    next_buf: Option<KeyValue>,
+impl<'a, C: SeekCursor<'a>> SeekCursor<'a> for MergingCursor<C> {
+    fn seek(&mut self, seek: Seek<'_>) -> KernelResult<()> {
+        for cursor in self.cursors.iter_mut() {
+            cursor.seek(seek)?;
+        }
+        self.rebuild_heap();
+
+        Ok(())
+    }
 }
 
+/// 基于`MergingCursor`对多个拉取式迭代器进行归并的迭代器
 pub(crate) struct MergingIter<'a> {
-    vec_iter: Vec<Box<dyn Iter<'a, Item = KeyValue> + 'a + Send + Sync>>,
-    inner: InnerIter,
+    iter: CursorIter<MergingCursor<IterCursor<BoxIter<'a>>>>,
+    /// 是否跳过墓碑(Value为None的删除标记)
+    ///
+    /// 墓碑仍参与归并以屏蔽较旧的同Key数据，仅不作为结果产出
+    skip_tombstones: bool,
 }
 
+/// 同`MergingIter`，各迭代器支持定位
 pub(crate) struct SeekMergingIter<'a> {
-    vec_iter: Vec<Box<dyn SeekIter<'a, Item = KeyValue> + 'a + Send + Sync>>,
-    inner: InnerIter,
+    iter: CursorIter<MergingCursor<IterCursor<BoxSeekIter<'a>>>>,
 }
 
-macro_rules! impl_new {
-    ($struct_name:ident, $vec_iter_type:ty) => {
-        impl<'a> $struct_name<'a> {
-            #[allow(clippy::mutable_key_type)]
-            pub(crate) fn new(mut vec_iter: $vec_iter_type) -> KernelResult<Self> {
-                let mut map_buf = BTreeMap::new();
-
-                for (num, iter) in vec_iter.iter_mut().enumerate() {
-                    if let Some(item) = iter.try_next()? {
-                        InnerIter::buf_map_insert(&mut map_buf, num, item);
-                    }
-                }
-                let mut inner = InnerIter {
-                    map_buf,
-                    next_buf: None,
-                };
-                inner.init_next_buf();
-
-                Ok($struct_name { vec_iter, inner })
-            }
-        }
-    };
+fn merging_cursor<'a, I: Iter<'a, Item = KeyValue>>(
+    vec_iter: Vec<I>,
+) -> KernelResult<CursorIter<MergingCursor<IterCursor<I>>>> {
+    let cursors = vec_iter.into_iter().map(IterCursor::new).try_collect()?;
+
+    Ok(CursorIter::new(MergingCursor::new(cursors)))
 }
 
-impl_new!(
-    MergingIter,
-    Vec<Box<dyn Iter<'a, Item = KeyValue> + 'a + Send + Sync>>
-);
-impl_new!(
-    SeekMergingIter,
-    Vec<Box<dyn SeekIter<'a, Item = KeyValue> + 'a + Send + Sync>>
-);
-
-macro_rules! is_valid {
-    ($vec_iter:expr) => {
-        $vec_iter
-            .iter()
-            .map(|iter| iter.is_valid())
-            .all(|is_valid| is_valid)
-    };
+/// 所有参与归并的迭代器是否均有效
+fn is_valid<'a, I: Iter<'a, Item = KeyValue>>(
+    iter: &CursorIter<MergingCursor<IterCursor<I>>>,
+) -> bool {
+    iter.cursor()
+        .cursors()
+        .iter()
+        .all(|cursor| cursor.iter().is_valid())
 }
 
-impl<'a> Iter<'a> for MergingIter<'a> {
-    type Item = KeyValue;
+impl<'a> MergingIter<'a> {
+    pub(crate) fn new(vec_iter: Vec<BoxIter<'a>>) -> KernelResult<Self> {
+        Ok(MergingIter {
+            iter: merging_cursor(vec_iter)?,
+            skip_tombstones: false,
+        })
+    }
 
-    fn try_next(&mut self) -> KernelResult<Option<Self::Item>> {
-        self.inner.try_next_1(&mut self.vec_iter)
+    /// 设置是否跳过墓碑，默认产出墓碑以供压缩等需要感知删除的场景使用
+    pub(crate) fn skip_tombstones(mut self, skip_tombstones: bool) -> Self {
+        self.set_skip_tombstones(skip_tombstones);
+        self
     }
 
-    fn is_valid(&self) -> bool {
-        is_valid!(&self.vec_iter)
+    /// 同`skip_tombstones`，供无法取得所有权的持有者(如实现了Drop的TransactionIter)使用
+    pub(crate) fn set_skip_tombstones(&mut self, skip_tombstones: bool) {
+        self.skip_tombstones = skip_tombstones;
     }
 }
 
-impl<'a> Iter<'a> for SeekMergingIter<'a> {
+impl<'a> SeekMergingIter<'a> {
+    pub(crate) fn new(vec_iter: Vec<BoxSeekIter<'a>>) -> KernelResult<Self> {
+        Ok(SeekMergingIter {
+            iter: merging_cursor(vec_iter)?,
+        })
+    }
+}
+
+impl<'a> Iter<'a> for MergingIter<'a> {
     type Item = KeyValue;
 
     fn try_next(&mut self) -> KernelResult<Option<Self::Item>> {
-        self.inner.try_next_2(&mut self.vec_iter)
+        while let Some(item) = self.iter.try_next()? {
+            if self.skip_tombstones && item.1.is_none() {
+                continue;
+            }
+            return Ok(Some(item));
+        }
+
+        Ok(None)
     }
 
     fn is_valid(&self) -> bool {
-        is_valid!(&self.vec_iter)
+        is_valid(&self.iter)
     }
 }
 
-impl InnerIter {
-    fn init_next_buf(&mut self) {
-        self.next_buf = self
-            .map_buf
-            .first_key_value()
-            .map(|(key, value)| (key.key.clone(), value.clone()));
-    }
-}
+impl<'a> Iter<'a> for SeekMergingIter<'a> {
+    type Item = KeyValue;
 
-macro_rules! impl_try_next {
-    ($func:ident, $vec_iter:ty) => {
-        impl InnerIter {
-            fn $func(&mut self, vec_iter: &mut [$vec_iter]) -> KernelResult<Option<KeyValue>> {
-                if let Some(item_buf) = self.next_buf.take() {
-                    while let Some((IterKey { num, key }, value)) = self.map_buf.pop_first() {
-                        if let Some(item) = vec_iter[num].try_next()? {
-                            Self::buf_map_insert(&mut self.map_buf, num, item);
-                        }
-                        if item_buf.0 == key {
-                            continue;
-                        }
-                        self.next_buf = Some((key, value));
-                        break;
-                    }
-                    return Ok(Some(item_buf));
-                }
-                Ok(None)
-            }
-        }
-    };
-}
+    fn try_next(&mut self) -> KernelResult<Option<Self::Item>> {
+        self.iter.try_next()
+    }
 
-impl_try_next!(
-    try_next_1,
-    Box<dyn Iter<'_, Item = KeyValue> + '_ + Send + Sync>
-);
-impl_try_next!(
-    try_next_2,
-    Box<dyn SeekIter<'_, Item = KeyValue> + '_ + Send + Sync>
-);
-
-impl InnerIter {
-    #[allow(clippy::mutable_key_type)]
-    fn buf_map_insert(
-        seek_map: &mut BTreeMap<IterKey, Option<Bytes>>,
-        num: usize,
-        (key, value): KeyValue,
-    ) {
-        let _ = seek_map.insert(IterKey { num, key }, value);
+    fn is_valid(&self) -> bool {
+        is_valid(&self.iter)
     }
 }
 
 impl<'a> SeekIter<'a> for SeekMergingIter<'a> {
-    #[allow(clippy::mutable_key_type)]
     fn seek(&mut self, seek: Seek<'_>) -> KernelResult<()> {
-        if let Seek::Last = seek {
-            self.inner.map_buf.clear();
-        } else {
-            let mut seek_map = BTreeMap::new();
-
-            for (num, iter) in self.vec_iter.iter_mut().enumerate() {
-                iter.seek(seek)?;
-
-                if let Some(item) = iter.try_next()? {
-                    InnerIter::buf_map_insert(&mut seek_map, num, item);
-                }
-            }
-
-            self.inner.map_buf = seek_map;
-        }
-        self.inner.init_next_buf();
-
-        Ok(())
+        self.iter.seek(seek)
     }
 }
 
-#[allow(clippy::mutable_key_type)]
-impl MergingIter<'_> {}
-
 #[cfg(test)]
 mod tests {
     use crate::kernel::io::{FileExtension, IoFactory, IoType};
-    use crate::kernel::lsm::iterator::merging_iter::SeekMergingIter;
-    use crate::kernel::lsm::iterator::{Iter, Seek, SeekIter};
+    use crate::kernel::lsm::iterator::merging_iter::{MergingCursor, MergingIter, SeekMergingIter};
+    use crate::kernel::lsm::iterator::{Cursor, CursorIter, Iter, Seek, SeekCursor, SeekIter};
     use crate::kernel::lsm::mem_table::KeyValue;
     use crate::kernel::lsm::storage::Config;
-    use crate::kernel::lsm::table::btree_table::iter::BTreeTableIter;
+    use crate::kernel::lsm::table::btree_table::iter::BTreeTableCursor;
     use crate::kernel::lsm::table::btree_table::BTreeTable;
-    use crate::kernel::lsm::table::ss_table::iter::SSTableIter;
+    use crate::kernel::lsm::table::ss_table::iter::SSTableCursor;
     use crate::kernel::lsm::table::ss_table::SSTable;
     use crate::kernel::lsm::version::DEFAULT_SS_TABLE_PATH;
     use crate::kernel::utils::lru_cache::ShardingLruCache;
@@ -287,6 +300,148 @@ mod tests {
         test_with_data(data_1, data_2, test_sequence).await
     }
 
+    #[tokio::test]
+    async fn test_skip_tombstones() -> KernelResult<()> {
+        // 序号较小的Iter优先，即data_1较新
+        let data_1 = vec![
+            (Bytes::from(vec![b'1']), None),
+            (Bytes::from(vec![b'3']), Some(Bytes::from(vec![b'1']))),
+            (Bytes::from(vec![b'5']), None),
+        ];
+        let data_2 = vec![
+            (Bytes::from(vec![b'1']), Some(Bytes::from(vec![b'0']))),
+            (Bytes::from(vec![b'2']), Some(Bytes::from(vec![b'0']))),
+            (Bytes::from(vec![b'3']), None),
+            (Bytes::from(vec![b'4']), None),
+        ];
+        let table_1 = BTreeTable::new(0, 0, data_1);
+        let table_2 = BTreeTable::new(0, 1, data_2);
+        let new_iter = |skip_tombstones: bool| {
+            MergingIter::new(vec![
+                Box::new(CursorIter::new(BTreeTableCursor::new(&table_1))),
+                Box::new(CursorIter::new(BTreeTableCursor::new(&table_2))),
+            ])
+            .map(|iter| iter.skip_tombstones(skip_tombstones))
+        };
+        let collect = |mut iter: MergingIter| -> KernelResult<Vec<KeyValue>> {
+            let mut vec_kv = Vec::new();
+            while let Some(item) = iter.try_next()? {
+                vec_kv.push(item);
+            }
+            Ok(vec_kv)
+        };
+
+        // 墓碑仍屏蔽较旧的数据，但不被产出
+        assert_eq!(
+            collect(new_iter(true)?)?,
+            vec![
+                (Bytes::from(vec![b'2']), Some(Bytes::from(vec![b'0']))),
+                (Bytes::from(vec![b'3']), Some(Bytes::from(vec![b'1']))),
+            ]
+        );
+        assert_eq!(
+            collect(new_iter(false)?)?,
+            vec![
+                (Bytes::from(vec![b'1']), None),
+                (Bytes::from(vec![b'2']), Some(Bytes::from(vec![b'0']))),
+                (Bytes::from(vec![b'3']), Some(Bytes::from(vec![b'1']))),
+                (Bytes::from(vec![b'4']), None),
+                (Bytes::from(vec![b'5']), None),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_way_merge() -> KernelResult<()> {
+        // 序号越小的Iter越新，同Key时仅产出最新的数据
+        let table_1 = BTreeTable::new(0, 0, vec![(Bytes::from(vec![b'3']), None)]);
+        let table_2 = BTreeTable::new(
+            0,
+            1,
+            vec![
+                (Bytes::from(vec![b'2']), Some(Bytes::from(vec![b'1']))),
+                (Bytes::from(vec![b'3']), Some(Bytes::from(vec![b'1']))),
+            ],
+        );
+        let table_3 = BTreeTable::new(
+            0,
+            2,
+            vec![
+                (Bytes::from(vec![b'1']), Some(Bytes::from(vec![b'2']))),
+                (Bytes::from(vec![b'2']), Some(Bytes::from(vec![b'2']))),
+                (Bytes::from(vec![b'3']), Some(Bytes::from(vec![b'2']))),
+                (Bytes::from(vec![b'4']), Some(Bytes::from(vec![b'2']))),
+            ],
+        );
+        let mut merging_iter = MergingIter::new(vec![
+            Box::new(CursorIter::new(BTreeTableCursor::new(&table_1))),
+            Box::new(CursorIter::new(BTreeTableCursor::new(&table_2))),
+            Box::new(CursorIter::new(BTreeTableCursor::new(&table_3))),
+        ])?;
+        let mut vec_kv = Vec::new();
+        while let Some(item) = merging_iter.try_next()? {
+            vec_kv.push(item);
+        }
+
+        assert_eq!(
+            vec_kv,
+            vec![
+                (Bytes::from(vec![b'1']), Some(Bytes::from(vec![b'2']))),
+                (Bytes::from(vec![b'2']), Some(Bytes::from(vec![b'1']))),
+                (Bytes::from(vec![b'3']), None),
+                (Bytes::from(vec![b'4']), Some(Bytes::from(vec![b'2']))),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merging_cursor() -> KernelResult<()> {
+        let table_1 = BTreeTable::new(
+            0,
+            0,
+            vec![
+                (Bytes::from(vec![b'1']), None),
+                (Bytes::from(vec![b'3']), Some(Bytes::from(vec![b'1']))),
+            ],
+        );
+        let table_2 = BTreeTable::new(
+            0,
+            1,
+            vec![
+                (Bytes::from(vec![b'1']), Some(Bytes::from(vec![b'2']))),
+                (Bytes::from(vec![b'2']), Some(Bytes::from(vec![b'2']))),
+            ],
+        );
+        let mut cursor = MergingCursor::new(vec![
+            BTreeTableCursor::new(&table_1),
+            BTreeTableCursor::new(&table_2),
+        ]);
+
+        // 查看当前元素不会将其消费
+        for _ in 0..2 {
+            assert!(cursor.valid());
+            assert_eq!(cursor.key(), &Bytes::from(vec![b'1']));
+            assert_eq!(cursor.value(), &None);
+        }
+        cursor.next()?;
+        assert_eq!(cursor.key(), &Bytes::from(vec![b'2']));
+        cursor.next()?;
+        assert_eq!(cursor.value(), &Some(Bytes::from(vec![b'1'])));
+        cursor.next()?;
+        assert!(!cursor.valid());
+
+        cursor.seek(Seek::Backward(&[b'2']))?;
+        assert_eq!(cursor.key(), &Bytes::from(vec![b'2']));
+        cursor.seek(Seek::Last)?;
+        assert!(!cursor.valid());
+
+        Ok(())
+    }
+
     async fn test_with_data(
         data_1: Vec<KeyValue>,
         data_2: Vec<KeyValue>,
@@ -295,7 +450,7 @@ mod tests {
         let btree_table = BTreeTable::new(0, 0, data_1);
 
         let temp_dir = TempDir::new().expect("unable to create temporary working directory");
-        let config = Config::new(temp_dir.into_path());
+        let config = Config::new(temp_dir.keep());
         let sst_factory = IoFactory::new(
             config.dir_path.join(DEFAULT_SS_TABLE_PATH),
             FileExtension::SSTable,
@@ -317,9 +472,9 @@ mod tests {
         )
         .await?;
 
-        let bt_iter = BTreeTableIter::new(&btree_table);
+        let bt_iter = CursorIter::new(BTreeTableCursor::new(&btree_table));
 
-        let sst_iter = SSTableIter::new(&ss_table)?;
+        let sst_iter = CursorIter::new(SSTableCursor::new(&ss_table)?);
 
         let mut sequence_iter = sequence.into_iter();
 
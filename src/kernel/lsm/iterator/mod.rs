@@ -1,7 +1,10 @@
 pub(crate) mod level_iter;
 pub(crate) mod merging_iter;
 
+use crate::kernel::lsm::mem_table::KeyValue;
 use crate::kernel::KernelResult;
+use bytes::Bytes;
+use std::mem;
 
 #[derive(Clone, Copy)]
 #[allow(dead_code)]
@@ -28,7 +31,181 @@ pub trait SeekIter<'a>: Iter<'a> {
 }
 
 /// 向前迭代器
-#[allow(dead_code)]
-pub(crate) trait ForwardIter<'a>: Iter<'a> {
+pub(crate) trait ForwardIter<'a>: SeekIter<'a> {
     fn try_prev(&mut self) -> KernelResult<Option<Self::Item>>;
+
+    /// 定位至最后一个元素并将其返回，之后可通过`try_prev`自大至小逆序遍历
+    fn seek_to_last(&mut self) -> KernelResult<Option<Self::Item>> {
+        self.seek(Seek::Last)?;
+        self.try_prev()
+    }
+}
+
+/// 游标迭代器
+///
+/// 游标停留于当前元素之上，可通过`key`/`value`多次查看当前元素而无需将其消费
+/// 拉取式的迭代器可通过`CursorIter`在其之上获得，反之可通过`IterCursor`将拉取式的迭代器视为游标
+pub(crate) trait Cursor<'a> {
+    /// 游标是否指向元素
+    fn valid(&self) -> bool;
+
+    /// 当前元素的Key
+    ///
+    /// Tips: 仅允许在`valid`时调用
+    fn key(&self) -> &Bytes;
+
+    /// 当前元素的Value，为None时表示该Key已被删除
+    ///
+    /// Tips: 仅允许在`valid`时调用
+    fn value(&self) -> &Option<Bytes>;
+
+    /// 移动至下一个元素，游标已越过末尾时不进行移动
+    fn next(&mut self) -> KernelResult<()>;
+}
+
+/// 支持定位的游标迭代器
+pub(crate) trait SeekCursor<'a>: Cursor<'a> {
+    /// 定位至Seek对应的元素，`Seek::Last`时游标越过最后一个元素
+    fn seek(&mut self, seek: Seek<'_>) -> KernelResult<()>;
+}
+
+/// 支持双向移动的游标迭代器
+pub(crate) trait BidirectionalCursor<'a>: SeekCursor<'a> {
+    /// 移动至上一个元素，游标位于第一个元素时将越过开头而不再指向元素
+    fn prev(&mut self) -> KernelResult<()>;
+}
+
+impl<'a, I: Iter<'a> + ?Sized> Iter<'a> for Box<I> {
+    type Item = I::Item;
+
+    fn try_next(&mut self) -> KernelResult<Option<Self::Item>> {
+        (**self).try_next()
+    }
+
+    fn is_valid(&self) -> bool {
+        (**self).is_valid()
+    }
+}
+
+impl<'a, I: SeekIter<'a> + ?Sized> SeekIter<'a> for Box<I> {
+    fn seek(&mut self, seek: Seek<'_>) -> KernelResult<()> {
+        (**self).seek(seek)
+    }
+}
+
+/// 基于游标迭代器实现的拉取式迭代器
+pub(crate) struct CursorIter<C> {
+    cursor: C,
+    /// 游标当前指向的元素是否已被`try_next`返回
+    is_consumed: bool,
+}
+
+impl<C> CursorIter<C> {
+    pub(crate) fn new(cursor: C) -> Self {
+        CursorIter {
+            cursor,
+            is_consumed: false,
+        }
+    }
+
+    /// 获取内部游标，用于查看下一个元素而不将其消费
+    pub(crate) fn cursor(&self) -> &C {
+        &self.cursor
+    }
+}
+
+impl<'a, C: Cursor<'a>> CursorIter<C> {
+    fn current(&self) -> Option<KeyValue> {
+        self.cursor
+            .valid()
+            .then(|| (self.cursor.key().clone(), self.cursor.value().clone()))
+    }
+}
+
+impl<'a, C: Cursor<'a>> Iter<'a> for CursorIter<C> {
+    type Item = KeyValue;
+
+    fn try_next(&mut self) -> KernelResult<Option<Self::Item>> {
+        if mem::replace(&mut self.is_consumed, true) {
+            self.cursor.next()?;
+        }
+
+        Ok(self.current())
+    }
+
+    fn is_valid(&self) -> bool {
+        self.cursor.valid()
+    }
+}
+
+impl<'a, C: SeekCursor<'a>> SeekIter<'a> for CursorIter<C> {
+    fn seek(&mut self, seek: Seek<'_>) -> KernelResult<()> {
+        self.cursor.seek(seek)?;
+        self.is_consumed = false;
+
+        Ok(())
+    }
+}
+
+impl<'a, C: BidirectionalCursor<'a>> ForwardIter<'a> for CursorIter<C> {
+    fn try_prev(&mut self) -> KernelResult<Option<Self::Item>> {
+        self.cursor.prev()?;
+        self.is_consumed = true;
+
+        Ok(self.current())
+    }
+}
+
+/// 基于拉取式迭代器实现的游标迭代器，以最近一次拉取的元素作为当前元素
+pub(crate) struct IterCursor<I> {
+    iter: I,
+    current: Option<KeyValue>,
+}
+
+impl<'a, I: Iter<'a, Item = KeyValue>> IterCursor<I> {
+    pub(crate) fn new(mut iter: I) -> KernelResult<Self> {
+        let current = iter.try_next()?;
+
+        Ok(IterCursor { iter, current })
+    }
+
+    /// 获取内部的拉取式迭代器
+    pub(crate) fn iter(&self) -> &I {
+        &self.iter
+    }
+}
+
+impl<'a, I: Iter<'a, Item = KeyValue>> Cursor<'a> for IterCursor<I> {
+    fn valid(&self) -> bool {
+        self.current.is_some()
+    }
+
+    fn key(&self) -> &Bytes {
+        &self.current.as_ref().expect("cursor is not valid").0
+    }
+
+    fn value(&self) -> &Option<Bytes> {
+        &self.current.as_ref().expect("cursor is not valid").1
+    }
+
+    fn next(&mut self) -> KernelResult<()> {
+        if self.current.is_some() {
+            self.current = self.iter.try_next()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, I: SeekIter<'a, Item = KeyValue>> SeekCursor<'a> for IterCursor<I> {
+    fn seek(&mut self, seek: Seek<'_>) -> KernelResult<()> {
+        self.iter.seek(seek)?;
+        self.current = if matches!(seek, Seek::Last) {
+            None
+        } else {
+            self.iter.try_next()?
+        };
+
+        Ok(())
+    }
 }
@@ -54,6 +54,7 @@ impl LogLoader {
                     .and_then(|vec| vec.last().cloned())
             })
             .unwrap_or(Gen::create());
+        Gen::advance(current_gen);
 
         Ok((LogLoader { factory, io_type }, current_gen))
     }
@@ -68,7 +69,8 @@ impl LogLoader {
     where
         F: Fn(&mut Vec<u8>, &mut Vec<R>) -> KernelResult<()>,
     {
-        let mut reader = LogReader::new(self.factory.reader(gen, self.io_type)?);
+        let (reader, _) = self.factory.read_or_create(gen, self.io_type)?;
+        let mut reader = LogReader::new(reader);
         let mut buf = vec![0; 128];
 
         // 当数据排列有误时仅恢复已正常读取的数据
@@ -79,15 +81,41 @@ impl LogLoader {
         Ok(())
     }
 
-    #[allow(dead_code)]
+    /// 删除日志，不存在时忽略
     pub(crate) fn clean(&self, gen: i64) -> KernelResult<()> {
-        self.factory.clean(gen)
+        if self.factory.exists(gen)? {
+            self.factory.clean(gen)?;
+        }
+
+        Ok(())
+    }
+
+    /// 将已切换的日志同步至硬盘，日志不存在时忽略
+    pub(crate) fn sync(&self, gen: i64, use_fsync: bool) -> KernelResult<()> {
+        if self.factory.exists(gen)? {
+            self.factory.writer(gen, self.io_type)?.sync(use_fsync)?;
+        }
+
+        Ok(())
+    }
+
+    /// 由旧至新排列的所有日志的Gen
+    pub(crate) fn gens(&self) -> KernelResult<Vec<i64>> {
+        sorted_gen_list(self.factory.get_path(), FileExtension::Log)
     }
 
     pub(crate) fn writer(&self, gen: i64) -> KernelResult<LogWriter<Box<dyn IoWriter>>> {
         let new_fs = self.factory.writer(gen, self.io_type)?;
         Ok(LogWriter::new(new_fs))
     }
+
+    /// 获取从日志末尾继续追加写入的Writer，用于重启后继续使用已恢复的日志
+    pub(crate) fn append_writer(&self, gen: i64) -> KernelResult<LogWriter<Box<dyn IoWriter>>> {
+        let mut fs = self.factory.writer(gen, self.io_type)?;
+        let off = fs.seek(SeekFrom::End(0))?;
+
+        Ok(LogWriter::new_with_off(fs, off as usize))
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -127,7 +155,6 @@ impl<W: Write + Seek> LogWriter<W> {
 
     /// new_with_off opens a writer starting at some offset of an existing log file. The file must
     /// have the default block size.
-    #[allow(dead_code)]
     pub(crate) fn new_with_off(writer: W, off: usize) -> LogWriter<W> {
         let mut w = LogWriter::new(writer);
         w.current_block_offset = off % BLOCK_SIZE;
@@ -183,10 +210,10 @@ impl<W: Write + Seek> LogWriter<W> {
         header_bytes.append(&mut (len as u32).encode_fixed_vec());
         header_bytes.append(&mut vec![t as u8]);
 
-        let mut offset = 0;
-        offset += self.dst.write(&header_bytes)?;
-        offset += self.dst.write(&data[0..len])?;
+        self.dst.write_all(&header_bytes)?;
+        self.dst.write_all(&data[0..len])?;
 
+        let offset = header_bytes.len() + len;
         self.current_block_offset += offset;
         Ok(offset)
     }
@@ -198,6 +225,28 @@ impl<W: Write + Seek> LogWriter<W> {
     }
 }
 
+impl LogWriter<Box<dyn IoWriter>> {
+    /// 写入一条完整的日志记录
+    ///
+    /// 写入失败时回退至该记录的起始位置，使残留的部分分片被之后的记录所覆盖
+    pub(crate) fn add_record_atomic(&mut self, r: &[u8]) -> KernelResult<usize> {
+        let start_pos = self.dst.current_pos()?;
+        let block_offset = self.current_block_offset;
+
+        self.add_record(r).map_err(|err| {
+            if self.dst.seek(SeekFrom::Start(start_pos)).is_ok() {
+                self.current_block_offset = block_offset;
+            }
+            err
+        })
+    }
+
+    /// 将已写入的日志持久化至硬盘，`use_fsync`为true时一同同步文件元数据
+    pub(crate) fn sync(&mut self, use_fsync: bool) -> KernelResult<()> {
+        self.dst.sync(use_fsync)
+    }
+}
+
 pub(crate) struct LogReader<R: Read + Seek> {
     src: R,
     offset: usize,
@@ -219,6 +268,8 @@ impl<R: Read + Seek> LogReader<R> {
     pub(crate) fn read(&mut self, dst: &mut Vec<u8>) -> KernelResult<usize> {
         let mut dst_offset = 0;
         let mut head_pos = 0;
+        // 是否已读取记录的起始分片而尚未读取到结尾分片
+        let mut is_fragmented = false;
 
         dst.clear();
 
@@ -232,10 +283,13 @@ impl<R: Read + Seek> LogReader<R> {
                 self.offset = 0;
             }
 
-            head_pos += self.src.read(&mut self.head_scratch[head_pos..])?;
+            let read_len = self.src.read(&mut self.head_scratch[head_pos..])?;
+            head_pos += read_len;
             // EOF
-            if head_pos == 0 {
-                return Ok(dst_offset);
+            if read_len == 0 {
+                // 缺少结尾分片(或Header不完整)的记录未完整写入，视作不存在
+                dst.clear();
+                return Ok(0);
             } else if head_pos != HEADER_SIZE {
                 continue;
             } else {
@@ -246,21 +300,37 @@ impl<R: Read + Seek> LogReader<R> {
 
             let crc = u32::decode_fixed(&self.head_scratch[0..4]);
             let length = u32::decode_fixed(&self.head_scratch[4..8]) as usize;
+            // 单个分片不会超出Block，超出时为损坏的数据
+            if length > self.block_size - HEADER_SIZE {
+                return Err(KernelError::CrcMisMatch);
+            }
 
             let mut buf = vec![0; length];
 
             self.src.read_exact(&mut buf)?;
             self.offset += length;
-            dst_offset += length;
 
             if crc32fast::hash(&buf) != crc {
                 return Err(KernelError::CrcMisMatch);
             }
 
+            let record_type = RecordType::from(self.head_scratch[8]);
+            match record_type {
+                // 上一条记录缺少结尾分片(写入失败时所残留)，丢弃后从该记录重新开始
+                RecordType::Full | RecordType::First if is_fragmented => {
+                    dst.clear();
+                    dst_offset = 0;
+                }
+                // 缺少起始分片的残留分片
+                RecordType::Middle | RecordType::Last if !is_fragmented => continue,
+                _ => (),
+            }
+            dst_offset += length;
             dst.append(&mut buf);
 
-            if let RecordType::Full | RecordType::Last = RecordType::from(self.head_scratch[8]) {
-                return Ok(dst_offset);
+            match record_type {
+                RecordType::Full | RecordType::Last => return Ok(dst_offset),
+                RecordType::First | RecordType::Middle => is_fragmented = true,
             }
         }
     }
@@ -335,6 +405,7 @@ mod tests {
 
         let fs = OpenOptions::new()
             .create(true)
+            .truncate(false)
             .write(true)
             .read(true)
             .open(file_path.clone())?;
@@ -376,7 +447,7 @@ mod tests {
     #[test]
     fn test_log_loader() -> KernelResult<()> {
         let temp_dir = TempDir::new().expect("unable to create temporary working directory");
-        let config = Config::new(temp_dir.into_path());
+        let config = Config::new(temp_dir.keep());
 
         let (loader, _) = LogLoader::reload(
             config.path(),
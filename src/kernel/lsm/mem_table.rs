@@ -1,21 +1,27 @@
 use crate::kernel::io::IoWriter;
+use crate::kernel::lsm::format::FORMAT_VERSION;
 use crate::kernel::lsm::iterator::{Iter, Seek, SeekIter};
 use crate::kernel::lsm::log::{LogLoader, LogWriter};
+use crate::kernel::lsm::negative_cache::NegativeCache;
 use crate::kernel::lsm::storage::{Config, Gen, Sequence};
 use crate::kernel::lsm::table::ss_table::block::{Entry, Value};
 use crate::kernel::lsm::trigger::{Trigger, TriggerFactory};
 use crate::kernel::KernelResult;
+use crate::KernelError;
 use bytes::Bytes;
 use itertools::Itertools;
 use parking_lot::Mutex;
 use skiplist::{skipmap, SkipMap};
 use std::cmp::Ordering;
-use std::collections::Bound;
+use std::collections::{Bound, HashSet, VecDeque};
 use std::io::Cursor;
+use std::iter;
 use std::mem;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering::Acquire;
 use std::sync::Arc;
+use tokio::sync::futures::Notified;
+use tokio::sync::Notify;
 
 pub(crate) const DEFAULT_WAL_PATH: &str = "wal";
 
@@ -29,6 +35,20 @@ pub(crate) type KeyValue = (Bytes, Option<Bytes>);
 /// 用于默认的key的填充(补充使UserKey为高位，因此默认获取最新的seq_id数据)
 const SEQ_MAX: i64 = i64::MAX;
 
+/// 解码WAL中的一条日志记录，恢复的数据统一以0作为seq_id
+fn decode_wal_record(
+    bytes: &mut Vec<u8>,
+    records: &mut Vec<(InternalKey, Option<Bytes>)>,
+) -> KernelResult<()> {
+    for (_, Entry { key, item, .. }) in
+        Entry::<Value>::batch_decode(&mut Cursor::new(mem::take(bytes)), FORMAT_VERSION)?
+    {
+        records.push((InternalKey::new_with_seq(key, 0), item.bytes));
+    }
+
+    Ok(())
+}
+
 pub(crate) fn key_value_bytes_len(key_value: &KeyValue) -> usize {
     key_value.0.len() + key_value.1.as_ref().map(Bytes::len).unwrap_or(0)
 }
@@ -143,11 +163,39 @@ impl<'a> SeekIter<'a> for MemMapIter<'a> {
 pub(crate) struct MemTable {
     inner: Mutex<TableInner>,
     pub(crate) tx_count: AtomicUsize,
+    /// Immut Table持久化完成时进行通知，用于唤醒因Immut Table已满而等待的写入
+    flush_notify: Notify,
+    /// 开启`Config::enable_negative_cache`时缓存查询未命中的Key，写入时在此失效
+    pub(crate) negative_cache: Option<NegativeCache>,
+}
+
+/// 被封存的MemTable
+pub(crate) struct ImmutTable {
+    /// 对应的WAL Gen，同时也是其持久化后的Table Gen
+    gen: i64,
+    mem_map: Arc<MemMap>,
+    is_flushed: bool,
+    /// 封存时MemTable占用的内存大小
+    memory_usage: usize,
+}
+
+/// 尝试封存MemTable的结果
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub(crate) enum SealStatus {
+    Sealed,
+    /// MemTable中没有数据
+    Empty,
+    /// 待持久化的Immut Table数量已达到`Config::max_write_buffer_number`
+    ImmutFull,
 }
 
 pub(crate) struct TableInner {
     pub(crate) _mem: MemMap,
-    pub(crate) _immut: Option<Arc<MemMap>>,
+    /// 已封存的MemTable，越新的越靠前
+    ///
+    /// 已持久化的Immut Table会保留至下一次封存，以供封存前开启的事务读取
+    _immut: VecDeque<ImmutTable>,
+    max_write_buffer_number: usize,
     /// WAL载入器
     ///
     /// 用于异常停机时MemTable的恢复
@@ -155,15 +203,123 @@ pub(crate) struct TableInner {
     log_loader: LogLoader,
     log_writer: (LogWriter<Box<dyn IoWriter>>, i64),
     trigger: Box<dyn Trigger + Send>,
+    /// 开启`Config::manual_wal_flush`时尚未写入WAL的日志记录
+    ///
+    /// 为None时则每次写入都直接写入WAL
+    pending_records: Option<Vec<Vec<u8>>>,
+    /// 已随Immut Table的替换而从MemTable中移除的最大seq_id
+    ///
+    /// 小于该值的seq_id的变更已无法通过MemTable获取
+    compacted_seq: i64,
+    /// 当前MemTable对应的WAL触发封存的字节数阈值，为0时不限制
+    wal_size_limit: usize,
+    /// 当前MemTable已写入(包括暂存于内存中)WAL的日志记录字节数
+    wal_size: usize,
+    /// 当前MemTable中所有键值对占用的内存大小
+    memory_usage: usize,
+    /// 同步WAL时是否使用fsync
+    use_fsync: bool,
+    /// 是否开启`Config::inplace_update`
+    inplace_update: bool,
 }
 
-macro_rules! check_count {
-    ($count:ident) => {
-        if 0 != $count.load(Acquire) {
-            std::hint::spin_loop();
-            continue;
+impl TableInner {
+    /// 写入日志记录，开启`Config::manual_wal_flush`时仅暂存于内存中
+    fn append_record(&mut self, record: Vec<u8>) -> KernelResult<()> {
+        if let Some(pending_records) = &mut self.pending_records {
+            self.wal_size += record.len();
+            pending_records.push(record);
+        } else {
+            let _ = self.log_writer.0.add_record_atomic(&record)?;
+            self.wal_size += record.len();
         }
-    };
+
+        Ok(())
+    }
+
+    /// MemTable是否达到触发器阈值或其WAL大小是否超过`Config::wal_size_limit`
+    fn is_exceeded(&self) -> bool {
+        self.trigger.is_exceeded()
+            || (self.wal_size_limit > 0 && self.wal_size >= self.wal_size_limit)
+    }
+
+    fn immut_maps(&self) -> impl Iterator<Item = &Arc<MemMap>> {
+        self._immut.iter().map(|immut| &immut.mem_map)
+    }
+
+    fn unflushed_len(&self) -> usize {
+        self._immut.iter().filter(|immut| !immut.is_flushed).count()
+    }
+
+    /// 将当前MemTable封存为Immut Table，并切换至新的WAL
+    ///
+    /// is_releasable为true时移除已持久化的Immut Table，需要确保此时不存在进行中的事务
+    fn seal(&mut self, is_releasable: bool) -> KernelResult<SealStatus> {
+        if self._mem.is_empty() {
+            return Ok(SealStatus::Empty);
+        }
+        self.trigger.reset();
+        self.wal_size = 0;
+        let memory_usage = mem::take(&mut self.memory_usage);
+
+        // 已持久化的Immut Table的数据已存在于Version中，此时没有事务因此可以安全移除
+        if is_releasable {
+            let mut compacted_seq = self.compacted_seq;
+            self._immut.retain(|immut| {
+                if immut.is_flushed {
+                    if let Some(max_seq) = immut.mem_map.iter().map(|(key, _)| key.seq_id).max() {
+                        compacted_seq = compacted_seq.max(max_seq);
+                    }
+                }
+                !immut.is_flushed
+            });
+            self.compacted_seq = compacted_seq;
+        }
+
+        // 将未写入的日志记录写入旧WAL中，以便Level 0的Table异常时仍可恢复
+        self.write_pending_records()?;
+        let new_gen = Gen::create();
+        let new_writer = (self.log_loader.writer(new_gen)?, new_gen);
+        let (mut old_writer, old_gen) = mem::replace(&mut self.log_writer, new_writer);
+        old_writer.flush()?;
+
+        self._immut.push_front(ImmutTable {
+            gen: old_gen,
+            mem_map: Arc::new(mem::replace(&mut self._mem, SkipMap::new())),
+            is_flushed: false,
+            memory_usage,
+        });
+
+        Ok(SealStatus::Sealed)
+    }
+
+    /// 移除MemTable中该Key的最新版本
+    fn remove_latest(&mut self, key: &Bytes) {
+        let internal_key = InternalKey::new_with_seq(key.clone(), SEQ_MAX);
+        let latest_key = self
+            ._mem
+            .upper_bound(Bound::Included(&internal_key))
+            .filter(|(latest_key, _)| latest_key.key == *key)
+            .map(|(latest_key, _)| latest_key.clone());
+
+        if let Some(latest_key) = latest_key {
+            if let Some(value) = self._mem.remove(&latest_key) {
+                self.memory_usage = self
+                    .memory_usage
+                    .saturating_sub(key_value_memory_usage(key, &value));
+            }
+        }
+    }
+
+    fn write_pending_records(&mut self) -> KernelResult<()> {
+        if let Some(pending_records) = &mut self.pending_records {
+            for record in pending_records.drain(..) {
+                let _ = self.log_writer.0.add_record_atomic(&record)?;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 macro_rules! range_iter {
@@ -185,72 +341,145 @@ impl MemTable {
             (DEFAULT_WAL_PATH, None),
             config.wal_io_type,
             &mut log_records,
-            |bytes, records| {
-                for (_, Entry { key, item, .. }) in
-                    Entry::<Value>::batch_decode(&mut Cursor::new(mem::take(bytes)))?
-                {
-                    records.push((InternalKey::new_with_seq(key, 0), item.bytes));
-                }
-
-                Ok(())
-            },
+            decode_wal_record,
         )?;
-        let log_writer = (log_loader.writer(log_gen)?, log_gen);
+        // 追加写入已恢复的WAL，避免覆盖其中尚未持久化的数据
+        let mut log_writer = (log_loader.append_writer(log_gen)?, log_gen);
+        let wal_size = log_writer.0.seek_end()? as usize;
         // Q: 为什么INIT_SEQ作为Seq id?
         // A: 因为此处是当存在有停机异常时使用wal恢复数据,此处也不存在有Version(VersionStatus的初始化在此代码之后)
         // 因此不会影响Version的读取顺序
+        let memory_usage = log_records
+            .iter()
+            .map(|(key, value)| key_value_memory_usage(&key.key, value))
+            .sum();
         let mem_map = MemMap::from_iter(log_records);
         let (trigger_type, threshold) = config.minor_trigger_with_threshold;
 
         Ok(MemTable {
             inner: Mutex::new(TableInner {
                 _mem: mem_map,
-                _immut: None,
+                _immut: VecDeque::new(),
+                max_write_buffer_number: config.max_write_buffer_number.max(1),
                 log_loader,
                 log_writer,
                 trigger: TriggerFactory::create(trigger_type, threshold),
+                pending_records: config.manual_wal_flush.then(Vec::new),
+                compacted_seq: 0,
+                wal_size_limit: config.wal_size_limit,
+                wal_size,
+                memory_usage,
+                use_fsync: config.use_fsync,
+                inplace_update: config.inplace_update,
             }),
             tx_count: AtomicUsize::new(0),
+            flush_notify: Notify::new(),
+            negative_cache: config
+                .enable_negative_cache
+                .then(NegativeCache::new)
+                .transpose()?,
         })
     }
 
+    /// 将最新的WAL之外尚未持久化的WAL恢复为待持久化的Immut Table，返回恢复的数量
+    ///
+    /// `Config::max_write_buffer_number`大于1时停机前可能存在多个尚未持久化的Immut Table；
+    /// Immut Table由旧至新持久化且其Table沿用WAL的Gen，因此新于Version(`live_gens`)中最新一个已持久化的WAL
+    /// 且未被Version引用的WAL即尚未持久化，而更旧的WAL则属于已被压缩而待清理的Table
+    pub(crate) fn recover_immuts(&self, live_gens: &HashSet<i64>) -> KernelResult<usize> {
+        let mut inner = self.inner.lock();
+        let current_gen = inner.log_writer.1;
+        let wal_gens = inner.log_loader.gens()?;
+        let last_flushed = wal_gens
+            .iter()
+            .copied()
+            .filter(|gen| live_gens.contains(gen))
+            .max();
+        let mut recovered = 0;
+
+        for gen in wal_gens {
+            if gen >= current_gen
+                || live_gens.contains(&gen)
+                || last_flushed.is_some_and(|flushed| gen < flushed)
+            {
+                continue;
+            }
+            let mut records = Vec::new();
+            inner
+                .log_loader
+                .load(gen, &mut records, decode_wal_record)?;
+            if records.is_empty() {
+                inner.log_loader.clean(gen)?;
+                continue;
+            }
+            let memory_usage = records
+                .iter()
+                .map(|(key, value)| key_value_memory_usage(&key.key, value))
+                .sum();
+
+            // 由旧至新恢复，越新的越靠前
+            inner._immut.push_front(ImmutTable {
+                gen,
+                mem_map: Arc::new(MemMap::from_iter(records)),
+                is_flushed: false,
+                memory_usage,
+            });
+            recovered += 1;
+        }
+
+        Ok(recovered)
+    }
+
     pub(crate) fn check_key_conflict(&self, kvs: &[KeyValue], seq_id: i64) -> bool {
         let inner = self.inner.lock();
 
-        for (key, _) in kvs {
+        // 事务存活时Immut Table不会被释放，因此事务开启后的写入必然存在于MemTable或Immut Table中
+        kvs.iter().any(|(key, _)| {
             let internal_key = InternalKey::new_with_seq(key.clone(), seq_id);
 
-            if let Some(true) = inner
-                ._mem
-                .lower_bound(Bound::Excluded(&internal_key))
-                .map(|(lower_key, _)| lower_key.key == key)
-            {
-                return true;
-            }
-        }
-
-        false
+            iter::once(&inner._mem)
+                .chain(inner.immut_maps().map(Arc::as_ref))
+                .any(|mem_map| {
+                    matches!(
+                        mem_map
+                            .lower_bound(Bound::Excluded(&internal_key))
+                            .map(|(lower_key, _)| lower_key.key == key),
+                        Some(true)
+                    )
+                })
+        })
     }
 
     /// 插入并判断是否溢出
     ///
     /// 插入时不会去除重复键值，而是进行追加
+    /// 写入WAL与插入MemTable在同一把锁内同步完成，且Sequence也在锁内生成，
+    /// 因此并发写入时WAL中的记录顺序与MemTable中的Sequence顺序一致，无需额外的WAL写入任务
+    ///
+    /// 开启`Config::inplace_update`且不存在事务时，会替换MemTable中该Key的旧版本
     pub(crate) fn insert_data(&self, data: KeyValue) -> KernelResult<bool> {
         let mut inner = self.inner.lock();
 
-        let _ = inner
-            .log_writer
-            .0
-            .add_record(&data_to_bytes(data.clone())?)?;
-
+        inner.append_record(data_to_bytes(data.clone())?)?;
         inner.trigger.item_process(&data);
         let (key, value) = data;
-        let _ = inner._mem.insert(InternalKey::new(key), value);
+        // 先生成Sequence再检查事务数量：检查后开启的事务的seq_id必然大于此次写入，
+        // 因此不会需要被替换的旧版本
+        let internal_key = InternalKey::new(key);
+        if let Some(negative_cache) = &self.negative_cache {
+            negative_cache.invalidate(&internal_key.key);
+        }
+        if inner.inplace_update && self.tx_count.load(Acquire) == 0 {
+            inner.remove_latest(&internal_key.key);
+        }
+        inner.memory_usage += key_value_memory_usage(&internal_key.key, &value);
+        let _ = inner._mem.insert(internal_key, value);
 
-        Ok(inner.trigger.is_exceeded())
+        Ok(inner.is_exceeded())
     }
 
-    /// Tips: 当数据在插入mem_table中停机，则不会存入日志中
+    /// 整批数据作为一条日志记录写入WAL后才插入MemTable，
+    /// 因此WAL写入失败时不会产生任何变更，恢复时也仅会看到完整的一批或完全看不到
     pub(crate) fn insert_batch_data(
         &self,
         vec_data: Vec<KeyValue>,
@@ -259,73 +488,204 @@ impl MemTable {
         let mut inner = self.inner.lock();
 
         let mut buf = Vec::new();
+        for item in &vec_data {
+            buf.append(&mut data_to_bytes(item.clone())?);
+        }
+        inner.append_record(buf)?;
+
         for item in vec_data {
-            let (key, value) = item.clone();
             inner.trigger.item_process(&item);
+            let (key, value) = item;
+            inner.memory_usage += key_value_memory_usage(&key, &value);
+            if let Some(negative_cache) = &self.negative_cache {
+                negative_cache.invalidate(&key);
+            }
 
             let _ = inner
                 ._mem
                 .insert(InternalKey::new_with_seq(key, seq_id), value);
-            buf.append(&mut data_to_bytes(item)?);
         }
-        let _ = inner.log_writer.0.add_record(&buf)?;
 
-        Ok(inner.trigger.is_exceeded())
+        Ok(inner.is_exceeded())
+    }
+
+    /// 将所有已插入的数据写入WAL并持久化至硬盘
+    pub(crate) fn sync_wal(&self) -> KernelResult<()> {
+        let mut inner = self.inner.lock();
+
+        inner.write_pending_records()?;
+        inner.log_writer.0.flush()?;
+        let use_fsync = inner.use_fsync;
+        inner.log_writer.0.sync(use_fsync)
+    }
+
+    /// 将尚未持久化的Immut Table的WAL同步至硬盘
+    ///
+    /// 封存时旧WAL仅flush至OS，持有锁期间Immut Table不会被标记为已持久化，因此其WAL不会被清理
+    pub(crate) fn sync_immut_wals(&self) -> KernelResult<()> {
+        let inner = self.inner.lock();
+
+        for immut in inner._immut.iter().filter(|immut| !immut.is_flushed) {
+            inner.log_loader.sync(immut.gen, inner.use_fsync)?;
+        }
+
+        Ok(())
     }
 
+    /// MemTable与尚未持久化的Immut Table是否为空
     pub(crate) fn is_empty(&self) -> bool {
-        self.inner.lock()._mem.is_empty()
+        let inner = self.inner.lock();
+
+        inner._mem.is_empty()
+            && inner
+                ._immut
+                .iter()
+                .all(|immut| immut.is_flushed || immut.mem_map.is_empty())
     }
 
+    /// MemTable与尚未持久化的Immut Table中的数据数量
     pub(crate) fn len(&self) -> usize {
-        self.inner.lock()._mem.len()
+        let inner = self.inner.lock();
+
+        inner._mem.len()
+            + inner
+                ._immut
+                .iter()
+                .filter(|immut| !immut.is_flushed)
+                .map(|immut| immut.mem_map.len())
+                .sum::<usize>()
+    }
+
+    /// MemTable、Immut Table与尚未写入WAL的日志记录占用的内存大小
+    pub(crate) fn memory_usage(&self) -> usize {
+        let inner = self.inner.lock();
+
+        inner.memory_usage
+            + inner
+                ._immut
+                .iter()
+                .map(|immut| immut.memory_usage)
+                .sum::<usize>()
+            + inner
+                .pending_records
+                .iter()
+                .flatten()
+                .map(Vec::len)
+                .sum::<usize>()
+    }
+
+    /// 是否存在尚未持久化的Immut Table
+    pub(crate) fn has_unflushed(&self) -> bool {
+        self.inner.lock().unflushed_len() > 0
     }
 
     pub(crate) fn log_loader_clone(&self) -> LogLoader {
         self.inner.lock().log_loader.clone()
     }
 
-    /// MemTable将数据弹出并转移到immut table中  (弹出数据为转移至immut table中数据的迭代器)
-    pub(crate) fn swap(&self) -> KernelResult<Option<(i64, Vec<KeyValue>)>> {
-        let count = &self.tx_count;
+    /// 当前MemTable是否已达到封存阈值
+    pub(crate) fn is_exceeded(&self) -> bool {
+        self.inner.lock().is_exceeded()
+    }
 
-        loop {
-            check_count!(count);
+    /// 等待的Immut Table持久化完成的通知
+    ///
+    /// 需在检查`SealStatus::ImmutFull`之前获取，避免错过通知
+    pub(crate) fn flushed(&self) -> Notified<'_> {
+        self.flush_notify.notified()
+    }
 
-            let mut inner = self.inner.lock();
-            // 二重检测防止lock时(前)突然出现事务
-            // 当lock后，即使出现事务，会因为lock已被Compactor获取而无法读写，
-            // 因此不会对读写进行干扰
-            // 并且事务即使在lock后出现，所持有的seq为该压缩之前，
-            // 也不会丢失该seq的_mem，因为转移到了_immut，可以从_immut得到对应seq的数据
-            check_count!(count);
-
-            return if !inner._mem.is_empty() {
-                inner.trigger.reset();
-
-                let mut vec_data = inner
-                    ._mem
-                    .iter()
-                    .map(|(k, v)| (k.key.clone(), v.clone()))
-                    // rev以使用最后(最新)的key
-                    .rev()
-                    .unique_by(|(k, _)| k.clone())
-                    .collect_vec();
-
-                vec_data.reverse();
-
-                inner._immut = Some(Arc::new(mem::replace(&mut inner._mem, SkipMap::new())));
-
-                let new_gen = Gen::create();
-                let new_writer = (inner.log_loader.writer(new_gen)?, new_gen);
-                let (mut old_writer, old_gen) = mem::replace(&mut inner.log_writer, new_writer);
-                old_writer.flush()?;
-
-                Ok(Some((old_gen, vec_data)))
-            } else {
-                Ok(None)
-            };
+    /// 将MemTable封存为Immut Table，不受`Config::max_write_buffer_number`的限制
+    ///
+    /// 不会等待进行中的事务结束: 事务开启前的数据仍可从Immut Table中读取，
+    /// 而存在进行中的事务时，已持久化的Immut Table会保留至事务结束后的下一次封存
+    pub(crate) fn seal(&self) -> KernelResult<SealStatus> {
+        let mut inner = self.inner.lock();
+
+        inner.seal(self.is_releasable())
+    }
+
+    /// 尝试将MemTable封存为Immut Table
+    ///
+    /// 待持久化的Immut Table已满时返回`SealStatus::ImmutFull`
+    pub(crate) fn try_seal(&self) -> KernelResult<SealStatus> {
+        let mut inner = self.inner.lock();
+
+        if inner.unflushed_len() >= inner.max_write_buffer_number {
+            return Ok(SealStatus::ImmutFull);
+        }
+
+        inner.seal(self.is_releasable())
+    }
+
+    /// 是否可以释放已持久化的Immut Table
+    ///
+    /// 需在持有`inner`锁时调用: 此后开启的事务所获取的Version已包含这些Immut Table的数据
+    fn is_releasable(&self) -> bool {
+        0 == self.tx_count.load(Acquire)
+    }
+
+    /// 获取最旧的待持久化的Immut Table的Gen与其去重后的有序数据
+    pub(crate) fn next_unflushed(&self) -> Option<(i64, Vec<KeyValue>)> {
+        let (gen, mem_map) = self
+            .inner
+            .lock()
+            ._immut
+            .iter()
+            .rev()
+            .find(|immut| !immut.is_flushed)
+            .map(|immut| (immut.gen, Arc::clone(&immut.mem_map)))?;
+
+        let mut vec_data = mem_map
+            .iter()
+            .map(|(k, v)| (k.key.clone(), v.clone()))
+            // rev以使用最后(最新)的key
+            .rev()
+            .unique_by(|(k, _)| k.clone())
+            .collect_vec();
+        vec_data.reverse();
+
+        Some((gen, vec_data))
+    }
+
+    /// 标记Immut Table已持久化，并唤醒等待的写入
+    pub(crate) fn mark_flushed(&self, gen: i64) {
+        if let Some(immut) = self
+            .inner
+            .lock()
+            ._immut
+            .iter_mut()
+            .find(|immut| immut.gen == gen)
+        {
+            immut.is_flushed = true;
         }
+        self.flush_notify.notify_waiters();
+    }
+
+    /// 唤醒所有因Immut Table已满而等待的写入
+    pub(crate) fn notify_flushed(&self) {
+        self.flush_notify.notify_waiters();
+    }
+
+    /// 获取seq_id大于`seq`的所有变更，并以seq_id排序
+    ///
+    /// 当`seq`所对应的变更已被转移至SSTable时返回`KernelError::SeqCompacted`
+    pub(crate) fn updates_since(&self, seq: i64) -> KernelResult<Vec<(i64, KeyValue)>> {
+        let inner = self.inner.lock();
+
+        if seq < inner.compacted_seq {
+            return Err(KernelError::SeqCompacted);
+        }
+        let mut updates = inner
+            .immut_maps()
+            .flat_map(|mem_map| mem_map.iter())
+            .chain(inner._mem.iter())
+            .filter(|(InternalKey { seq_id, .. }, _)| *seq_id > seq)
+            .map(|(InternalKey { key, seq_id }, value)| (*seq_id, (key.clone(), value.clone())))
+            .collect_vec();
+        updates.sort_by_key(|(seq_id, _)| *seq_id);
+
+        Ok(updates)
     }
 
     pub(crate) fn find(&self, key: &[u8]) -> Option<KeyValue> {
@@ -335,9 +695,8 @@ impl MemTable {
 
         Self::find_(&internal_key, &inner._mem).or_else(|| {
             inner
-                ._immut
-                .as_ref()
-                .and_then(|mem_map| Self::find_(&internal_key, mem_map))
+                .immut_maps()
+                .find_map(|mem_map| Self::find_(&internal_key, mem_map))
         })
     }
 
@@ -346,13 +705,11 @@ impl MemTable {
         let internal_key = InternalKey::new_with_seq(Bytes::copy_from_slice(key), seq_id);
         let inner = self.inner.lock();
 
-        if let Some(key_value) = MemTable::find_(&internal_key, &inner._mem) {
-            Some(key_value)
-        } else if let Some(mem_map) = &inner._immut {
-            MemTable::find_(&internal_key, mem_map)
-        } else {
-            None
-        }
+        MemTable::find_(&internal_key, &inner._mem).or_else(|| {
+            inner
+                .immut_maps()
+                .find_map(|mem_map| MemTable::find_(&internal_key, mem_map))
+        })
     }
 
     fn find_(internal_key: &InternalKey, mem_map: &MemMap) -> Option<KeyValue> {
@@ -395,59 +752,54 @@ impl MemTable {
         let max_key = to_internal_key(&max, i64::MAX, i64::MIN);
 
         let mut merged = Vec::new();
-        let fn_push =
-            |results: &mut Vec<KeyValue>, item: &mut Option<(&Bytes, &Option<Bytes>)>, new_item| {
-                if let Some((internal_key, value)) = mem::replace(item, new_item) {
-                    Self::duplicates_push(results, internal_key, value);
-                }
+        range_iter!(inner._mem, min_key, max_key, option_seq).for_each(|(internal_key, value)| {
+            Self::duplicates_push(&mut merged, internal_key, value)
+        });
+        // Immut Table由新至旧依次合并，相同Key时以较新的数据为准
+        for mem_map in inner.immut_maps() {
+            let immut_iter = range_iter!(mem_map, min_key, max_key, option_seq);
+
+            merged = Self::merge_newer_wins(merged, immut_iter);
+        }
+
+        merged.reverse();
+        assert!(merged.is_sorted_by_key(|(k, _)| k));
+        assert!(merged.iter().all_unique());
+        merged
+    }
+
+    /// 合并两组降序数据，Key相同时保留`newer`中的数据
+    fn merge_newer_wins<'a>(
+        newer: Vec<KeyValue>,
+        older: impl Iterator<Item = (&'a Bytes, &'a Option<Bytes>)>,
+    ) -> Vec<KeyValue> {
+        let mut results = Vec::with_capacity(newer.len());
+        let mut newer_iter = newer.into_iter().peekable();
+        let mut older_iter = older.peekable();
+
+        loop {
+            let ordering = match (newer_iter.peek(), older_iter.peek()) {
+                (Some((newer_key, _)), Some((older_key, _))) => newer_key.cmp(older_key),
+                (Some(_), None) => Ordering::Greater,
+                (None, Some(_)) => Ordering::Less,
+                (None, None) => break,
             };
-        let mut mem_iter = range_iter!(inner._mem, min_key, max_key, option_seq);
-
-        if let Some(immut) = &inner._immut {
-            let mut immut_mem_iter = range_iter!(immut, min_key, max_key, option_seq);
-            let (mut mem_current, mut immut_mem_current) = (mem_iter.next(), immut_mem_iter.next());
-
-            while mem_current.is_some() && immut_mem_current.is_some() {
-                match mem_current
-                    .as_ref()
-                    .unwrap()
-                    .0
-                    .cmp(immut_mem_current.as_ref().unwrap().0)
-                {
-                    Ordering::Greater => fn_push(&mut merged, &mut mem_current, mem_iter.next()),
-                    Ordering::Less => {
-                        fn_push(&mut merged, &mut immut_mem_current, immut_mem_iter.next())
-                    }
-                    Ordering::Equal => {
-                        fn_push(&mut merged, &mut mem_current, mem_iter.next());
 
-                        immut_mem_current = immut_mem_iter.next();
+            match ordering {
+                Ordering::Greater => results.extend(newer_iter.next()),
+                Ordering::Less => {
+                    if let Some((key, value)) = older_iter.next() {
+                        Self::duplicates_push(&mut results, key, value);
                     }
                 }
+                Ordering::Equal => {
+                    results.extend(newer_iter.next());
+                    let _ = older_iter.next();
+                }
             }
-
-            if let Some((internal_key, value)) = mem_current {
-                Self::duplicates_push(&mut merged, internal_key, value);
-            }
-            if let Some((internal_key, value)) = immut_mem_current {
-                Self::duplicates_push(&mut merged, internal_key, value);
-            }
-            // one of the two is empty
-            mem_iter
-                .chain(immut_mem_iter)
-                .for_each(|(internal_key, value)| {
-                    Self::duplicates_push(&mut merged, internal_key, value)
-                });
-        } else {
-            mem_iter.for_each(|(internal_key, value)| {
-                Self::duplicates_push(&mut merged, internal_key, value)
-            });
         }
 
-        merged.reverse();
-        assert!(merged.is_sorted_by_key(|(k, _)| k));
-        assert!(merged.iter().all_unique());
-        merged
+        results
     }
 
     fn duplicates_push(results: &mut Vec<KeyValue>, key: &Bytes, value: &Option<Bytes>) {
@@ -471,6 +823,13 @@ impl MemTable {
     }
 }
 
+/// 估算单个键值对在MemTable中占用的内存大小
+fn key_value_memory_usage(key: &Bytes, value: &Option<Bytes>) -> usize {
+    mem::size_of::<(InternalKey, Option<Bytes>)>()
+        + key.len()
+        + value.as_ref().map_or(0, Bytes::len)
+}
+
 pub(crate) fn data_to_bytes(data: KeyValue) -> KernelResult<Vec<u8>> {
     let (key, value) = data.clone();
     let mut bytes = Vec::new();
@@ -481,16 +840,70 @@ pub(crate) fn data_to_bytes(data: KeyValue) -> KernelResult<Vec<u8>> {
 
 #[cfg(test)]
 mod tests {
+    use crate::kernel::io::{FileExtension, IoFactory, IoWriter};
+    use crate::kernel::lsm::format::FORMAT_VERSION;
     use crate::kernel::lsm::iterator::{Iter, Seek, SeekIter};
+    use crate::kernel::lsm::log::LogWriter;
     use crate::kernel::lsm::mem_table::{
-        data_to_bytes, InternalKey, KeyValue, MemMap, MemMapIter, MemTable,
+        data_to_bytes, key_value_memory_usage, InternalKey, KeyValue, MemMap, MemMapIter, MemTable,
+        SealStatus, DEFAULT_WAL_PATH,
     };
     use crate::kernel::lsm::storage::{Config, Sequence};
+    use crate::kernel::lsm::table::ss_table::block::{Entry, Value};
+    use crate::kernel::lsm::trigger::TriggerType;
     use crate::kernel::KernelResult;
     use bytes::Bytes;
-    use std::collections::Bound;
+    use itertools::Itertools;
+    use std::collections::{Bound, HashSet};
+    use std::io;
+    use std::io::{Cursor, SeekFrom, Write};
+    use std::mem;
+    use std::sync::atomic::Ordering;
     use tempfile::TempDir;
 
+    /// 写入指定字节数后便返回错误的Writer，用于模拟WAL写入中途失败
+    struct FailingWriter {
+        inner: Box<dyn IoWriter>,
+        remaining: usize,
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.remaining == 0 {
+                return Err(io::Error::other("injected wal failure"));
+            }
+            let len = buf.len().min(self.remaining);
+            let written = self.inner.write(&buf[..len])?;
+            self.remaining -= written;
+
+            Ok(written)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    impl std::io::Seek for FailingWriter {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    impl IoWriter for FailingWriter {
+        fn current_pos(&mut self) -> KernelResult<u64> {
+            self.inner.current_pos()
+        }
+
+        fn sync_data(&mut self) -> KernelResult<()> {
+            self.inner.sync_data()
+        }
+
+        fn sync_all(&mut self) -> KernelResult<()> {
+            self.inner.sync_all()
+        }
+    }
+
     impl MemTable {
         pub(crate) fn insert_data_with_seq(&self, data: KeyValue, seq: i64) -> KernelResult<usize> {
             let (key, value) = data.clone();
@@ -505,6 +918,243 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_manual_wal_flush() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path()).manual_wal_flush(true);
+        let times = 100;
+
+        let mem_table = MemTable::new(&config)?;
+        for i in 0..times {
+            let key = Bytes::from(bincode::serialize(&i)?);
+            let _ = mem_table.insert_data((key.clone(), Some(key)))?;
+        }
+        mem_table.sync_wal()?;
+        for i in times..times * 2 {
+            let key = Bytes::from(bincode::serialize(&i)?);
+            let _ = mem_table.insert_data((key.clone(), Some(key)))?;
+        }
+        // 模拟停机: 未调用sync_wal便直接丢弃
+        drop(mem_table);
+
+        let mem_table = MemTable::new(&config)?;
+        assert_eq!(mem_table.len(), times);
+        for i in 0..times {
+            let key = Bytes::from(bincode::serialize(&i)?);
+            assert_eq!(mem_table.find(&key), Some((key.clone(), Some(key))));
+        }
+        for i in times..times * 2 {
+            let key = Bytes::from(bincode::serialize(&i)?);
+            assert_eq!(mem_table.find(&key), None);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_order_with_concurrent_insert() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path())
+            .minor_trigger_with_threshold(TriggerType::Count, usize::MAX);
+        let mem_table = MemTable::new(&config)?;
+
+        std::thread::scope(|scope| {
+            for t in 0..8_u8 {
+                let mem_table = &mem_table;
+                let _ = scope.spawn(move || {
+                    for i in 0..100_u32 {
+                        let _ = mem_table
+                            .insert_data((
+                                Bytes::from(vec![t]),
+                                Some(Bytes::from(i.to_be_bytes().to_vec())),
+                            ))
+                            .unwrap();
+                    }
+                });
+            }
+        });
+        mem_table.sync_wal()?;
+
+        let mut wal_records = Vec::new();
+        {
+            let inner = mem_table.inner.lock();
+            inner
+                .log_loader
+                .load(inner.log_writer.1, &mut wal_records, |bytes, records| {
+                    for (_, Entry { key, item, .. }) in Entry::<Value>::batch_decode(
+                        &mut Cursor::new(mem::take(bytes)),
+                        FORMAT_VERSION,
+                    )? {
+                        records.push((key, item.bytes));
+                    }
+
+                    Ok(())
+                })?;
+        }
+        // WAL中的记录顺序与MemTable中以Sequence排序的写入顺序一致
+        let mem_records = mem_table
+            .inner
+            .lock()
+            ._mem
+            .iter()
+            .sorted_by_key(|(internal_key, _)| internal_key.seq_id)
+            .map(|(internal_key, value)| (internal_key.key.clone(), value.clone()))
+            .collect_vec();
+        assert_eq!(wal_records.len(), 800);
+        assert_eq!(wal_records, mem_records);
+
+        // 同一写入方的多次写入在WAL中保持其调用顺序
+        for t in 0..8_u8 {
+            let values = wal_records
+                .iter()
+                .filter(|(key, _)| key[..] == [t])
+                .map(|(_, value)| value.clone().unwrap())
+                .collect_vec();
+            let expected = (0..100_u32)
+                .map(|i| Bytes::from(i.to_be_bytes().to_vec()))
+                .collect_vec();
+            assert_eq!(values, expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_atomic_with_wal_failure() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path());
+        let committed = (
+            Bytes::from_static(b"committed"),
+            Some(Bytes::from_static(b"v")),
+        );
+        // 超过单个Block大小以使该批次被拆分为多个日志片段
+        let batch = (0..64)
+            .map(|i: i32| {
+                (
+                    Bytes::from(format!("batch_{i}")),
+                    Some(Bytes::from(vec![b'v'; 1024])),
+                )
+            })
+            .collect_vec();
+
+        {
+            let mem_table = MemTable::new(&config)?;
+            let _ = mem_table.insert_data(committed.clone())?;
+            mem_table.sync_wal()?;
+
+            {
+                let mut inner = mem_table.inner.lock();
+                let gen = inner.log_writer.1;
+                let factory =
+                    IoFactory::new(config.path().join(DEFAULT_WAL_PATH), FileExtension::Log)?;
+                let mut writer = factory.writer(gen, config.wal_io_type)?;
+                let off = std::io::Seek::seek(&mut writer, SeekFrom::End(0))?;
+                inner.log_writer.0 = LogWriter::new_with_off(
+                    Box::new(FailingWriter {
+                        inner: writer,
+                        remaining: 40 * 1024,
+                    }),
+                    off as usize,
+                );
+            }
+            assert!(mem_table.insert_batch_data(batch.clone(), 1).is_err());
+            assert!(batch.iter().all(|(key, _)| mem_table.find(key).is_none()));
+            assert_eq!(mem_table.find(&committed.0), Some(committed.clone()));
+
+            mem_table.sync_wal()?;
+        }
+
+        // 重启后仅恢复已完整提交的数据，部分写入的批次不可见
+        let mem_table = MemTable::new(&config)?;
+        assert_eq!(mem_table.find(&committed.0), Some(committed));
+        assert!(batch.iter().all(|(key, _)| mem_table.find(key).is_none()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wal_size_limit() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path())
+            .minor_trigger_with_threshold(TriggerType::Count, usize::MAX)
+            .wal_size_limit(1024);
+
+        let mem_table = MemTable::new(&config)?;
+        let mut times = 0;
+        // 即使数量阈值无法达到，WAL超过大小限制后也需要封存
+        loop {
+            let key = Bytes::from(bincode::serialize(&times)?);
+            times += 1;
+            if mem_table.insert_data((key, Some(Bytes::from_static(b"v"))))? {
+                break;
+            }
+            assert!(times < 1024);
+        }
+        assert!(times > 1);
+        assert!(mem_table.is_exceeded());
+
+        assert!(matches!(mem_table.seal()?, SealStatus::Sealed));
+        assert!(!mem_table.is_exceeded());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_inplace_update() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path())
+            .minor_trigger_with_threshold(TriggerType::Count, usize::MAX)
+            .inplace_update(true);
+        let mem_table = MemTable::new(&config)?;
+        let key = Bytes::from_static(b"hot_key");
+
+        for i in 0..1_000_000_u32 {
+            let _ = mem_table
+                .insert_data((key.clone(), Some(Bytes::from(i.to_be_bytes().to_vec()))))?;
+        }
+        // 反复覆盖同一Key时MemTable仅保留最新的版本
+        assert_eq!(mem_table.len(), 1);
+        assert_eq!(
+            mem_table.memory_usage(),
+            key_value_memory_usage(&key, &Some(Bytes::from(vec![0; 4])))
+        );
+        assert_eq!(
+            mem_table.find(&key),
+            Some((
+                key.clone(),
+                Some(Bytes::from(999_999_u32.to_be_bytes().to_vec()))
+            ))
+        );
+
+        // 存在事务时旧版本仍可能被读取，因此追加写入
+        let _ = mem_table.tx_count.fetch_add(1, Ordering::Release);
+        let tx_seq_id = Sequence::create();
+        let _ = mem_table.insert_data((key.clone(), Some(Bytes::from_static(b"new"))))?;
+        assert_eq!(mem_table.len(), 2);
+        assert_eq!(
+            mem_table.find_with_sequence_id(&key, tx_seq_id),
+            Some((
+                key.clone(),
+                Some(Bytes::from(999_999_u32.to_be_bytes().to_vec()))
+            ))
+        );
+        let _ = mem_table.tx_count.fetch_sub(1, Ordering::Release);
+
+        let _ = mem_table.insert_data((key.clone(), None))?;
+        assert_eq!(mem_table.len(), 2);
+        assert_eq!(mem_table.find(&key), Some((key.clone(), None)));
+
+        // 未开启时每次写入都追加新的版本
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let mem_table = MemTable::new(&Config::new(temp_dir.path()))?;
+        for _ in 0..100 {
+            let _ = mem_table.insert_data((key.clone(), Some(Bytes::from_static(b"v"))))?;
+        }
+        assert_eq!(mem_table.len(), 100);
+
+        Ok(())
+    }
+
     #[test]
     fn test_mem_table_find() -> KernelResult<()> {
         let temp_dir = TempDir::new().expect("unable to create temporary working directory");
@@ -546,7 +1196,7 @@ mod tests {
     }
 
     #[test]
-    fn test_mem_table_swap() -> KernelResult<()> {
+    fn test_mem_table_seal() -> KernelResult<()> {
         let temp_dir = TempDir::new().expect("unable to create temporary working directory");
 
         let mem_table = MemTable::new(&Config::new(temp_dir.path()))?;
@@ -560,7 +1210,11 @@ mod tests {
         let _ = mem_table
             .insert_data((Bytes::from(vec![b'k', b'2']), Some(Bytes::from(vec![b'2']))))?;
 
-        let (_, mut vec) = mem_table.swap()?.unwrap();
+        assert_eq!(mem_table.seal()?, SealStatus::Sealed);
+        assert_eq!(mem_table.seal()?, SealStatus::Empty);
+        let (gen, mut vec) = mem_table.next_unflushed().unwrap();
+        mem_table.mark_flushed(gen);
+        assert!(mem_table.next_unflushed().is_none());
 
         assert_eq!(
             vec.pop(),
@@ -574,6 +1228,95 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_max_write_buffer_number() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path()).max_write_buffer_number(3);
+        let key = Bytes::from_static(b"k");
+
+        let mem_table = MemTable::new(&config)?;
+        for i in 0..3_u8 {
+            let _ = mem_table.insert_data((key.clone(), Some(Bytes::from(vec![i]))))?;
+            // 未进行持久化时也可以继续封存，直至达到上限
+            assert_eq!(mem_table.try_seal()?, SealStatus::Sealed);
+        }
+        // 读取时由新至旧查找Immut Table
+        assert_eq!(
+            mem_table.find(&key),
+            Some((key.clone(), Some(Bytes::from(vec![2]))))
+        );
+        assert_eq!(
+            mem_table.range_scan(Bound::Unbounded, Bound::Unbounded, None),
+            vec![(key.clone(), Some(Bytes::from(vec![2])))]
+        );
+        assert_eq!(mem_table.len(), 3);
+
+        let _ = mem_table.insert_data((key.clone(), Some(Bytes::from(vec![3]))))?;
+        assert_eq!(mem_table.try_seal()?, SealStatus::ImmutFull);
+
+        // 持久化最旧的Immut Table后可再次封存
+        let (gen, values) = mem_table.next_unflushed().unwrap();
+        assert_eq!(values, vec![(key.clone(), Some(Bytes::from(vec![0])))]);
+        mem_table.mark_flushed(gen);
+        assert_eq!(mem_table.try_seal()?, SealStatus::Sealed);
+        assert_eq!(
+            mem_table.find(&key),
+            Some((key.clone(), Some(Bytes::from(vec![3]))))
+        );
+        assert_eq!(mem_table.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_immuts() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path()).max_write_buffer_number(3);
+        let key_1 = Bytes::from_static(b"k1");
+        let key_2 = Bytes::from_static(b"k2");
+
+        let mem_table = MemTable::new(&config)?;
+        let _ = mem_table.insert_data((key_1.clone(), Some(Bytes::from_static(b"1"))))?;
+        assert_eq!(mem_table.try_seal()?, SealStatus::Sealed);
+        let _ = mem_table.insert_data((key_2.clone(), Some(Bytes::from_static(b"2"))))?;
+        assert_eq!(mem_table.try_seal()?, SealStatus::Sealed);
+        let _ = mem_table.insert_data((key_1.clone(), Some(Bytes::from_static(b"3"))))?;
+        let (flushed_gen, _) = mem_table.next_unflushed().unwrap();
+        mem_table.sync_immut_wals()?;
+        drop(mem_table);
+
+        // 仅最新的WAL被恢复为MemTable，其余尚未持久化的WAL恢复为Immut Table
+        let mem_table = MemTable::new(&config)?;
+        assert!(!mem_table.has_unflushed());
+        assert_eq!(mem_table.recover_immuts(&HashSet::new())?, 2);
+        assert_eq!(
+            mem_table.find(&key_1),
+            Some((key_1.clone(), Some(Bytes::from_static(b"3"))))
+        );
+        assert_eq!(
+            mem_table.find(&key_2),
+            Some((key_2.clone(), Some(Bytes::from_static(b"2"))))
+        );
+        let (gen, values) = mem_table.next_unflushed().unwrap();
+        assert_eq!(gen, flushed_gen);
+        assert_eq!(
+            values,
+            vec![(key_1.clone(), Some(Bytes::from_static(b"1")))]
+        );
+        drop(mem_table);
+
+        // 已被Version引用的WAL及更旧的WAL不再恢复
+        let mem_table = MemTable::new(&config)?;
+        assert_eq!(mem_table.recover_immuts(&HashSet::from([flushed_gen]))?, 1);
+        let (_, values) = mem_table.next_unflushed().unwrap();
+        assert_eq!(
+            values,
+            vec![(key_2.clone(), Some(Bytes::from_static(b"2")))]
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_mem_table_check_key_conflict() -> KernelResult<()> {
         let temp_dir = TempDir::new().expect("unable to create temporary working directory");
@@ -640,7 +1383,7 @@ mod tests {
             6
         );
 
-        let _ = mem_table.swap().unwrap();
+        let _ = mem_table.seal()?;
 
         assert_eq!(
             mem_table
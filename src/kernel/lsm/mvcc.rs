@@ -60,7 +60,7 @@ impl Transaction {
 
     /// 通过Key获取对应的Value
     ///
-    /// 此处不需要等待压缩，因为在Transaction存活时不会触发Compaction
+    /// 此处不需要等待压缩，因为在Transaction存活时已持久化的Immut Table不会被释放
     #[inline]
     pub fn get(&self, key: &[u8]) -> KernelResult<Option<Bytes>> {
         if let Some(value) = self.write_buf.as_ref().and_then(|buf| buf.get(key)) {
@@ -83,15 +83,28 @@ impl Transaction {
         let _ignore = self.write_buf_or_init().insert(key, Some(value));
     }
 
+    /// 删除Key，Key不存在时返回`KernelError::KeyNotFound`
+    ///
+    /// 若Key已在该事务中写入，则直接修改写入缓冲而不再查询MemTable与SSTable
     #[inline]
     pub fn remove(&mut self, key: &[u8]) -> KernelResult<()> {
+        if let Some(value) = self.write_buf.as_mut().and_then(|buf| buf.get_mut(key)) {
+            return value.take().map(|_| ()).ok_or(KernelError::KeyNotFound);
+        }
         let _ = self.get(key)?.ok_or(KernelError::KeyNotFound)?;
-        let bytes = Bytes::copy_from_slice(key);
-        let _ignore = self.write_buf_or_init().insert(bytes, None);
+        self.delete(key);
 
         Ok(())
     }
 
+    /// 删除Key，不检查Key是否存在因此不会产生任何读取
+    #[inline]
+    pub fn delete(&mut self, key: &[u8]) {
+        let _ignore = self
+            .write_buf_or_init()
+            .insert(Bytes::copy_from_slice(key), None);
+    }
+
     #[inline]
     pub async fn commit(mut self) -> KernelResult<()> {
         if let Some(buf) = self.write_buf.take() {
@@ -139,9 +152,58 @@ impl Transaction {
         &'a self,
         min: Bound<&[u8]>,
         max: Bound<&[u8]>,
+    ) -> KernelResult<TransactionIter> {
+        let mut vec_seek_iter = Vec::new();
+        VersionIter::merging_with_version(&self.version, min, max, &mut vec_seek_iter)?;
+
+        let mut vec_disk_iter: Vec<Box<dyn Iter<'a, Item = KeyValue> + 'a + Send + Sync>> =
+            Vec::with_capacity(vec_seek_iter.len());
+        for mut seek_iter in vec_seek_iter {
+            if let Bound::Included(key) | Bound::Excluded(key) = &min {
+                seek_iter.seek(Seek::Backward(key.as_slice()))?;
+            }
+            vec_disk_iter.push(seek_iter as Box<dyn Iter<Item = KeyValue> + Send + Sync>)
+        }
+
+        self.merging_iter(min, max, vec_disk_iter)
+    }
+
+    /// 获取所有以`prefix`开头的键值对
+    ///
+    /// 磁盘上的数据由各Table通过前缀布隆过滤器与PrefixIndex读取，
+    /// 仅读取包含该前缀的DataBlock而无需对每个Table进行Seek
+    #[inline]
+    pub fn prefix_iter(&self, prefix: &[u8]) -> KernelResult<TransactionIter> {
+        let upper = prefix_upper_bound(prefix);
+        let vec_disk_iter = self
+            .version
+            .prefix_scan(prefix)?
+            .into_iter()
+            .map(|vec_kv| {
+                Box::new(VecIter {
+                    inner: vec_kv.into_iter(),
+                }) as Box<dyn Iter<Item = KeyValue> + Send + Sync>
+            })
+            .collect_vec();
+
+        self.merging_iter(
+            Bound::Included(prefix),
+            upper
+                .as_ref()
+                .map_or(Bound::Unbounded, |upper| Bound::Excluded(upper.as_slice())),
+            vec_disk_iter,
+        )
+    }
+
+    /// 依次归并写入缓冲、MemTable与磁盘上的数据，越靠前的数据越新
+    fn merging_iter<'a>(
+        &'a self,
+        min: Bound<&[u8]>,
+        max: Bound<&[u8]>,
+        vec_disk_iter: Vec<Box<dyn Iter<'a, Item = KeyValue> + 'a + Send + Sync>>,
     ) -> KernelResult<TransactionIter> {
         let mut vec_iter: Vec<Box<dyn Iter<'a, Item = KeyValue> + 'a + Send + Sync>> =
-            Vec::with_capacity(3);
+            Vec::with_capacity(vec_disk_iter.len() + 2);
 
         if let Some(write_buf) = &self.write_buf {
             let range = write_buf.range::<Bytes, (Bound<&Bytes>, Bound<&Bytes>)>((
@@ -161,18 +223,7 @@ impl Transaction {
                 pos: 0,
             }
         }));
-        let mut vec_seek_iter = Vec::new();
-        VersionIter::merging_with_version(&self.version, &mut vec_seek_iter)?;
-
-        match &min {
-            Bound::Included(key) | Bound::Excluded(key) => {
-                for mut seek_iter in vec_seek_iter {
-                    seek_iter.seek(Seek::Backward(key.as_slice()))?;
-                    vec_iter.push(seek_iter as Box<dyn Iter<Item = KeyValue> + Send + Sync>)
-                }
-            }
-            Bound::Unbounded => (),
-        };
+        vec_iter.extend(vec_disk_iter);
 
         Ok(TransactionIter {
             inner: MergingIter::new(vec_iter)?,
@@ -185,6 +236,15 @@ impl Transaction {
     }
 }
 
+/// 所有以`prefix`开头的Key的上界(不包含)，`prefix`全为0xFF时不存在上界
+fn prefix_upper_bound(prefix: &[u8]) -> Option<Vec<u8>> {
+    let len = prefix.iter().rposition(|byte| *byte != u8::MAX)?;
+    let mut upper = prefix[..=len].to_vec();
+    upper[len] += 1;
+
+    Some(upper)
+}
+
 impl Drop for Transaction {
     #[inline]
     fn drop(&mut self) {
@@ -206,6 +266,15 @@ pub struct TransactionIter<'a> {
     is_inited: bool,
 }
 
+impl TransactionIter<'_> {
+    /// 设置是否跳过已删除的Key，默认会以Value为None的形式产出已删除的Key
+    #[inline]
+    pub fn skip_tombstones(mut self, skip_tombstones: bool) -> Self {
+        self.inner.set_skip_tombstones(skip_tombstones);
+        self
+    }
+}
+
 impl<'a> Iter<'a> for TransactionIter<'a> {
     type Item = KeyValue;
 
@@ -261,6 +330,11 @@ struct BufIter<'a> {
     pos: usize,
 }
 
+/// 持有一组有序键值对的迭代器，用于前缀扫描时各Level所读取出的数据
+struct VecIter {
+    inner: std::vec::IntoIter<KeyValue>,
+}
+
 struct InnerIter<'a> {
     iter: Range<'a, Bytes, Option<Bytes>>,
 }
@@ -281,6 +355,18 @@ impl<'a> Iter<'a> for BufIter<'a> {
     }
 }
 
+impl<'a> Iter<'a> for VecIter {
+    type Item = KeyValue;
+
+    fn try_next(&mut self) -> KernelResult<Option<Self::Item>> {
+        Ok(self.inner.next())
+    }
+
+    fn is_valid(&self) -> bool {
+        self.inner.len() > 0
+    }
+}
+
 impl<'a> Iter<'a> for InnerIter<'a> {
     type Item = KeyValue;
 
@@ -299,9 +385,12 @@ impl<'a> Iter<'a> for InnerIter<'a> {
 /// TODO: 更多的Test Case
 #[cfg(test)]
 mod tests {
+    use crate::kernel::lsm::compactor::CompactionOptions;
     use crate::kernel::lsm::iterator::Iter;
-    use crate::kernel::lsm::mvcc::CheckType;
+    use crate::kernel::lsm::mem_table::KeyValue;
+    use crate::kernel::lsm::mvcc::{CheckType, Transaction};
     use crate::kernel::lsm::storage::{Config, KipStorage};
+    use crate::kernel::lsm::PrefixExtractor;
     use crate::kernel::{KernelResult, Storage};
     use crate::KernelError;
     use bincode::Options;
@@ -318,7 +407,7 @@ mod tests {
 
         let value = b"0";
 
-        let config = Config::new(temp_dir.into_path()).major_threshold_with_sst_size(4);
+        let config = Config::new(temp_dir.keep()).major_threshold_with_sst_size(4);
         let kv_store = KipStorage::open_with_config(config).await?;
 
         let mut vec_kv = Vec::new();
@@ -391,7 +480,7 @@ mod tests {
     async fn test_transaction_check_optimistic() -> KernelResult<()> {
         let temp_dir = TempDir::new().expect("unable to create temporary working directory");
 
-        let config = Config::new(temp_dir.into_path()).major_threshold_with_sst_size(4);
+        let config = Config::new(temp_dir.keep()).major_threshold_with_sst_size(4);
         let kv_store = KipStorage::open_with_config(config).await?;
 
         let mut tx_1 = kv_store.new_transaction(CheckType::Optimistic).await;
@@ -409,4 +498,163 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_iter_skip_tombstones() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let kv_store = KipStorage::open_with_config(Config::new(temp_dir.keep())).await?;
+        let key = |i: u8| Bytes::from(vec![i]);
+
+        for i in 0..10 {
+            kv_store.set(key(i), key(i)).await?;
+        }
+        kv_store.flush().await?;
+        // 墓碑位于较新的Table中
+        for i in (0..10).step_by(2) {
+            kv_store.remove(&[i]).await?;
+        }
+        kv_store.flush().await?;
+
+        let kv_store = &kv_store;
+        let scan = |skip_tombstones: bool| async move {
+            let tx = kv_store.new_transaction(CheckType::Optimistic).await;
+            let mut iter = tx
+                .iter(Bound::Unbounded, Bound::Unbounded)?
+                .skip_tombstones(skip_tombstones);
+            let mut vec_kv = Vec::new();
+            while let Some(item) = iter.try_next()? {
+                vec_kv.push(item);
+            }
+            Ok::<_, KernelError>(vec_kv)
+        };
+        let live_kv = (1..10)
+            .step_by(2)
+            .map(|i| (key(i), Some(key(i))))
+            .collect_vec();
+
+        assert_eq!(scan(true).await?, live_kv);
+        assert_eq!(
+            scan(false).await?,
+            (0..10)
+                .map(|i| (key(i), (i % 2 == 1).then(|| key(i))))
+                .collect_vec()
+        );
+
+        // 压缩仍需感知墓碑以覆盖较旧Table中的数据
+        kv_store.compact_all(CompactionOptions::default()).await?;
+        assert_eq!(kv_store.current_version().await.level_len(0), 0);
+        assert_eq!(scan(true).await?, live_kv);
+        for i in 0..10 {
+            let expect = (i % 2 == 1).then(|| key(i));
+            assert_eq!(kv_store.get(&[i]).await?, expect);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_prefix_iter() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.keep())
+            .prefix_extractor(PrefixExtractor::FixedLength(4))
+            .prefix_bloom_len(4);
+        let kv_store = KipStorage::open_with_config(config).await?;
+        let key =
+            |tenant: u32, i: u32| Bytes::from([tenant.to_be_bytes(), i.to_be_bytes()].concat());
+        let scan = |tx: &Transaction, prefix: &[u8]| -> KernelResult<Vec<KeyValue>> {
+            let mut iter = tx.prefix_iter(prefix)?.skip_tombstones(true);
+            let mut vec_kv = Vec::new();
+            while let Some(item) = iter.try_next()? {
+                vec_kv.push(item);
+            }
+            Ok(vec_kv)
+        };
+
+        for tenant in 0..8 {
+            for i in 0..100 {
+                kv_store.set(key(tenant, i), key(tenant, i)).await?;
+            }
+        }
+        kv_store.flush().await?;
+        kv_store.compact_all(CompactionOptions::default()).await?;
+        // 较新的Level 0中覆盖与删除部分数据，MemTable中再覆盖部分数据
+        for i in (0..100).step_by(2) {
+            kv_store.remove(&key(3, i)).await?;
+        }
+        kv_store.flush().await?;
+        for i in (1..100).step_by(4) {
+            kv_store.set(key(3, i), Bytes::from_static(b"new")).await?;
+        }
+
+        let mut tx = kv_store.new_transaction(CheckType::Optimistic).await;
+        tx.set(key(3, 100), Bytes::from_static(b"buffered"));
+        let expect = (1..100)
+            .step_by(2)
+            .map(|i| {
+                let value = if i % 4 == 1 {
+                    Bytes::from_static(b"new")
+                } else {
+                    key(3, i)
+                };
+                (key(3, i), Some(value))
+            })
+            .chain([(key(3, 100), Some(Bytes::from_static(b"buffered")))])
+            .collect_vec();
+
+        assert_eq!(scan(&tx, &3_u32.to_be_bytes())?, expect);
+        assert_eq!(scan(&tx, &key(3, 1))?, expect[..1]);
+        assert!(scan(&tx, &8_u32.to_be_bytes())?.is_empty());
+        assert!(scan(&tx, &[u8::MAX; 4])?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_transaction_remove_buffered_key() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let kv_store = KipStorage::open_with_config(Config::new(temp_dir.keep())).await?;
+        let loader = kv_store.inner.ver_status.loader();
+
+        kv_store
+            .set(Bytes::from_static(b"KipDB"), Bytes::from_static(b"on disk"))
+            .await?;
+        kv_store.flush().await?;
+        let misses = loader.cache_misses();
+
+        // 已在事务中写入的Key直接在写入缓冲中删除，不产生SSTable读取
+        let mut tx = kv_store.new_transaction(CheckType::Optimistic).await;
+        tx.set(
+            Bytes::from_static(b"KipDB"),
+            Bytes::from_static(b"buffered"),
+        );
+        tx.remove(b"KipDB")?;
+        assert!(matches!(tx.remove(b"KipDB"), Err(KernelError::KeyNotFound)));
+        tx.delete(b"not exist");
+        assert_eq!(tx.get(b"KipDB")?, None);
+        assert_eq!(loader.cache_misses(), misses);
+        tx.commit().await?;
+        assert_eq!(kv_store.get(b"KipDB").await?, None);
+
+        // 未在事务中写入的Key仍需读取以检查其是否存在
+        kv_store
+            .set(Bytes::from_static(b"KipDB"), Bytes::from_static(b"on disk"))
+            .await?;
+        kv_store.flush().await?;
+        // 再次封存以释放已持久化的Immut Table，使该Key仅存在于SSTable中
+        kv_store
+            .set(Bytes::from_static(b"other"), Bytes::from_static(b"on disk"))
+            .await?;
+        kv_store.flush().await?;
+        let misses = loader.cache_misses();
+
+        let mut tx = kv_store.new_transaction(CheckType::Optimistic).await;
+        tx.remove(b"KipDB")?;
+        assert!(loader.cache_misses() > misses);
+        assert!(matches!(
+            tx.remove(b"not exist"),
+            Err(KernelError::KeyNotFound)
+        ));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,64 @@
+use crate::kernel::lsm::storage::CACHE_SHARDING_SIZE;
+use crate::kernel::utils::lru_cache::ShardingLruCache;
+use crate::kernel::KernelResult;
+use bytes::Bytes;
+use std::collections::hash_map::RandomState;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 缓存的未命中Key的数量
+pub(crate) const DEFAULT_NEGATIVE_CACHE_SIZE: usize = 4096;
+
+/// 近期查询未命中的Key，使重复查询不存在的Key时跳过MemTable与各Level的查找
+///
+/// 写入任意Key时都会使其失效；为避免查询与并发的写入交错而缓存写入前的查询结果，
+/// 查询前需记录写入纪元，缓存后若纪元已变化则撤销此次缓存
+pub(crate) struct NegativeCache {
+    keys: ShardingLruCache<Bytes, ()>,
+    /// 每次写入时递增
+    epoch: AtomicU64,
+}
+
+impl NegativeCache {
+    pub(crate) fn new() -> KernelResult<Self> {
+        Ok(NegativeCache {
+            keys: ShardingLruCache::new(
+                DEFAULT_NEGATIVE_CACHE_SIZE,
+                CACHE_SHARDING_SIZE,
+                RandomState::default(),
+            )?,
+            epoch: AtomicU64::new(0),
+        })
+    }
+
+    /// 当前的写入纪元，需在查询前获取
+    pub(crate) fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn contains(&self, key: &[u8]) -> bool {
+        self.keys.get(&Bytes::copy_from_slice(key)).is_some()
+    }
+
+    /// 缓存未命中的Key，`epoch`为查询前所获取的写入纪元
+    pub(crate) fn insert(&self, key: Bytes, epoch: u64) {
+        let _ = self.keys.put(key.clone(), ());
+
+        // 查询期间存在写入时，此次的未命中结果可能已过期
+        if self.epoch() != epoch {
+            let _ = self.keys.remove(&key);
+        }
+    }
+
+    /// 写入Key后使其失效
+    ///
+    /// 先递增纪元再移除，使正在进行的查询无法再缓存该Key
+    pub(crate) fn invalidate(&self, key: &Bytes) {
+        let _ = self.epoch.fetch_add(1, Ordering::SeqCst);
+        let _ = self.keys.remove(key);
+    }
+
+    /// 通过缓存跳过查找的次数
+    pub(crate) fn hits(&self) -> u64 {
+        self.keys.hits()
+    }
+}
@@ -0,0 +1,140 @@
+use crate::kernel::lsm::MAX_LEVEL;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// 存储引擎运行时统计信息
+///
+/// 用于运维观察各Level的Table分布与缓存情况
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct KipStats {
+    /// 当前Version的序号
+    pub version_num: u64,
+    /// 各Level所持有的Table数量
+    pub level_counts: [usize; MAX_LEVEL],
+    /// SSTable集合占有磁盘大小
+    pub size_of_disk: u64,
+    /// 数据数量(包含MemTable)
+    pub len: usize,
+    /// Block缓存命中率
+    pub block_cache_hit_rate: f64,
+    /// 从磁盘中载入Table的次数
+    pub table_loads: u64,
+    /// 因超出Table缓存数量而被驱逐的Table数量
+    ///
+    /// 载入与驱逐频率过高说明Table缓存过小而发生了颠簸
+    pub table_evictions: u64,
+    /// 当前常驻于内存中的Table数量
+    pub table_resident: usize,
+    /// Flush写入Level 0的字节数，需开启`Config::report_bg_io_stats`
+    pub user_bytes_flushed: u64,
+    /// 压缩读取的字节数，需开启`Config::report_bg_io_stats`
+    pub compaction_bytes_read: u64,
+    /// 压缩写入的字节数，需开启`Config::report_bg_io_stats`
+    pub compaction_bytes_written: u64,
+    /// 写放大系数：(Flush写入字节数 + 压缩写入字节数) / Flush写入字节数，未统计时为0
+    pub write_amplification: f64,
+    /// 常驻于内存中的Table的布隆过滤器位数总和
+    pub bloom_filter_bits: u64,
+    /// 布隆过滤器判定Key不存在而跳过读取DataBlock的次数
+    pub bloom_useful: u64,
+    /// 布隆过滤器判定Key可能存在但DataBlock中并不存在的次数
+    pub bloom_false_positives: u64,
+    /// 实际观测到的布隆过滤器误判率：误判次数 / (误判次数 + 判定不存在次数)，未统计时为0
+    ///
+    /// 可与`Config::desired_error_prob`对照以调整误判率
+    pub bloom_false_positive_rate: f64,
+    /// 通过未命中缓存跳过查找的次数，需开启`Config::enable_negative_cache`
+    pub negative_cache_hits: u64,
+}
+
+impl fmt::Display for KipStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Version: {}", self.version_num)?;
+
+        for (level, count) in self.level_counts.iter().enumerate() {
+            writeln!(f, "Level {level}: {count}")?;
+        }
+        writeln!(f, "Size of Disk: {}", self.size_of_disk)?;
+        writeln!(f, "Len: {}", self.len)?;
+        writeln!(
+            f,
+            "Block Cache Hit Rate: {:.2}%",
+            self.block_cache_hit_rate * 100.0
+        )?;
+        writeln!(f, "Table Loads: {}", self.table_loads)?;
+        writeln!(f, "Table Evictions: {}", self.table_evictions)?;
+        writeln!(f, "Table Resident: {}", self.table_resident)?;
+        writeln!(f, "User Bytes Flushed: {}", self.user_bytes_flushed)?;
+        writeln!(f, "Compaction Bytes Read: {}", self.compaction_bytes_read)?;
+        writeln!(
+            f,
+            "Compaction Bytes Written: {}",
+            self.compaction_bytes_written
+        )?;
+        writeln!(f, "Write Amplification: {:.2}", self.write_amplification)?;
+        writeln!(f, "Bloom Filter Bits: {}", self.bloom_filter_bits)?;
+        writeln!(f, "Bloom Useful: {}", self.bloom_useful)?;
+        writeln!(f, "Bloom False Positives: {}", self.bloom_false_positives)?;
+        writeln!(
+            f,
+            "Bloom False Positive Rate: {:.4}%",
+            self.bloom_false_positive_rate * 100.0
+        )?;
+        write!(f, "Negative Cache Hits: {}", self.negative_cache_hits)
+    }
+}
+
+/// 启动以来累计的压缩统计信息
+///
+/// 定期采集并与前一次作差，可观察压缩的速率以发现压缩积压
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct CompactionStats {
+    /// Minor压缩(将Immut Table持久化至Level 0)的次数
+    pub minor_count: u64,
+    /// Major压缩中Level向下一级压缩的次数
+    pub major_count: u64,
+    /// Major压缩读取的输入Table的磁盘大小总和
+    pub bytes_read: u64,
+    /// Minor与Major压缩所写入的Table的磁盘大小总和
+    pub bytes_written: u64,
+    /// Minor与Major压缩所新建的Table数量
+    pub tables_created: u64,
+    /// Major压缩所删除的输入Table数量
+    pub tables_deleted: u64,
+}
+
+/// 单个SSTable的布隆过滤器统计信息
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[non_exhaustive]
+pub struct BloomStats {
+    /// 布隆过滤器的位数，未构建布隆过滤器时为0
+    pub bits: u64,
+    /// 哈希函数的数量
+    pub hash_fn_count: u64,
+    /// 依据已置位的比例估算的误判率
+    pub estimated_fpr: f64,
+}
+
+/// 存储引擎各组件的近似内存占用，单位为B
+///
+/// 各项均为估算值，用于嵌入方施加全局的内存预算
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct MemoryUsage {
+    /// MemTable与Immut Table(包括尚未写入WAL的日志记录)
+    pub mem_tables: usize,
+    /// Block缓存中的DataBlock与IndexBlock
+    pub block_cache: usize,
+    /// Table缓存中常驻的Table(Footer、MetaBlock中的布隆过滤器等)
+    pub table_cache: usize,
+}
+
+impl MemoryUsage {
+    /// 内存占用总和
+    #[inline]
+    pub fn total(&self) -> usize {
+        self.mem_tables + self.block_cache + self.table_cache
+    }
+}
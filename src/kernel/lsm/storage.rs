@@ -1,29 +1,49 @@
-use crate::kernel::io::IoType;
-use crate::kernel::lsm::compactor::{CompactTask, Compactor};
-use crate::kernel::lsm::mem_table::{KeyValue, MemTable};
+use crate::kernel::io::priority::IoPriority;
+use crate::kernel::io::{FileExtension, IoType};
+use crate::kernel::lsm::backup::{ArchiveWriter, EntryType};
+use crate::kernel::lsm::compactor::{
+    BgIoStats, CompactTask, CompactionOptions, CompactionPriority, CompactionStrategy, Compactor,
+};
+use crate::kernel::lsm::iterator::Iter;
+use crate::kernel::lsm::mem_table::{KeyValue, MemTable, SealStatus, DEFAULT_WAL_PATH};
 use crate::kernel::lsm::mvcc::{CheckType, Transaction};
+use crate::kernel::lsm::negative_cache::NegativeCache;
+use crate::kernel::lsm::stats::{CompactionStats, KipStats, MemoryUsage};
 use crate::kernel::lsm::table::scope::Scope;
 use crate::kernel::lsm::table::ss_table::block;
+use crate::kernel::lsm::table::ss_table::value_log::ValueLog;
 use crate::kernel::lsm::table::TableType;
 use crate::kernel::lsm::trigger::TriggerType;
+use crate::kernel::lsm::version::edit::VersionEdit;
 use crate::kernel::lsm::version::status::VersionStatus;
-use crate::kernel::lsm::version::Version;
-use crate::kernel::lsm::{query_and_compaction, version, MAX_LEVEL};
+use crate::kernel::lsm::version::{Version, DEFAULT_SS_TABLE_PATH};
+use crate::kernel::lsm::{
+    backup, check_sorted, format, query_and_compaction, version, BloomPolicy, ChecksumType,
+    CompressType, PrefixExtractor, MAX_LEVEL,
+};
 use crate::kernel::KernelResult;
-use crate::kernel::{lock_or_time_out, Storage, DEFAULT_LOCK_FILE};
+use crate::kernel::{lock_or_time_out, CommandData, Storage, DEFAULT_LOCK_FILE};
 use crate::KernelError;
 use async_trait::async_trait;
 use bytes::Bytes;
 use chrono::Local;
 use fslock::LockFile;
+use futures::{stream, Stream};
+use itertools::Itertools;
+use parking_lot::{Mutex, RwLock};
+use std::collections::Bound;
 use std::fs;
+use std::io::{self, Read, Write};
+use std::ops::Range;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicI64, Ordering};
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 use tokio::sync::mpsc::error::TrySendError;
 use tokio::sync::mpsc::{channel, Sender};
 use tokio::sync::oneshot;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 pub(crate) const BANNER: &str = "
 █████   ████  ███            ██████████   ███████████
@@ -56,6 +76,14 @@ pub(crate) const DEFAULT_WAL_THRESHOLD: usize = 20;
 
 pub(crate) const DEFAULT_WAL_IO_TYPE: IoType = IoType::Buf;
 
+pub(crate) const COMPACTOR_THREAD_NAME: &str = "kipdb-compactor";
+
+/// Level 0的Table数量超出`Config::l0_slowdown_trigger`时每次写入的延迟
+pub(crate) const L0_SLOWDOWN_DELAY: Duration = Duration::from_millis(1);
+
+/// Table与Block缓存的分片数量，缓存大小需为其整数倍
+pub(crate) const CACHE_SHARDING_SIZE: usize = 16;
+
 static SEQ_COUNT: AtomicI64 = AtomicI64::new(1);
 
 static GEN_BUF: AtomicI64 = AtomicI64::new(0);
@@ -71,6 +99,98 @@ pub struct KipStorage {
     pub(crate) compactor_tx: Sender<CompactTask>,
 }
 
+/// 通过`KipStorage::write_batch`原子写入的一批操作
+///
+/// 同一批次中对同一Key的多次操作以最后一次为准
+#[derive(Debug, Default, Clone)]
+pub struct WriteBatch {
+    vec_data: Vec<KeyValue>,
+}
+
+impl WriteBatch {
+    #[inline]
+    pub fn new() -> Self {
+        WriteBatch::default()
+    }
+
+    #[inline]
+    pub fn set(&mut self, key: Bytes, value: Bytes) {
+        self.vec_data.push((key, Some(value)));
+    }
+
+    #[inline]
+    pub fn remove(&mut self, key: Bytes) {
+        self.vec_data.push((key, None));
+    }
+
+    /// 写入`cf`中的Key，与其余列族的操作在同一批次中原子生效
+    #[inline]
+    pub fn set_cf(&mut self, cf: &ColumnFamily, key: Bytes, value: Bytes) {
+        self.vec_data.push((cf.encode_key(&key), Some(value)));
+    }
+
+    /// 删除`cf`中的Key，与其余列族的操作在同一批次中原子生效
+    #[inline]
+    pub fn remove_cf(&mut self, cf: &ColumnFamily, key: Bytes) {
+        self.vec_data.push((cf.encode_key(&key), None));
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.vec_data.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.vec_data.is_empty()
+    }
+}
+
+/// 列族，以名称前缀划分同一存储中的Key空间
+///
+/// 各列族共享MemTable、WAL与Sequence，因此跨列族的`WriteBatch`同样共用一个seq_id并作为一条WAL记录写入；
+/// 列族中的Key编码为`[名称长度(u8)][名称][Key]`，默认列族的Key原样存放，需避免与该编码冲突
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ColumnFamily {
+    name: String,
+    prefix: Bytes,
+}
+
+impl ColumnFamily {
+    /// 名称为空或超过255字节时返回`KernelError::NotSupport`
+    #[inline]
+    pub fn new(name: &str) -> KernelResult<Self> {
+        let len =
+            u8::try_from(name.len())
+                .ok()
+                .filter(|len| *len > 0)
+                .ok_or(KernelError::NotSupport(
+                    "the column family name must be 1 to 255 bytes",
+                ))?;
+        let mut prefix = Vec::with_capacity(name.len() + 1);
+        prefix.push(len);
+        prefix.extend_from_slice(name.as_bytes());
+
+        Ok(ColumnFamily {
+            name: name.to_string(),
+            prefix: Bytes::from(prefix),
+        })
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn encode_key(&self, key: &[u8]) -> Bytes {
+        let mut encoded = Vec::with_capacity(self.prefix.len() + key.len());
+        encoded.extend_from_slice(&self.prefix);
+        encoded.extend_from_slice(key);
+
+        Bytes::from(encoded)
+    }
+}
+
 pub(crate) struct StoreInner {
     /// MemTable
     /// https://zhuanlan.zhihu.com/p/79064869
@@ -79,7 +199,13 @@ pub(crate) struct StoreInner {
     /// 用于管理内部多版本状态
     pub(crate) ver_status: VersionStatus,
     /// LSM全局参数配置
-    pub(crate) config: Config,
+    ///
+    /// 可通过`KipStorage::update_config`在运行时替换
+    config: RwLock<Arc<Config>>,
+    /// 后台Flush与压缩的次数与读写量统计
+    pub(crate) bg_io_stats: BgIoStats,
+    /// 最近一次后台Flush与压缩的错误，成功完成后清除
+    bg_error: Mutex<Option<String>>,
 }
 
 impl StoreInner {
@@ -87,13 +213,55 @@ impl StoreInner {
         let mem_table = MemTable::new(&config)?;
         let ver_status =
             VersionStatus::load_with_path(config.clone(), mem_table.log_loader_clone())?;
+        let live_gens = ver_status
+            .current()
+            .await
+            .level_slice
+            .iter()
+            .flatten()
+            .map(Scope::gen)
+            .collect();
+        let recovered = mem_table.recover_immuts(&live_gens)?;
+        if recovered > 0 {
+            info!("[LsmStore][recover immutable tables]: {recovered}");
+        }
 
         Ok(StoreInner {
             mem_table,
             ver_status,
-            config,
+            config: RwLock::new(Arc::new(config)),
+            bg_io_stats: BgIoStats::default(),
+            bg_error: Mutex::new(None),
         })
     }
+
+    /// 记录后台Flush与压缩的结果，失败时唤醒被阻塞的写入使其返回该错误
+    pub(crate) fn record_bg_result(&self, result: &KernelResult<()>) {
+        match result {
+            Ok(()) => *self.bg_error.lock() = None,
+            Err(err) => {
+                *self.bg_error.lock() = Some(err.to_string());
+                self.ver_status.notify_waiters();
+            }
+        }
+    }
+
+    /// 获取当前的Config快照
+    pub(crate) fn config(&self) -> Arc<Config> {
+        Arc::clone(&self.config.read())
+    }
+
+    /// 以`f`修改当前Config，修改了运行时不可修改的参数时返回`KernelError::NotSupport`
+    pub(crate) fn update_config(&self, f: impl FnOnce(Config) -> Config) -> KernelResult<()> {
+        let mut config = self.config.write();
+        let new_config = f(Config::clone(&config));
+
+        config.check_immutable(&new_config)?;
+        new_config.check_write_stall()?;
+        *config = Arc::new(new_config);
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -111,13 +279,12 @@ impl Storage for KipStorage {
         KipStorage::open_with_config(Config::new(path.into())).await
     }
 
+    /// 将MemTable持久化并等待完成
+    ///
+    /// 存在进行中的事务时不会等待其结束，事务仍可从保留的Immut Table中读取其开启前的数据
     #[inline]
     async fn flush(&self) -> KernelResult<()> {
-        let (tx, rx) = oneshot::channel();
-
-        self.compactor_tx.send(CompactTask::Flush(Some(tx))).await?;
-
-        rx.await.map_err(|_| KernelError::ChannelClose)?;
+        let _ = self.flush_with_gens().await?;
 
         Ok(())
     }
@@ -129,16 +296,25 @@ impl Storage for KipStorage {
 
     #[inline]
     async fn get(&self, key: &[u8]) -> KernelResult<Option<Bytes>> {
-        if let Some((_, value)) = self.mem_table().find(key) {
-            return Ok(value);
+        let negative_cache = self.mem_table().negative_cache.as_ref();
+        let epoch = negative_cache.map(|cache| cache.epoch());
+        if negative_cache.is_some_and(|cache| cache.contains(key)) {
+            return Ok(None);
         }
 
-        let version = self.current_version().await;
-        if let Some((_, value)) = query_and_compaction(key, &version, &self.compactor_tx)? {
-            return Ok(value);
+        let value = match self.mem_table().find(key) {
+            Some((_, value)) => value,
+            None => {
+                let version = self.current_version().await;
+                query_and_compaction(key, &version, &self.compactor_tx)?
+                    .and_then(|(_, value)| value)
+            }
+        };
+        if let (None, Some(negative_cache), Some(epoch)) = (&value, negative_cache, epoch) {
+            negative_cache.insert(Bytes::copy_from_slice(key), epoch);
         }
 
-        Ok(None)
+        Ok(value)
     }
 
     #[inline]
@@ -157,6 +333,10 @@ impl Storage for KipStorage {
         Ok(self.current_version().await.size_of_disk())
     }
 
+    /// 原始的数据条目数量
+    ///
+    /// 包含已删除Key的墓碑以及同一Key尚未被压缩合并的多个版本，因此可能大于存活的Key数量，
+    /// 需要准确的存活Key数量时请使用`KipStorage::count_live_keys`
     #[inline]
     async fn len(&self) -> KernelResult<usize> {
         Ok(self.current_version().await.len() + self.mem_table().len())
@@ -181,11 +361,64 @@ impl Drop for KipStorage {
 impl KipStorage {
     /// 追加数据
     async fn append_cmd_data(&self, data: KeyValue) -> KernelResult<()> {
-        if self.mem_table().insert_data(data)? {
-            if let Err(TrySendError::Closed(_)) =
-                self.compactor_tx.try_send(CompactTask::Flush(None))
-            {
-                return Err(KernelError::ChannelClose);
+        self.delay_write_by_level_0().await?;
+        let is_exceeded = self.mem_table().insert_data(data)?;
+
+        self.seal_if_exceeded(is_exceeded).await
+    }
+
+    /// Level 0的Table过多时延迟或阻塞写入，避免Flush快于压缩时Level 0无限增长而拖慢读取
+    ///
+    /// 超出`Config::l0_slowdown_trigger`时写入前等待`L0_SLOWDOWN_DELAY`，
+    /// 超出`Config::l0_stop_trigger`时阻塞写入直至新的Version使Level 0回落至该值之下，
+    /// 阻塞期间后台压缩失败时返回`KernelError::BackgroundCompaction`
+    async fn delay_write_by_level_0(&self) -> KernelResult<()> {
+        let config = self.inner.config();
+        if config.l0_slowdown_trigger.is_none() && config.l0_stop_trigger.is_none() {
+            return Ok(());
+        }
+        let ver_status = &self.inner.ver_status;
+        let is_exceeded =
+            |trigger: Option<usize>, len: usize| trigger.is_some_and(|trigger| len > trigger);
+
+        loop {
+            let notified = ver_status.notified();
+            let level_0_len = ver_status.level_0_len();
+
+            if is_exceeded(config.l0_stop_trigger, level_0_len) {
+                if let Some(err) = self.inner.bg_error.lock().clone() {
+                    return Err(KernelError::BackgroundCompaction(err));
+                }
+                notified.await;
+                continue;
+            }
+            if is_exceeded(config.l0_slowdown_trigger, level_0_len) {
+                tokio::time::sleep(L0_SLOWDOWN_DELAY).await;
+            }
+            return Ok(());
+        }
+    }
+
+    /// 写入后MemTable超出阈值时进行封存并通知Compactor
+    async fn seal_if_exceeded(&self, is_exceeded: bool) -> KernelResult<()> {
+        let mem_table = self.mem_table();
+
+        if is_exceeded {
+            loop {
+                // 需在封存前获取通知，避免Compactor在此期间完成持久化而错过唤醒
+                let flushed = mem_table.flushed();
+                let status = mem_table.try_seal()?;
+
+                if let Err(TrySendError::Closed(_)) =
+                    self.compactor_tx.try_send(CompactTask::Flush(None))
+                {
+                    return Err(KernelError::ChannelClose);
+                }
+                // 待持久化的Immut Table已满时等待Compactor持久化后再次尝试封存
+                if status != SealStatus::ImmutFull {
+                    break;
+                }
+                flushed.await;
             }
         }
 
@@ -199,32 +432,76 @@ impl KipStorage {
         Self: Sized,
     {
         info!("{} \nVersion: {}", BANNER, env!("CARGO_PKG_VERSION"));
+        let config = config.align_cache_size();
+        config.check_write_stall()?;
+        config.bloom_policy.check()?;
         Gen::init();
         // 若lockfile的文件夹路径不存在则创建
         fs::create_dir_all(&config.dir_path)?;
         let lock_file = lock_or_time_out(&config.path().join(DEFAULT_LOCK_FILE)).await?;
+        format::check_and_migrate(config.path(), format::FORMAT_VERSION)?;
         let inner = Arc::new(StoreInner::new(config.clone()).await?);
         let mut compactor = Compactor::new(Arc::clone(&inner));
+        let bg_inner = Arc::clone(&inner);
         let (task_tx, mut task_rx) = channel(1);
-
-        let _ignore = tokio::spawn(async move {
-            while let Some(task) = task_rx.recv().await {
-                match task {
-                    CompactTask::Seek((scope, level)) => {
-                        if let Err(err) =
-                            compactor.major_compaction(level, scope, vec![], true).await
-                        {
-                            error!("[Compactor][manual compaction][error happen]: {:?}", err);
-                        }
+        let io_priority = config.compaction_io_priority;
+        // Compactor运行于独立线程中，以便单独设置其I/O调度优先级
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+
+        let _ignore = thread::Builder::new()
+            .name(COMPACTOR_THREAD_NAME.to_string())
+            .spawn(move || {
+                if let Some(io_priority) = io_priority {
+                    if let Err(err) = io_priority.apply_to_current_thread() {
+                        warn!("[Compactor][set io priority][error happen]: {:?}", err);
                     }
-                    CompactTask::Flush(option_tx) => {
-                        if let Err(err) = compactor.check_then_compaction(option_tx).await {
-                            error!("[Compactor][compaction][error happen]: {:?}", err);
+                }
+                runtime.block_on(async move {
+                    while let Some(task) = task_rx.recv().await {
+                        match task {
+                            CompactTask::Seek(_) if compactor.config().disable_auto_compaction => {}
+                            CompactTask::Seek((scope, level))
+                            | CompactTask::Manual((scope, level)) => {
+                                if let Err(err) = compactor
+                                    .major_compaction(level, scope, vec![], true, None)
+                                    .await
+                                {
+                                    error!(
+                                        "[Compactor][manual compaction][error happen]: {:?}",
+                                        err
+                                    );
+                                }
+                            }
+                            CompactTask::Range {
+                                levels,
+                                scope,
+                                options,
+                                tx,
+                            } => {
+                                let result =
+                                    compactor.range_compaction(levels, scope, &options).await;
+                                if let Err(err) = &result {
+                                    warn!("[Compactor][range compaction][interrupted]: {:?}", err);
+                                }
+                                let _ = tx.send(result);
+                            }
+                            CompactTask::Flush(option_tx) => {
+                                let result = compactor.check_then_compaction(option_tx).await;
+                                if let Err(err) = &result {
+                                    error!("[Compactor][compaction][error happen]: {:?}", err);
+                                }
+                                bg_inner.record_bg_result(&result);
+                            }
                         }
                     }
-                }
-            }
-        });
+                })
+            })?;
+        // 重启时恢复的Immut Table交由Compactor持久化
+        if inner.mem_table.has_unflushed() {
+            let _ = task_tx.try_send(CompactTask::Flush(None));
+        }
 
         Ok(KipStorage {
             inner,
@@ -247,6 +524,259 @@ impl KipStorage {
         Transaction::new(self, check_type).await
     }
 
+    /// 统计当前存活的Key数量
+    ///
+    /// 归并MemTable与所有Table的数据，同一Key仅计数一次且不计入已删除的Key，
+    /// 需要遍历全部数据，因此开销远大于`len`
+    #[inline]
+    pub async fn count_live_keys(&self) -> KernelResult<usize> {
+        let tx = self.new_transaction(CheckType::Optimistic).await;
+        let mut iter = tx
+            .iter(Bound::Unbounded, Bound::Unbounded)?
+            .skip_tombstones(true);
+        let mut count = 0;
+
+        while iter.try_next()?.is_some() {
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// 获取存储引擎当前的统计信息
+    #[inline]
+    pub async fn stats(&self) -> KernelResult<KipStats> {
+        let version = self.current_version().await;
+        let loader = self.inner.ver_status.loader();
+        // 未开启`Config::report_bg_io_stats`时不给出Flush与压缩的读写字节数
+        let bg_io_stats = self
+            .inner
+            .config()
+            .report_bg_io_stats
+            .then_some(&self.inner.bg_io_stats);
+
+        Ok(KipStats {
+            version_num: version.version_num,
+            level_counts: std::array::from_fn(|level| version.level_len(level)),
+            size_of_disk: version.size_of_disk(),
+            len: version.len() + self.mem_table().len(),
+            block_cache_hit_rate: loader.cache_hit_rate(),
+            table_loads: loader.table_loads(),
+            table_evictions: loader.table_evictions(),
+            table_resident: loader.table_resident(),
+            user_bytes_flushed: bg_io_stats.map_or(0, BgIoStats::user_bytes_flushed),
+            compaction_bytes_read: bg_io_stats.map_or(0, BgIoStats::compaction_bytes_read),
+            compaction_bytes_written: bg_io_stats.map_or(0, BgIoStats::compaction_bytes_written),
+            write_amplification: bg_io_stats.map_or(0.0, BgIoStats::write_amplification),
+            bloom_filter_bits: loader.bloom_filter_bits(),
+            bloom_useful: loader.bloom_counters().useful(),
+            bloom_false_positives: loader.bloom_counters().false_positives(),
+            bloom_false_positive_rate: loader.bloom_counters().false_positive_rate(),
+            negative_cache_hits: self
+                .mem_table()
+                .negative_cache
+                .as_ref()
+                .map_or(0, NegativeCache::hits),
+        })
+    }
+
+    /// 关闭存储引擎
+    ///
+    /// 默认将MemTable持久化为Table后关闭，下次开启时无需通过WAL恢复数据；
+    /// 开启`Config::avoid_flush_during_shutdown`时仅将WAL持久化至硬盘便返回，
+    /// 尚未持久化的Immut Table同样在下次开启时通过其WAL恢复
+    #[inline]
+    pub async fn shut_down(self) -> KernelResult<()> {
+        if !self.inner.config().avoid_flush_during_shutdown {
+            return self.flush().await;
+        }
+        let mem_table = self.mem_table();
+
+        mem_table.sync_immut_wals()?;
+        mem_table.sync_wal()
+    }
+
+    /// 获取启动以来累计的压缩统计信息
+    #[inline]
+    pub fn compaction_stats(&self) -> CompactionStats {
+        self.inner.bg_io_stats.compaction_stats()
+    }
+
+    /// 获取MemTable、Block缓存与Table缓存的近似内存占用
+    #[inline]
+    pub fn approximate_memory_usage(&self) -> MemoryUsage {
+        let loader = self.inner.ver_status.loader();
+
+        MemoryUsage {
+            mem_tables: self.mem_table().memory_usage(),
+            block_cache: loader.block_cache_usage(),
+            table_cache: loader.table_cache_usage(),
+        }
+    }
+
+    /// 通过名称获取数值类型的属性，便于监控系统采集
+    ///
+    /// 名称保持稳定，未知名称或Level越界时返回None:
+    /// - `kipdb.num-files-at-level<N>`: Level N中的Table数量
+    /// - `kipdb.size-at-level<N>`: Level N中Table占有的磁盘大小
+    /// - `kipdb.total-sst-files-size`: 所有Table占有的磁盘大小
+    /// - `kipdb.num-entries`: 数据数量(包含MemTable)
+    /// - `kipdb.cur-size-all-mem-tables`: MemTable与Immut Table的近似内存占用
+    /// - `kipdb.block-cache-usage`: Block缓存的近似内存占用
+    /// - `kipdb.block-cache-hits`: Block缓存的命中次数
+    /// - `kipdb.block-cache-misses`: Block缓存的未命中次数
+    /// - `kipdb.table-loads`: 从磁盘中载入Table的次数
+    /// - `kipdb.table-evictions`: 因超出Table缓存数量而被驱逐的Table数量
+    /// - `kipdb.estimate-pending-compaction-bytes`: 估算等待Major压缩的数据大小
+    #[inline]
+    pub async fn get_property_int(&self, name: &str) -> Option<u64> {
+        let name = name.strip_prefix("kipdb.")?;
+        let loader = self.inner.ver_status.loader();
+
+        if let Some(level) = name.strip_prefix("num-files-at-level") {
+            let level = level
+                .parse::<usize>()
+                .ok()
+                .filter(|level| *level < MAX_LEVEL)?;
+            return Some(self.current_version().await.level_len(level) as u64);
+        }
+        if let Some(level) = name.strip_prefix("size-at-level") {
+            let level = level
+                .parse::<usize>()
+                .ok()
+                .filter(|level| *level < MAX_LEVEL)?;
+            return Some(self.current_version().await.level_size(level));
+        }
+
+        Some(match name {
+            "total-sst-files-size" => self.current_version().await.size_of_disk(),
+            "num-entries" => (self.current_version().await.len() + self.mem_table().len()) as u64,
+            "cur-size-all-mem-tables" => self.mem_table().memory_usage() as u64,
+            "block-cache-usage" => loader.block_cache_usage() as u64,
+            "block-cache-hits" => loader.cache_hits(),
+            "block-cache-misses" => loader.cache_misses(),
+            "table-loads" => loader.table_loads(),
+            "table-evictions" => loader.table_evictions(),
+            "estimate-pending-compaction-bytes" => self
+                .current_version()
+                .await
+                .pending_compaction_bytes(&self.inner.config()),
+            _ => return None,
+        })
+    }
+
+    /// 批量获取多个Key对应的Value，结果与`keys`的顺序一致
+    ///
+    /// 所有Key共享同一个Version与Sequence快照，不需要为每个Key重复获取Version，
+    /// 且各结果为同一时刻的数据视图
+    #[inline]
+    pub async fn multi_get<K: AsRef<[u8]>>(&self, keys: &[K]) -> KernelResult<Vec<Option<Bytes>>> {
+        let tx = self.new_transaction(CheckType::Optimistic).await;
+
+        keys.iter().map(|key| tx.get(key.as_ref())).collect()
+    }
+
+    /// 与`Storage::flush`相同，但返回此次持久化所新建的SSTable的Gen
+    ///
+    /// 包括Level 0的Table与随之触发的压缩所新建的Table，
+    /// 其中Level 0的Table可能已被压缩所替换，因此并非所有Gen对应的文件都仍然存在
+    #[inline]
+    pub async fn flush_with_gens(&self) -> KernelResult<Vec<i64>> {
+        let (tx, rx) = oneshot::channel();
+
+        self.compactor_tx.send(CompactTask::Flush(Some(tx))).await?;
+
+        rx.await.map_err(|_| KernelError::ChannelClose)
+    }
+
+    /// 将所有已写入的数据写入WAL并持久化至硬盘
+    ///
+    /// 开启`Config::manual_wal_flush`时，在调用该方法之前停机会丢失未写入WAL的数据
+    #[inline]
+    pub async fn sync_wal(&self) -> KernelResult<()> {
+        self.mem_table().sync_wal()
+    }
+
+    /// 获取seq_id大于`seq`的所有变更，并以seq_id顺序产出
+    ///
+    /// 仅包含仍位于MemTable(含Immut Table)中的变更，若`seq`之后的变更已被转移至SSTable
+    /// 则返回`KernelError::SeqCompacted`
+    ///
+    /// Tips: seq_id仅在当次运行中有效，重启后会重新计数
+    #[inline]
+    pub async fn get_updates_since(
+        &self,
+        seq: i64,
+    ) -> KernelResult<impl Stream<Item = (i64, CommandData)>> {
+        let updates = self.mem_table().updates_since(seq)?;
+
+        Ok(stream::iter(updates.into_iter().map(
+            |(seq_id, (key, value))| {
+                let cmd = match value {
+                    Some(value) => CommandData::set(key.to_vec(), value.to_vec()),
+                    None => CommandData::remove(key.to_vec()),
+                };
+                (seq_id, cmd)
+            },
+        )))
+    }
+
+    /// 将当前数据的一致性快照以归档格式写入writer
+    ///
+    /// 归档包含格式版本、Version快照以及其所引用的所有Table文件，每个文件附带CRC用于还原时校验；
+    /// 文件在阻塞线程中分块拷贝至writer，因此writer需要能够转移至该线程
+    #[inline]
+    pub async fn backup_to(&self, writer: impl Write + Send + 'static) -> KernelResult<()> {
+        self.flush().await?;
+        // 持有该Version以保证其引用的Table在备份期间不会被Cleaner删除
+        let version = self.current_version().await;
+        let config = self.inner.config();
+
+        tokio::task::spawn_blocking(move || {
+            let sst_path = config.path().join(DEFAULT_SS_TABLE_PATH);
+            let wal_path = config.path().join(DEFAULT_WAL_PATH);
+            let mut archive = ArchiveWriter::new(writer)?;
+
+            archive.add(EntryType::Format, 0, &format::FORMAT_VERSION.to_le_bytes())?;
+            archive.add(
+                EntryType::Manifest,
+                Gen::create(),
+                &VersionEdit::encode_vec(&version.to_vec_edit())?,
+            )?;
+            for gen in version.level_slice.iter().flatten().map(Scope::gen) {
+                let sst_file = [Some(&sst_path), config.slow_tier_path.as_ref()]
+                    .into_iter()
+                    .flatten()
+                    .map(|path| FileExtension::SSTable.path_with_gen(path, gen))
+                    .find(|sst_file| sst_file.exists());
+
+                if let Some(sst_file) = sst_file {
+                    let value_log_file = ValueLog::path_of(&sst_file);
+
+                    archive.add_file(EntryType::SSTable, gen, &sst_file)?;
+                    if value_log_file.exists() {
+                        archive.add_file(EntryType::ValueLog, gen, &value_log_file)?;
+                    }
+                } else {
+                    let wal_file = FileExtension::Log.path_with_gen(&wal_path, gen);
+                    archive.add_file(EntryType::Log, gen, &wal_file)?;
+                }
+            }
+
+            archive.finish()
+        })
+        .await
+        .map_err(io::Error::other)?
+    }
+
+    /// 将`backup_to`生成的归档还原至一个新的数据目录
+    ///
+    /// 还原后可直接通过该目录开启KipStorage
+    #[inline]
+    pub fn restore_from(reader: impl Read, dir: impl Into<PathBuf>) -> KernelResult<()> {
+        backup::restore(reader, &dir.into())
+    }
+
     #[inline]
     pub async fn manual_compaction(
         &self,
@@ -256,13 +786,167 @@ impl KipStorage {
     ) -> KernelResult<()> {
         if min <= max {
             self.compactor_tx
-                .send(CompactTask::Seek((Scope::from_range(0, min, max), level)))
+                .send(CompactTask::Manual((Scope::from_range(0, min, max), level)))
                 .await?;
         }
 
         Ok(())
     }
 
+    /// 将Level`level`中与`[min, max]`相交的Table向下一级压缩，并等待压缩完成
+    ///
+    /// 可通过`CompactionOptions`中止压缩或获取压缩进度，中止时返回`KernelError::CompactionCancelled`，
+    /// 此时已完成的部分保留，进行中的部分所生成的Table将被删除，Version始终保持一致
+    #[inline]
+    pub async fn compact_range(
+        &self,
+        min: Bytes,
+        max: Bytes,
+        level: usize,
+        options: CompactionOptions,
+    ) -> KernelResult<()> {
+        if min > max || level >= MAX_LEVEL - 1 {
+            return Ok(());
+        }
+        self.range_compaction(
+            level..level + 1,
+            Some(Scope::from_range(0, min, max)),
+            options,
+        )
+        .await
+    }
+
+    /// 由Level 0起逐级将全部Table向下一级压缩，并等待压缩完成
+    ///
+    /// 中止与进度的处理同`compact_range`
+    #[inline]
+    pub async fn compact_all(&self, options: CompactionOptions) -> KernelResult<()> {
+        self.range_compaction(0..MAX_LEVEL - 1, None, options).await
+    }
+
+    /// 由Level 0起逐级将与`[start, end]`相交的Table向下一级压缩，并等待压缩完成
+    ///
+    /// `start`或`end`为None时该侧不设边界，用于将堆积了大量删除或覆盖写入的范围压缩至最底层；
+    /// 中止与进度的处理同`compact_range`
+    #[inline]
+    pub async fn compact_key_range(
+        &self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        options: CompactionOptions,
+    ) -> KernelResult<()> {
+        let Some(fusion) = Scope::fusion(&self.current_version().await.level_slice.concat()) else {
+            return Ok(());
+        };
+        let min = start.map_or(fusion.start, Bytes::copy_from_slice);
+        let max = end.map_or(fusion.end, Bytes::copy_from_slice);
+        if min > max {
+            return Ok(());
+        }
+
+        self.range_compaction(
+            0..MAX_LEVEL - 1,
+            Some(Scope::from_range(0, min, max)),
+            options,
+        )
+        .await
+    }
+
+    async fn range_compaction(
+        &self,
+        levels: Range<usize>,
+        scope: Option<Scope>,
+        options: CompactionOptions,
+    ) -> KernelResult<()> {
+        let (tx, rx) = oneshot::channel();
+
+        self.compactor_tx
+            .send(CompactTask::Range {
+                levels,
+                scope,
+                options,
+                tx,
+            })
+            .await?;
+
+        rx.await.map_err(|_| KernelError::ChannelClose)?
+    }
+
+    /// 批量写入已按Key严格升序排列的键值对
+    ///
+    /// 整个批次仅获取一次MemTable锁并在写入完成后检查一次压缩阈值，
+    /// Key未严格升序(包括重复Key)时返回`KernelError::UnsortedInput`且不写入任何数据
+    #[inline]
+    pub async fn set_batch_sorted(&self, batch: Vec<(Bytes, Bytes)>) -> KernelResult<()> {
+        check_sorted(&batch)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        self.delay_write_by_level_0().await?;
+        let vec_data = batch
+            .into_iter()
+            .map(|(key, value)| (key, Some(value)))
+            .collect();
+        let is_exceeded = self
+            .mem_table()
+            .insert_batch_data(vec_data, Sequence::create())?;
+
+        self.seal_if_exceeded(is_exceeded).await
+    }
+
+    /// 原子地写入一批操作
+    ///
+    /// 整批操作共用同一个seq_id并作为一条记录写入WAL，
+    /// 因此读取与恢复时仅会看到完整的一批或完全看不到
+    #[inline]
+    pub async fn write_batch(&self, batch: WriteBatch) -> KernelResult<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        self.delay_write_by_level_0().await?;
+        // 相同seq_id下同一Key仅能保留一个版本，因此只保留最后一次操作
+        let vec_data = batch
+            .vec_data
+            .into_iter()
+            .rev()
+            .unique_by(|(key, _)| key.clone())
+            .collect_vec();
+        let is_exceeded = self
+            .mem_table()
+            .insert_batch_data(vec_data, Sequence::create())?;
+
+        self.seal_if_exceeded(is_exceeded).await
+    }
+
+    /// 读取`cf`中的Key
+    #[inline]
+    pub async fn get_cf(&self, cf: &ColumnFamily, key: &[u8]) -> KernelResult<Option<Bytes>> {
+        self.get(&cf.encode_key(key)).await
+    }
+
+    /// 在运行时修改配置
+    ///
+    /// `f`接收当前配置并返回修改后的配置，仅以下参数允许在运行时修改，并于下一次压缩时生效:
+    /// - `sst_file_size`
+    /// - `major_threshold_with_sst_size`
+    /// - `level0_file_num_compaction_trigger`
+    /// - `level_sst_magnification`
+    /// - `ver_log_snapshot_threshold`
+    /// - `compaction_readahead_size`
+    /// - `disable_auto_compaction`
+    /// - `compression_dict`
+    /// - `max_compaction_bytes`
+    /// - `l0_slowdown_trigger`
+    /// - `l0_stop_trigger`
+    /// - `max_entries_per_sst`
+    /// - `compaction_bytes_per_sec`
+    ///
+    /// 修改其余参数时返回`KernelError::NotSupport`且不会应用任何修改
+    #[inline]
+    pub fn update_config(&self, f: impl FnOnce(Config) -> Config) -> KernelResult<()> {
+        self.inner.update_config(f)
+    }
+
     #[allow(dead_code)]
     async fn flush_background(&self) -> KernelResult<()> {
         self.compactor_tx.send(CompactTask::Flush(None)).await?;
@@ -271,7 +955,7 @@ impl KipStorage {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Config {
     /// 数据目录地址
     pub(crate) dir_path: PathBuf,
@@ -286,6 +970,11 @@ pub struct Config {
     pub(crate) minor_trigger_with_threshold: (TriggerType, usize),
     /// Major压缩触发阈值
     pub(crate) major_threshold_with_sst_size: usize,
+    /// Level 0中Table数量达到该值时触发Level 0至Level 1的压缩
+    ///
+    /// Level 0中的Table之间相互重叠，读取时需要逐个查询，因此单独以数量作为触发条件；
+    /// 为None时与其他Level一致使用`major_threshold_with_sst_size`
+    pub(crate) level0_file_num_compaction_trigger: Option<usize>,
     /// 每级SSTable数量倍率
     pub(crate) level_sst_magnification: usize,
     /// 布隆过滤器 期望的错误概率
@@ -307,6 +996,151 @@ pub struct Config {
     pub(crate) index_restart_interval: usize,
     /// VersionLog触发快照化的运行时计量阈值
     pub(crate) ver_log_snapshot_threshold: usize,
+    /// Level 0的SSTable是否在创建与加载时预取IndexBlock
+    pub(crate) prefetch_index: bool,
+    /// SSTable写入时每写入该字节数便进行一次同步，为0时仅在写入完成后同步一次
+    pub(crate) bytes_per_sync: usize,
+    /// Compactor线程的I/O调度优先级，仅在Linux下生效
+    pub(crate) compaction_io_priority: Option<IoPriority>,
+    /// 是否由使用者通过`KipStorage::sync_wal`手动写入WAL
+    ///
+    /// 开启后写入的日志记录仅暂存于内存中，在`sync_wal`前停机将丢失这部分数据
+    pub(crate) manual_wal_flush: bool,
+    /// 允许同时存在的待持久化的Immut Table数量上限
+    ///
+    /// 达到上限时写入将等待Compactor持久化最旧的Immut Table
+    /// Tips: 停机重启时尚未持久化的Immut Table将由其WAL恢复并重新持久化
+    pub(crate) max_write_buffer_number: usize,
+    /// 压缩时顺序读取Table所使用的预读缓冲大小，为0时不进行预读
+    ///
+    /// 开启后压缩读取不经过Block缓存，适用于机械硬盘与网络文件系统
+    pub(crate) compaction_readahead_size: usize,
+    /// 是否关闭自动压缩
+    ///
+    /// 关闭后Flush仅生成Level 0的Table，Seek Miss也不再触发压缩，
+    /// 压缩仅在调用`KipStorage::manual_compaction`时进行，便于基准测试控制压缩时机
+    pub(crate) disable_auto_compaction: bool,
+    /// 每个SSTable所训练的Zstd压缩字典的最大大小，为0时不使用字典而以LZ4压缩
+    ///
+    /// 字典以该SSTable的数据作为样本训练并存储于其中，适用于大量相似的小Value
+    pub(crate) compression_dict: usize,
+    /// `KipStorage::shut_down`时是否跳过MemTable的持久化
+    ///
+    /// 开启后关闭时仅将WAL持久化至硬盘，MemTable中的数据将在下次开启时通过WAL恢复，
+    /// 以更长的开启时间换取更快的关闭，MemTable越大则恢复耗时越长，适用于临时数据与缓存场景
+    pub(crate) avoid_flush_during_shutdown: bool,
+    /// 范围扫描时在当前DataBlock之后异步预取至Block缓存的DataBlock数量，为0时不进行预取
+    ///
+    /// 用于隐藏顺序扫描时逐个载入Block的IO延迟，其值应小于Block缓存的容量以免预取的Block被提前驱逐
+    pub(crate) scan_prefetch_blocks: usize,
+    /// 当前MemTable对应的WAL超过该字节数时便触发封存并Flush，为0时不限制
+    ///
+    /// 独立于Minor触发器的阈值，用于限制停机重启时需要恢复的WAL大小
+    pub(crate) wal_size_limit: usize,
+    /// 慢速存储层的Table文件目录，为None时所有Table均存放于`dir_path`中
+    ///
+    /// 用于分层存储：将较小且访问频繁的低Level置于快速设备，数据量最大的高Level置于大容量的慢速设备
+    pub(crate) slow_tier_path: Option<PathBuf>,
+    /// 设置`slow_tier_path`时，Level大于等于该值的Table将写入慢速存储层
+    pub(crate) min_compaction_level: usize,
+    /// WAL与SSTable的持久化是否使用fsync(`sync_all`)，默认使用fdatasync(`sync_data`)
+    ///
+    /// fdatasync不同步与读取无关的文件元数据(如修改时间)，在部分文件系统上远快于fsync
+    pub(crate) use_fsync: bool,
+    /// 是否在`KipStorage::stats`中给出Flush与压缩的读写字节数及写放大系数
+    pub(crate) report_bg_io_stats: bool,
+    /// DataBlock使用该级别的Zstd压缩，为None时使用LZ4压缩
+    ///
+    /// 较高的级别以更多的CPU开销换取更小的磁盘占用，适用于冷数据；
+    /// 设置`compression_dict`且字典训练成功时优先使用带字典的Zstd压缩
+    pub(crate) zstd_compression_level: Option<i32>,
+    /// 压缩生成最深的有数据的Level的Table时所使用的DataBlock压缩类型，为None时与其他Level一致
+    ///
+    /// 最深的Level的数据最冷且数据量最大，适合在此使用压缩率更高的压缩类型，
+    /// 而较浅的Level保持轻量的压缩以降低读写开销；Flush生成的Level 0的Table不受影响
+    pub(crate) bottommost_compression: Option<CompressType>,
+    /// SSTable中Block的校验类型
+    ///
+    /// 校验类型记录于各个Block中，因此修改后已有的SSTable仍按其写入时的类型进行校验
+    pub(crate) block_checksum_type: ChecksumType,
+    /// 复合Key的前缀提取方式，设置后SSTable会额外构建前缀索引，使前缀扫描跳过不相关的DataBlock
+    ///
+    /// 提取方式记录于各个SSTable中，因此修改后仅对新生成的SSTable生效
+    pub(crate) prefix_extractor: Option<PrefixExtractor>,
+    /// 同时打开的SSTable文件数量上限，为0时不作限制
+    ///
+    /// 快速与慢速存储层中的SSTable及其ValueLog共同计入该上限；
+    /// 为0时每个位于Table缓存中的SSTable均常驻其文件句柄；设置后SSTable仅在读取时获取文件句柄，
+    /// 超出上限时关闭最久未使用的文件，避免SSTable过多时耗尽进程的文件描述符
+    pub(crate) max_open_files: usize,
+    /// 写入MemTable中已存在的Key时直接替换其旧版本，而不是追加新的版本
+    ///
+    /// 适用于在持久化前反复覆盖同一批Key的场景，以限制MemTable的增长；
+    /// 存在进行中的事务时旧版本仍可能被读取，此时依旧追加写入。
+    /// 被替换的中间版本无法再通过`KipStorage::get_updates_since`获取
+    pub(crate) inplace_update: bool,
+    /// Flush前是否校验待持久化的MemTable数据严格按Key升序排列
+    ///
+    /// 开启后乱序或重复的Key会使Flush返回附带相邻两个Key的`KernelError::UnsortedInput`，
+    /// 且不会生成任何Table文件；用于排查MemTable的排序问题，会带来一次额外的数据遍历
+    pub(crate) paranoid_memtable_order_check: bool,
+    /// 长度不小于该值的Value会分离存储于SSTable同Gen的ValueLog中，为0时不进行分离
+    ///
+    /// ValueLog随SSTable一同重写与删除，因此分离并不减少Compaction的写放大，
+    /// 主要用于减小DataBlock的体积以提高Block缓存的命中率；仅对新创建的SSTable生效
+    pub(crate) value_separation_threshold: usize,
+    /// 自动压缩时Level 0之下的Level挑选优先压缩的Table的策略
+    pub(crate) compaction_priority: CompactionPriority,
+    /// 是否缓存`KipStorage::get`未命中的Key，使重复查询不存在的Key时跳过MemTable与各Level的查找
+    ///
+    /// 缓存的Key会在写入时失效，适用于大量查询不存在的Key的场景
+    pub(crate) enable_negative_cache: bool,
+    /// 单次压缩所读取的输入Table的磁盘大小上限，为0时不作限制
+    ///
+    /// 超出时按Key边界将该次压缩拆分为多次较小的压缩，以限制单次压缩的耗时与内存占用；
+    /// 与下一级重叠过多的单个Table仍会被完整压缩
+    pub(crate) max_compaction_bytes: u64,
+    /// 各Level的SSTable布隆过滤器的构建策略，仅对新创建的SSTable生效
+    ///
+    /// 可对数据量最大的底层Level关闭布隆过滤器或提高其误判率以节省内存
+    pub(crate) bloom_policy: BloomPolicy,
+    /// 以各Key的前N个字节额外构建前缀布隆过滤器，为0时不构建
+    ///
+    /// 前缀扫描的前缀长度不小于N时可借此跳过不含该前缀的SSTable；仅对新创建的SSTable生效
+    pub(crate) prefix_bloom_len: usize,
+    /// 自动压缩的策略，默认为`CompactionStrategy::Leveled`
+    ///
+    /// 为`CompactionStrategy::Tiered`时不再使用`compaction_priority`挑选Table
+    pub(crate) compaction_strategy: CompactionStrategy,
+    /// Major压缩写入新Table的速率上限(字节/秒)，为0时不作限制
+    ///
+    /// 以令牌桶限制后台压缩的写入，避免大量压缩占满磁盘带宽而拖慢前台写入；
+    /// 令牌由所有并行的压缩任务共享，Flush不受限制
+    pub(crate) compaction_bytes_per_sec: u64,
+    /// Level 0的Table数量超出该值时延迟每次写入，为None时不延迟
+    ///
+    /// Level 0的Table之间相互重叠而需逐个查询，以此限制Flush快于压缩时的读放大
+    pub(crate) l0_slowdown_trigger: Option<usize>,
+    /// Level 0的Table数量超出该值时阻塞写入直至压缩完成，为None时不阻塞
+    ///
+    /// 不可小于Level 0的压缩阈值，否则Level 0在触发压缩前便阻塞写入；
+    /// 关闭自动压缩时写入将被一直阻塞直至手动压缩
+    pub(crate) l0_stop_trigger: Option<usize>,
+    /// 压缩生成的单个SSTable所含数据条数上限，为0时不作限制
+    ///
+    /// 与`sst_file_size`任一达到时即切分出新的SSTable，避免Key与Value极小时单个SSTable的索引过大
+    pub(crate) max_entries_per_sst: usize,
+    /// 启动时是否保留未被Version引用的Table文件，默认删除
+    ///
+    /// 这些文件通常由中断的压缩所遗留，保留时仅记录日志以供排查
+    pub(crate) keep_orphans: bool,
+    /// 各Table写入时所生成的摘要过滤器的位数，为0时不生成
+    ///
+    /// 同一Level中各Table的摘要合并为该Level的摘要，查询时摘要判定不存在的Key可跳过整个Level的Table，
+    /// 适用于`KipStorage::multi_get`大量查询不存在的Key的场景；
+    /// 每个Table的摘要均占用该位数的内存，且仅对新创建的Table生效；
+    /// 摘要仅保存于内存中而不会持久化，重启后此前创建的Table不再被摘要覆盖
+    pub(crate) level_summary_bits: usize,
 }
 
 impl Config {
@@ -322,6 +1156,7 @@ impl Config {
                 DEFAULT_MINOR_THRESHOLD_WITH_SIZE_WITH_MEM,
             ),
             major_threshold_with_sst_size: DEFAULT_MAJOR_THRESHOLD_WITH_SST_SIZE,
+            level0_file_num_compaction_trigger: None,
             level_sst_magnification: DEFAULT_LEVEL_SST_MAGNIFICATION,
             desired_error_prob: DEFAULT_DESIRED_ERROR_PROB,
             block_cache_size: DEFAULT_BLOCK_CACHE_SIZE,
@@ -331,6 +1166,41 @@ impl Config {
             data_restart_interval: block::DEFAULT_DATA_RESTART_INTERVAL,
             index_restart_interval: block::DEFAULT_INDEX_RESTART_INTERVAL,
             ver_log_snapshot_threshold: version::DEFAULT_VERSION_LOG_THRESHOLD,
+            prefetch_index: true,
+            bytes_per_sync: 0,
+            compaction_io_priority: None,
+            manual_wal_flush: false,
+            max_write_buffer_number: 1,
+            compaction_readahead_size: 0,
+            disable_auto_compaction: false,
+            compression_dict: 0,
+            avoid_flush_during_shutdown: false,
+            scan_prefetch_blocks: 0,
+            wal_size_limit: 0,
+            slow_tier_path: None,
+            min_compaction_level: MAX_LEVEL - 1,
+            use_fsync: false,
+            report_bg_io_stats: false,
+            zstd_compression_level: None,
+            bottommost_compression: None,
+            block_checksum_type: ChecksumType::Crc32,
+            prefix_extractor: None,
+            max_open_files: 0,
+            inplace_update: false,
+            paranoid_memtable_order_check: false,
+            value_separation_threshold: 0,
+            compaction_priority: CompactionPriority::Cascade,
+            enable_negative_cache: false,
+            max_compaction_bytes: 0,
+            bloom_policy: BloomPolicy::Uniform,
+            prefix_bloom_len: 0,
+            compaction_strategy: CompactionStrategy::Leveled,
+            compaction_bytes_per_sec: 0,
+            l0_slowdown_trigger: None,
+            l0_stop_trigger: None,
+            max_entries_per_sst: 0,
+            keep_orphans: false,
+            level_summary_bits: 0,
         }
     }
 
@@ -338,22 +1208,103 @@ impl Config {
         &self.dir_path
     }
 
-    #[inline]
-    pub fn enable_level_0_memorization(mut self) -> Self {
-        self.level_table_type[0] = TableType::BTree;
-        self
+    /// Level 0的Table数量达到该值时触发Level 0至Level 1的压缩
+    pub(crate) fn level_0_compaction_threshold(&self) -> usize {
+        match (
+            self.level0_file_num_compaction_trigger,
+            self.compaction_strategy,
+        ) {
+            (Some(trigger), _) => trigger,
+            (None, CompactionStrategy::Tiered { min_merge_width }) => min_merge_width,
+            (None, CompactionStrategy::Leveled) => self.major_threshold_with_sst_size,
+        }
     }
 
-    #[inline]
-    pub fn dir_path(mut self, dir_path: PathBuf) -> Self {
-        self.dir_path = dir_path;
-        self
+    /// 校验`l0_stop_trigger`不小于Level 0的压缩阈值，否则写入将在触发压缩前被永久阻塞
+    fn check_write_stall(&self) -> KernelResult<()> {
+        if self
+            .l0_stop_trigger
+            .is_some_and(|trigger| trigger < self.level_0_compaction_threshold())
+        {
+            return Err(KernelError::NotSupport(
+                "`l0_stop_trigger` cannot be less than the Level 0 compaction threshold",
+            ));
+        }
+
+        Ok(())
     }
 
-    #[inline]
-    pub fn level_table_type(mut self, level: usize, table_type: TableType) -> Self {
-        self.level_table_type[level] = table_type;
-        self
+    /// 校验`new_config`仅修改了`KipStorage::update_config`中所列出的参数，其余参数均视为不可修改
+    fn check_immutable(&self, new_config: &Config) -> KernelResult<()> {
+        macro_rules! mutable_fields {
+            ($config:ident, $($field:ident),+) => {
+                $($config.$field = self.$field.clone();)+
+            };
+        }
+        let mut config = new_config.clone();
+        mutable_fields!(
+            config,
+            sst_file_size,
+            major_threshold_with_sst_size,
+            level0_file_num_compaction_trigger,
+            level_sst_magnification,
+            ver_log_snapshot_threshold,
+            compaction_readahead_size,
+            disable_auto_compaction,
+            compression_dict,
+            max_compaction_bytes,
+            l0_slowdown_trigger,
+            l0_stop_trigger,
+            max_entries_per_sst,
+            compaction_bytes_per_sec
+        );
+        if &config != self {
+            return Err(KernelError::NotSupport(
+                "only the parameters listed in `KipStorage::update_config` can be changed at runtime",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 将不合法的缓存大小向上对齐至`CACHE_SHARDING_SIZE`的整数倍
+    ///
+    /// 避免因缓存大小设置失误而导致无法开启
+    pub(crate) fn align_cache_size(mut self) -> Self {
+        fn align(name: &str, cache_size: usize) -> usize {
+            if cache_size != 0 && cache_size % CACHE_SHARDING_SIZE == 0 {
+                return cache_size;
+            }
+            let aligned = cache_size.div_ceil(CACHE_SHARDING_SIZE).max(1) * CACHE_SHARDING_SIZE;
+            warn!(
+                "[Config][{}: {}][must be a non-zero multiple of {}, fallback to {}]",
+                name, cache_size, CACHE_SHARDING_SIZE, aligned
+            );
+
+            aligned
+        }
+        self.block_cache_size = align("block_cache_size", self.block_cache_size);
+        self.table_cache_size = align("table_cache_size", self.table_cache_size);
+
+        self
+    }
+
+    #[inline]
+    pub fn enable_level_0_memorization(mut self) -> Self {
+        self.level_table_type[0] = TableType::BTree;
+        self
+    }
+
+    #[inline]
+    pub fn dir_path(mut self, dir_path: PathBuf) -> Self {
+        self.dir_path = dir_path;
+        self
+    }
+
+    #[inline]
+    pub fn level_table_type(mut self, level: usize, table_type: TableType) -> Self {
+        self.level_table_type[level] = table_type;
+        self
     }
 
     #[inline]
@@ -402,6 +1353,15 @@ impl Config {
         self
     }
 
+    #[inline]
+    pub fn level0_file_num_compaction_trigger(
+        mut self,
+        level0_file_num_compaction_trigger: usize,
+    ) -> Self {
+        self.level0_file_num_compaction_trigger = Some(level0_file_num_compaction_trigger);
+        self
+    }
+
     #[inline]
     pub fn level_sst_magnification(mut self, level_sst_magnification: usize) -> Self {
         self.level_sst_magnification = level_sst_magnification;
@@ -437,6 +1397,225 @@ impl Config {
         self.ver_log_snapshot_threshold = ver_log_snapshot_threshold;
         self
     }
+
+    #[inline]
+    pub fn prefetch_index(mut self, prefetch_index: bool) -> Self {
+        self.prefetch_index = prefetch_index;
+        self
+    }
+
+    #[inline]
+    pub fn bytes_per_sync(mut self, bytes_per_sync: usize) -> Self {
+        self.bytes_per_sync = bytes_per_sync;
+        self
+    }
+
+    #[inline]
+    pub fn compaction_io_priority(mut self, io_priority: IoPriority) -> Self {
+        self.compaction_io_priority = Some(io_priority);
+        self
+    }
+
+    #[inline]
+    pub fn manual_wal_flush(mut self, manual_wal_flush: bool) -> Self {
+        self.manual_wal_flush = manual_wal_flush;
+        self
+    }
+
+    #[inline]
+    pub fn max_write_buffer_number(mut self, max_write_buffer_number: usize) -> Self {
+        self.max_write_buffer_number = max_write_buffer_number;
+        self
+    }
+
+    #[inline]
+    pub fn compaction_readahead_size(mut self, compaction_readahead_size: usize) -> Self {
+        self.compaction_readahead_size = compaction_readahead_size;
+        self
+    }
+
+    #[inline]
+    pub fn disable_auto_compaction(mut self, disable_auto_compaction: bool) -> Self {
+        self.disable_auto_compaction = disable_auto_compaction;
+        self
+    }
+
+    #[inline]
+    pub fn compression_dict(mut self, compression_dict: usize) -> Self {
+        self.compression_dict = compression_dict;
+        self
+    }
+
+    #[inline]
+    pub fn avoid_flush_during_shutdown(mut self, avoid_flush_during_shutdown: bool) -> Self {
+        self.avoid_flush_during_shutdown = avoid_flush_during_shutdown;
+        self
+    }
+
+    #[inline]
+    pub fn scan_prefetch_blocks(mut self, scan_prefetch_blocks: usize) -> Self {
+        self.scan_prefetch_blocks = scan_prefetch_blocks;
+        self
+    }
+
+    #[inline]
+    pub fn wal_size_limit(mut self, wal_size_limit: usize) -> Self {
+        self.wal_size_limit = wal_size_limit;
+        self
+    }
+
+    #[inline]
+    pub fn slow_tier_path(mut self, slow_tier_path: impl Into<PathBuf>) -> Self {
+        self.slow_tier_path = Some(slow_tier_path.into());
+        self
+    }
+
+    #[inline]
+    pub fn min_compaction_level(mut self, min_compaction_level: usize) -> Self {
+        self.min_compaction_level = min_compaction_level;
+        self
+    }
+
+    #[inline]
+    pub fn use_fsync(mut self, use_fsync: bool) -> Self {
+        self.use_fsync = use_fsync;
+        self
+    }
+
+    #[inline]
+    pub fn report_bg_io_stats(mut self, report_bg_io_stats: bool) -> Self {
+        self.report_bg_io_stats = report_bg_io_stats;
+        self
+    }
+
+    #[inline]
+    pub fn zstd_compression_level(mut self, zstd_compression_level: i32) -> Self {
+        self.zstd_compression_level = Some(zstd_compression_level);
+        self
+    }
+
+    #[inline]
+    pub fn bottommost_compression(mut self, bottommost_compression: CompressType) -> Self {
+        self.bottommost_compression = Some(bottommost_compression);
+        self
+    }
+
+    #[inline]
+    pub fn block_checksum_type(mut self, block_checksum_type: ChecksumType) -> Self {
+        self.block_checksum_type = block_checksum_type;
+        self
+    }
+
+    #[inline]
+    pub fn prefix_extractor(mut self, prefix_extractor: PrefixExtractor) -> Self {
+        self.prefix_extractor = Some(prefix_extractor);
+        self
+    }
+
+    #[inline]
+    pub fn max_open_files(mut self, max_open_files: usize) -> Self {
+        self.max_open_files = max_open_files;
+        self
+    }
+
+    #[inline]
+    pub fn inplace_update(mut self, inplace_update: bool) -> Self {
+        self.inplace_update = inplace_update;
+        self
+    }
+
+    #[inline]
+    pub fn paranoid_memtable_order_check(mut self, paranoid_memtable_order_check: bool) -> Self {
+        self.paranoid_memtable_order_check = paranoid_memtable_order_check;
+        self
+    }
+
+    #[inline]
+    pub fn value_separation_threshold(mut self, value_separation_threshold: usize) -> Self {
+        self.value_separation_threshold = value_separation_threshold;
+        self
+    }
+
+    #[inline]
+    pub fn compaction_priority(mut self, compaction_priority: CompactionPriority) -> Self {
+        self.compaction_priority = compaction_priority;
+        self
+    }
+
+    /// # Panics
+    ///
+    /// `CompactionStrategy::Tiered`的`min_merge_width`小于2时，单个Table便会被反复地向下一级压缩
+    #[inline]
+    pub fn compaction_strategy(mut self, compaction_strategy: CompactionStrategy) -> Self {
+        if let CompactionStrategy::Tiered { min_merge_width } = compaction_strategy {
+            assert!(
+                min_merge_width >= 2,
+                "min_merge_width must be at least 2, got {min_merge_width}"
+            );
+        }
+        self.compaction_strategy = compaction_strategy;
+        self
+    }
+
+    #[inline]
+    pub fn l0_slowdown_trigger(mut self, l0_slowdown_trigger: usize) -> Self {
+        self.l0_slowdown_trigger = Some(l0_slowdown_trigger);
+        self
+    }
+
+    #[inline]
+    pub fn l0_stop_trigger(mut self, l0_stop_trigger: usize) -> Self {
+        self.l0_stop_trigger = Some(l0_stop_trigger);
+        self
+    }
+
+    #[inline]
+    pub fn keep_orphans(mut self, keep_orphans: bool) -> Self {
+        self.keep_orphans = keep_orphans;
+        self
+    }
+
+    #[inline]
+    pub fn max_entries_per_sst(mut self, max_entries_per_sst: usize) -> Self {
+        self.max_entries_per_sst = max_entries_per_sst;
+        self
+    }
+
+    #[inline]
+    pub fn compaction_bytes_per_sec(mut self, compaction_bytes_per_sec: u64) -> Self {
+        self.compaction_bytes_per_sec = compaction_bytes_per_sec;
+        self
+    }
+
+    #[inline]
+    pub fn enable_negative_cache(mut self, enable_negative_cache: bool) -> Self {
+        self.enable_negative_cache = enable_negative_cache;
+        self
+    }
+
+    #[inline]
+    pub fn max_compaction_bytes(mut self, max_compaction_bytes: u64) -> Self {
+        self.max_compaction_bytes = max_compaction_bytes;
+        self
+    }
+
+    #[inline]
+    pub fn bloom_policy(mut self, bloom_policy: BloomPolicy) -> Self {
+        self.bloom_policy = bloom_policy;
+        self
+    }
+
+    #[inline]
+    pub fn prefix_bloom_len(mut self, prefix_bloom_len: usize) -> Self {
+        self.prefix_bloom_len = prefix_bloom_len;
+        self
+    }
+
+    #[inline]
+    pub fn level_summary_bits(mut self, level_summary_bits: usize) -> Self {
+        self.level_summary_bits = level_summary_bits;
+        self
+    }
 }
 
 /// 插入时Sequence id生成器
@@ -455,11 +1634,19 @@ impl Sequence {
 }
 
 impl Gen {
-    /// 将GEN_BUF初始化至当前时间戳
+    /// 将GEN_BUF推进至当前时间戳
     ///
     /// 与create_gen相对应，需要将GEN初始化为当前时间戳
+    /// Tips: GEN_BUF只增不减，避免同一进程中多次开启时回退至已分配过的gen
     pub(crate) fn init() {
-        GEN_BUF.store(Local::now().timestamp_millis(), Ordering::Relaxed);
+        let _ = GEN_BUF.fetch_max(Local::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    /// 确保之后生成的gen均大于已存在的gen
+    ///
+    /// 用于重启时跳过上次运行中超前于时间戳所分配的gen
+    pub(crate) fn advance(gen: i64) {
+        let _ = GEN_BUF.fetch_max(gen + 1, Ordering::Relaxed);
     }
 
     pub(crate) fn create() -> i64 {
@@ -469,9 +1656,1701 @@ impl Gen {
 
 #[cfg(test)]
 mod tests {
-    use crate::kernel::lsm::storage::{Gen, Sequence};
+    use crate::kernel::io::{FileExtension, IoFactory, IoType};
+    use crate::kernel::lsm::backup::EntryType;
+    use crate::kernel::lsm::compactor::{CompactionOptions, CompactionProgress};
+    use crate::kernel::lsm::iterator::Iter;
+    use crate::kernel::lsm::mvcc::CheckType;
+    use crate::kernel::lsm::stats::CompactionStats;
+    use crate::kernel::lsm::storage::{
+        ColumnFamily, Config, Gen, KipStorage, Sequence, WriteBatch,
+    };
+    use crate::kernel::lsm::table::scope::Scope;
+    use crate::kernel::lsm::table::ss_table::SSTable;
+    use crate::kernel::lsm::version::{Version, DEFAULT_SS_TABLE_PATH};
+    use crate::kernel::lsm::{CompressType, MAX_LEVEL};
+    use crate::kernel::utils::lru_cache::ShardingLruCache;
+    use crate::kernel::{CommandData, KernelResult, Storage};
+    use crate::KernelError;
+    use bytes::Bytes;
+    use futures::StreamExt;
+    use itertools::Itertools;
+    use parking_lot::Mutex;
+    use std::collections::hash_map::RandomState;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
     use std::thread::sleep;
     use std::time::Duration;
+    use tempfile::TempDir;
+    use tokio_util::sync::CancellationToken;
+
+    #[tokio::test]
+    async fn test_backup_and_restore() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let restore_dir = TempDir::new().expect("unable to create temporary working directory");
+        let kv_store = KipStorage::open_with_config(Config::new(temp_dir.path())).await?;
+        let times = 1000;
+
+        for i in 0..times {
+            let key = Bytes::from(bincode::serialize(&i)?);
+            kv_store.set(key.clone(), key).await?;
+        }
+        kv_store.flush().await?;
+        for i in times..times * 2 {
+            let key = Bytes::from(bincode::serialize(&i)?);
+            kv_store.set(key.clone(), key).await?;
+        }
+
+        let archive_dir = TempDir::new().expect("unable to create temporary working directory");
+        let archive_path = archive_dir.path().join("backup");
+        kv_store.backup_to(fs::File::create(&archive_path)?).await?;
+        let mut archive = fs::read(&archive_path)?;
+        KipStorage::restore_from(archive.as_slice(), restore_dir.path())?;
+
+        let restored = KipStorage::open_with_config(Config::new(restore_dir.path())).await?;
+        for i in 0..times * 2 {
+            let key = Bytes::from(bincode::serialize(&i)?);
+            assert_eq!(restored.get(&key).await?, Some(key));
+        }
+        assert_eq!(restored.len().await?, kv_store.len().await?);
+
+        // 校验失败时应拒绝还原
+        let corrupted_dir = TempDir::new().expect("unable to create temporary working directory");
+        let last = archive.len() - 1;
+        archive[last] = !archive[last];
+        assert!(matches!(
+            KipStorage::restore_from(archive.as_slice(), corrupted_dir.path()),
+            Err(KernelError::CrcMisMatch)
+        ));
+        // 归档被截断时应拒绝还原
+        let truncated_dir = TempDir::new().expect("unable to create temporary working directory");
+        assert!(matches!(
+            KipStorage::restore_from(&archive[..last], truncated_dir.path()),
+            Err(KernelError::BackupCorrupted)
+        ));
+        // 帧中损坏的长度不会按其大小分配内存，而是在归档耗尽时拒绝还原
+        let mut forged = archive[..8].to_vec();
+        forged.push(EntryType::SSTable as u8);
+        forged.extend_from_slice(&0_i64.to_le_bytes());
+        forged.extend_from_slice(&u64::MAX.to_le_bytes());
+        forged.extend_from_slice(&0_u32.to_le_bytes());
+        forged.extend_from_slice(b"bytes");
+        let forged_dir = TempDir::new().expect("unable to create temporary working directory");
+        assert!(matches!(
+            KipStorage::restore_from(forged.as_slice(), forged_dir.path()),
+            Err(KernelError::BackupCorrupted)
+        ));
+
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_compaction_io_priority() -> KernelResult<()> {
+        use crate::kernel::io::priority::{thread_ioprio, IoPriority};
+        use crate::kernel::lsm::storage::COMPACTOR_THREAD_NAME;
+
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let _kv_store = KipStorage::open_with_config(
+            Config::new(temp_dir.path()).compaction_io_priority(IoPriority::Idle),
+        )
+        .await?;
+
+        let compactor_ioprio = || -> KernelResult<Vec<i32>> {
+            let mut vec_ioprio = Vec::new();
+
+            for entry in std::fs::read_dir("/proc/self/task")? {
+                let task_path = entry?.path();
+                // 其他测试的线程可能在遍历期间退出
+                let Ok(comm) = std::fs::read_to_string(task_path.join("comm")) else {
+                    continue;
+                };
+                if comm.trim_end() != COMPACTOR_THREAD_NAME {
+                    continue;
+                }
+                if let Some(tid) = task_path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(|name| name.parse::<i32>().ok())
+                {
+                    vec_ioprio.push(thread_ioprio(tid)?);
+                }
+            }
+
+            Ok(vec_ioprio)
+        };
+        let expect_ioprio = IoPriority::Idle.to_ioprio();
+
+        // 等待Compactor线程启动并完成设置
+        // 其他测试也可能同时开启了Compactor线程，因此只需其中存在即可
+        let mut is_applied = false;
+        for _ in 0..100 {
+            if compactor_ioprio()?.contains(&expect_ioprio) {
+                is_applied = true;
+                break;
+            }
+            sleep(Duration::from_millis(10));
+        }
+        assert!(is_applied);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_flush_with_outstanding_transaction() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let kv_store = KipStorage::open(temp_dir.path()).await?;
+        let timeout = Duration::from_secs(5);
+
+        kv_store
+            .set(Bytes::from_static(b"k1"), Bytes::from_static(b"v1"))
+            .await?;
+        let mut tx = kv_store.new_transaction(CheckType::Optimistic).await;
+        tx.set(Bytes::from_static(b"k2"), Bytes::from_static(b"v2"));
+
+        // 事务存活时多次Flush均不应阻塞
+        for i in 0..3_u8 {
+            kv_store
+                .set(Bytes::from(vec![b'k', b'0', i]), Bytes::from_static(b"v"))
+                .await?;
+            tokio::time::timeout(timeout, kv_store.flush())
+                .await
+                .expect("flush hangs with an outstanding transaction")?;
+        }
+        assert_eq!(tx.get(b"k1")?, Some(Bytes::from_static(b"v1")));
+        assert_eq!(tx.get(&[b'k', b'0', 0])?, None);
+
+        tokio::time::timeout(timeout, tx.commit())
+            .await
+            .expect("commit hangs after flush")?;
+        tokio::time::timeout(timeout, kv_store.flush())
+            .await
+            .expect("flush hangs after the transaction ends")?;
+        assert_eq!(kv_store.get(b"k2").await?, Some(Bytes::from_static(b"v2")));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_updates_since() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let kv_store = KipStorage::open_with_config(Config::new(temp_dir.path())).await?;
+        let times = 100;
+        let start_seq = Sequence::create();
+
+        let mut expected = Vec::new();
+        for i in 0..times {
+            let key = Bytes::from(bincode::serialize(&i)?);
+            kv_store.set(key.clone(), key.clone()).await?;
+            expected.push(CommandData::set(key.to_vec(), key.to_vec()));
+            if i % 10 == 0 {
+                kv_store.remove(&key).await?;
+                expected.push(CommandData::remove(key.to_vec()));
+            }
+        }
+
+        let updates = kv_store
+            .get_updates_since(start_seq)
+            .await?
+            .collect::<Vec<_>>()
+            .await;
+        assert!(updates.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(
+            updates.into_iter().map(|(_, cmd)| cmd).collect::<Vec<_>>(),
+            expected
+        );
+
+        // 第一次Flush后变更仍保留于Immut Table中
+        kv_store.flush().await?;
+        assert_eq!(
+            kv_store.get_updates_since(start_seq).await?.count().await,
+            expected.len()
+        );
+
+        // 第二次Flush后上一个Immut Table被替换，其变更已无法获取
+        kv_store
+            .set(Bytes::from_static(b"KipDB"), Bytes::from_static(b"KipDB"))
+            .await?;
+        kv_store.flush().await?;
+        assert!(matches!(
+            kv_store.get_updates_since(start_seq).await,
+            Err(KernelError::SeqCompacted)
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_batch_sorted() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let times = 1000_u32;
+        let batch = (0..times)
+            .map(|i| {
+                let key = Bytes::from(i.to_be_bytes().to_vec());
+                (key.clone(), key)
+            })
+            .collect::<Vec<_>>();
+
+        let kv_store = KipStorage::open(temp_dir.path()).await?;
+
+        // 未排序或存在重复Key时不写入任何数据
+        let mut unsorted = batch.clone();
+        unsorted.swap(1, 2);
+        assert!(matches!(
+            kv_store.set_batch_sorted(unsorted).await,
+            Err(KernelError::UnsortedInput { .. })
+        ));
+        let mut duplicated = batch.clone();
+        duplicated[1] = duplicated[0].clone();
+        assert!(matches!(
+            kv_store.set_batch_sorted(duplicated).await,
+            Err(KernelError::UnsortedInput { .. })
+        ));
+        assert!(kv_store.is_empty().await);
+
+        kv_store.set_batch_sorted(batch.clone()).await?;
+        assert_eq!(kv_store.len().await?, times as usize);
+        for (key, value) in batch.iter() {
+            assert_eq!(kv_store.get(key).await?, Some(value.clone()));
+        }
+        kv_store.flush().await?;
+        for (key, value) in batch.iter() {
+            assert_eq!(kv_store.get(key).await?, Some(value.clone()));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_update_config() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path()).major_threshold_with_sst_size(10);
+        let kv_store = KipStorage::open_with_config(config).await?;
+
+        for i in 0..2_u8 {
+            kv_store
+                .set(Bytes::from_static(b"KipDB"), Bytes::from(vec![i]))
+                .await?;
+            kv_store.flush().await?;
+        }
+        let version = kv_store.current_version().await;
+        assert_eq!(version.level_len(0), 2);
+        assert_eq!(version.level_len(1), 0);
+
+        // 运行时降低Major压缩阈值，并在下一次压缩时生效
+        kv_store.update_config(|config| config.major_threshold_with_sst_size(2))?;
+        assert_eq!(kv_store.inner.config().major_threshold_with_sst_size, 2);
+        kv_store
+            .set(Bytes::from_static(b"KipDB"), Bytes::from(vec![2]))
+            .await?;
+        kv_store.flush().await?;
+
+        let version = kv_store.current_version().await;
+        assert_eq!(version.level_len(0), 0);
+        assert_eq!(version.level_len(1), 1);
+        assert_eq!(kv_store.get(b"KipDB").await?, Some(Bytes::from(vec![2])));
+
+        // 不可修改的参数将被拒绝，且不会应用同时进行的其他修改
+        assert!(matches!(
+            kv_store.update_config(|config| config
+                .major_threshold_with_sst_size(4)
+                .dir_path(PathBuf::from("other"))),
+            Err(KernelError::NotSupport(_))
+        ));
+        assert_eq!(kv_store.inner.config().major_threshold_with_sst_size, 2);
+        assert_eq!(kv_store.inner.config().path(), temp_dir.path());
+        // 未列出的参数均不可修改
+        assert!(matches!(
+            kv_store.update_config(|config| config.avoid_flush_during_shutdown(true)),
+            Err(KernelError::NotSupport(_))
+        ));
+        assert!(!kv_store.inner.config().avoid_flush_during_shutdown);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_disable_auto_compaction() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path())
+            .major_threshold_with_sst_size(2)
+            .disable_auto_compaction(true);
+        let kv_store = KipStorage::open_with_config(config).await?;
+
+        for i in 0..6_u8 {
+            kv_store
+                .set(Bytes::from(vec![i]), Bytes::from(vec![i]))
+                .await?;
+            kv_store.flush().await?;
+        }
+        // 即使超出Major压缩阈值，Flush也仅累积Level 0的Table
+        let version = kv_store.current_version().await;
+        assert_eq!(version.level_len(0), 6);
+        assert_eq!(version.level_len(1), 0);
+
+        // 手动压缩请求与Flush请求由Compactor依次处理，因此Flush完成时手动压缩也已完成
+        kv_store
+            .manual_compaction(Bytes::from(vec![0]), Bytes::from(vec![5]), 0)
+            .await?;
+        kv_store.flush().await?;
+
+        let version = kv_store.current_version().await;
+        assert_eq!(version.level_len(0), 0);
+        assert!(version.level_len(1) > 0);
+        for i in 0..6_u8 {
+            assert_eq!(kv_store.get(&[i]).await?, Some(Bytes::from(vec![i])));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_slow_tier() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let slow_dir = TempDir::new().expect("unable to create temporary working directory");
+        let fast_sst_path = temp_dir.path().join(DEFAULT_SS_TABLE_PATH);
+        let config = Config::new(temp_dir.path())
+            .slow_tier_path(slow_dir.path())
+            .min_compaction_level(1)
+            .disable_auto_compaction(true);
+        let table_path = |path: &Path, gen: i64| FileExtension::SSTable.path_with_gen(path, gen);
+
+        let kv_store = KipStorage::open_with_config(config.clone()).await?;
+        for i in 0..6_u8 {
+            kv_store
+                .set(Bytes::from(vec![i]), Bytes::from(vec![i]))
+                .await?;
+            kv_store.flush().await?;
+        }
+        let version = kv_store.current_version().await;
+        assert_eq!(version.level_len(0), 6);
+        for gen in version.level_slice[0].iter().map(Scope::gen) {
+            assert!(table_path(&fast_sst_path, gen).exists());
+            assert!(!table_path(slow_dir.path(), gen).exists());
+        }
+        drop(version);
+
+        kv_store
+            .manual_compaction(Bytes::from(vec![0]), Bytes::from(vec![5]), 0)
+            .await?;
+        kv_store.flush().await?;
+
+        let version = kv_store.current_version().await;
+        assert_eq!(version.level_len(0), 0);
+        assert!(version.level_len(1) > 0);
+        for gen in version.level_slice[1].iter().map(Scope::gen) {
+            assert!(table_path(slow_dir.path(), gen).exists());
+            assert!(!table_path(&fast_sst_path, gen).exists());
+        }
+        drop(version);
+        drop(kv_store);
+
+        // 重新开启后从各自的存储层中载入Table
+        let kv_store = KipStorage::open_with_config(config).await?;
+        for i in 0..6_u8 {
+            assert_eq!(kv_store.get(&[i]).await?, Some(Bytes::from(vec![i])));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cancel_compaction() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path())
+            .sst_file_size(4 * 1024)
+            .disable_auto_compaction(true);
+        let sst_dir = config.dir_path.join(DEFAULT_SS_TABLE_PATH);
+        let kv_store = KipStorage::open_with_config(config).await?;
+        let value = Bytes::from(vec![b'K'; 128]);
+        let keys = (0..800_u32)
+            .map(|i| Bytes::from(i.to_be_bytes().to_vec()))
+            .collect_vec();
+
+        for chunk in keys.chunks(100) {
+            for key in chunk {
+                kv_store.set(key.clone(), value.clone()).await?;
+            }
+            kv_store.flush().await?;
+        }
+        let list_sst = || -> KernelResult<Vec<PathBuf>> {
+            Ok(fs::read_dir(&sst_dir)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .sorted()
+                .collect_vec())
+        };
+        let sst_files = list_sst()?;
+        let version_num = kv_store.current_version().await.version_num;
+
+        // 于第一组Table归并完成时中止，此时生成的Table应被删除且Version不变
+        let cancel = CancellationToken::new();
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let options = CompactionOptions::new(cancel.clone()).on_progress({
+            let reported = Arc::clone(&reported);
+            move |progress| {
+                reported.lock().push(progress);
+                cancel.cancel();
+            }
+        });
+        assert!(matches!(
+            kv_store.compact_all(options).await,
+            Err(KernelError::CompactionCancelled)
+        ));
+        let progress = reported.lock().clone();
+        assert_eq!(progress.len(), 1);
+        assert_eq!(progress[0].tables_processed, 8);
+        assert!(progress[0].bytes_processed > 0);
+
+        let version = kv_store.current_version().await;
+        assert_eq!(version.version_num, version_num);
+        assert_eq!(version.level_len(0), 8);
+        assert_eq!(version.level_len(1), 0);
+        assert_eq!(list_sst()?, sst_files);
+        for key in &keys {
+            assert_eq!(kv_store.get(key).await?, Some(value.clone()));
+        }
+
+        // 已中止的Token会使压缩立即中止
+        assert!(matches!(
+            kv_store
+                .compact_range(keys[0].clone(), keys[799].clone(), 0, {
+                    let cancel = CancellationToken::new();
+                    cancel.cancel();
+                    CompactionOptions::new(cancel)
+                })
+                .await,
+            Err(KernelError::CompactionCancelled)
+        ));
+        assert_eq!(kv_store.current_version().await.level_len(0), 8);
+
+        let last_progress = Arc::new(Mutex::new(CompactionProgress::default()));
+        let options = CompactionOptions::default().on_progress({
+            let last_progress = Arc::clone(&last_progress);
+            move |progress| *last_progress.lock() = progress
+        });
+        kv_store.compact_all(options).await?;
+
+        let version = kv_store.current_version().await;
+        assert_eq!(version.level_len(0), 0);
+        assert!(last_progress.lock().tables_processed >= 8);
+        for key in &keys {
+            assert_eq!(kv_store.get(key).await?, Some(value.clone()));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_compact_key_range() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path()).disable_auto_compaction(true);
+        let kv_store = KipStorage::open_with_config(config).await?;
+        let value = Bytes::from(vec![b'K'; 128]);
+        let keys = (0..800_u32)
+            .map(|i| Bytes::from(i.to_be_bytes().to_vec()))
+            .collect_vec();
+
+        // 每次Flush生成的Table之间范围互不重叠
+        for chunk in keys.chunks(100) {
+            for key in chunk {
+                kv_store.set(key.clone(), value.clone()).await?;
+            }
+            kv_store.flush().await?;
+        }
+        assert_eq!(kv_store.current_version().await.level_len(0), 8);
+
+        // 仅与[keys[650], +∞)相交的两个Table被压缩至最底层
+        kv_store
+            .compact_key_range(Some(&keys[650]), None, CompactionOptions::default())
+            .await?;
+        let version = kv_store.current_version().await;
+        assert_eq!(version.level_len(0), 6);
+        assert!(version.level_slice[1..MAX_LEVEL - 1]
+            .iter()
+            .all(Vec::is_empty));
+        assert!(version.level_len(MAX_LEVEL - 1) > 0);
+        drop(version);
+
+        // 范围之外无Table时不进行压缩
+        let version_num = kv_store.current_version().await.version_num;
+        kv_store
+            .compact_key_range(Some(&[u8::MAX; 8]), None, CompactionOptions::default())
+            .await?;
+        assert_eq!(kv_store.current_version().await.version_num, version_num);
+
+        kv_store
+            .compact_key_range(None, None, CompactionOptions::default())
+            .await?;
+        let version = kv_store.current_version().await;
+        assert_eq!(version.level_len(0), 0);
+        assert!(version.level_slice[1..MAX_LEVEL - 1]
+            .iter()
+            .all(Vec::is_empty));
+        for key in &keys {
+            assert_eq!(kv_store.get(key).await?, Some(value.clone()));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_drop_tombstone_at_bottommost() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path()).disable_auto_compaction(true);
+        let kv_store = KipStorage::open_with_config(config).await?;
+        let value = Bytes::from(vec![b'K'; 128]);
+        let keys = (0..200_u32)
+            .map(|i| Bytes::from(i.to_be_bytes().to_vec()))
+            .collect_vec();
+
+        for key in &keys {
+            kv_store.set(key.clone(), value.clone()).await?;
+        }
+        kv_store.flush().await?;
+        for key in keys.iter().step_by(2) {
+            kv_store.remove(key).await?;
+        }
+        kv_store.flush().await?;
+
+        kv_store.compact_all(CompactionOptions::default()).await?;
+
+        // 压缩至最底层后删除标记不再存于磁盘中
+        let version = kv_store.current_version().await;
+        assert!(version.level_slice[..MAX_LEVEL - 1]
+            .iter()
+            .all(Vec::is_empty));
+        assert_eq!(version.len(), keys.len() / 2);
+        for (i, key) in keys.iter().enumerate() {
+            if i % 2 == 0 {
+                assert_eq!(version.query(key)?.0, None);
+                assert_eq!(kv_store.get(key).await?, None);
+            } else {
+                assert_eq!(
+                    version.query(key)?.0,
+                    Some((key.clone(), Some(value.clone())))
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_compaction_stats() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path()).disable_auto_compaction(true);
+        let kv_store = KipStorage::open_with_config(config).await?;
+        let value = Bytes::from(vec![b'K'; 128]);
+
+        assert_eq!(kv_store.compaction_stats(), CompactionStats::default());
+        for i in 0..2_u32 {
+            for j in 0..100_u32 {
+                let key = Bytes::from([i.to_be_bytes(), j.to_be_bytes()].concat());
+                kv_store.set(key, value.clone()).await?;
+            }
+            kv_store.flush().await?;
+        }
+        let stats = kv_store.compaction_stats();
+        assert_eq!(stats.minor_count, 2);
+        assert_eq!(stats.major_count, 0);
+        assert_eq!(stats.tables_created, 2);
+        assert_eq!(stats.bytes_written, kv_store.stats().await?.size_of_disk);
+
+        kv_store.compact_all(CompactionOptions::default()).await?;
+
+        let stats = kv_store.compaction_stats();
+        let level_counts = kv_store.stats().await?.level_counts;
+        assert_eq!(stats.minor_count, 2);
+        assert!(stats.major_count >= 1);
+        assert!(stats.bytes_read > 0);
+        assert_eq!(
+            stats.tables_created - stats.tables_deleted,
+            level_counts.iter().sum::<usize>() as u64
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_l0_stop_trigger() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path())
+            .disable_auto_compaction(true)
+            .level0_file_num_compaction_trigger(2)
+            .l0_slowdown_trigger(1)
+            .l0_stop_trigger(2);
+        // 停止阈值小于Level 0的压缩阈值时拒绝开启与修改
+        assert!(matches!(
+            KipStorage::open_with_config(config.clone().level0_file_num_compaction_trigger(3))
+                .await,
+            Err(KernelError::NotSupport(_))
+        ));
+        let kv_store = KipStorage::open_with_config(config).await?;
+        assert!(matches!(
+            kv_store.update_config(|config| config.l0_stop_trigger(1)),
+            Err(KernelError::NotSupport(_))
+        ));
+        let value = Bytes::from(vec![b'K'; 128]);
+
+        for i in 0..3_u32 {
+            kv_store
+                .set(Bytes::from(i.to_be_bytes().to_vec()), value.clone())
+                .await?;
+            kv_store.flush().await?;
+        }
+        assert_eq!(kv_store.current_version().await.level_len(0), 3);
+
+        // Level 0超出停止阈值时写入被阻塞
+        let key = Bytes::from(3_u32.to_be_bytes().to_vec());
+        assert!(tokio::time::timeout(
+            Duration::from_millis(100),
+            kv_store.set(key.clone(), value.clone())
+        )
+        .await
+        .is_err());
+        assert_eq!(kv_store.get(&key).await?, None);
+
+        // 后台压缩失败时被阻塞的写入返回该错误
+        let (result, _) = tokio::join!(kv_store.set(key.clone(), value.clone()), async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            kv_store
+                .inner
+                .record_bg_result(&Err(KernelError::CompactionCancelled));
+        });
+        assert!(matches!(result, Err(KernelError::BackgroundCompaction(_))));
+        kv_store.inner.record_bg_result(&Ok(()));
+
+        // 压缩使Level 0回落后写入得以继续
+        kv_store.compact_all(CompactionOptions::default()).await?;
+        tokio::time::timeout(
+            Duration::from_secs(1),
+            kv_store.set(key.clone(), value.clone()),
+        )
+        .await
+        .expect("write is still stopped")?;
+        assert_eq!(kv_store.get(&key).await?, Some(value));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_count_live_keys() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let kv_store = KipStorage::open_with_config(Config::new(temp_dir.path())).await?;
+        let key = |i: u32| Bytes::from(i.to_be_bytes().to_vec());
+
+        for i in 0..100 {
+            kv_store.set(key(i), Bytes::from_static(b"v1")).await?;
+        }
+        kv_store.flush().await?;
+        // 覆盖写入与删除的Key仍各自占有原始条目
+        for i in 0..50 {
+            kv_store.set(key(i), Bytes::from_static(b"v2")).await?;
+        }
+        kv_store.flush().await?;
+        for i in 0..25 {
+            kv_store.remove(&key(i)).await?;
+        }
+        for i in 100..110 {
+            kv_store.set(key(i), Bytes::from_static(b"v1")).await?;
+        }
+
+        assert_eq!(kv_store.len().await?, 100 + 50 + 25 + 10);
+        assert_eq!(kv_store.count_live_keys().await?, 110 - 25);
+
+        // 压缩后依旧准确
+        kv_store.flush().await?;
+        kv_store.compact_all(CompactionOptions::default()).await?;
+        assert_eq!(kv_store.count_live_keys().await?, 110 - 25);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_approximate_memory_usage() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let kv_store = KipStorage::open_with_config(Config::new(temp_dir.path())).await?;
+        let key = |i: u32| Bytes::from(i.to_be_bytes().to_vec());
+        let value = Bytes::from(vec![b'v'; 128]);
+
+        let empty_usage = kv_store.approximate_memory_usage();
+        for i in 0..1000 {
+            kv_store.set(key(i), value.clone()).await?;
+        }
+        let write_usage = kv_store.approximate_memory_usage();
+        assert!(write_usage.mem_tables > empty_usage.mem_tables + 1000 * value.len());
+
+        // 第二次封存时释放已持久化的Immut Table
+        kv_store.flush().await?;
+        kv_store.set(key(1000), value.clone()).await?;
+        kv_store.flush().await?;
+        kv_store.compact_all(CompactionOptions::default()).await?;
+        for i in 0..1000 {
+            assert_eq!(kv_store.get(&key(i)).await?, Some(value.clone()));
+        }
+        let compacted_usage = kv_store.approximate_memory_usage();
+        assert!(compacted_usage.mem_tables < write_usage.mem_tables / 100);
+        assert!(compacted_usage.block_cache > 1000 * value.len());
+        assert!(compacted_usage.table_cache > 0);
+        assert_eq!(
+            compacted_usage.total(),
+            compacted_usage.mem_tables + compacted_usage.block_cache + compacted_usage.table_cache
+        );
+
+        // 压缩所删除的Table在Cleaner清理前仍可能位于缓存中，因此按磁盘中的Table文件移除
+        let loader = kv_store.inner.ver_status.loader();
+        for gen in loader.disk_gens()? {
+            let _ = loader.remove(&gen);
+        }
+        let cleared_usage = kv_store.approximate_memory_usage();
+        assert_eq!(cleared_usage.table_cache, 0);
+        assert!(cleared_usage.total() < compacted_usage.total());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_property_int() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let kv_store = KipStorage::open_with_config(Config::new(temp_dir.path())).await?;
+        let key = |i: u32| Bytes::from(i.to_be_bytes().to_vec());
+        let value = Bytes::from(vec![b'v'; 128]);
+
+        assert_eq!(
+            kv_store.get_property_int("kipdb.num-files-at-level0").await,
+            Some(0)
+        );
+        for i in 0..1000 {
+            kv_store.set(key(i), value.clone()).await?;
+        }
+        assert!(
+            kv_store
+                .get_property_int("kipdb.cur-size-all-mem-tables")
+                .await
+                .unwrap()
+                > 1000 * value.len() as u64
+        );
+
+        // 第二次封存时释放已持久化的Immut Table，使读取落至SSTable
+        kv_store.flush().await?;
+        kv_store.set(key(1000), value.clone()).await?;
+        kv_store.flush().await?;
+
+        let stats = kv_store.stats().await?;
+        assert_eq!(
+            kv_store.get_property_int("kipdb.num-files-at-level0").await,
+            Some(2)
+        );
+        let level_0_size = kv_store
+            .get_property_int("kipdb.size-at-level0")
+            .await
+            .unwrap();
+        assert!(level_0_size > 0);
+        assert_eq!(
+            kv_store
+                .get_property_int("kipdb.total-sst-files-size")
+                .await,
+            Some(level_0_size)
+        );
+        assert_eq!(
+            kv_store.get_property_int("kipdb.num-entries").await,
+            Some(stats.len as u64)
+        );
+        assert_eq!(
+            kv_store
+                .get_property_int("kipdb.estimate-pending-compaction-bytes")
+                .await,
+            Some(0)
+        );
+
+        let _ = kv_store.get(&key(0)).await?;
+        let hits = kv_store
+            .get_property_int("kipdb.block-cache-hits")
+            .await
+            .unwrap();
+        let misses = kv_store
+            .get_property_int("kipdb.block-cache-misses")
+            .await
+            .unwrap();
+        let _ = kv_store.get(&key(0)).await?;
+        assert!(
+            kv_store
+                .get_property_int("kipdb.block-cache-hits")
+                .await
+                .unwrap()
+                > hits
+        );
+        assert_eq!(
+            kv_store.get_property_int("kipdb.block-cache-misses").await,
+            Some(misses)
+        );
+
+        assert_eq!(
+            kv_store.get_property_int("kipdb.num-files-at-level7").await,
+            None
+        );
+        assert_eq!(kv_store.get_property_int("kipdb.unknown").await, None);
+        assert_eq!(kv_store.get_property_int("num-entries").await, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_batch() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path()).avoid_flush_during_shutdown(true);
+        let key = |i: u32| Bytes::from(i.to_be_bytes().to_vec());
+
+        let kv_store = KipStorage::open_with_config(config.clone()).await?;
+        kv_store.set(key(0), key(0)).await?;
+        let seq = Sequence::create();
+
+        let mut batch = WriteBatch::new();
+        batch.set(key(1), key(1));
+        batch.remove(key(0));
+        batch.set(key(2), key(0));
+        batch.set(key(2), key(2));
+        assert_eq!(batch.len(), 4);
+        kv_store.write_batch(batch).await?;
+        kv_store.write_batch(WriteBatch::new()).await?;
+
+        // 整批操作共用同一个seq_id
+        let updates = kv_store
+            .get_updates_since(seq)
+            .await?
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(updates.len(), 3);
+        assert!(updates.iter().map(|(seq_id, _)| seq_id).all_equal());
+        kv_store.shut_down().await?;
+
+        // 通过WAL恢复后同样可见整批操作
+        let kv_store = KipStorage::open_with_config(config).await?;
+        assert_eq!(kv_store.current_version().await.len(), 0);
+        assert_eq!(kv_store.get(&key(0)).await?, None);
+        assert_eq!(kv_store.get(&key(1)).await?, Some(key(1)));
+        assert_eq!(kv_store.get(&key(2)).await?, Some(key(2)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_write_batch_with_column_family() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path()).avoid_flush_during_shutdown(true);
+        let key = Bytes::from_static(b"KipDB");
+
+        assert!(ColumnFamily::new("").is_err());
+        assert!(ColumnFamily::new(&"x".repeat(256)).is_err());
+        let cf_1 = ColumnFamily::new("cf_1")?;
+        let cf_2 = ColumnFamily::new("cf_2")?;
+        assert_eq!(cf_1.name(), "cf_1");
+
+        let kv_store = KipStorage::open_with_config(config.clone()).await?;
+        kv_store
+            .set(key.clone(), Bytes::from_static(b"default"))
+            .await?;
+        let seq = Sequence::create();
+
+        let mut batch = WriteBatch::new();
+        batch.set_cf(&cf_1, key.clone(), Bytes::from_static(b"1"));
+        batch.set_cf(&cf_2, key.clone(), Bytes::from_static(b"2"));
+        batch.remove(key.clone());
+        kv_store.write_batch(batch).await?;
+
+        // 跨列族的操作共用同一个seq_id
+        let updates = kv_store
+            .get_updates_since(seq)
+            .await?
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(updates.len(), 3);
+        assert!(updates.iter().map(|(seq_id, _)| seq_id).all_equal());
+        kv_store.shut_down().await?;
+
+        // 通过WAL恢复后各列族同时可见
+        let kv_store = KipStorage::open_with_config(config).await?;
+        assert_eq!(kv_store.current_version().await.len(), 0);
+        assert_eq!(kv_store.get(&key).await?, None);
+        assert_eq!(
+            kv_store.get_cf(&cf_1, &key).await?,
+            Some(Bytes::from_static(b"1"))
+        );
+        assert_eq!(
+            kv_store.get_cf(&cf_2, &key).await?,
+            Some(Bytes::from_static(b"2"))
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shut_down_without_flush() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path()).avoid_flush_during_shutdown(true);
+        let key = |i: u32| Bytes::from(i.to_be_bytes().to_vec());
+
+        for round in 0..2 {
+            let kv_store = KipStorage::open_with_config(config.clone()).await?;
+            for i in round * 100..(round + 1) * 100 {
+                kv_store.set(key(i), key(i)).await?;
+            }
+            kv_store.shut_down().await?;
+
+            // 关闭时未进行持久化，数据均通过WAL恢复至MemTable中
+            let kv_store = KipStorage::open_with_config(config.clone()).await?;
+            assert_eq!(kv_store.current_version().await.len(), 0);
+            for i in 0..(round + 1) * 100 {
+                assert_eq!(kv_store.get(&key(i)).await?, Some(key(i)));
+            }
+            drop(kv_store);
+        }
+
+        let kv_store =
+            KipStorage::open_with_config(config.avoid_flush_during_shutdown(false)).await?;
+        kv_store.shut_down().await?;
+        let kv_store = KipStorage::open_with_config(Config::new(temp_dir.path())).await?;
+        assert_eq!(kv_store.current_version().await.len(), 200);
+        for i in 0..200 {
+            assert_eq!(kv_store.get(&key(i)).await?, Some(key(i)));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_use_fsync() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path())
+            .use_fsync(true)
+            .bytes_per_sync(1024);
+        let key = |i: u32| Bytes::from(i.to_be_bytes().to_vec());
+
+        let kv_store = KipStorage::open_with_config(config.clone()).await?;
+        for i in 0..1000 {
+            kv_store.set(key(i), key(i)).await?;
+        }
+        kv_store.flush().await?;
+        for i in 1000..2000 {
+            kv_store.set(key(i), key(i)).await?;
+        }
+        kv_store.sync_wal().await?;
+        drop(kv_store);
+
+        // 分别通过SSTable与WAL恢复数据
+        let kv_store = KipStorage::open_with_config(config).await?;
+        for i in 0..2000 {
+            assert_eq!(kv_store.get(&key(i)).await?, Some(key(i)));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_open_with_misaligned_cache_size() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path())
+            .block_cache_size(100)
+            .table_cache_size(0);
+        let kv_store = KipStorage::open_with_config(config).await?;
+
+        assert_eq!(kv_store.inner.config().block_cache_size, 112);
+        assert_eq!(kv_store.inner.config().table_cache_size, 16);
+
+        kv_store
+            .set(Bytes::from_static(b"KipDB"), Bytes::from_static(b"KipDB"))
+            .await?;
+        kv_store.flush().await?;
+        assert_eq!(
+            kv_store.get(b"KipDB").await?,
+            Some(Bytes::from_static(b"KipDB"))
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stats() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let kv_store = KipStorage::open_with_config(Config::new(temp_dir.keep())).await?;
+
+        for i in 0..10u8 {
+            kv_store
+                .set(Bytes::from(vec![i]), Bytes::from(vec![i]))
+                .await?;
+        }
+        let stats = kv_store.stats().await?;
+        assert_eq!(stats.level_counts[0], 0);
+        assert_eq!(stats.len, 10);
+
+        kv_store.flush().await?;
+
+        let stats = kv_store.stats().await?;
+        assert_eq!(stats.level_counts[0], 1);
+        assert!(stats.version_num > 0);
+        assert!(stats.size_of_disk > 0);
+        assert_eq!(stats.table_resident, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bloom_stats() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let kv_store = KipStorage::open_with_config(Config::new(temp_dir.keep())).await?;
+        let key = |i: u32| Bytes::from(i.to_be_bytes().to_vec());
+
+        for i in 0..100 {
+            kv_store.set(key(i * 2), key(i)).await?;
+        }
+        kv_store.flush().await?;
+        for i in 0..100 {
+            assert_eq!(kv_store.get(&key(i * 2)).await?, Some(key(i)));
+        }
+        let stats = kv_store.stats().await?;
+        assert!(stats.bloom_filter_bits > 0);
+        assert_eq!(stats.bloom_useful + stats.bloom_false_positives, 0);
+
+        // 位于Table范围内但不存在的Key会经过布隆过滤器判定
+        for i in 0..99 {
+            assert_eq!(kv_store.get(&key(i * 2 + 1)).await?, None);
+        }
+        let stats = kv_store.stats().await?;
+        assert_eq!(stats.bloom_useful + stats.bloom_false_positives, 99);
+        assert!(stats.bloom_useful > stats.bloom_false_positives);
+        assert!(stats.bloom_false_positive_rate < 0.5);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bottommost_compression() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path())
+            .disable_auto_compaction(true)
+            .bottommost_compression(CompressType::Zstd { level: 19 });
+        let kv_store = &KipStorage::open_with_config(config.clone()).await?;
+        let sst_factory = IoFactory::new(
+            config.path().join(DEFAULT_SS_TABLE_PATH),
+            FileExtension::SSTable,
+        )?;
+        let level_compress_types = |version: &Version, level: usize| {
+            version.level_slice[level]
+                .iter()
+                .map(|scope| {
+                    let cache = Arc::new(ShardingLruCache::new(
+                        config.block_cache_size,
+                        16,
+                        RandomState::default(),
+                    )?);
+                    let ss_table = SSTable::load_from_file(
+                        sst_factory.reader(scope.gen(), IoType::Direct)?,
+                        cache,
+                        false,
+                    )?;
+                    Ok(ss_table.compress_type()?.clone())
+                })
+                .collect::<KernelResult<Vec<_>>>()
+        };
+        let compact = |level: usize| async move {
+            kv_store
+                .manual_compaction(Bytes::from(vec![0]), Bytes::from(vec![u8::MAX]), level)
+                .await?;
+            // 手动压缩请求与Flush请求由Compactor依次处理，因此Flush完成时手动压缩也已完成
+            kv_store.flush().await
+        };
+
+        for i in 0..10_u8 {
+            kv_store
+                .set(Bytes::from(vec![i]), Bytes::from(vec![i; 128]))
+                .await?;
+        }
+        kv_store.flush().await?;
+        // 依次压缩至Level 1与Level 2，每次生成的都是最深的Level
+        compact(0).await?;
+        compact(1).await?;
+
+        for i in 10..20_u8 {
+            kv_store
+                .set(Bytes::from(vec![i]), Bytes::from(vec![i; 128]))
+                .await?;
+        }
+        kv_store.flush().await?;
+        // Level 2已有数据，此次生成的Level 1的Table使用默认的压缩类型
+        compact(0).await?;
+
+        let version = kv_store.current_version().await;
+        let level_1 = level_compress_types(&version, 1)?;
+        let level_2 = level_compress_types(&version, 2)?;
+        assert!(!level_1.is_empty() && !level_2.is_empty());
+        assert!(level_1.iter().all(|ty| ty == &CompressType::LZ4));
+        assert!(level_2
+            .iter()
+            .all(|ty| ty == &CompressType::Zstd { level: 19 }));
+
+        for i in 0..20_u8 {
+            assert_eq!(kv_store.get(&[i]).await?, Some(Bytes::from(vec![i; 128])));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_multi_get() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let kv_store = KipStorage::open(temp_dir.path()).await?;
+
+        for i in 0..100_u32 {
+            kv_store
+                .set(Bytes::from(i.to_be_bytes().to_vec()), Bytes::from(vec![1]))
+                .await?;
+        }
+        kv_store.flush().await?;
+        // 部分Key位于MemTable中覆盖或删除SSTable中的数据
+        for i in 0..50_u32 {
+            kv_store
+                .set(Bytes::from(i.to_be_bytes().to_vec()), Bytes::from(vec![2]))
+                .await?;
+        }
+        kv_store.remove(&99_u32.to_be_bytes()).await?;
+
+        let keys = (0..200_u32).map(u32::to_be_bytes).collect_vec();
+        let values = kv_store.multi_get(&keys).await?;
+        assert_eq!(values.len(), keys.len());
+
+        for (i, value) in values.into_iter().enumerate() {
+            let expect = match i {
+                0..=49 => Some(Bytes::from(vec![2])),
+                50..=98 => Some(Bytes::from(vec![1])),
+                _ => None,
+            };
+            assert_eq!(value, expect);
+        }
+        assert!(kv_store.multi_get::<&[u8]>(&[]).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_multi_get_with_level_summary() -> KernelResult<()> {
+        let mut filter_checks = Vec::new();
+
+        for level_summary_bits in [0, 1 << 16] {
+            let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+            let config = Config::new(temp_dir.path())
+                .disable_auto_compaction(true)
+                .level_summary_bits(level_summary_bits);
+            let kv_store = KipStorage::open_with_config(config).await?;
+
+            // 各Table的Key范围相互重叠，使不存在的Key均位于各Table的范围之内
+            for table in 0..8_u32 {
+                for i in 0..100_u32 {
+                    let key = (table + i * 8) * 2;
+                    kv_store
+                        .set(
+                            Bytes::from(key.to_be_bytes().to_vec()),
+                            Bytes::from(vec![1]),
+                        )
+                        .await?;
+                }
+                kv_store.flush().await?;
+            }
+            assert_eq!(kv_store.current_version().await.level_len(0), 8);
+
+            let bloom_counters = kv_store.inner.ver_status.loader().bloom_counters();
+            let checks = || bloom_counters.useful() + bloom_counters.false_positives();
+            let start = checks();
+            let absent_keys = (0..800_u32)
+                .map(|i| (i * 2 + 1).to_be_bytes())
+                .collect_vec();
+            assert!(kv_store
+                .multi_get(&absent_keys)
+                .await?
+                .iter()
+                .all(Option::is_none));
+            filter_checks.push(checks() - start);
+
+            let keys = (0..800_u32).map(|i| (i * 2).to_be_bytes()).collect_vec();
+            assert!(kv_store
+                .multi_get(&keys)
+                .await?
+                .iter()
+                .all(|value| value == &Some(Bytes::from(vec![1]))));
+        }
+        // 未启用摘要时几乎每个Key均需查询8个Table的过滤器，启用后绝大多数Key由Level 0的摘要直接排除
+        assert!(filter_checks[0] > 800 * 7);
+        assert!(filter_checks[1] < filter_checks[0] / 10);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_flush_with_gens() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let kv_store = KipStorage::open_with_config(Config::new(temp_dir.path())).await?;
+
+        // 无数据时不产生Table
+        assert!(kv_store.flush_with_gens().await?.is_empty());
+
+        for i in 0..10_u8 {
+            kv_store
+                .set(Bytes::from(vec![i]), Bytes::from(vec![i]))
+                .await?;
+        }
+        let gens = kv_store.flush_with_gens().await?;
+        assert_eq!(gens.len(), 1);
+
+        let version = kv_store.current_version().await;
+        assert_eq!(version.level_slice[0][0].gen(), gens[0]);
+        assert!(FileExtension::SSTable
+            .path_with_gen(&temp_dir.path().join(DEFAULT_SS_TABLE_PATH), gens[0])
+            .exists());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bg_io_stats() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path())
+            .major_threshold_with_sst_size(2)
+            .report_bg_io_stats(true);
+        let kv_store = KipStorage::open_with_config(config).await?;
+
+        for i in 0..6_u8 {
+            for j in 0..100_u8 {
+                kv_store
+                    .set(Bytes::from(vec![i, j]), Bytes::from(vec![j; 128]))
+                    .await?;
+            }
+            kv_store.flush().await?;
+        }
+
+        let stats = kv_store.stats().await?;
+        assert!(stats.user_bytes_flushed > 0);
+        assert!(stats.compaction_bytes_read > 0);
+        assert!(stats.compaction_bytes_written > 0);
+        // 每份数据至少被写入一次，且在仅有少量Level的压缩下不会被重写过多次
+        assert!(stats.write_amplification > 1.0);
+        assert!(stats.write_amplification < 10.0);
+
+        // 未开启时不进行统计
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let kv_store = KipStorage::open_with_config(Config::new(temp_dir.path())).await?;
+        kv_store
+            .set(Bytes::from_static(b"k"), Bytes::from_static(b"v"))
+            .await?;
+        kv_store.flush().await?;
+
+        let stats = kv_store.stats().await?;
+        assert_eq!(stats.user_bytes_flushed, 0);
+        assert_eq!(stats.write_amplification, 0.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gen_create_concurrently() {
+        let handles = (0..8)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    (0..10000)
+                        .map(|i| {
+                            // 并发开启时的初始化不会使gen回退
+                            if i % 100 == 0 {
+                                Gen::init();
+                            }
+                            Gen::create()
+                        })
+                        .collect_vec()
+                })
+            })
+            .collect_vec();
+        let gens = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect_vec();
+
+        assert_eq!(gens.iter().unique().count(), gens.len());
+    }
+
+    #[tokio::test]
+    async fn test_max_open_files() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path())
+            .disable_auto_compaction(true)
+            .max_open_files(16);
+        let key = |i: u32| Bytes::from(i.to_be_bytes().to_vec());
+
+        let kv_store = KipStorage::open_with_config(config.clone()).await?;
+        // 生成数量超过文件句柄上限的SSTable
+        for i in 0..40 {
+            for j in i * 10..(i + 1) * 10 {
+                kv_store.set(key(j), key(j)).await?;
+            }
+            kv_store.flush().await?;
+        }
+        assert_eq!(kv_store.current_version().await.level_len(0), 40);
+        for i in 0..400 {
+            assert_eq!(kv_store.get(&key(i)).await?, Some(key(i)));
+        }
+        drop(kv_store);
+
+        let kv_store = KipStorage::open_with_config(config).await?;
+        for i in 0..400 {
+            assert_eq!(kv_store.get(&key(i)).await?, Some(key(i)));
+        }
+        kv_store.compact_all(CompactionOptions::default()).await?;
+        for i in 0..400 {
+            assert_eq!(kv_store.get(&key(i)).await?, Some(key(i)));
+        }
+
+        Ok(())
+    }
+
+    /// 压缩期间同时打开的SSTable文件数量受`Config::max_open_files`限制，而与参与压缩的Table数量无关
+    #[cfg(target_os = "linux")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_compaction_open_files_bounded() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let max_open_files = 8;
+        let config = Config::new(temp_dir.path())
+            .disable_auto_compaction(true)
+            .compaction_readahead_size(4096)
+            .sst_file_size(4 * 1024)
+            .max_open_files(max_open_files);
+        let key = |i: u32| Bytes::from(i.to_be_bytes().to_vec());
+        let value = Bytes::from(vec![b'v'; 64]);
+
+        let kv_store = KipStorage::open_with_config(config.clone()).await?;
+        for i in 0..60 {
+            for j in i * 20..(i + 1) * 20 {
+                kv_store.set(key(j), value.clone()).await?;
+            }
+            kv_store.flush().await?;
+        }
+        assert_eq!(kv_store.current_version().await.level_len(0), 60);
+
+        let sst_dir = fs::canonicalize(config.dir_path.join(DEFAULT_SS_TABLE_PATH))?;
+        let is_finished = Arc::new(AtomicBool::new(false));
+        let sampler = {
+            let is_finished = Arc::clone(&is_finished);
+
+            std::thread::spawn(move || {
+                let mut max_opened = 0;
+                while !is_finished.load(Ordering::Acquire) {
+                    let opened = fs::read_dir("/proc/self/fd").map_or(0, |dir| {
+                        dir.flatten()
+                            .filter(|entry| {
+                                fs::read_link(entry.path())
+                                    .is_ok_and(|path| path.starts_with(&sst_dir))
+                            })
+                            .count()
+                    });
+                    max_opened = max_opened.max(opened);
+                    std::thread::yield_now();
+                }
+                max_opened
+            })
+        };
+        kv_store.compact_all(CompactionOptions::default()).await?;
+        is_finished.store(true, Ordering::Release);
+        let max_opened = sampler.join().expect("sampler panicked");
+
+        // 缓存的读取器之外，至多还有正在写入的Table以及读取期间被淘汰的读取器
+        assert!(
+            max_opened <= max_open_files + 2,
+            "opened {max_opened} sstable files during compaction"
+        );
+        assert_eq!(kv_store.current_version().await.level_len(0), 0);
+        for i in 0..1200 {
+            assert_eq!(kv_store.get(&key(i)).await?, Some(value.clone()));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_value_separation() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let restore_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path())
+            .disable_auto_compaction(true)
+            .value_separation_threshold(128);
+        let kv_store = KipStorage::open_with_config(config.clone()).await?;
+        let key = |i: u32| Bytes::from(i.to_be_bytes().to_vec());
+        // 奇数Key的Value超过阈值，需分离存储
+        let value = |i: u32, round: u8| {
+            let len = if i % 2 == 0 { 16 } else { 512 };
+            Bytes::from(vec![round; len])
+        };
+        let sst_dir = config.dir_path.join(DEFAULT_SS_TABLE_PATH);
+        let count_files = |extension: FileExtension| -> KernelResult<usize> {
+            Ok(fs::read_dir(&sst_dir)?
+                .flatten()
+                .filter(|entry| {
+                    entry.path().extension() == Some(extension.extension_str().as_ref())
+                })
+                .count())
+        };
+
+        for round in 0..2_u8 {
+            for i in 0..1000 {
+                kv_store.set(key(i), value(i, round)).await?;
+            }
+            kv_store.flush().await?;
+            kv_store.compact_all(CompactionOptions::default()).await?;
+
+            // 旧SSTable的ValueLog应随其一同删除
+            assert!(count_files(FileExtension::ValueLog)? > 0);
+            assert!(count_files(FileExtension::ValueLog)? <= count_files(FileExtension::SSTable)?);
+            for i in 0..1000 {
+                assert_eq!(kv_store.get(&key(i)).await?, Some(value(i, round)));
+            }
+            let tx = kv_store.new_transaction(CheckType::Optimistic).await;
+            let mut iter = tx.iter(std::ops::Bound::Unbounded, std::ops::Bound::Unbounded)?;
+            let mut i = 0;
+            while let Some((item_key, item_value)) = iter.try_next()? {
+                assert_eq!(item_key, key(i));
+                assert_eq!(item_value, Some(value(i, round)));
+                i += 1;
+            }
+            assert_eq!(i, 1000);
+        }
+
+        let archive_dir = TempDir::new().expect("unable to create temporary working directory");
+        let archive_path = archive_dir.path().join("backup");
+        kv_store.backup_to(fs::File::create(&archive_path)?).await?;
+        KipStorage::restore_from(fs::File::open(&archive_path)?, restore_dir.path())?;
+
+        let restored = KipStorage::open_with_config(Config::new(restore_dir.path())).await?;
+        for i in 0..1000 {
+            assert_eq!(restored.get(&key(i)).await?, Some(value(i, 1)));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_negative_cache() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let kv_store =
+            KipStorage::open_with_config(Config::new(temp_dir.path()).enable_negative_cache(true))
+                .await?;
+        let (key, value) = (Bytes::from_static(b"key"), Bytes::from_static(b"value"));
+
+        kv_store
+            .set(Bytes::from_static(b"other"), value.clone())
+            .await?;
+        kv_store.flush().await?;
+
+        // 首次未命中时进行完整的查找，其后的未命中由缓存直接返回
+        for _ in 0..3 {
+            assert_eq!(kv_store.get(&key).await?, None);
+        }
+        assert_eq!(kv_store.stats().await?.negative_cache_hits, 2);
+
+        kv_store.set(key.clone(), value.clone()).await?;
+        assert_eq!(kv_store.get(&key).await?, Some(value.clone()));
+
+        // 删除后重新被缓存，且写入批次同样会使其失效
+        kv_store.remove(&key).await?;
+        assert_eq!(kv_store.get(&key).await?, None);
+        assert_eq!(kv_store.get(&key).await?, None);
+        assert_eq!(kv_store.stats().await?.negative_cache_hits, 3);
+
+        let mut batch = WriteBatch::default();
+        batch.set(key.clone(), value.clone());
+        kv_store.write_batch(batch).await?;
+        assert_eq!(kv_store.get(&key).await?, Some(value));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_size_of_disk_matches_disk_usage() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path()).disable_auto_compaction(true);
+        let kv_store = KipStorage::open_with_config(config.clone()).await?;
+
+        for i in 0..1000_u32 {
+            kv_store
+                .set(
+                    Bytes::from(i.to_be_bytes().to_vec()),
+                    Bytes::from_static(b"disk usage"),
+                )
+                .await?;
+            if i % 300 == 0 {
+                kv_store.flush().await?;
+            }
+        }
+        kv_store.flush().await?;
+
+        let sst_factory = IoFactory::new(
+            config.dir_path.join(DEFAULT_SS_TABLE_PATH),
+            FileExtension::SSTable,
+        )?;
+        let version = kv_store.current_version().await;
+        assert!(version.size_of_disk() > 0);
+        assert_eq!(version.size_of_disk(), sst_factory.total_disk_usage()?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_level0_file_num_compaction_trigger() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        // 通用的阈值远大于Level 0的Table数量阈值
+        let config = Config::new(temp_dir.path())
+            .major_threshold_with_sst_size(100)
+            .level0_file_num_compaction_trigger(3);
+        let kv_store = KipStorage::open_with_config(config).await?;
+
+        for i in 0..2_u8 {
+            kv_store
+                .set(Bytes::from(vec![i]), Bytes::from(vec![i]))
+                .await?;
+            kv_store.flush().await?;
+        }
+        let version = kv_store.current_version().await;
+        assert_eq!(version.level_len(0), 2);
+        assert_eq!(version.level_len(1), 0);
+
+        kv_store
+            .set(Bytes::from(vec![2]), Bytes::from(vec![2]))
+            .await?;
+        kv_store.flush().await?;
+        // 达到Level 0的Table数量阈值时即向Level 1压缩
+        let version = kv_store.current_version().await;
+        assert!(version.level_len(0) < 3);
+        assert!(version.level_len(1) > 0);
+
+        // Level 0的阈值不影响其他Level
+        let config = kv_store.inner.config();
+        assert!(!version.is_threshold_exceeded_major(&config, 1));
+        for i in 0..3_u8 {
+            assert_eq!(kv_store.get(&[i]).await?, Some(Bytes::from(vec![i])));
+        }
+
+        Ok(())
+    }
+
+    /// 压缩生成的新Table在Version应用前便已写入，而旧Table直到不再被任何Version引用才会删除，
+    /// 因此压缩期间的读取无论持有新旧哪个Version，都不会读到缺失或过期的数据
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_get_during_major_compaction() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path())
+            .disable_auto_compaction(true)
+            .sst_file_size(16 * 1024);
+        let kv_store = Arc::new(KipStorage::open_with_config(config).await?);
+        let key = |i: u32| Bytes::from(i.to_be_bytes().to_vec());
+        let value =
+            move |i: u32, round: u8| Bytes::from([vec![round; 64], key(i).to_vec()].concat());
+        let times = 2000_u32;
+
+        // 两轮写入分别持久化至Level 0，第二轮覆盖第一轮的所有Key
+        for round in 1..=2 {
+            for i in 0..times {
+                kv_store.set(key(i), value(i, round)).await?;
+            }
+            kv_store.flush().await?;
+        }
+
+        let is_stopped = Arc::new(AtomicBool::new(false));
+        let readers = (0..4)
+            .map(|_| {
+                let kv_store = Arc::clone(&kv_store);
+                let is_stopped = Arc::clone(&is_stopped);
+                tokio::spawn(async move {
+                    let mut rounds = 0;
+                    loop {
+                        for i in 0..times {
+                            assert_eq!(kv_store.get(&key(i)).await?, Some(value(i, 2)));
+                        }
+                        rounds += 1;
+                        if is_stopped.load(Ordering::Acquire) {
+                            break;
+                        }
+                    }
+                    Ok::<_, KernelError>(rounds)
+                })
+            })
+            .collect_vec();
+        // 依次将数据压缩至最深的Level，期间持续读取
+        for _ in 0..3 {
+            kv_store.compact_all(CompactionOptions::default()).await?;
+        }
+        is_stopped.store(true, Ordering::Release);
+
+        for reader in readers {
+            assert!(reader.await.unwrap()? > 0);
+        }
+        let version = kv_store.current_version().await;
+        assert_eq!(version.level_len(0), 0);
+        assert!(version
+            .level_slice
+            .iter()
+            .skip(1)
+            .any(|scopes| scopes.len() > 1));
+        for i in 0..times {
+            assert_eq!(kv_store.get(&key(i)).await?, Some(value(i, 2)));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_flush_and_compaction() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.path()).major_threshold_with_sst_size(2);
+        let kv_store = Arc::new(KipStorage::open_with_config(config).await?);
+        let key = |i: u32| Bytes::from(i.to_be_bytes().to_vec());
+
+        let writers = (0..8_u32).map(|task| {
+            let kv_store = Arc::clone(&kv_store);
+            tokio::spawn(async move {
+                for i in task * 200..(task + 1) * 200 {
+                    kv_store.set(key(i), key(i)).await?;
+                    if i % 50 == 0 {
+                        kv_store.flush().await?;
+                    }
+                }
+                Ok::<_, KernelError>(())
+            })
+        });
+        let compactions = (0..4).map(|_| {
+            let kv_store = Arc::clone(&kv_store);
+            tokio::spawn(async move {
+                for _ in 0..4 {
+                    kv_store.compact_all(CompactionOptions::default()).await?;
+                }
+                Ok::<_, KernelError>(())
+            })
+        });
+        for result in futures::future::join_all(writers.chain(compactions)).await {
+            result.unwrap()?;
+        }
+        kv_store.flush().await?;
+
+        let version = kv_store.current_version().await;
+        let gens = version
+            .level_slice
+            .iter()
+            .flatten()
+            .map(Scope::gen)
+            .collect_vec();
+        assert_eq!(gens.iter().unique().count(), gens.len());
+        for i in 0..1600 {
+            assert_eq!(kv_store.get(&key(i)).await?, Some(key(i)));
+        }
+
+        Ok(())
+    }
 
     #[test]
     fn test_seq_create() {
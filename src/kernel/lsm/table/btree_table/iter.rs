@@ -1,20 +1,29 @@
-use crate::kernel::lsm::iterator::{Iter, Seek, SeekIter};
+use crate::kernel::lsm::iterator::{Cursor, Seek, SeekCursor};
 use crate::kernel::lsm::mem_table::KeyValue;
 use crate::kernel::lsm::table::btree_table::BTreeTable;
+use crate::kernel::KernelResult;
 use bytes::Bytes;
 use std::collections::btree_map::Range;
 use std::collections::Bound;
 
-pub(crate) struct BTreeTableIter<'a> {
+/// BTreeTable的游标迭代器
+///
+/// 直接引用Table中的键值对，移动时不产生拷贝
+pub(crate) struct BTreeTableCursor<'a> {
     inner: Option<Range<'a, Bytes, KeyValue>>,
     table: &'a BTreeTable,
+    current: Option<&'a KeyValue>,
 }
 
-impl<'a> BTreeTableIter<'a> {
-    pub(crate) fn new(table: &'a BTreeTable) -> BTreeTableIter<'a> {
-        let mut iter = BTreeTableIter { inner: None, table };
-        iter._seek(Seek::First);
-        iter
+impl<'a> BTreeTableCursor<'a> {
+    pub(crate) fn new(table: &'a BTreeTable) -> BTreeTableCursor<'a> {
+        let mut cursor = BTreeTableCursor {
+            inner: None,
+            table,
+            current: None,
+        };
+        cursor._seek(Seek::First);
+        cursor
     }
 
     fn _seek(&mut self, seek: Seek) {
@@ -37,40 +46,50 @@ impl<'a> BTreeTableIter<'a> {
                     )),
             ),
         };
+        self._next();
     }
-}
 
-impl<'a> Iter<'a> for BTreeTableIter<'a> {
-    type Item = KeyValue;
-
-    fn try_next(&mut self) -> crate::kernel::KernelResult<Option<Self::Item>> {
-        Ok(self
+    fn _next(&mut self) {
+        self.current = self
             .inner
             .as_mut()
             .and_then(|iter| iter.next())
-            .map(item_clone))
+            .map(|(_, key_value)| key_value);
     }
+}
 
-    fn is_valid(&self) -> bool {
-        true
+impl<'a> Cursor<'a> for BTreeTableCursor<'a> {
+    fn valid(&self) -> bool {
+        self.current.is_some()
     }
-}
 
-impl<'a> SeekIter<'a> for BTreeTableIter<'a> {
-    fn seek(&mut self, seek: Seek<'_>) -> crate::kernel::KernelResult<()> {
-        self._seek(seek);
+    fn key(&self) -> &Bytes {
+        &self.current.expect("cursor is not valid").0
+    }
+
+    fn value(&self) -> &Option<Bytes> {
+        &self.current.expect("cursor is not valid").1
+    }
+
+    fn next(&mut self) -> KernelResult<()> {
+        self._next();
 
         Ok(())
     }
 }
 
-fn item_clone((_, value): (&Bytes, &KeyValue)) -> KeyValue {
-    value.clone()
+impl<'a> SeekCursor<'a> for BTreeTableCursor<'a> {
+    fn seek(&mut self, seek: Seek<'_>) -> KernelResult<()> {
+        self._seek(seek);
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::kernel::lsm::iterator::Seek;
+    use crate::kernel::lsm::iterator::{Cursor, CursorIter, Iter, Seek, SeekCursor, SeekIter};
+    use crate::kernel::lsm::table::btree_table::iter::BTreeTableCursor;
     use crate::kernel::lsm::table::btree_table::BTreeTable;
     use crate::kernel::lsm::table::Table;
     use crate::kernel::KernelResult;
@@ -106,4 +125,37 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_cursor_with_pull() -> KernelResult<()> {
+        let vec = (0..100_u32)
+            .map(|i| {
+                let value = (i % 3 != 0).then(|| Bytes::from(i.to_le_bytes().to_vec()));
+                (Bytes::from(i.to_be_bytes().to_vec()), value)
+            })
+            .collect::<Vec<_>>();
+        let table = BTreeTable::new(0, 0, vec.clone());
+
+        let mut cursor = BTreeTableCursor::new(&table);
+        let mut iter = CursorIter::new(BTreeTableCursor::new(&table));
+        let mut vec_cursor = Vec::new();
+        let mut vec_pull = Vec::new();
+
+        while cursor.valid() {
+            vec_cursor.push((cursor.key().clone(), cursor.value().clone()));
+            cursor.next()?;
+        }
+        while let Some(item) = iter.try_next()? {
+            vec_pull.push(item);
+        }
+        assert_eq!(vec_cursor, vec);
+        assert_eq!(vec_pull, vec);
+
+        cursor.seek(Seek::Backward(&vec[42].0))?;
+        iter.seek(Seek::Backward(&vec[42].0))?;
+        assert_eq!((cursor.key(), cursor.value()), (&vec[42].0, &vec[42].1));
+        assert_eq!(iter.try_next()?, Some(vec[42].clone()));
+
+        Ok(())
+    }
 }
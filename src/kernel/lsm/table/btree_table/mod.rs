@@ -1,11 +1,15 @@
 pub(crate) mod iter;
 
-use crate::kernel::lsm::iterator::SeekIter;
+use crate::kernel::lsm::iterator::{CursorIter, SeekIter};
 use crate::kernel::lsm::mem_table::KeyValue;
-use crate::kernel::lsm::table::btree_table::iter::BTreeTableIter;
+use crate::kernel::lsm::table::btree_table::iter::BTreeTableCursor;
+use crate::kernel::lsm::table::scope::Scope;
 use crate::kernel::lsm::table::Table;
+use crate::kernel::KernelResult;
+use crate::KernelError;
 use bytes::Bytes;
 use std::collections::BTreeMap;
+use std::mem;
 
 pub(crate) struct BTreeTable {
     level: usize,
@@ -40,10 +44,30 @@ impl Table for BTreeTable {
         self.len
     }
 
+    fn tombstone_count(&self) -> usize {
+        self.inner
+            .values()
+            .filter(|(_, value)| value.is_none())
+            .count()
+    }
+
     fn size_of_disk(&self) -> u64 {
         0
     }
 
+    fn memory_usage(&self) -> usize {
+        mem::size_of::<BTreeTable>()
+            + self
+                .inner
+                .values()
+                .map(|(key, value)| {
+                    mem::size_of::<(Bytes, KeyValue)>()
+                        + key.len()
+                        + value.as_ref().map_or(0, Bytes::len)
+                })
+                .sum::<usize>()
+    }
+
     fn gen(&self) -> i64 {
         self.gen
     }
@@ -52,11 +76,20 @@ impl Table for BTreeTable {
         self.level
     }
 
+    fn scope(&self) -> KernelResult<Scope> {
+        match (self.inner.first_key_value(), self.inner.last_key_value()) {
+            (Some((start, _)), Some((end, _))) => {
+                Ok(Scope::from_range(self.gen, start.clone(), end.clone()))
+            }
+            _ => Err(KernelError::DataEmpty),
+        }
+    }
+
     #[allow(clippy::todo)]
     fn iter<'a>(
         &'a self,
     ) -> crate::kernel::KernelResult<Box<dyn SeekIter<'a, Item = KeyValue> + 'a + Send + Sync>>
     {
-        Ok(Box::new(BTreeTableIter::new(self)))
+        Ok(Box::new(CursorIter::new(BTreeTableCursor::new(self))))
     }
 }
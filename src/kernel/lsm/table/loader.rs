@@ -1,20 +1,29 @@
-use crate::kernel::io::{IoFactory, IoType};
+use crate::kernel::io::{FileExtension, IoFactory, IoType};
 use crate::kernel::lsm::compactor::LEVEL_0;
+use crate::kernel::lsm::format::FORMAT_VERSION;
 use crate::kernel::lsm::log::LogLoader;
 use crate::kernel::lsm::mem_table::KeyValue;
-use crate::kernel::lsm::storage::Config;
+use crate::kernel::lsm::storage::{Config, CACHE_SHARDING_SIZE};
 use crate::kernel::lsm::table::btree_table::BTreeTable;
 use crate::kernel::lsm::table::meta::TableMeta;
 use crate::kernel::lsm::table::scope::Scope;
-use crate::kernel::lsm::table::ss_table::block::{BlockCache, Entry, Value};
-use crate::kernel::lsm::table::ss_table::SSTable;
+use crate::kernel::lsm::table::ss_table::block::{BlockCache, CompressType, Entry, Value};
+use crate::kernel::lsm::table::ss_table::value_log::ValueLog;
+use crate::kernel::lsm::table::ss_table::{BloomCounters, MetaRepair, SSTable};
 use crate::kernel::lsm::table::{BoxTable, Table, TableType};
+use crate::kernel::lsm::version::summary::{self, TableSummary};
 use crate::kernel::utils::lru_cache::ShardingLruCache;
-use crate::kernel::KernelResult;
+use crate::kernel::utils::rate_limiter::RateLimiter;
+use crate::kernel::{sorted_gen_list, KernelResult};
+use crate::KernelError;
 use bytes::Bytes;
+use itertools::Itertools;
+use parking_lot::Mutex;
 use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
 use std::io::Cursor;
 use std::mem;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tracing::warn;
 
@@ -22,9 +31,19 @@ use tracing::warn;
 pub(crate) struct TableLoader {
     inner: Arc<ShardingLruCache<i64, BoxTable>>,
     factory: Arc<IoFactory>,
+    /// 慢速存储层，存放Level大于等于`Config::min_compaction_level`的Table
+    slow_factory: Option<Arc<IoFactory>>,
     config: Config,
     wal: LogLoader,
     cache: Arc<BlockCache>,
+    /// 从磁盘中载入Table的次数
+    loads: Arc<AtomicU64>,
+    /// 所载入的SSTable共享的布隆过滤器判定统计
+    bloom_counters: Arc<BloomCounters>,
+    /// 所载入的SSTable共享的MetaBlock重建配置与已重建的MetaBlock
+    meta_repair: Arc<MetaRepair>,
+    /// 写入时所生成的各Table的摘要过滤器，见`Config::level_summary_bits`
+    summaries: Arc<Mutex<HashMap<i64, TableSummary>>>,
 }
 
 impl TableLoader {
@@ -35,53 +54,112 @@ impl TableLoader {
     ) -> KernelResult<Self> {
         let inner = Arc::new(ShardingLruCache::new(
             config.table_cache_size,
-            16,
+            CACHE_SHARDING_SIZE,
             RandomState::default(),
         )?);
         let cache = Arc::new(ShardingLruCache::new(
             config.block_cache_size,
-            16,
+            CACHE_SHARDING_SIZE,
             RandomState::default(),
         )?);
+        let slow_factory = config
+            .slow_tier_path
+            .as_ref()
+            .map(|path| {
+                // 与快速存储层共享`Config::max_open_files`的限制
+                IoFactory::new(path, FileExtension::SSTable)
+                    .map(|slow_factory| Arc::new(slow_factory.share_open_files(&factory)))
+            })
+            .transpose()?;
+        let meta_repair = Arc::new(MetaRepair::new(&config));
+
         Ok(TableLoader {
             inner,
             factory,
+            slow_factory,
             config,
             wal,
             cache,
+            loads: Arc::new(AtomicU64::new(0)),
+            bloom_counters: Arc::default(),
+            meta_repair,
+            summaries: Arc::default(),
         })
     }
 
-    #[allow(clippy::match_single_binding)]
     pub(crate) async fn create(
         &self,
         gen: i64,
         vec_data: Vec<KeyValue>,
         level: usize,
         table_type: TableType,
+    ) -> KernelResult<(Scope, TableMeta)> {
+        self.create_with_compress_type(gen, vec_data, level, table_type, None, None)
+            .await
+    }
+
+    /// 以指定的DataBlock压缩类型创建Table，为None时依据Config决定压缩类型
+    ///
+    /// 压缩类型与写入限速仅对SSTable生效
+    #[allow(clippy::match_single_binding)]
+    pub(crate) async fn create_with_compress_type(
+        &self,
+        gen: i64,
+        vec_data: Vec<KeyValue>,
+        level: usize,
+        table_type: TableType,
+        compress_type: Option<CompressType>,
+        rate_limiter: Option<&RateLimiter>,
     ) -> KernelResult<(Scope, TableMeta)> {
         // 获取数据的Key涵盖范围
         let scope = Scope::from_sorted_vec_data(gen, &vec_data)?;
+        if self.config.level_summary_bits > 0 {
+            let summary = summary::table_summary(
+                self.config.level_summary_bits,
+                self.config.desired_error_prob,
+                &vec_data,
+            );
+            let _ = self.summaries.lock().insert(gen, Arc::new(summary));
+        }
         let table: Box<dyn Table> = match table_type {
-            TableType::SortedString => Box::new(self.create_ss_table(gen, vec_data, level).await?),
+            TableType::SortedString => Box::new(
+                self.create_ss_table(gen, vec_data, level, compress_type, rate_limiter)
+                    .await?,
+            ),
             TableType::BTree => Box::new(BTreeTable::new(level, gen, vec_data)),
         };
         let table_meta = TableMeta::from(table.as_ref());
         let _ = self.inner.put(gen, table);
 
-        Ok((scope, table_meta))
+        Ok((scope.with_size_of_disk(table_meta.size_of_disk), table_meta))
+    }
+
+    /// Table写入时所生成的摘要过滤器，重启前写入的Table不存在摘要
+    pub(crate) fn summary(&self, gen: i64) -> Option<TableSummary> {
+        self.summaries.lock().get(&gen).cloned()
     }
 
     pub(crate) fn get(&self, gen: i64) -> Option<&dyn Table> {
         self.inner
             .get_or_insert(gen, |gen| {
-                let table_factory = &self.factory;
+                let _ = self.loads.fetch_add(1, Ordering::Relaxed);
+
+                let loaded = self.factory_by_gen(*gen).and_then(|factory| {
+                    let ss_table = SSTable::load_from_file(
+                        factory.shared_reader(*gen, IoType::Direct)?,
+                        Arc::clone(&self.cache),
+                        self.config.prefetch_index,
+                    )?;
 
-                let table: Box<dyn Table> = match table_factory
-                    .reader(*gen, IoType::Direct)
-                    .and_then(|reader| SSTable::load_from_file(reader, Arc::clone(&self.cache)))
-                {
-                    Ok(ss_table) => Box::new(ss_table),
+                    Ok(self
+                        .share_reader(ss_table, factory)
+                        .bloom_counters(Arc::clone(&self.bloom_counters))
+                        .meta_repair(Arc::clone(&self.meta_repair)))
+                });
+                let table: Box<dyn Table> = match loaded {
+                    Ok(ss_table) => {
+                        Box::new(ss_table.scan_prefetch_blocks(self.config.scan_prefetch_blocks))
+                    }
                     Err(err) => {
                         // 尝试恢复仅对Level 0的Table有效
                         warn!(
@@ -90,9 +168,10 @@ impl TableLoader {
                         );
                         let mut reload_data = Vec::new();
                         self.wal.load(*gen, &mut reload_data, |bytes, records| {
-                            for (_, Entry { key, item, .. }) in
-                                Entry::<Value>::batch_decode(&mut Cursor::new(mem::take(bytes)))?
-                            {
+                            for (_, Entry { key, item, .. }) in Entry::<Value>::batch_decode(
+                                &mut Cursor::new(mem::take(bytes)),
+                                FORMAT_VERSION,
+                            )? {
                                 records.push((key, item.bytes));
                             }
 
@@ -114,17 +193,91 @@ impl TableLoader {
         gen: i64,
         reload_data: Vec<(Bytes, Option<Bytes>)>,
         level: usize,
+        compress_type: Option<CompressType>,
+        rate_limiter: Option<&RateLimiter>,
     ) -> KernelResult<SSTable> {
-        SSTable::new(
-            &self.factory,
+        // 写入已存在的Table文件会覆盖其数据，而该Table可能仍被Version所引用
+        if self.factory_by_gen(gen)?.exists(gen)? {
+            return Err(KernelError::TableExists(gen));
+        }
+        let factory = self.factory_by_level(level);
+        let ss_table = SSTable::new_with_compress_type(
+            factory,
             &self.config,
             Arc::clone(&self.cache),
             gen,
             reload_data,
             level,
             IoType::Direct,
+            compress_type,
+            rate_limiter,
         )
-        .await
+        .await?;
+
+        Ok(self
+            .share_reader(ss_table, factory)
+            .bloom_counters(Arc::clone(&self.bloom_counters))
+            .meta_repair(Arc::clone(&self.meta_repair)))
+    }
+
+    /// 设置了`Config::max_open_files`时SSTable不常驻文件句柄，而是在读取时通过IoFactory获取
+    fn share_reader(&self, ss_table: SSTable, factory: &Arc<IoFactory>) -> SSTable {
+        if self.config.max_open_files > 0 {
+            ss_table.share_reader(Arc::clone(factory))
+        } else {
+            ss_table
+        }
+    }
+
+    /// Block缓存的命中率
+    pub(crate) fn cache_hit_rate(&self) -> f64 {
+        self.cache.hit_rate()
+    }
+
+    /// Block缓存的命中次数
+    pub(crate) fn cache_hits(&self) -> u64 {
+        self.cache.hits()
+    }
+
+    /// Block缓存的未命中次数，即从磁盘中读取Block的次数
+    pub(crate) fn cache_misses(&self) -> u64 {
+        self.cache.misses()
+    }
+
+    /// 从磁盘中载入Table的次数
+    pub(crate) fn table_loads(&self) -> u64 {
+        self.loads.load(Ordering::Relaxed)
+    }
+
+    /// 因超出`Config::table_cache_size`而被驱逐的Table数量
+    pub(crate) fn table_evictions(&self) -> u64 {
+        self.inner.evictions()
+    }
+
+    /// 所载入的SSTable的布隆过滤器判定统计
+    pub(crate) fn bloom_counters(&self) -> &BloomCounters {
+        &self.bloom_counters
+    }
+
+    /// 常驻于内存中的Table的布隆过滤器位数总和
+    pub(crate) fn bloom_filter_bits(&self) -> u64 {
+        self.inner
+            .weight(|_, table| table.bloom_stats().bits as usize) as u64
+    }
+
+    /// 当前常驻于内存中的Table数量
+    pub(crate) fn table_resident(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Block缓存中所有Block占用的内存大小
+    pub(crate) fn block_cache_usage(&self) -> usize {
+        self.cache.weight(|_, block_type| block_type.memory_usage())
+    }
+
+    /// Table缓存中所有常驻Table占用的内存大小
+    pub(crate) fn table_cache_usage(&self) -> usize {
+        self.inner.weight(|_, table| table.memory_usage())
     }
 
     pub(crate) fn remove(&self, gen: &i64) -> Option<BoxTable> {
@@ -136,18 +289,75 @@ impl TableLoader {
         self.inner.is_empty()
     }
 
+    /// 获取指定Level的Table所应写入的存储层
+    fn factory_by_level(&self, level: usize) -> &Arc<IoFactory> {
+        match &self.slow_factory {
+            Some(slow_factory) if level >= self.config.min_compaction_level => slow_factory,
+            _ => &self.factory,
+        }
+    }
+
+    /// 获取Table文件所在的存储层，均不存在时返回快速存储层
+    fn factory_by_gen(&self, gen: i64) -> KernelResult<&Arc<IoFactory>> {
+        if let Some(slow_factory) = &self.slow_factory {
+            if !self.factory.exists(gen)? && slow_factory.exists(gen)? {
+                return Ok(slow_factory);
+            }
+        }
+
+        Ok(&self.factory)
+    }
+
     pub(crate) fn clean(&self, gen: i64) -> KernelResult<()> {
         let _ = self.remove(&gen);
-        self.factory.clean(gen)?;
+        self.meta_repair.forget(gen);
+        let _ = self.summaries.lock().remove(&gen);
+        // 先于Table删除WAL，避免中断时残留的WAL在重启时被视为尚未持久化的数据；
+        // 由压缩所产生的Table不存在对应的WAL
         self.wal.clean(gen)?;
+        let factory = self.factory_by_gen(gen)?;
+        factory.clean(gen)?;
+        ValueLog::remove(&factory.path_with_gen(gen))?;
+
+        Ok(())
+    }
+
+    /// 丢弃尚未写入Version的Table
+    ///
+    /// 与`clean`不同，此时Table不存在对应的WAL，因此仅删除Table文件
+    pub(crate) fn discard(&self, gen: i64) -> KernelResult<()> {
+        let _ = self.remove(&gen);
+        self.meta_repair.forget(gen);
+        let _ = self.summaries.lock().remove(&gen);
+        let factory = self.factory_by_gen(gen)?;
+        if factory.exists(gen)? {
+            factory.clean(gen)?;
+        }
+        ValueLog::remove(&factory.path_with_gen(gen))?;
 
         Ok(())
     }
 
+    /// 快速与慢速存储层中所有Table文件的Gen
+    pub(crate) fn disk_gens(&self) -> KernelResult<Vec<i64>> {
+        let mut gens = Vec::new();
+
+        for factory in [Some(&self.factory), self.slow_factory.as_ref()]
+            .into_iter()
+            .flatten()
+        {
+            gens.append(&mut sorted_gen_list(
+                factory.get_path(),
+                FileExtension::SSTable,
+            )?);
+        }
+
+        Ok(gens.into_iter().sorted_unstable().dedup().collect())
+    }
+
     // Tips: 仅仅对持久化Table有效，SkipTable类内存Table始终为false
-    #[allow(dead_code)]
     pub(crate) fn is_table_file_exist(&self, gen: i64) -> KernelResult<bool> {
-        self.factory.exists(gen)
+        self.factory_by_gen(gen)?.exists(gen)
     }
 }
 
@@ -160,6 +370,7 @@ mod tests {
     use crate::kernel::lsm::table::loader::{TableLoader, TableType};
     use crate::kernel::lsm::version::DEFAULT_SS_TABLE_PATH;
     use crate::kernel::KernelResult;
+    use crate::KernelError;
     use bincode::Options;
     use bytes::Bytes;
     use std::sync::Arc;
@@ -172,7 +383,7 @@ mod tests {
         let value = Bytes::copy_from_slice(
             b"If you shed tears when you miss the sun, you also miss the stars.",
         );
-        let config = Config::new(temp_dir.into_path());
+        let config = Config::new(temp_dir.keep());
         let sst_factory = Arc::new(IoFactory::new(
             config.dir_path.join(DEFAULT_SS_TABLE_PATH),
             FileExtension::SSTable,
@@ -253,4 +464,124 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_create_with_unsorted_data() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.keep());
+        let sst_factory = Arc::new(IoFactory::new(
+            config.dir_path.join(DEFAULT_SS_TABLE_PATH),
+            FileExtension::SSTable,
+        )?);
+        let (log_loader, _) = LogLoader::reload(
+            config.path(),
+            (DEFAULT_WAL_PATH, Some(1)),
+            IoType::Buf,
+            &mut vec![0],
+            |_, _| Ok(()),
+        )?;
+        let sst_loader = TableLoader::new(config, sst_factory, log_loader)?;
+
+        let vec_data = vec![
+            (Bytes::from_static(b"b"), None),
+            (Bytes::from_static(b"a"), None),
+        ];
+        // SSTable在构建Block时逐个比较Key，无论是否开启`paranoid_memtable_order_check`
+        assert!(matches!(
+            sst_loader
+                .create(1, vec_data, 0, TableType::SortedString)
+                .await,
+            Err(KernelError::UnsortedInput { .. })
+        ));
+        assert!(!sst_loader.is_table_file_exist(1)?);
+        assert!(sst_loader.is_emtpy());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_with_existing_gen() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.keep());
+        let sst_factory = Arc::new(IoFactory::new(
+            config.dir_path.join(DEFAULT_SS_TABLE_PATH),
+            FileExtension::SSTable,
+        )?);
+        let (log_loader, _) = LogLoader::reload(
+            config.path(),
+            (DEFAULT_WAL_PATH, Some(1)),
+            IoType::Buf,
+            &mut vec![0],
+            |_, _| Ok(()),
+        )?;
+        let sst_loader = TableLoader::new(config, sst_factory, log_loader)?;
+        let data = vec![(Bytes::from_static(b"a"), Some(Bytes::from_static(b"1")))];
+
+        let _ = sst_loader
+            .create(1, data.clone(), 0, TableType::SortedString)
+            .await?;
+        assert!(matches!(
+            sst_loader
+                .create(
+                    1,
+                    vec![(Bytes::from_static(b"b"), None)],
+                    0,
+                    TableType::SortedString
+                )
+                .await,
+            Err(KernelError::TableExists(1))
+        ));
+        // 原有的Table未被覆盖
+        assert!(sst_loader.remove(&1).is_some());
+        assert_eq!(
+            sst_loader.get(1).unwrap().query(b"a")?,
+            Some(data[0].clone())
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_load_and_evict_counters() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        // 16个分片各仅能常驻一个Table
+        let config = Config::new(temp_dir.keep()).table_cache_size(16);
+        let sst_factory = Arc::new(IoFactory::new(
+            config.dir_path.join(DEFAULT_SS_TABLE_PATH),
+            FileExtension::SSTable,
+        )?);
+        let (log_loader, _) = LogLoader::reload(
+            config.path(),
+            (DEFAULT_WAL_PATH, Some(1)),
+            IoType::Buf,
+            &mut vec![0],
+            |_, _| Ok(()),
+        )?;
+        let sst_loader = TableLoader::new(config, sst_factory, log_loader)?;
+        let times = 64;
+
+        for gen in 0..times {
+            let key = Bytes::from(bincode::options().with_big_endian().serialize(&gen)?);
+            let _ = sst_loader
+                .create(gen, vec![(key, None)], 1, TableType::SortedString)
+                .await?;
+        }
+        assert_eq!(sst_loader.table_loads(), 0);
+        assert!(sst_loader.table_resident() <= 16);
+        assert!(sst_loader.table_evictions() >= times as u64 - 16);
+
+        for gen in 0..times {
+            assert!(sst_loader.get(gen).is_some());
+        }
+        let loads = sst_loader.table_loads();
+        assert!(loads >= times as u64 - 16);
+        assert!(sst_loader.table_resident() <= 16);
+        // 每次创建或载入都使常驻数量加一，每次驱逐都使其减一
+        assert_eq!(
+            sst_loader.table_resident() as u64,
+            times as u64 + loads - sst_loader.table_evictions()
+        );
+
+        Ok(())
+    }
 }
@@ -1,7 +1,10 @@
-use crate::kernel::lsm::iterator::SeekIter;
+use crate::kernel::lsm::iterator::{Seek, SeekIter};
 use crate::kernel::lsm::mem_table::KeyValue;
+use crate::kernel::lsm::stats::BloomStats;
 use crate::kernel::lsm::table::meta::TableMeta;
+use crate::kernel::lsm::table::scope::Scope;
 use crate::kernel::KernelResult;
+use bytes::Bytes;
 use itertools::Itertools;
 
 pub(crate) mod btree_table;
@@ -10,7 +13,7 @@ pub(crate) mod meta;
 pub(crate) mod scope;
 pub(crate) mod ss_table;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
 pub enum TableType {
     SortedString,
     BTree,
@@ -23,15 +26,79 @@ pub(crate) trait Table: Sync + Send {
 
     fn len(&self) -> usize;
 
+    /// Table中删除标记的数量
+    fn tombstone_count(&self) -> usize;
+
     fn size_of_disk(&self) -> u64;
 
+    /// Table常驻于内存中的数据大小，不包含Block缓存中的DataBlock
+    fn memory_usage(&self) -> usize;
+
     fn gen(&self) -> i64;
 
     fn level(&self) -> usize;
 
+    /// Table中数据的Key范围
+    #[allow(dead_code)]
+    fn scope(&self) -> KernelResult<Scope>;
+
+    /// 布隆过滤器的统计信息，不具有布隆过滤器的Table返回默认值
+    fn bloom_stats(&self) -> BloomStats {
+        BloomStats::default()
+    }
+
     fn iter<'a>(
         &'a self,
     ) -> KernelResult<Box<dyn SeekIter<'a, Item = KeyValue> + 'a + Sync + Send>>;
+
+    /// 获取所有以`prefix`开头的键值对
+    fn prefix_scan(&self, prefix: &[u8]) -> KernelResult<Vec<KeyValue>> {
+        load_by_prefix(self, prefix)
+    }
+
+    /// 压缩时全量读取Table中满足`fn_is_filter`的数据
+    ///
+    /// readahead_size大于0时，实现可以使用预读缓冲进行顺序读取
+    fn load_for_compaction(
+        &self,
+        _readahead_size: usize,
+        fn_is_filter: &dyn Fn(&Bytes) -> bool,
+    ) -> KernelResult<Vec<KeyValue>> {
+        load_by_iter(self, fn_is_filter)
+    }
+}
+
+/// 通过Table的迭代器全量读取满足`fn_is_filter`的数据
+pub(crate) fn load_by_iter<T: Table + ?Sized>(
+    table: &T,
+    fn_is_filter: &dyn Fn(&Bytes) -> bool,
+) -> KernelResult<Vec<KeyValue>> {
+    let mut iter = table.iter()?;
+    let mut vec_cmd = Vec::with_capacity(table.len());
+    while let Some(item) = iter.try_next()? {
+        if fn_is_filter(&item.0) {
+            vec_cmd.push(item)
+        }
+    }
+    Ok(vec_cmd)
+}
+
+/// 通过Table的迭代器由`prefix`处开始顺序读取以其开头的数据
+pub(crate) fn load_by_prefix<T: Table + ?Sized>(
+    table: &T,
+    prefix: &[u8],
+) -> KernelResult<Vec<KeyValue>> {
+    let mut iter = table.iter()?;
+    iter.seek(Seek::Backward(prefix))?;
+
+    let mut vec_kv = Vec::new();
+    while let Some((key, value)) = iter.try_next()? {
+        if !key.starts_with(prefix) {
+            break;
+        }
+        vec_kv.push((key, value));
+    }
+    Ok(vec_kv)
 }
 
 /// 通过一组SSTable收集对应的Gen
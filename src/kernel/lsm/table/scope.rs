@@ -20,6 +20,8 @@ pub struct Scope {
     gen: i64,
     // SeekMiss计数
     pub(crate) allowed_seeks: Option<Arc<AtomicU32>>,
+    /// Table所占用的磁盘大小，为0时表示未记录(如由旧版本的Version日志载入)
+    pub(crate) size_of_disk: u64,
 }
 
 impl PartialEq for Scope {
@@ -29,6 +31,7 @@ impl PartialEq for Scope {
                 && self.start == other.start
                 && self.end == other.end
                 && self.gen == other.gen
+                && self.size_of_disk == other.size_of_disk
         } else {
             false
         }
@@ -62,9 +65,16 @@ impl Scope {
             end: last,
             gen,
             allowed_seeks: Some(Arc::new(AtomicU32::new(0))),
+            size_of_disk: 0,
         }
     }
 
+    /// 记录Table所占用的磁盘大小
+    pub(crate) fn with_size_of_disk(mut self, size_of_disk: u64) -> Self {
+        self.size_of_disk = size_of_disk;
+        self
+    }
+
     #[allow(dead_code)]
     pub(crate) fn from_key(key: &[u8]) -> Self {
         let bytes = Bytes::copy_from_slice(key);
@@ -73,9 +83,18 @@ impl Scope {
             end: bytes,
             gen: 0,
             allowed_seeks: None,
+            size_of_disk: 0,
         }
     }
 
+    /// 范围内是否可能存在以`prefix`开头的Key
+    ///
+    /// 以`prefix`开头的Key不小于`prefix`，且不大于任何以`prefix`开头的`start`
+    pub(crate) fn may_contain_prefix(&self, prefix: &[u8]) -> bool {
+        self.end.as_ref() >= prefix
+            && (self.start.as_ref() <= prefix || self.start.starts_with(prefix))
+    }
+
     /// 将多个scope重组融合成一个scope
     pub(crate) fn fusion(scopes: &[Scope]) -> Option<Self> {
         let start = scopes.iter().map(|scope| &scope.start).min()?.clone();
@@ -86,6 +105,7 @@ impl Scope {
             end,
             gen: 0,
             allowed_seeks: None,
+            size_of_disk: 0,
         })
     }
 
@@ -102,6 +122,31 @@ impl Scope {
         self.start.as_ref().le(key) && self.end.as_ref().ge(key)
     }
 
+    /// 判断Scope与范围`(start, end)`是否相交
+    ///
+    /// 当范围为空(如start大于end或`[a, a)`)时始终返回`false`
+    pub(crate) fn meet_with_range(&self, start: Bound<&[u8]>, end: Bound<&[u8]>) -> bool {
+        let is_range_empty = match (start, end) {
+            (Bound::Included(start), Bound::Included(end)) => start > end,
+            (Bound::Included(start), Bound::Excluded(end))
+            | (Bound::Excluded(start), Bound::Included(end))
+            | (Bound::Excluded(start), Bound::Excluded(end)) => start >= end,
+            _ => false,
+        };
+        let is_start_meet = match start {
+            Bound::Included(key) => self.end.as_ref() >= key,
+            Bound::Excluded(key) => self.end.as_ref() > key,
+            Bound::Unbounded => true,
+        };
+        let is_end_meet = match end {
+            Bound::Included(key) => self.start.as_ref() <= key,
+            Bound::Excluded(key) => self.start.as_ref() < key,
+            Bound::Unbounded => true,
+        };
+
+        !is_range_empty && is_start_meet && is_end_meet
+    }
+
     #[allow(dead_code)]
     pub(crate) fn meet_bound(&self, min: Bound<&[u8]>, max: Bound<&[u8]>) -> bool {
         let is_min_inside = match min {
@@ -119,6 +164,9 @@ impl Scope {
     }
 
     /// 由一组有序KeyValue组成一个scope
+    ///
+    /// 仅取首尾两个Key，不校验数据是否有序；需要校验时由调用方使用`check_sorted`，
+    /// 如Flush时的`Config::paranoid_memtable_order_check`
     #[allow(clippy::pattern_type_mismatch)]
     pub(crate) fn from_sorted_vec_data(
         gen: i64,
@@ -131,3 +179,39 @@ impl Scope {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::kernel::lsm::table::scope::Scope;
+    use bytes::Bytes;
+    use std::collections::Bound;
+
+    #[test]
+    fn test_meet_with_range() {
+        let scope = Scope::from_range(0, Bytes::from_static(b"c"), Bytes::from_static(b"f"));
+
+        // 有界
+        assert!(scope.meet_with_range(Bound::Included(&b"a"[..]), Bound::Included(&b"c"[..])));
+        assert!(!scope.meet_with_range(Bound::Included(&b"a"[..]), Bound::Excluded(&b"c"[..])));
+        assert!(scope.meet_with_range(Bound::Included(&b"f"[..]), Bound::Included(&b"z"[..])));
+        assert!(!scope.meet_with_range(Bound::Excluded(&b"f"[..]), Bound::Included(&b"z"[..])));
+        assert!(scope.meet_with_range(Bound::Included(&b"d"[..]), Bound::Excluded(&b"e"[..])));
+        assert!(scope.meet_with_range(Bound::Included(&b"a"[..]), Bound::Included(&b"z"[..])));
+        assert!(!scope.meet_with_range(Bound::Included(&b"a"[..]), Bound::Included(&b"b"[..])));
+        assert!(!scope.meet_with_range(Bound::Included(&b"g"[..]), Bound::Included(&b"z"[..])));
+
+        // 无界
+        assert!(scope.meet_with_range(Bound::Unbounded, Bound::Unbounded));
+        assert!(scope.meet_with_range(Bound::Unbounded, Bound::Included(&b"c"[..])));
+        assert!(!scope.meet_with_range(Bound::Unbounded, Bound::Excluded(&b"c"[..])));
+        assert!(scope.meet_with_range(Bound::Included(&b"f"[..]), Bound::Unbounded));
+        assert!(!scope.meet_with_range(Bound::Excluded(&b"f"[..]), Bound::Unbounded));
+
+        // 空范围
+        assert!(!scope.meet_with_range(Bound::Included(&b"d"[..]), Bound::Excluded(&b"d"[..])));
+        assert!(!scope.meet_with_range(Bound::Excluded(&b"d"[..]), Bound::Included(&b"d"[..])));
+        assert!(!scope.meet_with_range(Bound::Excluded(&b"d"[..]), Bound::Excluded(&b"d"[..])));
+        assert!(!scope.meet_with_range(Bound::Included(&b"e"[..]), Bound::Included(&b"d"[..])));
+        assert!(scope.meet_with_range(Bound::Included(&b"d"[..]), Bound::Included(&b"d"[..])));
+    }
+}
@@ -1,13 +1,17 @@
+use crate::kernel::lsm::format::{
+    BLOCK_HEADER_VERSION, COMPRESS_TYPE_VERSION, PREFIX_FILTER_VERSION, TOMBSTONES_VERSION,
+    VALUE_POINTER_VERSION, WIDE_OFFSET_VERSION,
+};
 use crate::kernel::lsm::storage::Config;
 use crate::kernel::utils::bloom_filter::BloomFilter;
 use crate::kernel::utils::lru_cache::ShardingLruCache;
 use crate::kernel::KernelResult;
 use crate::KernelError;
 use bytes::{Buf, BufMut, Bytes};
-use integer_encoding::{FixedInt, FixedIntWriter, VarIntReader, VarIntWriter};
+use integer_encoding::{FixedInt, FixedIntReader, FixedIntWriter, VarIntReader, VarIntWriter};
 use itertools::Itertools;
 use lz4::Decoder;
-use std::cmp::min;
+use std::cmp::{min, Ordering};
 use std::io::{Cursor, Read, Write};
 use std::mem;
 
@@ -26,13 +30,58 @@ pub(crate) const DEFAULT_DATA_RESTART_INTERVAL: usize = 16;
 
 pub(crate) const DEFAULT_INDEX_RESTART_INTERVAL: usize = 2;
 
-const CRC_SIZE: usize = 4;
+/// 旧格式的Block末尾固定附带CRC32校验码
+const LEGACY_CRC_SIZE: usize = 4;
+
+/// 带校验类型的Block的头部标记
+///
+/// 旧格式的Block以首个Entry的unshared_len(varint)开头，而规范的varint编码不会以`0x80 0x00`开头，
+/// 以此区分Block是否带有头部
+const BLOCK_HEADER_MAGIC: [u8; 2] = [0x80, 0x00];
+
+/// Block头部长度: 标记与校验类型
+const BLOCK_HEADER_SIZE: usize = BLOCK_HEADER_MAGIC.len() + 1;
+
+const ZSTD_LEVEL: i32 = 3;
+/// LZ4帧格式的魔数(小端序)
+const LZ4_FRAME_MAGIC: [u8; 4] = [0x04, 0x22, 0x4D, 0x18];
+/// Zstd帧格式的魔数(小端序)
+const ZSTD_FRAME_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+/// 序列化后的布隆过滤器的最小长度：哈希函数数量、两个哈希种子与位数各8字节
+const BLOOM_FILTER_MIN_SIZE: usize = 32;
+
+/// MetaBlock中压缩类型标记
+///
+/// 旧的MetaBlock在该位置存放字典长度，不可能为`u32::MAX`，以此区分是否存有压缩类型
+const COMPRESS_TYPE_MARKER: u32 = u32::MAX;
+
+/// MetaBlock中压缩类型及前缀布隆过滤器标记
+///
+/// 在压缩类型之后额外存放前缀长度与前缀布隆过滤器
+const PREFIX_FILTER_MARKER: u32 = u32::MAX - 1;
+
+/// MetaBlock中压缩类型、删除标记数量及前缀布隆过滤器标记
+///
+/// 在压缩类型之后额外存放删除标记数量，其后与`PREFIX_FILTER_MARKER`一致存放前缀长度与前缀布隆过滤器，
+/// 不存在前缀布隆过滤器时其长度为0
+const TOMBSTONES_MARKER: u32 = u32::MAX - 2;
+
+const DICT_SAMPLES_MAGNIFICATION: usize = 100;
+
+/// Value分离存储时替代value_len写入的标记，其后紧接着ValuePointer
+///
+/// 单个Value的长度不可能达到`u64::MAX`，以此区分内联的Value与ValuePointer
+const VALUE_POINTER_MARKER: u64 = u64::MAX;
+
+/// 格式版本为`VALUE_POINTER_VERSION`的SSTable中ValuePointer的标记
+const LEGACY_VALUE_POINTER_MARKER: u32 = u32::MAX;
 
 pub(crate) type KeyValue<T> = (Bytes, T);
 
 pub(crate) enum BlockType {
     Data(Block<Value>),
     Index(Block<Index>),
+    PrefixIndex(PrefixIndex),
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -65,30 +114,34 @@ where
         Ok(())
     }
 
-    pub(crate) fn batch_decode(cursor: &mut Cursor<Vec<u8>>) -> KernelResult<Vec<(usize, Self)>> {
+    /// `format_version`为写入时的格式版本，决定Entry中各元素的解析方式
+    pub(crate) fn batch_decode<B: AsRef<[u8]>>(
+        cursor: &mut Cursor<B>,
+        format_version: u32,
+    ) -> KernelResult<Vec<(usize, Self)>> {
         let mut vec_entry = Vec::new();
         let mut index = 0;
 
         while !cursor.is_empty() {
-            vec_entry.push((index, Self::decode(cursor)?));
+            vec_entry.push((index, Self::decode(cursor, format_version)?));
             index += 1;
         }
 
         Ok(vec_entry)
     }
 
-    pub(crate) fn decode<R: Read>(reader: &mut R) -> KernelResult<Entry<T>> {
+    pub(crate) fn decode<R: Read>(reader: &mut R, format_version: u32) -> KernelResult<Entry<T>> {
         let unshared_len = reader.read_varint::<u32>()? as usize;
         let shared_len = reader.read_varint::<u32>()? as usize;
 
         let mut bytes = vec![0u8; unshared_len];
-        let _ = reader.read(&mut bytes)?;
+        reader.read_exact(&mut bytes)?;
 
         Ok(Self {
             unshared_len,
             shared_len,
             key: Bytes::from(bytes),
-            item: T::decode(reader)?,
+            item: T::decode(reader, format_version)?,
         })
     }
 }
@@ -98,28 +151,52 @@ where
 pub(crate) struct Value {
     value_len: usize,
     pub(crate) bytes: Option<Bytes>,
+    /// Value分离存储于ValueLog时其所在的位置，此时`bytes`为None
+    pub(crate) pointer: Option<ValuePointer>,
 }
 
 impl From<Option<Bytes>> for Value {
     fn from(bytes: Option<Bytes>) -> Self {
         let value_len = bytes.as_ref().map_or(0, Bytes::len);
-        Value { value_len, bytes }
+        Value {
+            value_len,
+            bytes,
+            pointer: None,
+        }
     }
 }
 
+impl Value {
+    pub(crate) fn from_pointer(pointer: ValuePointer) -> Self {
+        Value {
+            value_len: 0,
+            bytes: None,
+            pointer: Some(pointer),
+        }
+    }
+}
+
+/// 分离存储的Value在ValueLog中的位置
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub(crate) struct ValuePointer {
+    pub(crate) gen: i64,
+    pub(crate) offset: u64,
+    pub(crate) len: usize,
+}
+
 /// Block索引
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
 pub(crate) struct Index {
-    offset: u32,
+    offset: u64,
     len: usize,
 }
 
 impl Index {
-    fn new(offset: u32, len: usize) -> Self {
+    pub(crate) fn new(offset: u64, len: usize) -> Self {
         Index { offset, len }
     }
 
-    pub(crate) fn offset(&self) -> u32 {
+    pub(crate) fn offset(&self) -> u64 {
         self.offset
     }
 
@@ -130,97 +207,628 @@ impl Index {
 
 pub(crate) trait BlockItem: Sized + Clone {
     /// 由于需要直接连续序列化，因此使用Read进行Bytes读取
-    fn decode<T>(reader: &mut T) -> KernelResult<Self>
+    ///
+    /// `format_version`为写入时的格式版本
+    fn decode<T>(reader: &mut T, format_version: u32) -> KernelResult<Self>
     where
         T: Read + ?Sized;
 
     fn encode(&self, bytes: &mut Vec<u8>) -> KernelResult<()>;
+
+    /// 该元素在堆上额外占用的内存大小
+    fn heap_size(&self) -> usize {
+        0
+    }
 }
 
 impl BlockItem for Value {
-    fn decode<T>(mut reader: &mut T) -> KernelResult<Self>
+    fn decode<T>(mut reader: &mut T, format_version: u32) -> KernelResult<Self>
     where
         T: Read + ?Sized,
     {
-        let value_len = reader.read_varint::<u32>()? as usize;
+        // 旧版本以varint u32写入长度，与varint u64的编码一致
+        let value_len = reader.read_varint::<u64>()?;
+        let is_pointer = if format_version >= WIDE_OFFSET_VERSION {
+            value_len == VALUE_POINTER_MARKER
+        } else {
+            format_version >= VALUE_POINTER_VERSION
+                && value_len == u64::from(LEGACY_VALUE_POINTER_MARKER)
+        };
 
-        let bytes = (value_len > 0)
-            .then(|| {
-                let mut value = vec![0u8; value_len];
-                reader.read(&mut value).ok().map(|_| Bytes::from(value))
-            })
-            .flatten();
+        if is_pointer {
+            return Ok(Value::from_pointer(ValuePointer {
+                gen: reader.read_varint::<i64>()?,
+                offset: reader.read_varint::<u64>()?,
+                len: reader.read_varint::<u64>()? as usize,
+            }));
+        }
+        let value_len = value_len as usize;
+        let bytes = if value_len > 0 {
+            let mut value = vec![0u8; value_len];
+            reader.read_exact(&mut value)?;
+            Some(Bytes::from(value))
+        } else {
+            None
+        };
 
-        Ok(Value { value_len, bytes })
+        Ok(Value {
+            value_len,
+            bytes,
+            pointer: None,
+        })
     }
 
     fn encode(&self, bytes: &mut Vec<u8>) -> KernelResult<()> {
-        bytes.write_varint(self.value_len as u32)?;
+        if let Some(pointer) = &self.pointer {
+            bytes.write_varint(VALUE_POINTER_MARKER)?;
+            bytes.write_varint(pointer.gen)?;
+            bytes.write_varint(pointer.offset)?;
+            bytes.write_varint(pointer.len as u64)?;
+
+            return Ok(());
+        }
+        bytes.write_varint(self.value_len as u64)?;
 
         if let Some(value) = &self.bytes {
             bytes.write_all(value)?;
         }
         Ok(())
     }
+
+    fn heap_size(&self) -> usize {
+        self.value_len
+    }
 }
 
 impl BlockItem for Index {
-    fn decode<T>(mut reader: &mut T) -> KernelResult<Self>
+    fn decode<T>(mut reader: &mut T, _format_version: u32) -> KernelResult<Self>
     where
         T: Read + ?Sized,
     {
-        let offset = reader.read_varint::<u32>()?;
-        let len = reader.read_varint::<u32>()? as usize;
+        let offset = reader.read_varint::<u64>()?;
+        let len = reader.read_varint::<u64>()? as usize;
 
         Ok(Index { offset, len })
     }
 
     fn encode(&self, bytes: &mut Vec<u8>) -> KernelResult<()> {
         bytes.write_varint(self.offset)?;
-        bytes.write_varint(self.len as u32)?;
+        bytes.write_varint(self.len as u64)?;
 
         Ok(())
     }
 }
 
-#[derive(Clone, Copy)]
-pub(crate) enum CompressType {
+/// DataBlock的压缩类型
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CompressType {
     None,
     LZ4,
+    /// 指定压缩级别的Zstd压缩，适用于更看重磁盘占用的冷数据
+    Zstd {
+        level: i32,
+    },
+    /// 使用预训练字典的Zstd压缩，字典存储于SSTable的MetaBlock中
+    ZstdDict(Bytes),
+}
+
+/// Block的校验类型
+///
+/// 校验类型存放于各个Block的头部，因此修改后新旧Block仍可被正确校验
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChecksumType {
+    /// 不进行校验，适用于可信的存储介质以换取吞吐
+    None,
+    Crc32,
+    /// 64位的xxHash，相较CRC32能发现更多的数据损坏
+    XxHash64,
+}
+
+impl ChecksumType {
+    fn tag(&self) -> u8 {
+        match self {
+            ChecksumType::None => 0,
+            ChecksumType::Crc32 => 1,
+            ChecksumType::XxHash64 => 2,
+        }
+    }
+
+    fn from_tag(tag: u8) -> KernelResult<Self> {
+        Ok(match tag {
+            0 => ChecksumType::None,
+            1 => ChecksumType::Crc32,
+            2 => ChecksumType::XxHash64,
+            _ => return Err(KernelError::NotSupport("unknown block checksum type")),
+        })
+    }
+
+    /// 校验码所占用的字节数
+    fn size(&self) -> usize {
+        match self {
+            ChecksumType::None => 0,
+            ChecksumType::Crc32 => 4,
+            ChecksumType::XxHash64 => 8,
+        }
+    }
+
+    fn checksum(&self, bytes: &[u8]) -> Vec<u8> {
+        match self {
+            ChecksumType::None => Vec::new(),
+            ChecksumType::Crc32 => crc32fast::hash(bytes).encode_fixed_vec(),
+            ChecksumType::XxHash64 => xxhash_rust::xxh64::xxh64(bytes, 0).encode_fixed_vec(),
+        }
+    }
+}
+
+/// SSTable的布隆过滤器构建策略
+///
+/// 越深的Level数据越多而被查询的概率越低，其布隆过滤器占用的内存未必值得
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum BloomPolicy {
+    /// 所有Level均以`Config::desired_error_prob`构建布隆过滤器
+    #[default]
+    Uniform,
+    /// 仅Level不大于该值的SSTable构建布隆过滤器
+    DisableAbove(usize),
+    /// 以下标为Level指定各Level所使用的误判率，为None或超出长度的Level不构建布隆过滤器
+    ///
+    /// 误判率需位于(0, 1)之间，否则开启时返回`KernelError::NotSupport`
+    PerLevel(Vec<Option<f64>>),
+}
+
+impl BloomPolicy {
+    /// 获取`level`的SSTable构建布隆过滤器时所使用的误判率，为None时不构建布隆过滤器
+    pub(crate) fn error_prob(&self, level: usize, desired_error_prob: f64) -> Option<f64> {
+        match self {
+            BloomPolicy::Uniform => Some(desired_error_prob),
+            BloomPolicy::DisableAbove(max_level) => {
+                (level <= *max_level).then_some(desired_error_prob)
+            }
+            BloomPolicy::PerLevel(error_probs) => error_probs.get(level).copied().flatten(),
+        }
+    }
+
+    /// 校验`PerLevel`中所指定的误判率均位于(0, 1)之间
+    pub(crate) fn check(&self) -> KernelResult<()> {
+        if let BloomPolicy::PerLevel(error_probs) = self {
+            if error_probs
+                .iter()
+                .flatten()
+                .any(|error_prob| !(*error_prob > 0.0 && *error_prob < 1.0))
+            {
+                return Err(KernelError::NotSupport(
+                    "the error probabilities of `BloomPolicy::PerLevel` must be in (0, 1)",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 复合Key(如`tenant_id || key`)的前缀提取方式
+///
+/// 设置后SSTable会在IndexBlock之后额外构建以前缀为Key的PrefixIndex，
+/// 使前缀扫描仅需读取包含该前缀的DataBlock
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrefixExtractor {
+    /// 以Key的前N个字节作为前缀，长度不足N的Key不具有前缀
+    FixedLength(usize),
+    /// 以Key中首个分隔符及其之前的字节作为前缀，不含分隔符的Key不具有前缀
+    Separator(u8),
+}
+
+impl PrefixExtractor {
+    /// 提取Key的前缀
+    ///
+    /// 按顺序排列的Key所提取出的前缀同样是有序的，因此相同前缀的Key必定连续
+    pub(crate) fn extract<'a>(&self, key: &'a [u8]) -> Option<&'a [u8]> {
+        match self {
+            PrefixExtractor::FixedLength(len) => key.get(..*len),
+            PrefixExtractor::Separator(separator) => key
+                .iter()
+                .position(|byte| byte == separator)
+                .map(|pos| &key[..=pos]),
+        }
+    }
+
+    /// `prefix`自身是否为一个完整的前缀，此时所有以其开头的Key都会提取出该前缀
+    pub(crate) fn is_whole_prefix(&self, prefix: &[u8]) -> bool {
+        self.extract(prefix)
+            .is_some_and(|extracted| extracted.len() == prefix.len())
+    }
+
+    fn to_raw(self, bytes: &mut Vec<u8>) -> KernelResult<()> {
+        let (tag, arg) = match self {
+            PrefixExtractor::FixedLength(len) => (0_u8, len as u32),
+            PrefixExtractor::Separator(separator) => (1_u8, u32::from(separator)),
+        };
+        bytes.write_fixedint(tag)?;
+        bytes.write_fixedint(arg)?;
+
+        Ok(())
+    }
+
+    fn from_raw<R: Read>(reader: &mut R) -> KernelResult<Self> {
+        let tag = reader.read_fixedint::<u8>()?;
+        let arg = reader.read_fixedint::<u32>()?;
+
+        Ok(match tag {
+            0 => PrefixExtractor::FixedLength(arg as usize),
+            1 => PrefixExtractor::Separator(
+                u8::try_from(arg)
+                    .map_err(|_| KernelError::NotSupport("invalid prefix separator"))?,
+            ),
+            _ => return Err(KernelError::NotSupport("unknown prefix extractor")),
+        })
+    }
+}
+
+impl CompressType {
+    fn to_raw(&self, bytes: &mut Vec<u8>) -> KernelResult<()> {
+        match self {
+            CompressType::None => {
+                bytes.write_fixedint(0_u8)?;
+            }
+            CompressType::LZ4 => {
+                bytes.write_fixedint(1_u8)?;
+            }
+            CompressType::Zstd { level } => {
+                bytes.write_fixedint(2_u8)?;
+                bytes.write_fixedint(*level)?;
+            }
+            CompressType::ZstdDict(dict) => {
+                bytes.write_fixedint(3_u8)?;
+                bytes.write_fixedint(dict.len() as u32)?;
+                bytes.extend_from_slice(dict);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn from_raw(reader: &mut Cursor<&[u8]>) -> KernelResult<Self> {
+        Ok(match reader.read_fixedint::<u8>()? {
+            0 => CompressType::None,
+            1 => CompressType::LZ4,
+            2 => CompressType::Zstd {
+                level: reader.read_fixedint()?,
+            },
+            3 => {
+                let mut dict = vec![0; reader.read_fixedint::<u32>()? as usize];
+                reader.read_exact(&mut dict)?;
+                CompressType::ZstdDict(Bytes::from(dict))
+            }
+            _ => return Err(KernelError::NotSupport("unknown block compress type")),
+        })
+    }
+}
+
+impl CompressType {
+    /// 将`raw`压缩后追加至`bytes`
+    ///
+    /// 除DataBlock外，网络传输中的大Value同样以此进行压缩
+    pub(crate) fn compress(&self, raw: &[u8], bytes: &mut Vec<u8>) -> KernelResult<()> {
+        match self {
+            CompressType::None => bytes.extend_from_slice(raw),
+            CompressType::LZ4 => {
+                let mut encoder = lz4::EncoderBuilder::new().level(4).build(bytes.writer())?;
+                encoder.write_all(raw)?;
+                let (_, result) = encoder.finish();
+
+                result?;
+            }
+            CompressType::ZstdDict(dict) => {
+                let mut encoder = zstd::Encoder::with_dictionary(&mut *bytes, ZSTD_LEVEL, dict)?;
+                encoder.write_all(raw)?;
+                let _ = encoder.finish()?;
+            }
+            CompressType::Zstd { level } => {
+                let mut encoder = zstd::Encoder::new(&mut *bytes, *level)?;
+                encoder.write_all(raw)?;
+                let _ = encoder.finish()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 与compress对应，解压出原始字节
+    pub(crate) fn decompress(&self, buf: &[u8]) -> KernelResult<Vec<u8>> {
+        let mut decoded = Vec::with_capacity(DEFAULT_BLOCK_SIZE);
+        match self {
+            CompressType::None => decoded.extend_from_slice(buf),
+            CompressType::LZ4 => {
+                let _ = Decoder::new(buf.reader())?.read_to_end(&mut decoded)?;
+            }
+            CompressType::ZstdDict(dict) => {
+                let _ = zstd::Decoder::with_dictionary(Cursor::new(buf), dict)?
+                    .read_to_end(&mut decoded)?;
+            }
+            CompressType::Zstd { .. } => {
+                let _ = zstd::Decoder::new(Cursor::new(buf))?.read_to_end(&mut decoded)?;
+            }
+        }
+
+        Ok(decoded)
+    }
+
+    /// 依据Block头部的魔数推断其压缩类型，用于MetaBlock损坏时的修复
+    ///
+    /// 带字典的Zstd压缩会被推断为Zstd，因缺失字典而在解压时失败
+    pub(crate) fn detect(buf: &[u8]) -> CompressType {
+        if buf.starts_with(&LZ4_FRAME_MAGIC) {
+            CompressType::LZ4
+        } else if buf.starts_with(&ZSTD_FRAME_MAGIC) {
+            CompressType::Zstd { level: ZSTD_LEVEL }
+        } else {
+            CompressType::None
+        }
+    }
 }
 
 #[derive(Debug)]
 pub(crate) struct MetaBlock {
-    pub(crate) filter: BloomFilter<[u8]>,
+    /// 依据`Config::bloom_policy`可能不构建布隆过滤器，此时查询总是需要读取DataBlock
+    pub(crate) filter: Option<BloomFilter<[u8]>>,
     pub(crate) len: usize,
+    /// 删除标记的数量，旧的MetaBlock中未记录时为0
+    pub(crate) tombstones: usize,
     pub(crate) index_restart_interval: usize,
     pub(crate) data_restart_interval: usize,
+    /// DataBlock所使用的压缩类型，载入时以此选择对应的解压方式
+    pub(crate) compress_type: CompressType,
+    /// 以各Key的前`prefix_bloom_len`个字节构建的布隆过滤器，用于前缀扫描时跳过整个SSTable
+    pub(crate) prefix_filter: Option<BloomFilter<[u8]>>,
+    pub(crate) prefix_bloom_len: usize,
+}
+
+impl BlockType {
+    /// Block占用的内存大小
+    pub(crate) fn memory_usage(&self) -> usize {
+        match self {
+            BlockType::Data(block) => block.memory_usage(),
+            BlockType::Index(block) => block.memory_usage(),
+            BlockType::PrefixIndex(prefix_index) => prefix_index.block.memory_usage(),
+        }
+    }
 }
 
 impl MetaBlock {
+    /// MetaBlock中布隆过滤器与压缩字典占用的内存大小
+    pub(crate) fn memory_usage(&self) -> usize {
+        let dict_len = match &self.compress_type {
+            CompressType::ZstdDict(dict) => dict.len(),
+            _ => 0,
+        };
+
+        self.filter.as_ref().map_or(0, BloomFilter::memory_usage)
+            + self
+                .prefix_filter
+                .as_ref()
+                .map_or(0, BloomFilter::memory_usage)
+            + dict_len
+    }
+
     pub(crate) fn to_raw(&self, bytes: &mut Vec<u8>) -> KernelResult<()> {
         bytes.write_fixedint(self.len as u32)?;
         bytes.write_fixedint(self.index_restart_interval as u32)?;
         bytes.write_fixedint(self.data_restart_interval as u32)?;
+        bytes.write_fixedint(TOMBSTONES_MARKER)?;
+        self.compress_type.to_raw(bytes)?;
+        bytes.write_fixedint(self.tombstones as u32)?;
+
+        let mut filter_bytes = Vec::new();
+        if let Some(prefix_filter) = &self.prefix_filter {
+            prefix_filter.to_raw(&mut filter_bytes)?;
+        }
+        bytes.write_fixedint(self.prefix_bloom_len as u32)?;
+        bytes.write_fixedint(filter_bytes.len() as u32)?;
+        bytes.extend_from_slice(&filter_bytes);
 
-        self.filter.to_raw(bytes)?;
+        // 布隆过滤器位于末尾，不存在时不写入任何数据
+        if let Some(filter) = &self.filter {
+            filter.to_raw(bytes)?;
+        }
 
         Ok(())
     }
 
-    pub(crate) fn from_raw(bytes: &[u8]) -> Self {
-        let len = u32::decode_fixed(&bytes[0..4]) as usize;
-        let index_restart_interval = u32::decode_fixed(&bytes[4..8]) as usize;
-        let data_restart_interval = u32::decode_fixed(&bytes[8..12]) as usize;
-        let filter = BloomFilter::from_raw(&bytes[12..]);
+    /// 依据`format_version`判断其中的标记是否有效，早于标记引入的版本出现标记时说明已损坏
+    pub(crate) fn from_raw(bytes: &[u8], format_version: u32) -> KernelResult<Self> {
+        let mut reader = Cursor::new(bytes);
+        let len = reader.read_fixedint::<u32>()? as usize;
+        let index_restart_interval = reader.read_fixedint::<u32>()? as usize;
+        let data_restart_interval = reader.read_fixedint::<u32>()? as usize;
+        let marker = reader.read_fixedint::<u32>()?;
+        let marker_version = match marker {
+            COMPRESS_TYPE_MARKER => COMPRESS_TYPE_VERSION,
+            PREFIX_FILTER_MARKER => PREFIX_FILTER_VERSION,
+            TOMBSTONES_MARKER => TOMBSTONES_VERSION,
+            _ => 0,
+        };
+        if format_version < marker_version {
+            return Err(KernelError::CrcMisMatch);
+        }
+        let compress_type = match marker {
+            COMPRESS_TYPE_MARKER | PREFIX_FILTER_MARKER | TOMBSTONES_MARKER => {
+                CompressType::from_raw(&mut reader)?
+            }
+            // 旧的MetaBlock仅存有Zstd字典，无字典时DataBlock使用LZ4压缩
+            0 => CompressType::LZ4,
+            dict_len => {
+                let mut dict = vec![0; dict_len as usize];
+                reader.read_exact(&mut dict)?;
+                CompressType::ZstdDict(Bytes::from(dict))
+            }
+        };
+        let tombstones = match marker {
+            TOMBSTONES_MARKER => reader.read_fixedint::<u32>()? as usize,
+            _ => 0,
+        };
+        let (prefix_filter, prefix_bloom_len) =
+            if matches!(marker, PREFIX_FILTER_MARKER | TOMBSTONES_MARKER) {
+                let prefix_bloom_len = reader.read_fixedint::<u32>()? as usize;
+                let filter_len = reader.read_fixedint::<u32>()? as usize;
+                let start = reader.position() as usize;
+                let filter_bytes = bytes
+                    .get(start..start + filter_len)
+                    .ok_or(KernelError::CrcMisMatch)?;
+                reader.set_position((start + filter_len) as u64);
+
+                (
+                    (filter_len > 0).then(|| BloomFilter::from_raw(filter_bytes)),
+                    prefix_bloom_len,
+                )
+            } else {
+                (None, 0)
+            };
+        let filter_bytes = &bytes[reader.position() as usize..];
+        if !filter_bytes.is_empty() && filter_bytes.len() < BLOOM_FILTER_MIN_SIZE {
+            return Err(KernelError::CrcMisMatch);
+        }
+        let filter = (!filter_bytes.is_empty()).then(|| BloomFilter::from_raw(filter_bytes));
 
-        Self {
+        Ok(Self {
             filter,
             len,
+            tombstones,
             index_restart_interval,
             data_restart_interval,
+            compress_type,
+            prefix_filter,
+            prefix_bloom_len,
+        })
+    }
+
+    /// 是否可能存在以`prefix`为前缀的Key
+    ///
+    /// 未构建前缀布隆过滤器或`prefix`短于前缀长度时无法判断，总是返回true
+    pub(crate) fn may_contain_prefix(&self, prefix: &[u8]) -> bool {
+        match &self.prefix_filter {
+            Some(prefix_filter) if prefix.len() >= self.prefix_bloom_len => {
+                prefix_filter.contains(&prefix[..self.prefix_bloom_len])
+            }
+            _ => true,
         }
     }
+
+    /// DataBlock所使用的压缩类型
+    pub(crate) fn data_compress_type(&self) -> CompressType {
+        self.compress_type.clone()
+    }
+}
+
+/// 以各键值对作为样本训练Zstd压缩字典
+///
+/// 样本过少等原因导致训练失败时返回None，此时DataBlock回退至LZ4压缩
+pub(crate) fn train_compression_dict(
+    vec_data: &[(Bytes, Option<Bytes>)],
+    max_size: usize,
+) -> Option<Bytes> {
+    let mut samples_size = 0;
+    let samples = vec_data
+        .iter()
+        .map(|(key, value)| [key.as_ref(), value.as_deref().unwrap_or_default()].concat())
+        // 样本总量为字典大小的100倍左右时便足以训练
+        .take_while(|sample| {
+            samples_size += sample.len();
+            samples_size <= max_size * DICT_SAMPLES_MAGNIFICATION
+        })
+        .collect_vec();
+
+    zstd::dict::from_samples(&samples, max_size)
+        .ok()
+        .filter(|dict| !dict.is_empty())
+        .map(Bytes::from)
+}
+
+/// SSTable的Key范围
+///
+/// 使SSTable载入时无需读取数据便可得到对应的Scope
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct ScopeBlock {
+    pub(crate) start: Bytes,
+    pub(crate) end: Bytes,
+}
+
+impl ScopeBlock {
+    pub(crate) fn to_raw(&self, bytes: &mut Vec<u8>) -> KernelResult<()> {
+        for key in [&self.start, &self.end] {
+            bytes.write_fixedint(key.len() as u32)?;
+            bytes.extend_from_slice(key);
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn from_raw(bytes: &[u8]) -> KernelResult<Self> {
+        let mut reader = Cursor::new(bytes);
+        let mut read_key = || -> KernelResult<Bytes> {
+            let mut key = vec![0; reader.read_fixedint::<u32>()? as usize];
+            reader.read_exact(&mut key)?;
+
+            Ok(Bytes::from(key))
+        };
+
+        Ok(ScopeBlock {
+            start: read_key()?,
+            end: read_key()?,
+        })
+    }
+}
+
+/// 以DataBlock中Key的前缀作为Key的索引
+///
+/// 位于SSTable的IndexBlock与MetaBlock之间，每个前缀对应包含该前缀的连续DataBlock所覆盖的范围，
+/// 因此前缀扫描无需解码不相关的DataBlock
+pub(crate) struct PrefixIndex {
+    extractor: PrefixExtractor,
+    block: Block<Index>,
+}
+
+impl PrefixIndex {
+    pub(crate) fn extractor(&self) -> PrefixExtractor {
+        self.extractor
+    }
+
+    /// 查询包含该前缀的DataBlock所覆盖的范围，为None时表示不存在具有该前缀的Key
+    pub(crate) fn find(&self, prefix: &[u8]) -> Option<Index> {
+        self.block
+            .binary_search(prefix)
+            .ok()
+            .map(|index| self.block.get_entry(index).item)
+    }
+
+    fn encode(
+        extractor: PrefixExtractor,
+        vec_prefix: Vec<KeyValue<Index>>,
+        restart_interval: usize,
+        checksum_type: ChecksumType,
+        bytes: &mut Vec<u8>,
+    ) -> KernelResult<()> {
+        extractor.to_raw(bytes)?;
+        Block::new(vec_prefix, restart_interval).encode(&CompressType::None, checksum_type, bytes)
+    }
+
+    pub(crate) fn decode(
+        buf: &[u8],
+        restart_interval: usize,
+        format_version: u32,
+    ) -> KernelResult<Self> {
+        let mut reader = Cursor::new(buf);
+        let extractor = PrefixExtractor::from_raw(&mut reader)?;
+        let position = reader.position() as usize;
+
+        Ok(PrefixIndex {
+            extractor,
+            block: Block::from_raw(
+                &reader.get_ref()[position..],
+                restart_interval,
+                format_version,
+            )?,
+        })
+    }
 }
 
 /// Block SSTable最小的存储单位
@@ -236,8 +844,10 @@ pub(crate) struct Block<T> {
 pub(crate) struct BlockOptions {
     block_size: usize,
     compress_type: CompressType,
+    checksum_type: ChecksumType,
     data_restart_interval: usize,
     index_restart_interval: usize,
+    prefix_extractor: Option<PrefixExtractor>,
 }
 
 impl From<&Config> for BlockOptions {
@@ -245,8 +855,10 @@ impl From<&Config> for BlockOptions {
         BlockOptions {
             block_size: config.block_size,
             compress_type: CompressType::None,
+            checksum_type: config.block_checksum_type,
             data_restart_interval: config.data_restart_interval,
             index_restart_interval: config.index_restart_interval,
+            prefix_extractor: config.prefix_extractor,
         }
     }
 }
@@ -257,8 +869,10 @@ impl BlockOptions {
         BlockOptions {
             block_size: DEFAULT_BLOCK_SIZE,
             compress_type: CompressType::None,
+            checksum_type: ChecksumType::Crc32,
             data_restart_interval: DEFAULT_DATA_RESTART_INTERVAL,
             index_restart_interval: DEFAULT_INDEX_RESTART_INTERVAL,
+            prefix_extractor: None,
         }
     }
     #[allow(dead_code)]
@@ -272,6 +886,11 @@ impl BlockOptions {
         self
     }
     #[allow(dead_code)]
+    pub(crate) fn checksum_type(mut self, checksum_type: ChecksumType) -> Self {
+        self.checksum_type = checksum_type;
+        self
+    }
+    #[allow(dead_code)]
     pub(crate) fn data_restart_interval(mut self, data_restart_interval: usize) -> Self {
         self.data_restart_interval = data_restart_interval;
         self
@@ -281,6 +900,11 @@ impl BlockOptions {
         self.index_restart_interval = index_restart_interval;
         self
     }
+    #[allow(dead_code)]
+    pub(crate) fn prefix_extractor(mut self, prefix_extractor: PrefixExtractor) -> Self {
+        self.prefix_extractor = Some(prefix_extractor);
+        self
+    }
 }
 
 struct BlockBuf {
@@ -297,10 +921,6 @@ impl BlockBuf {
     }
 
     fn add(&mut self, key_value: KeyValue<Value>) {
-        // 断言新插入的键值对的Key大于buf中最后的key
-        if let Some(last_key) = self.last_key() {
-            assert!(last_key.cmp(&key_value.0).is_lt());
-        }
         self.bytes_size += key_value_bytes_len(&key_value);
         self.vec_key_value.push(key_value);
     }
@@ -326,7 +946,8 @@ pub(crate) struct BlockBuilder {
     options: BlockOptions,
     len: usize,
     buf: BlockBuf,
-    vec_block: Vec<(Block<Value>, Bytes)>,
+    /// 已构建的Block与其last_key，以及其中Key所具有的各个前缀
+    vec_block: Vec<(Block<Value>, Bytes, Vec<Bytes>)>,
 }
 
 /// 获取键值对得到其空间占用数
@@ -353,14 +974,32 @@ impl BlockBuilder {
 
     /// 插入需要构建为Block的键值对
     ///
-    /// 请注意add的键值对需要自行保证key顺序插入,否则可能会出现问题
-    pub(crate) fn add(&mut self, key_value: KeyValue<Value>) {
+    /// 键值对需要按key严格递增的顺序插入，乱序或重复的key会返回`KernelError::UnsortedInput`
+    pub(crate) fn add(&mut self, key_value: KeyValue<Value>) -> KernelResult<()> {
+        // buf刚被构建为Block时，以最后一个Block的last_key进行比较
+        let last_key = self
+            .buf
+            .last_key()
+            .or_else(|| self.vec_block.last().map(|(_, last_key, _)| last_key));
+        if let Some(last_key) = last_key.filter(|last_key| *last_key >= &key_value.0) {
+            return Err(KernelError::UnsortedInput {
+                prev: last_key.clone(),
+                next: key_value.0.clone(),
+            });
+        }
+        // 单个键值对便超过Block大小时，将其单独构建为一个Block
+        // 避免其与前后的键值对共用Block，使得读取小Value时也需要解码该大Value
+        if key_value_bytes_len(&key_value) >= self.options.block_size {
+            self._build();
+        }
         self.buf.add(key_value);
         self.len += 1;
         // 超过指定的Block大小后进行Block构建(默认为4K大小)
         if self.is_out_of_byte() {
             self._build();
         }
+
+        Ok(())
     }
 
     fn is_out_of_byte(&self) -> bool {
@@ -372,58 +1011,144 @@ impl BlockBuilder {
     /// 刷新buf获取其中的所有键值对与其中最大的key进行前缀压缩构建为Block
     fn _build(&mut self) {
         if let (vec_kv, Some(last_key)) = self.buf.flush() {
+            let prefixes = self
+                .options
+                .prefix_extractor
+                .map(|extractor| {
+                    vec_kv
+                        .iter()
+                        .filter_map(|(key, _)| {
+                            extractor
+                                .extract(key)
+                                .map(|prefix| key.slice(..prefix.len()))
+                        })
+                        .dedup()
+                        .collect_vec()
+                })
+                .unwrap_or_default();
+
             self.vec_block.push((
                 Block::new(vec_kv, self.options.data_restart_interval),
                 last_key,
+                prefixes,
             ));
         }
     }
 
-    /// 构建多个Block连续序列化组合成的两个Bytes 前者为多个DataBlock，后者为单个IndexBlock
-    pub(crate) async fn build(mut self) -> KernelResult<(Vec<u8>, usize, usize)> {
+    /// 构建多个Block连续序列化组合成的Bytes，依次为多个DataBlock、单个IndexBlock
+    /// 以及设置了前缀提取方式时的PrefixIndex
+    ///
+    /// 返回值中其后依次为DataBlock与IndexBlock的总长度(其后剩余的部分即为PrefixIndex)以及DataBlock的数量
+    pub(crate) async fn build(mut self) -> KernelResult<(Vec<u8>, usize, usize, usize)> {
         self._build();
 
         let mut blocks_bytes = vec![];
-        let mut offset = 0u32;
+        let mut offset = 0u64;
 
         let mut indexes = Vec::with_capacity(self.vec_block.len());
+        let mut prefix_indexes: Vec<KeyValue<Index>> = Vec::new();
 
-        for (block, last_key) in self.vec_block {
-            block.encode(self.options.compress_type, &mut blocks_bytes)?;
+        for (block, last_key, prefixes) in self.vec_block {
+            block.encode(
+                &self.options.compress_type,
+                self.options.checksum_type,
+                &mut blocks_bytes,
+            )?;
 
             let len = blocks_bytes.len() - offset as usize;
 
+            for prefix in prefixes {
+                match prefix_indexes.last_mut() {
+                    // 同一前缀跨越多个Block时，将范围延伸至该Block的末尾
+                    Some((last_prefix, range)) if last_prefix == &prefix => {
+                        range.len = offset as usize + len - range.offset as usize;
+                    }
+                    _ => prefix_indexes.push((prefix, Index::new(offset, len))),
+                }
+            }
             indexes.push((last_key, Index::new(offset, len)));
-            offset += len as u32;
+            offset += len as u64;
         }
         let data_bytes_len = blocks_bytes.len();
+        let data_block_count = indexes.len();
 
-        Block::new(indexes, self.options.index_restart_interval)
-            .encode(CompressType::None, &mut blocks_bytes)?;
+        Block::new(indexes, self.options.index_restart_interval).encode(
+            &CompressType::None,
+            self.options.checksum_type,
+            &mut blocks_bytes,
+        )?;
         let index_bytes_len = blocks_bytes.len() - data_bytes_len;
 
-        Ok((blocks_bytes, data_bytes_len, index_bytes_len))
+        if let Some(extractor) = self.options.prefix_extractor {
+            PrefixIndex::encode(
+                extractor,
+                prefix_indexes,
+                self.options.index_restart_interval,
+                self.options.checksum_type,
+                &mut blocks_bytes,
+            )?;
+        }
+
+        Ok((
+            blocks_bytes,
+            data_bytes_len,
+            index_bytes_len,
+            data_block_count,
+        ))
+    }
+}
+
+/// `Block<Value>::find`的查询结果
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum FindResult {
+    Found(Bytes),
+    /// Value分离存储于ValueLog中，需通过该位置读取
+    Separated(ValuePointer),
+    /// Key对应的数据为删除标记，查询应止步于此而不再查找更旧的数据
+    Deleted,
+    Absent,
+}
+
+impl Block<Index> {
+    /// 获取起始位置处于`range`范围内的各个DataBlock的索引
+    pub(crate) fn indexes_within(&self, range: Index) -> Vec<Index> {
+        let end = range.offset as usize + range.len;
+        let start = self
+            .vec_entry
+            .partition_point(|(_, entry)| entry.item.offset < range.offset);
+
+        self.vec_entry[start..]
+            .iter()
+            .map(|(_, entry)| entry.item)
+            .take_while(|index| (index.offset as usize) < end)
+            .collect_vec()
     }
 }
 
 impl Block<Value> {
-    /// 通过Key查询对应Value
-    ///
-    /// 返回数据为Value的Option以及是否存在
-    pub(crate) fn find(&self, key: &[u8]) -> (Option<Bytes>, bool) {
-        self.binary_search(key)
+    /// 通过Key查询对应Value，区分Key已被删除与Key不存在于该Block中
+    pub(crate) fn find(&self, key: &[u8]) -> FindResult {
+        let value = self
+            .binary_search(key)
             .ok()
-            .and_then(|index| {
-                self.vec_entry
-                    .get(index)
-                    .map(|(_, entry)| (entry.item.bytes.clone(), true))
-            })
-            .unwrap_or((None, false))
+            .and_then(|index| self.vec_entry.get(index))
+            .map(|(_, entry)| &entry.item);
+
+        match value {
+            Some(Value {
+                bytes: Some(bytes), ..
+            }) => FindResult::Found(bytes.clone()),
+            Some(Value {
+                pointer: Some(pointer),
+                ..
+            }) => FindResult::Separated(*pointer),
+            Some(_) => FindResult::Deleted,
+            None => FindResult::Absent,
+        }
     }
 }
 
 impl<T> Block<T> {
-    #[allow(dead_code)]
     pub(crate) fn entry_len(&self) -> usize {
         self.vec_entry.len()
     }
@@ -440,6 +1165,42 @@ impl<T> Block<T> {
         self.restart_interval
     }
 
+    /// 以`restart_interval`作为Restart间隔时能否正确还原该Block中的各个Key
+    ///
+    /// 用于在Restart间隔未知时推断该Block的Restart间隔
+    pub(crate) fn is_restart_consistent(&self, restart_interval: usize) -> bool {
+        let keys = self.sequential_keys();
+
+        self.vec_entry.iter().enumerate().all(|(i, (_, entry))| {
+            if i % restart_interval == 0 {
+                return entry.shared_len == 0;
+            }
+            let restart_key = &self.vec_entry[i - i % restart_interval].1.key;
+
+            restart_key.len() >= entry.shared_len
+                && restart_key[..entry.shared_len] == keys[i][..entry.shared_len]
+        })
+    }
+
+    /// 不依赖Restart间隔，依据前一个完整的Key顺序还原各个Key
+    ///
+    /// 同一Restart区域内的Key共享相同的前缀，因此前一个Key的前缀即为Restart处Key的前缀
+    pub(crate) fn sequential_keys(&self) -> Vec<Bytes> {
+        let mut keys: Vec<Bytes> = Vec::with_capacity(self.vec_entry.len());
+
+        for (_, entry) in &self.vec_entry {
+            let key = match keys.last() {
+                Some(prev_key) if entry.shared_len > 0 => {
+                    Bytes::from([&prev_key[..entry.shared_len], &entry.key[..]].concat())
+                }
+                _ => entry.key.clone(),
+            };
+            keys.push(key);
+        }
+
+        keys
+    }
+
     /// 获取指定index的entry
     pub(crate) fn get_entry(&self, index: usize) -> &Entry<T> {
         &self.vec_entry[index].1
@@ -463,6 +1224,32 @@ impl<T> Block<T>
 where
     T: BlockItem,
 {
+    /// Block占用的内存大小
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.vec_entry.capacity() * mem::size_of::<(usize, Entry<T>)>()
+            + self
+                .vec_entry
+                .iter()
+                .map(|(_, entry)| entry.key.len() + entry.item.heap_size())
+                .sum::<usize>()
+    }
+
+    /// 顺序遍历Block中的键值对
+    ///
+    /// 迭代时才对前缀压缩的Key进行拼接，避免一次性还原整个Block中的Key
+    pub(crate) fn entries(&self) -> impl Iterator<Item = KeyValue<T>> + '_ {
+        self.vec_entry.iter().map(|(index, entry)| {
+            let key = if entry.shared_len > 0 {
+                let shared_prefix = self.shared_key_prefix(*index, entry.shared_len);
+                Bytes::from([shared_prefix, &entry.key[..]].concat())
+            } else {
+                entry.key.clone()
+            };
+
+            (key, entry.item.clone())
+        })
+    }
+
     /// 新建Block，同时Block会进行前缀压缩
     pub(crate) fn new(vec_kv: Vec<KeyValue<T>>, restart_interval: usize) -> Block<T> {
         let vec_sharding_len = sharding_shared_len(&vec_kv, restart_interval);
@@ -501,14 +1288,31 @@ where
         self.vec_entry[index].1.item.clone()
     }
 
+    /// 获取第一个不小于`key`的Entry的下标，所有Key均小于`key`时返回Entry数量
+    ///
+    /// 比较时仅拼接所需的共享前缀，无需还原整个Block中的Key
+    pub(crate) fn lower_bound(&self, key: &[u8]) -> usize {
+        self.binary_search(key).unwrap_or_else(|index| index)
+    }
+
     pub(crate) fn binary_search(&self, key: &[u8]) -> Result<usize, usize> {
         self.vec_entry.binary_search_by(|(index, entry)| {
             if entry.shared_len > 0 {
                 // 对有前缀压缩的Key进行前缀拼接
-                let shared_len = min(entry.shared_len, key.len());
-                key[0..shared_len]
-                    .cmp(self.shared_key_prefix(*index, shared_len))
-                    .then_with(|| key[shared_len..].cmp(&entry.key))
+                // 即以`shared_prefix + entry.key`作为完整的Key进行比较
+                let shared_prefix = self.shared_key_prefix(*index, entry.shared_len);
+                let cmp_len = min(shared_prefix.len(), key.len());
+
+                key[0..cmp_len]
+                    .cmp(&shared_prefix[0..cmp_len])
+                    .then_with(|| {
+                        if key.len() < shared_prefix.len() {
+                            // key为完整Key的严格前缀，因此必然更小
+                            Ordering::Less
+                        } else {
+                            key[cmp_len..].cmp(&entry.key)
+                        }
+                    })
             } else {
                 key.cmp(&entry.key)
             }
@@ -518,60 +1322,79 @@ where
 
     /// 序列化后进行压缩
     ///
-    /// 可选LZ4与不压缩
+    /// 可选LZ4、Zstd、带字典的Zstd与不压缩
     pub(crate) fn encode(
         &self,
-        compress_type: CompressType,
+        compress_type: &CompressType,
+        checksum_type: ChecksumType,
         bytes: &mut Vec<u8>,
     ) -> KernelResult<()> {
-        match compress_type {
-            CompressType::None => self.to_raw(bytes)?,
-            CompressType::LZ4 => {
-                let mut buf = Vec::new();
-                self.to_raw(&mut buf)?;
-
-                let mut encoder = lz4::EncoderBuilder::new().level(4).build(bytes.writer())?;
-                let _ = encoder.write(&buf[..])?;
-                let (_, result) = encoder.finish();
-
-                result?;
-            }
+        if let CompressType::None = compress_type {
+            return self.to_raw(checksum_type, bytes);
         }
+        let mut buf = Vec::new();
+        self.to_raw(checksum_type, &mut buf)?;
 
-        Ok(())
+        compress_type.compress(&buf, bytes)
     }
 
     /// 解压后反序列化
     ///
     /// 与encode对应，进行数据解压操作并反序列化为Block
+    ///
+    /// 反序列化时会复制出各个键值对，因此`buf`可在解码后复用
     pub(crate) fn decode(
-        buf: Vec<u8>,
-        compress_type: CompressType,
+        buf: &[u8],
+        compress_type: &CompressType,
         restart_interval: usize,
+        format_version: u32,
     ) -> KernelResult<Self> {
-        let buf = match compress_type {
-            CompressType::None => buf,
-            CompressType::LZ4 => {
-                let mut decoder = Decoder::new(buf.reader())?;
-                let mut decoded = Vec::with_capacity(DEFAULT_BLOCK_SIZE);
-                let _ = decoder.read_to_end(&mut decoded)?;
-                decoded
-            }
-        };
-        Self::from_raw(buf, restart_interval)
+        if let CompressType::None = compress_type {
+            return Self::from_raw(buf, restart_interval, format_version);
+        }
+        Self::from_raw(
+            &compress_type.decompress(buf)?,
+            restart_interval,
+            format_version,
+        )
     }
 
     /// 读取Bytes进行Block的反序列化
-    pub(crate) fn from_raw(mut buf: Vec<u8>, restart_interval: usize) -> KernelResult<Self> {
-        assert!(!buf.is_empty());
-        let date_bytes_len = buf.len() - CRC_SIZE;
-        if crc32fast::hash(&buf) == u32::decode_fixed(&buf[date_bytes_len..]) {
+    ///
+    /// 依据头部中的校验类型进行校验，不带头部的旧Block使用CRC32校验
+    ///
+    /// `format_version`为所属SSTable的格式版本，早于`BLOCK_HEADER_VERSION`的Block不带头部
+    pub(crate) fn from_raw(
+        buf: &[u8],
+        restart_interval: usize,
+        format_version: u32,
+    ) -> KernelResult<Self> {
+        let has_header =
+            format_version >= BLOCK_HEADER_VERSION && buf.starts_with(&BLOCK_HEADER_MAGIC);
+        let (header_len, checksum_type) = if has_header {
+            let tag = *buf
+                .get(BLOCK_HEADER_MAGIC.len())
+                .ok_or(KernelError::CrcMisMatch)?;
+            (BLOCK_HEADER_SIZE, ChecksumType::from_tag(tag)?)
+        } else {
+            (0, ChecksumType::Crc32)
+        };
+        let checksum_size = if header_len == 0 {
+            LEGACY_CRC_SIZE
+        } else {
+            checksum_type.size()
+        };
+        let data_bytes_len = buf
+            .len()
+            .checked_sub(checksum_size)
+            .filter(|len| *len >= header_len)
+            .ok_or(KernelError::CrcMisMatch)?;
+        // 校验码覆盖其之前的全部数据(包括头部)
+        if checksum_type.checksum(&buf[..data_bytes_len]) != buf[data_bytes_len..] {
             return Err(KernelError::CrcMisMatch);
         }
-        buf.truncate(date_bytes_len);
-
-        let mut cursor = Cursor::new(buf);
-        let vec_entry = Entry::<T>::batch_decode(&mut cursor)?;
+        let mut cursor = Cursor::new(&buf[header_len..data_bytes_len]);
+        let vec_entry = Entry::<T>::batch_decode(&mut cursor, format_version)?;
         Ok(Self {
             restart_interval,
             vec_entry,
@@ -580,19 +1403,45 @@ where
 
     /// 序列化该Block
     ///
-    /// 与from_raw对应，序列化时会生成crc_code用于反序列化时校验
-    pub(crate) fn to_raw(&self, bytes: &mut Vec<u8>) -> KernelResult<()> {
+    /// 与from_raw对应，序列化时于头部写入校验类型，并在末尾附带对应的校验码用于反序列化时校验
+    pub(crate) fn to_raw(
+        &self,
+        checksum_type: ChecksumType,
+        bytes: &mut Vec<u8>,
+    ) -> KernelResult<()> {
         let start = bytes.len();
+        bytes.extend_from_slice(&BLOCK_HEADER_MAGIC);
+        bytes.push(checksum_type.tag());
         for (_, entry) in &self.vec_entry {
             entry.encode(bytes)?;
         }
-        bytes.append(&mut crc32fast::hash(&bytes[start..]).encode_fixed_vec());
+        bytes.append(&mut checksum_type.checksum(&bytes[start..]));
 
         Ok(())
     }
 }
 
 /// 批量以restart_interval进行shared_len的获取
+/// 推断能正确还原各Block中所有Key的最小Restart间隔
+///
+/// 推断出的间隔未必与构建时所使用的间隔相同，但以其还原Key与查询的结果总是一致的
+pub(crate) fn infer_restart_interval<T>(blocks: &[&Block<T>]) -> usize {
+    let max_len = blocks
+        .iter()
+        .map(|block| block.entry_len())
+        .max()
+        .unwrap_or(1)
+        .max(1);
+
+    (1..max_len)
+        .find(|restart_interval| {
+            blocks
+                .iter()
+                .all(|block| block.is_restart_consistent(*restart_interval))
+        })
+        .unwrap_or(max_len)
+}
+
 fn sharding_shared_len<T>(vec_kv: &[KeyValue<T>], restart_interval: usize) -> Vec<usize>
 where
     T: BlockItem,
@@ -647,15 +1496,205 @@ fn longest_shared_len<T>(sharding: Vec<&KeyValue<T>>) -> usize {
 
 #[cfg(test)]
 mod tests {
+    use crate::kernel::lsm::format::{
+        FORMAT_VERSION, PREFIX_FILTER_VERSION, TOMBSTONES_VERSION, VALUE_POINTER_VERSION,
+    };
     use crate::kernel::lsm::table::ss_table::block::{
-        Block, BlockBuilder, BlockOptions, CompressType, Entry, Index, Value,
+        Block, BlockBuilder, BlockOptions, ChecksumType, CompressType, Entry, FindResult, Index,
+        MetaBlock, Value, ValuePointer, BLOCK_HEADER_SIZE, DEFAULT_BLOCK_SIZE,
+        DEFAULT_DATA_RESTART_INTERVAL,
     };
+    use crate::kernel::utils::bloom_filter::BloomFilter;
     use crate::kernel::utils::lru_cache::LruCache;
     use crate::kernel::KernelResult;
+    use crate::KernelError;
     use bincode::Options;
     use bytes::Bytes;
+    use integer_encoding::{FixedInt, FixedIntWriter, VarIntWriter};
     use std::io::Cursor;
 
+    #[test]
+    fn test_binary_search_with_prefix_key() {
+        let block = Block::new(
+            vec![
+                (Bytes::from_static(b"ab"), Value::from(None)),
+                (Bytes::from_static(b"abc"), Value::from(None)),
+                (Bytes::from_static(b"abd"), Value::from(None)),
+            ],
+            3,
+        );
+        assert_eq!(block.get_entry(1).shared_len, 2);
+        assert!(block.get_entry(2).key.as_ref() == b"d");
+
+        assert_eq!(block.binary_search(b"a"), Err(0));
+        assert_eq!(block.binary_search(b"ab"), Ok(0));
+        assert_eq!(block.binary_search(b"abb"), Err(1));
+        assert_eq!(block.binary_search(b"abc"), Ok(1));
+        assert_eq!(block.binary_search(b"abcz"), Err(2));
+        assert_eq!(block.binary_search(b"abd"), Ok(2));
+        assert_eq!(block.binary_search(b"abe"), Err(3));
+
+        // 查询Key为所有已存储Key的严格前缀
+        let block = Block::new(
+            vec![
+                (Bytes::from_static(b"abc"), Value::from(None)),
+                (Bytes::from_static(b"abcd"), Value::from(None)),
+                (Bytes::from_static(b"abce"), Value::from(None)),
+                (Bytes::from_static(b"abcf"), Value::from(None)),
+            ],
+            2,
+        );
+        assert_eq!(block.get_entry(1).shared_len, 3);
+        assert_eq!(block.get_entry(3).shared_len, 3);
+
+        assert_eq!(block.binary_search(b"a"), Err(0));
+        assert_eq!(block.binary_search(b"ab"), Err(0));
+        assert_eq!(block.binary_search(b"abc"), Ok(0));
+        assert_eq!(block.binary_search(b"abcd"), Ok(1));
+        assert_eq!(block.binary_search(b"abcdz"), Err(2));
+        assert_eq!(block.binary_search(b"abce"), Ok(2));
+        assert_eq!(block.binary_search(b"abcf"), Ok(3));
+        assert_eq!(block.binary_search(b"abd"), Err(4));
+        assert_eq!(block.find(b"ab"), FindResult::Absent);
+        assert_eq!(block.find(b"abc"), FindResult::Deleted);
+
+        assert_eq!(block.lower_bound(b"a"), 0);
+        assert_eq!(block.lower_bound(b"abc"), 0);
+        assert_eq!(block.lower_bound(b"abcd"), 1);
+        assert_eq!(block.lower_bound(b"abcda"), 2);
+        assert_eq!(block.lower_bound(b"abcf"), 3);
+        assert_eq!(block.lower_bound(b"abd"), 4);
+    }
+
+    #[test]
+    fn test_block_entries() {
+        let vec_kv = (0..10_u8)
+            .map(|i| {
+                (
+                    Bytes::from(vec![b'k', b'e', b'y', i]),
+                    Value::from(Some(Bytes::from(vec![i]))),
+                )
+            })
+            .collect::<Vec<_>>();
+        let block = Block::new(vec_kv.clone(), 4);
+        assert_eq!(block.get_entry(1).shared_len, 3);
+
+        assert_eq!(block.entries().collect::<Vec<_>>(), vec_kv);
+        assert_eq!(block.entries().nth(5), Some(vec_kv[5].clone()));
+    }
+
+    #[test]
+    fn test_meta_block_compress_type() -> KernelResult<()> {
+        let new_filter = || {
+            let mut filter = BloomFilter::new(1, 0.05);
+            filter.insert(b"KipDB".as_slice());
+            filter
+        };
+
+        for compress_type in [
+            CompressType::None,
+            CompressType::LZ4,
+            CompressType::Zstd { level: 19 },
+            CompressType::ZstdDict(Bytes::from_static(b"What you are you do not see")),
+        ] {
+            let meta = MetaBlock {
+                filter: Some(new_filter()),
+                len: 1,
+                tombstones: 0,
+                index_restart_interval: 2,
+                data_restart_interval: 16,
+                compress_type: compress_type.clone(),
+                prefix_filter: None,
+                prefix_bloom_len: 0,
+            };
+            let mut bytes = Vec::new();
+            meta.to_raw(&mut bytes)?;
+
+            let decoded = MetaBlock::from_raw(&bytes, FORMAT_VERSION)?;
+            assert_eq!(decoded.compress_type, compress_type);
+            assert_eq!(decoded.data_restart_interval, 16);
+            assert!(decoded
+                .filter
+                .is_some_and(|filter| filter.contains(b"KipDB".as_slice())));
+        }
+
+        // 不构建布隆过滤器时MetaBlock中不存在过滤器
+        let meta = MetaBlock {
+            filter: None,
+            len: 1,
+            tombstones: 0,
+            index_restart_interval: 2,
+            data_restart_interval: 16,
+            compress_type: CompressType::LZ4,
+            prefix_filter: None,
+            prefix_bloom_len: 0,
+        };
+        let mut bytes = Vec::new();
+        meta.to_raw(&mut bytes)?;
+        let decoded = MetaBlock::from_raw(&bytes, FORMAT_VERSION)?;
+        assert!(decoded.filter.is_none());
+        assert_eq!(decoded.memory_usage(), 0);
+
+        // 前缀布隆过滤器位于压缩类型之后，不影响末尾的布隆过滤器
+        let mut prefix_filter = BloomFilter::new(1, 0.05);
+        prefix_filter.insert(b"Kip".as_slice());
+        let meta = MetaBlock {
+            filter: Some(new_filter()),
+            len: 1,
+            tombstones: 3,
+            index_restart_interval: 2,
+            data_restart_interval: 16,
+            compress_type: CompressType::Zstd { level: 3 },
+            prefix_filter: Some(prefix_filter),
+            prefix_bloom_len: 3,
+        };
+        let mut bytes = Vec::new();
+        meta.to_raw(&mut bytes)?;
+        let decoded = MetaBlock::from_raw(&bytes, FORMAT_VERSION)?;
+        assert_eq!(decoded.compress_type, CompressType::Zstd { level: 3 });
+        assert_eq!(decoded.tombstones, 3);
+        assert!(decoded.may_contain_prefix(b"Kip"));
+        assert!(decoded.may_contain_prefix(b"KipDB"));
+        assert!(decoded.may_contain_prefix(b"K"));
+        assert!(decoded
+            .filter
+            .is_some_and(|filter| filter.contains(b"KipDB".as_slice())));
+
+        // 旧的MetaBlock仅存有字典长度与字典
+        for (dict, compress_type) in [
+            (&b""[..], CompressType::LZ4),
+            (
+                &b"dict"[..],
+                CompressType::ZstdDict(Bytes::from_static(b"dict")),
+            ),
+        ] {
+            let mut bytes = Vec::new();
+            bytes.write_fixedint(1_u32)?;
+            bytes.write_fixedint(2_u32)?;
+            bytes.write_fixedint(16_u32)?;
+            bytes.write_fixedint(dict.len() as u32)?;
+            bytes.extend_from_slice(dict);
+            new_filter().to_raw(&mut bytes)?;
+
+            let decoded = MetaBlock::from_raw(&bytes, FORMAT_VERSION)?;
+            assert_eq!(decoded.compress_type, compress_type);
+            assert!(decoded
+                .filter
+                .is_some_and(|filter| filter.contains(b"KipDB".as_slice())));
+        }
+
+        // 早于标记引入的格式版本中出现的标记视为损坏
+        let mut bytes = Vec::new();
+        meta.to_raw(&mut bytes)?;
+        assert!(MetaBlock::from_raw(&bytes, TOMBSTONES_VERSION).is_ok());
+        assert!(matches!(
+            MetaBlock::from_raw(&bytes, PREFIX_FILTER_VERSION),
+            Err(KernelError::CrcMisMatch)
+        ));
+
+        Ok(())
+    }
+
     #[test]
     fn test_entry_serialization() -> KernelResult<()> {
         let entry1 = Entry::new(
@@ -675,13 +1714,92 @@ mod tests {
         entry1.encode(&mut bytes)?;
         entry2.encode(&mut bytes)?;
 
-        let vec_entry = Entry::batch_decode(&mut Cursor::new(bytes))?;
+        let vec_entry = Entry::batch_decode(&mut Cursor::new(bytes), FORMAT_VERSION)?;
 
         assert_eq!(vec![(0, entry1), (1, entry2)], vec_entry);
 
         Ok(())
     }
 
+    #[test]
+    fn test_value_pointer_serialization() -> KernelResult<()> {
+        let vec_entry = vec![
+            Entry::new(
+                0,
+                1,
+                Bytes::from(vec![b'1']),
+                Value::from_pointer(ValuePointer {
+                    gen: i64::MAX,
+                    offset: u64::from(u32::MAX) + 1,
+                    len: 1024 * 1024,
+                }),
+            ),
+            Entry::new(0, 1, Bytes::from(vec![b'2']), Value::from(None)),
+            Entry::new(
+                0,
+                1,
+                Bytes::from(vec![b'3']),
+                Value::from(Some(Bytes::from(vec![b'3']))),
+            ),
+        ];
+        let mut bytes = Vec::new();
+
+        for entry in &vec_entry {
+            entry.encode(&mut bytes)?;
+        }
+        let de_vec_entry = Entry::<Value>::batch_decode(&mut Cursor::new(bytes), FORMAT_VERSION)?;
+
+        assert_eq!(
+            vec_entry.iter().cloned().enumerate().collect::<Vec<_>>(),
+            de_vec_entry
+        );
+
+        // VALUE_POINTER_VERSION的SSTable中以u32::MAX标记ValuePointer
+        let mut legacy_bytes = Vec::new();
+        legacy_bytes.write_varint(1_u32)?;
+        legacy_bytes.write_varint(0_u32)?;
+        legacy_bytes.push(b'1');
+        legacy_bytes.write_varint(u32::MAX)?;
+        legacy_bytes.write_varint(i64::MAX)?;
+        legacy_bytes.write_varint(u64::from(u32::MAX) + 1)?;
+        legacy_bytes.write_varint(1024 * 1024_u64)?;
+        let de_vec_entry = Entry::<Value>::batch_decode(
+            &mut Cursor::new(legacy_bytes.as_slice()),
+            VALUE_POINTER_VERSION,
+        )?;
+        assert_eq!(de_vec_entry, vec![(0, vec_entry[0].clone())]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_builder_unsorted_input() -> KernelResult<()> {
+        let mut builder = BlockBuilder::new(BlockOptions::new().block_size(8));
+
+        builder.add((Bytes::from_static(b"b"), Value::from(None)))?;
+        builder.add((
+            Bytes::from_static(b"d"),
+            Value::from(Some(Bytes::from_static(b"large value"))),
+        ))?;
+        // 无论比较对象仍在buf中还是已被构建为Block，乱序与重复的key都应返回错误
+        assert!(matches!(
+            builder.add((Bytes::from_static(b"c"), Value::from(None))),
+            Err(KernelError::UnsortedInput { .. })
+        ));
+        assert!(matches!(
+            builder.add((Bytes::from_static(b"d"), Value::from(None))),
+            Err(KernelError::UnsortedInput { .. })
+        ));
+        builder.add((Bytes::from_static(b"e"), Value::from(None)))?;
+        assert!(matches!(
+            builder.add((Bytes::from_static(b"a"), Value::from(None))),
+            Err(KernelError::UnsortedInput { .. })
+        ));
+        assert_eq!(builder.len(), 3);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_block() -> KernelResult<()> {
         let value = Bytes::from_static(b"Let life be beautiful like summer flowers");
@@ -699,17 +1817,18 @@ mod tests {
 
         for data in vec_data.iter().cloned() {
             let (key, value) = data;
-            builder.add((key, Value::from(value)));
+            builder.add((key, Value::from(value)))?;
         }
 
         let block = builder.vec_block[0].0.clone();
 
-        let (full_bytes, data_len, _) = builder.build().await?;
+        let (full_bytes, data_len, _, _) = builder.build().await?;
 
         let index_block = Block::<Index>::decode(
-            full_bytes[data_len..].to_vec(),
-            CompressType::None,
+            &full_bytes[data_len..],
+            &CompressType::None,
             options.index_restart_interval,
+            FORMAT_VERSION,
         )?;
 
         let mut cache = LruCache::new(5)?;
@@ -719,13 +1838,14 @@ mod tests {
             let data_block = cache.get_or_insert(index_block.find_with_upper(key), |index| {
                 let &Index { offset, len } = index;
                 let target_block = Block::<Value>::decode(
-                    full_bytes[offset as usize..offset as usize + len].to_vec(),
-                    options.compress_type,
+                    &full_bytes[offset as usize..offset as usize + len],
+                    &options.compress_type,
                     options.data_restart_interval,
+                    FORMAT_VERSION,
                 )?;
                 Ok(target_block)
             })?;
-            assert_eq!(data_block.find(key), (Some(value.clone()), true))
+            assert_eq!(data_block.find(key), FindResult::Found(value.clone()))
         }
 
         test_block_serialization_(
@@ -738,6 +1858,87 @@ mod tests {
             CompressType::LZ4,
             options.data_restart_interval,
         )?;
+        test_block_serialization_(
+            block.clone(),
+            CompressType::ZstdDict(Bytes::from_static(b"What you are you do not see")),
+            options.data_restart_interval,
+        )?;
+        test_block_serialization_(
+            block.clone(),
+            CompressType::Zstd { level: 19 },
+            options.data_restart_interval,
+        )?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_block_with_oversized_value() -> KernelResult<()> {
+        let options = BlockOptions::new().compress_type(CompressType::LZ4);
+        let small_value = Bytes::from_static(b"small");
+        let large_value = Bytes::from(
+            (0..DEFAULT_BLOCK_SIZE * 5)
+                .map(|i| (i % 251) as u8)
+                .collect::<Vec<u8>>(),
+        );
+        let mut vec_data = Vec::new();
+
+        for i in 0..100_u32 {
+            let value = if i % 25 == 10 {
+                large_value.clone()
+            } else {
+                small_value.clone()
+            };
+            vec_data.push((Bytes::from(i.to_be_bytes().to_vec()), Some(value)));
+        }
+
+        let mut builder = BlockBuilder::new(options.clone());
+        for (key, value) in vec_data.iter().cloned() {
+            builder.add((key, Value::from(value)))?;
+        }
+        builder._build();
+
+        // 大Value独占一个Block，其余Block的大小不受其影响
+        for (block, _, _) in builder.vec_block.iter() {
+            let has_large_value = block
+                .vec_entry
+                .iter()
+                .any(|(_, entry)| entry.item.value_len == large_value.len());
+            if has_large_value {
+                assert_eq!(block.entry_len(), 1);
+            } else {
+                assert!(block
+                    .vec_entry
+                    .iter()
+                    .all(|(_, entry)| entry.item.value_len == small_value.len()));
+            }
+        }
+
+        let (full_bytes, data_len, _, _) = builder.build().await?;
+        let index_block = Block::<Index>::decode(
+            &full_bytes[data_len..],
+            &CompressType::None,
+            options.index_restart_interval,
+            FORMAT_VERSION,
+        )?;
+
+        for (key, value) in vec_data.iter() {
+            let Index { offset, len } = index_block.find_with_upper(key);
+            let data_block = Block::<Value>::decode(
+                &full_bytes[offset as usize..offset as usize + len],
+                &options.compress_type,
+                options.data_restart_interval,
+                FORMAT_VERSION,
+            )?;
+            // 仅大Value所在的Block需要分配大Value的空间
+            if value.as_ref() == Some(&small_value) {
+                assert!(data_block.entry_len() > 1);
+            }
+            assert_eq!(
+                data_block.find(key),
+                value.clone().map_or(FindResult::Deleted, FindResult::Found)
+            );
+        }
 
         Ok(())
     }
@@ -747,12 +1948,104 @@ mod tests {
         compress_type: CompressType,
         restart_interval: usize,
     ) -> KernelResult<()> {
+        for checksum_type in [
+            ChecksumType::None,
+            ChecksumType::Crc32,
+            ChecksumType::XxHash64,
+        ] {
+            let mut bytes = Vec::new();
+            block.encode(&compress_type, checksum_type, &mut bytes)?;
+
+            let de_block = Block::decode(&bytes, &compress_type, restart_interval, FORMAT_VERSION)?;
+            assert_eq!(block, de_block);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_checksum() -> KernelResult<()> {
+        let block = Block::new(
+            vec![
+                (
+                    Bytes::from_static(b"k1"),
+                    Value::from(Some(Bytes::from_static(b"v1"))),
+                ),
+                (
+                    Bytes::from_static(b"k2"),
+                    Value::from(Some(Bytes::from_static(b"v2"))),
+                ),
+            ],
+            DEFAULT_DATA_RESTART_INTERVAL,
+        );
+        let corrupt = |mut bytes: Vec<u8>, checksum_type: ChecksumType| {
+            // 篡改最后一个Value的末尾字节
+            let pos = bytes.len() - checksum_type.size() - 1;
+            bytes[pos] ^= 0xff;
+            bytes
+        };
+
+        for checksum_type in [ChecksumType::Crc32, ChecksumType::XxHash64] {
+            let mut bytes = Vec::new();
+            block.to_raw(checksum_type, &mut bytes)?;
+            assert_eq!(
+                bytes.len(),
+                BLOCK_HEADER_SIZE + entries_len(&block)? + checksum_type.size()
+            );
+            assert_eq!(
+                Block::<Value>::from_raw(&bytes, DEFAULT_DATA_RESTART_INTERVAL, FORMAT_VERSION)?,
+                block
+            );
+            assert!(matches!(
+                Block::<Value>::from_raw(
+                    &corrupt(bytes, checksum_type),
+                    DEFAULT_DATA_RESTART_INTERVAL,
+                    FORMAT_VERSION
+                ),
+                Err(KernelError::CrcMisMatch)
+            ));
+        }
+
+        // 不进行校验时无法发现数据损坏
         let mut bytes = Vec::new();
-        block.encode(compress_type, &mut bytes)?;
+        block.to_raw(ChecksumType::None, &mut bytes)?;
+        let de_block = Block::<Value>::from_raw(
+            &corrupt(bytes, ChecksumType::None),
+            DEFAULT_DATA_RESTART_INTERVAL,
+            FORMAT_VERSION,
+        )?;
+        assert_eq!(
+            de_block.find(b"k2"),
+            FindResult::Found(Bytes::from_static(b"v\xcd"))
+        );
 
-        let de_block = Block::decode(bytes, compress_type, restart_interval)?;
-        assert_eq!(block, de_block);
+        // 不带头部的旧Block使用CRC32校验
+        let mut bytes = Vec::new();
+        for (_, entry) in &block.vec_entry {
+            entry.encode(&mut bytes)?;
+        }
+        bytes.append(&mut crc32fast::hash(&bytes).encode_fixed_vec());
+        assert_eq!(
+            Block::<Value>::from_raw(&bytes, DEFAULT_DATA_RESTART_INTERVAL, FORMAT_VERSION)?,
+            block
+        );
+        assert!(matches!(
+            Block::<Value>::from_raw(
+                &corrupt(bytes, ChecksumType::Crc32),
+                DEFAULT_DATA_RESTART_INTERVAL,
+                FORMAT_VERSION
+            ),
+            Err(KernelError::CrcMisMatch)
+        ));
 
         Ok(())
     }
+
+    fn entries_len(block: &Block<Value>) -> KernelResult<usize> {
+        let mut bytes = Vec::new();
+        for (_, entry) in &block.vec_entry {
+            entry.encode(&mut bytes)?;
+        }
+        Ok(bytes.len())
+    }
 }
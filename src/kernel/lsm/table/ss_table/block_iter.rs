@@ -29,6 +29,13 @@ where
         }
     }
 
+    /// 获取当前位置之后的至多n个元素的值，不会移动迭代器
+    pub(crate) fn peek_items(&self, n: usize) -> Vec<T> {
+        (self.offset..self.entry_len.min(self.offset + n))
+            .map(|index| self.block.get_entry(index).item.clone())
+            .collect()
+    }
+
     fn item(&self) -> (Bytes, T) {
         let offset = self.offset - 1;
         let Entry { key, item, .. } = self.block.get_entry(offset);
@@ -97,10 +104,10 @@ where
         match seek {
             Seek::First => Some(0),
             Seek::Last => Some(self.entry_len + 1),
-            Seek::Backward(key) => match self.block.binary_search(key) {
-                Ok(index) => Some(index),
-                Err(index) => (index < self.entry_len).then_some(index),
-            },
+            Seek::Backward(key) => {
+                let index = self.block.lower_bound(key);
+                (index < self.entry_len).then_some(index)
+            }
         }
         .and_then(|index| self.offset_move(index, true));
 
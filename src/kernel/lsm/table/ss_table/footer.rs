@@ -1,35 +1,150 @@
-use crate::kernel::io::IoReader;
+use crate::kernel::io::{IoReader, FILE_CHECKSUM_SIZE};
+use crate::kernel::lsm::format::{
+    FORMAT_VERSION, TOMBSTONES_VERSION, VALUE_POINTER_VERSION, WIDE_OFFSET_VERSION,
+};
 use crate::kernel::KernelResult;
+use crate::KernelError;
 use integer_encoding::{FixedIntReader, FixedIntWriter};
-use std::io::SeekFrom;
+use std::io::{Cursor, Read};
+use std::{io, mem};
 
 /// Footer序列化长度定长
 /// 注意Footer序列化时，需要使用类似BinCode这样的定长序列化框架，否则若类似Rmp的话会导致Footer在不同数据时，长度不一致
-pub(crate) const TABLE_FOOTER_SIZE: usize = 21;
+pub(crate) const TABLE_FOOTER_SIZE: usize = 85;
+/// 未记录格式版本的Footer长度，其与`WIDE_TABLE_FOOTER_SIZE`的Footer使用相同的`LEGACY_FOOTER_MAGIC`
+const UNVERSIONED_TABLE_FOOTER_SIZE: usize = 81;
+/// 偏移量与长度扩展为u64后，尚未存放IndexBlock信息的Footer长度
+const WIDE_TABLE_FOOTER_SIZE: usize = 73;
+/// 旧格式的Footer长度，其偏移量与长度均为u32，因此SSTable至多为4GiB
+const LEGACY_TABLE_FOOTER_SIZE: usize = 37;
+/// Footer末尾的文件校验和长度
+pub(crate) const TABLE_CHECKSUM_SIZE: usize = FILE_CHECKSUM_SIZE;
+/// 紧接于校验和之前的Footer标记，表示该Footer记录了SSTable的格式版本
+///
+/// 旧格式的Footer在该位置存放scope_len与size_of_disk，
+/// 而size_of_disk等于文件大小，不可能在为`u32::MAX`的同时使scope_len恰为标记的低位，以此区分Footer版本
+const FOOTER_MAGIC: u64 = 0xFFFF_FFFF_4B49_5056;
+/// size_of_disk在u64偏移量的Footer中的偏移：level及其后的6个u64
+const SIZE_OF_DISK_OFFSET: usize = 1 + 6 * mem::size_of::<u64>();
+/// 未记录格式版本的Footer所使用的标记，为73与81字节的两种Footer所共用
+const LEGACY_FOOTER_MAGIC: u64 = 0xFFFF_FFFF_4B49_5044;
 
 #[derive(Debug, PartialEq, Eq)]
 #[repr(C, align(32))]
 pub(crate) struct Footer {
     pub(crate) level: u8,
-    pub(crate) index_offset: u32,
-    pub(crate) index_len: u32,
-    pub(crate) meta_offset: u32,
-    pub(crate) meta_len: u32,
-    pub(crate) size_of_disk: u32,
+    pub(crate) index_offset: u64,
+    pub(crate) index_len: u64,
+    pub(crate) meta_offset: u64,
+    pub(crate) meta_len: u64,
+    pub(crate) scope_offset: u64,
+    pub(crate) scope_len: u64,
+    pub(crate) size_of_disk: u64,
+    /// IndexBlock的Restart间隔，使IndexBlock无需载入MetaBlock即可解码
+    ///
+    /// 旧格式的Footer中为0，此时需从MetaBlock中获取
+    pub(crate) index_restart_interval: u32,
+    /// DataBlock的数量，即IndexBlock中的索引数量，旧格式的Footer中为0
+    pub(crate) data_block_count: u32,
+    /// 写入该SSTable时的格式版本，旧格式的Footer中依据其长度推断
+    pub(crate) format_version: u32,
+    /// 文件中除该字段外所有数据的校验和
+    pub(crate) checksum: u64,
 }
 
 impl Footer {
     /// 从对应文件的IOHandler中将Footer读取出来
-    pub(crate) fn read_to_file(mut reader: &mut dyn IoReader) -> KernelResult<Self> {
-        let _ = reader.seek(SeekFrom::End(-(TABLE_FOOTER_SIZE as i64)))?;
+    ///
+    /// 以定位读取的方式读取，因此可使用被共享的IOHandler
+    ///
+    /// 兼容读取未记录格式版本的旧格式Footer，格式版本高于`FORMAT_VERSION`时返回`KernelError::UnsupportedTableVersion`
+    pub(crate) fn read_to_file(reader: &dyn IoReader) -> KernelResult<Self> {
+        let file_size = reader.file_size()?;
+        let tail_len = file_size.min(TABLE_FOOTER_SIZE as u64) as usize;
+        let tail = reader.read_range(file_size - tail_len as u64, tail_len)?;
+        let magic = tail
+            .len()
+            .checked_sub(TABLE_CHECKSUM_SIZE + mem::size_of::<u64>())
+            .map(|offset| &tail[offset..offset + mem::size_of::<u64>()]);
+        let footer_size = if magic == Some(&FOOTER_MAGIC.to_le_bytes()) {
+            TABLE_FOOTER_SIZE
+        } else if magic == Some(&LEGACY_FOOTER_MAGIC.to_le_bytes()) {
+            // 共用标记的两种Footer中，仅有一种使size_of_disk恰好等于文件大小
+            let size_offset = tail_len
+                .checked_sub(UNVERSIONED_TABLE_FOOTER_SIZE)
+                .map(|start| start + SIZE_OF_DISK_OFFSET);
+            let is_unversioned = size_offset.is_some_and(|offset| {
+                tail[offset..offset + mem::size_of::<u64>()] == file_size.to_le_bytes()
+            });
+            if is_unversioned {
+                UNVERSIONED_TABLE_FOOTER_SIZE
+            } else {
+                WIDE_TABLE_FOOTER_SIZE
+            }
+        } else {
+            LEGACY_TABLE_FOOTER_SIZE
+        };
+        let start = tail_len.checked_sub(footer_size).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::UnexpectedEof, "file is smaller than footer")
+        })?;
+        let mut reader = Cursor::new(&tail[start..]);
 
-        Ok(Footer {
+        if footer_size == LEGACY_TABLE_FOOTER_SIZE {
+            return Self::read_legacy(&mut reader);
+        }
+        let mut footer = Footer {
             level: reader.read_fixedint()?,
             index_offset: reader.read_fixedint()?,
             index_len: reader.read_fixedint()?,
             meta_offset: reader.read_fixedint()?,
             meta_len: reader.read_fixedint()?,
+            scope_offset: reader.read_fixedint()?,
+            scope_len: reader.read_fixedint()?,
             size_of_disk: reader.read_fixedint()?,
+            index_restart_interval: 0,
+            data_block_count: 0,
+            format_version: WIDE_OFFSET_VERSION,
+            checksum: 0,
+        };
+        if footer_size >= UNVERSIONED_TABLE_FOOTER_SIZE {
+            footer.index_restart_interval = reader.read_fixedint()?;
+            footer.data_block_count = reader.read_fixedint()?;
+            // 记录格式版本之前，81字节的Footer为最后写入的格式
+            footer.format_version = TOMBSTONES_VERSION;
+        }
+        if footer_size == TABLE_FOOTER_SIZE {
+            footer.format_version = reader.read_fixedint()?;
+            if footer.format_version > FORMAT_VERSION {
+                return Err(KernelError::UnsupportedTableVersion {
+                    found: footer.format_version,
+                    supported: FORMAT_VERSION,
+                });
+            }
+        }
+        let _magic = reader.read_fixedint::<u64>()?;
+        footer.checksum = reader.read_fixedint()?;
+
+        Ok(footer)
+    }
+
+    /// 读取偏移量与长度均为u32的旧格式Footer
+    ///
+    /// 此类Footer由ValuePointer的标记改为`u64::MAX`之前的各版本写入，
+    /// 各版本的MetaBlock与Block均可依据其中的标记区分，因此视为其中最新的版本
+    fn read_legacy<R: Read>(reader: &mut R) -> KernelResult<Self> {
+        Ok(Footer {
+            level: reader.read_fixedint()?,
+            index_offset: u64::from(reader.read_fixedint::<u32>()?),
+            index_len: u64::from(reader.read_fixedint::<u32>()?),
+            meta_offset: u64::from(reader.read_fixedint::<u32>()?),
+            meta_len: u64::from(reader.read_fixedint::<u32>()?),
+            scope_offset: u64::from(reader.read_fixedint::<u32>()?),
+            scope_len: u64::from(reader.read_fixedint::<u32>()?),
+            size_of_disk: u64::from(reader.read_fixedint::<u32>()?),
+            index_restart_interval: 0,
+            data_block_count: 0,
+            format_version: VALUE_POINTER_VERSION,
+            checksum: reader.read_fixedint()?,
         })
     }
 
@@ -39,7 +154,14 @@ impl Footer {
         bytes.write_fixedint(self.index_len)?;
         bytes.write_fixedint(self.meta_offset)?;
         bytes.write_fixedint(self.meta_len)?;
+        bytes.write_fixedint(self.scope_offset)?;
+        bytes.write_fixedint(self.scope_len)?;
         bytes.write_fixedint(self.size_of_disk)?;
+        bytes.write_fixedint(self.index_restart_interval)?;
+        bytes.write_fixedint(self.data_block_count)?;
+        bytes.write_fixedint(self.format_version)?;
+        bytes.write_fixedint(FOOTER_MAGIC)?;
+        bytes.write_fixedint(self.checksum)?;
 
         Ok(())
     }
@@ -47,8 +169,19 @@ impl Footer {
 
 #[cfg(test)]
 mod test {
-    use crate::kernel::lsm::table::ss_table::footer::{Footer, TABLE_FOOTER_SIZE};
+    use crate::kernel::io::{FileExtension, IoFactory, IoType};
+    use crate::kernel::lsm::format::{
+        FORMAT_VERSION, TOMBSTONES_VERSION, VALUE_POINTER_VERSION, WIDE_OFFSET_VERSION,
+    };
+    use crate::kernel::lsm::table::ss_table::footer::{
+        Footer, LEGACY_FOOTER_MAGIC, LEGACY_TABLE_FOOTER_SIZE, TABLE_FOOTER_SIZE,
+        UNVERSIONED_TABLE_FOOTER_SIZE, WIDE_TABLE_FOOTER_SIZE,
+    };
     use crate::kernel::KernelResult;
+    use crate::KernelError;
+    use integer_encoding::FixedIntWriter;
+    use std::io::Write;
+    use tempfile::TempDir;
 
     #[test]
     fn test_footer() -> KernelResult<()> {
@@ -59,7 +192,13 @@ mod test {
             index_len: 0,
             meta_offset: 0,
             meta_len: 0,
+            scope_offset: 0,
+            scope_len: 0,
             size_of_disk: 0,
+            index_restart_interval: 0,
+            data_block_count: 0,
+            format_version: FORMAT_VERSION,
+            checksum: 0,
         };
         info.to_raw(&mut bytes)?;
 
@@ -67,4 +206,126 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_read_footer_with_version() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let factory = IoFactory::new(temp_dir.path(), FileExtension::SSTable)?;
+        // 超出u32范围的偏移量
+        let footer = Footer {
+            level: 2,
+            index_offset: u64::from(u32::MAX) + 1,
+            index_len: 2,
+            meta_offset: u64::from(u32::MAX) + 3,
+            meta_len: 4,
+            scope_offset: u64::from(u32::MAX) + 7,
+            scope_len: 8,
+            size_of_disk: u64::from(u32::MAX) + 15 + TABLE_FOOTER_SIZE as u64,
+            index_restart_interval: 2,
+            data_block_count: 10,
+            format_version: FORMAT_VERSION,
+            checksum: 9,
+        };
+        let mut bytes = vec![0; 10];
+        footer.to_raw(&mut bytes)?;
+
+        let mut writer = factory.writer(1, IoType::Buf)?;
+        writer.write_all(&bytes)?;
+        writer.flush()?;
+        assert_eq!(
+            Footer::read_to_file(factory.reader(1, IoType::Buf)?.as_ref())?,
+            footer
+        );
+
+        // 旧格式的Footer中偏移量与长度均为u32
+        let mut legacy_bytes = vec![0; 100];
+        legacy_bytes.write_fixedint(1_u8)?;
+        for field in 1..=7_u32 {
+            legacy_bytes.write_fixedint(field)?;
+        }
+        legacy_bytes.write_fixedint(8_u64)?;
+        assert_eq!(legacy_bytes.len(), 100 + LEGACY_TABLE_FOOTER_SIZE);
+
+        let mut writer = factory.writer(2, IoType::Buf)?;
+        writer.write_all(&legacy_bytes)?;
+        writer.flush()?;
+        assert_eq!(
+            Footer::read_to_file(factory.reader(2, IoType::Buf)?.as_ref())?,
+            Footer {
+                level: 1,
+                index_offset: 1,
+                index_len: 2,
+                meta_offset: 3,
+                meta_len: 4,
+                scope_offset: 5,
+                scope_len: 6,
+                size_of_disk: 7,
+                index_restart_interval: 0,
+                data_block_count: 0,
+                format_version: VALUE_POINTER_VERSION,
+                checksum: 8,
+            }
+        );
+
+        // 未记录格式版本的两种Footer共用同一标记，依据size_of_disk区分
+        for (gen, footer_size, format_version) in [
+            (3, UNVERSIONED_TABLE_FOOTER_SIZE, TOMBSTONES_VERSION),
+            (4, WIDE_TABLE_FOOTER_SIZE, WIDE_OFFSET_VERSION),
+        ] {
+            let mut unversioned_bytes = vec![0; 100];
+            let size_of_disk = (100 + footer_size) as u64;
+            unversioned_bytes.write_fixedint(1_u8)?;
+            for field in 1..=6_u64 {
+                unversioned_bytes.write_fixedint(field)?;
+            }
+            unversioned_bytes.write_fixedint(size_of_disk)?;
+            if footer_size == UNVERSIONED_TABLE_FOOTER_SIZE {
+                unversioned_bytes.write_fixedint(2_u32)?;
+                unversioned_bytes.write_fixedint(10_u32)?;
+            }
+            unversioned_bytes.write_fixedint(LEGACY_FOOTER_MAGIC)?;
+            unversioned_bytes.write_fixedint(8_u64)?;
+            assert_eq!(unversioned_bytes.len() as u64, size_of_disk);
+
+            let mut writer = factory.writer(gen, IoType::Buf)?;
+            writer.write_all(&unversioned_bytes)?;
+            writer.flush()?;
+            let is_unversioned = footer_size == UNVERSIONED_TABLE_FOOTER_SIZE;
+            assert_eq!(
+                Footer::read_to_file(factory.reader(gen, IoType::Buf)?.as_ref())?,
+                Footer {
+                    level: 1,
+                    index_offset: 1,
+                    index_len: 2,
+                    meta_offset: 3,
+                    meta_len: 4,
+                    scope_offset: 5,
+                    scope_len: 6,
+                    size_of_disk,
+                    index_restart_interval: if is_unversioned { 2 } else { 0 },
+                    data_block_count: if is_unversioned { 10 } else { 0 },
+                    format_version,
+                    checksum: 8,
+                }
+            );
+        }
+
+        // 由更新的版本所写入的SSTable拒绝载入
+        let mut bytes = vec![0; 10];
+        Footer {
+            format_version: FORMAT_VERSION + 1,
+            ..footer
+        }
+        .to_raw(&mut bytes)?;
+        let mut writer = factory.writer(5, IoType::Buf)?;
+        writer.write_all(&bytes)?;
+        writer.flush()?;
+        assert!(matches!(
+            Footer::read_to_file(factory.reader(5, IoType::Buf)?.as_ref()),
+            Err(KernelError::UnsupportedTableVersion { found, supported })
+                if found == FORMAT_VERSION + 1 && supported == FORMAT_VERSION
+        ));
+
+        Ok(())
+    }
 }
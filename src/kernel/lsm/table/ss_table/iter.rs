@@ -1,38 +1,61 @@
-use crate::kernel::lsm::iterator::{ForwardIter, Iter, Seek, SeekIter};
-use crate::kernel::lsm::mem_table::KeyValue;
+use crate::kernel::lsm::iterator::{
+    BidirectionalCursor, Cursor, ForwardIter, Iter, Seek, SeekCursor, SeekIter,
+};
 use crate::kernel::lsm::table::ss_table::block::{BlockType, Index, Value};
 use crate::kernel::lsm::table::ss_table::block_iter::BlockIter;
 use crate::kernel::lsm::table::ss_table::SSTable;
 use crate::kernel::lsm::table::Table;
 use crate::kernel::KernelResult;
 use crate::KernelError;
+use bytes::Bytes;
+use itertools::Itertools;
 
-pub(crate) struct SSTableIter<'a> {
+/// SSTable的游标迭代器
+///
+/// 创建时游标指向SSTable中的第一个元素
+pub(crate) struct SSTableCursor<'a> {
     ss_table: &'a SSTable,
     data_iter: BlockIter<'a, Value>,
     index_iter: BlockIter<'a, Index>,
+    current: Option<(Bytes, Value)>,
+    /// 已提交预取的DataBlock中最大的偏移量
+    prefetched_offset: Option<u64>,
+    /// 读取DataBlock时复用的缓冲区，随迭代扩容至最大的DataBlock长度
+    buf: Vec<u8>,
 }
 
-impl<'a> SSTableIter<'a> {
-    pub(crate) fn new(ss_table: &'a SSTable) -> KernelResult<SSTableIter<'a>> {
+impl<'a> SSTableCursor<'a> {
+    pub(crate) fn new(ss_table: &'a SSTable) -> KernelResult<SSTableCursor<'a>> {
         let mut index_iter = BlockIter::new(ss_table.index_block()?);
         let index = index_iter.try_next()?.ok_or(KernelError::DataEmpty)?.1;
-        let data_iter = Self::data_iter_init(ss_table, index)?;
+        let mut buf = Vec::new();
+        let data_iter = Self::data_iter_init(ss_table, index, &mut buf)?;
 
-        Ok(Self {
+        let mut cursor = Self {
             ss_table,
             data_iter,
             index_iter,
-        })
+            current: None,
+            prefetched_offset: None,
+            buf,
+        };
+        cursor.prefetch();
+        cursor.current = cursor.next_entry()?;
+
+        Ok(cursor)
     }
 
-    fn data_iter_init(ss_table: &'a SSTable, index: Index) -> KernelResult<BlockIter<'a, Value>> {
+    fn data_iter_init(
+        ss_table: &'a SSTable,
+        index: Index,
+        buf: &mut Vec<u8>,
+    ) -> KernelResult<BlockIter<'a, Value>> {
         let block = {
             ss_table
                 .cache
                 .get_or_insert((ss_table.gen(), Some(index)), |(_, index)| {
                     let index = (*index).ok_or_else(|| KernelError::DataEmpty)?;
-                    ss_table.data_block(index)
+                    ss_table.data_block_into(index, buf)
                 })
                 .map(|block_type| match block_type {
                     BlockType::Data(data_block) => Some(data_block),
@@ -45,69 +68,125 @@ impl<'a> SSTableIter<'a> {
     }
 
     fn data_iter_seek(&mut self, seek: Seek<'_>, index: Index) -> KernelResult<()> {
-        self.data_iter = Self::data_iter_init(self.ss_table, index)?;
+        self.data_iter = Self::data_iter_init(self.ss_table, index, &mut self.buf)?;
         self.data_iter.seek(seek)?;
 
         Ok(())
     }
-}
 
-impl<'a> ForwardIter<'a> for SSTableIter<'a> {
-    fn try_prev(&mut self) -> KernelResult<Option<Self::Item>> {
-        match self.data_iter.try_prev()? {
+    /// 将Block迭代器移动至下一个键值对，当前Block耗尽时切换至下一个Block
+    fn next_entry(&mut self) -> KernelResult<Option<(Bytes, Value)>> {
+        let item = match self.data_iter.try_next()? {
             None => {
-                if let Some((_, index)) = self.index_iter.try_prev()? {
-                    self.data_iter_seek(Seek::Last, index)?;
+                if let Some((_, index)) = self.index_iter.try_next()? {
+                    self.data_iter_seek(Seek::First, index)?;
+                    self.prefetch();
 
-                    Ok(self
-                        .data_iter
-                        .try_prev()?
-                        .map(|(key, value)| (key, value.bytes)))
+                    self.data_iter.try_next()?
                 } else {
-                    Ok(None)
+                    None
                 }
             }
-            Some((key, value)) => Ok(Some((key, value.bytes))),
-        }
+            item => item,
+        };
+
+        self.resolve(item)
     }
-}
 
-impl<'a> Iter<'a> for SSTableIter<'a> {
-    type Item = KeyValue;
+    /// 异步预取当前DataBlock之后的`Config::scan_prefetch_blocks`个DataBlock至Block缓存
+    fn prefetch(&mut self) {
+        let depth = self.ss_table.scan_prefetch_blocks;
+        if depth == 0 {
+            return;
+        }
+        let prefetched_offset = self.prefetched_offset;
+        let vec_index = self
+            .index_iter
+            .peek_items(depth)
+            .into_iter()
+            .filter(|index| prefetched_offset.map_or(true, |offset| index.offset() > offset))
+            .collect_vec();
+
+        if let Some(index) = vec_index.last() {
+            self.prefetched_offset = Some(index.offset());
+            self.ss_table.prefetch_data_blocks(vec_index);
+        }
+    }
 
-    fn try_next(&mut self) -> KernelResult<Option<Self::Item>> {
-        match self.data_iter.try_next()? {
+    /// 将Block迭代器移动至上一个键值对，当前Block耗尽时切换至上一个Block
+    fn prev_entry(&mut self) -> KernelResult<Option<(Bytes, Value)>> {
+        let item = match self.data_iter.try_prev()? {
             None => {
-                if let Some((_, index)) = self.index_iter.try_next()? {
-                    self.data_iter_seek(Seek::First, index)?;
+                if let Some((_, index)) = self.index_iter.try_prev()? {
+                    self.data_iter = Self::data_iter_init(self.ss_table, index, &mut self.buf)?;
 
-                    Ok(self
-                        .data_iter
-                        .try_next()?
-                        .map(|(key, value)| (key, value.bytes)))
+                    self.data_iter.seek_to_last()?
                 } else {
-                    Ok(None)
+                    None
                 }
             }
-            Some((key, value)) => Ok(Some((key, value.bytes))),
-        }
+            item => item,
+        };
+
+        self.resolve(item)
     }
 
-    fn is_valid(&self) -> bool {
-        self.data_iter.is_valid()
+    /// 将分离存储的Value从ValueLog中读出，使游标所指向的Value总是完整的
+    fn resolve(&self, item: Option<(Bytes, Value)>) -> KernelResult<Option<(Bytes, Value)>> {
+        item.map(|(key, value)| Ok((key, Value::from(self.ss_table.resolve_value(value)?))))
+            .transpose()
     }
 }
 
-impl<'a> SeekIter<'a> for SSTableIter<'a> {
-    fn seek(&mut self, seek: Seek<'_>) -> KernelResult<()> {
-        self.index_iter.seek(seek)?;
+impl<'a> Cursor<'a> for SSTableCursor<'a> {
+    fn valid(&self) -> bool {
+        self.current.is_some()
+    }
+
+    fn key(&self) -> &Bytes {
+        &self.current.as_ref().expect("cursor is not valid").0
+    }
+
+    fn value(&self) -> &Option<Bytes> {
+        &self.current.as_ref().expect("cursor is not valid").1.bytes
+    }
+
+    fn next(&mut self) -> KernelResult<()> {
+        self.current = self.next_entry()?;
 
-        if let Some((_, index)) = self.index_iter.try_next()? {
+        Ok(())
+    }
+}
+
+impl<'a> SeekCursor<'a> for SSTableCursor<'a> {
+    fn seek(&mut self, seek: Seek<'_>) -> KernelResult<()> {
+        // `Seek::Last`时游标越过末尾，但仍需载入最后一个DataBlock以便通过`prev`逆序遍历
+        let index = if matches!(seek, Seek::Last) {
+            self.index_iter.seek_to_last()?
+        } else {
+            self.index_iter.seek(seek)?;
+            self.index_iter.try_next()?
+        };
+        if let Some((_, index)) = index {
             self.data_iter_seek(seek, index)?;
         }
-        if matches!(seek, Seek::Last) {
-            self.data_iter.seek(Seek::Last)?;
+        self.prefetched_offset = None;
+        if !matches!(seek, Seek::Last) {
+            self.prefetch();
         }
+        self.current = if matches!(seek, Seek::Last) {
+            None
+        } else {
+            self.next_entry()?
+        };
+
+        Ok(())
+    }
+}
+
+impl<'a> BidirectionalCursor<'a> for SSTableCursor<'a> {
+    fn prev(&mut self) -> KernelResult<()> {
+        self.current = self.prev_entry()?;
 
         Ok(())
     }
@@ -116,9 +195,12 @@ impl<'a> SeekIter<'a> for SSTableIter<'a> {
 #[cfg(test)]
 mod tests {
     use crate::kernel::io::{FileExtension, IoFactory, IoType};
-    use crate::kernel::lsm::iterator::{ForwardIter, Iter, Seek, SeekIter};
+    use crate::kernel::lsm::iterator::{
+        BidirectionalCursor, Cursor, CursorIter, ForwardIter, Iter, Seek, SeekCursor, SeekIter,
+    };
+    use crate::kernel::lsm::mem_table::KeyValue;
     use crate::kernel::lsm::storage::Config;
-    use crate::kernel::lsm::table::ss_table::iter::SSTableIter;
+    use crate::kernel::lsm::table::ss_table::iter::SSTableCursor;
     use crate::kernel::lsm::table::ss_table::SSTable;
     use crate::kernel::lsm::version::DEFAULT_SS_TABLE_PATH;
     use crate::kernel::utils::lru_cache::ShardingLruCache;
@@ -127,13 +209,142 @@ mod tests {
     use bytes::Bytes;
     use std::collections::hash_map::RandomState;
     use std::sync::Arc;
+    use std::time::Duration;
     use tempfile::TempDir;
+    use tokio::time::sleep;
 
     #[tokio::test]
     async fn test_iterator() -> KernelResult<()> {
+        let times = 2333;
+        let (ss_table, vec_data) = ss_table_with_data(times, 0).await?;
+
+        let mut iterator = CursorIter::new(SSTableCursor::new(&ss_table)?);
+
+        for kv in vec_data.iter().take(times) {
+            assert_eq!(iterator.try_next()?.unwrap(), kv.clone());
+        }
+
+        for i in (0..times - 1).rev() {
+            assert_eq!(iterator.try_prev()?.unwrap(), vec_data[i]);
+        }
+
+        iterator.seek(Seek::Backward(&vec_data[114].0))?;
+        assert_eq!(iterator.try_next()?.unwrap(), vec_data[114]);
+
+        iterator.seek(Seek::First)?;
+        assert_eq!(iterator.try_next()?.unwrap(), vec_data[0]);
+
+        iterator.seek(Seek::Last)?;
+        assert_eq!(iterator.try_next()?, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cursor_reverse() -> KernelResult<()> {
+        let (ss_table, vec_data) = ss_table_with_data(2333, 0).await?;
+
+        let mut cursor = SSTableCursor::new(&ss_table)?;
+        let mut forward_keys = Vec::new();
+        while cursor.valid() {
+            forward_keys.push(cursor.key().clone());
+            cursor.next()?;
+        }
+
+        // 自第一个DataBlock直接定位至最后一个元素并逆序遍历，跨越各个DataBlock
+        let mut cursor = SSTableCursor::new(&ss_table)?;
+        cursor.seek(Seek::Last)?;
+        cursor.prev()?;
+        let mut backward_keys = Vec::new();
+        while cursor.valid() {
+            backward_keys.push(cursor.key().clone());
+            cursor.prev()?;
+        }
+        backward_keys.reverse();
+
+        assert_eq!(forward_keys.len(), vec_data.len());
+        assert_eq!(forward_keys, backward_keys);
+
+        let mut iterator = CursorIter::new(SSTableCursor::new(&ss_table)?);
+        assert_eq!(iterator.seek_to_last()?, vec_data.last().cloned());
+        assert_eq!(iterator.try_prev()?, vec_data.iter().nth_back(1).cloned());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cursor_with_pull() -> KernelResult<()> {
+        let (ss_table, vec_data) = ss_table_with_data(2333, 0).await?;
+
+        let mut cursor = SSTableCursor::new(&ss_table)?;
+        let mut iterator = CursorIter::new(SSTableCursor::new(&ss_table)?);
+        let mut vec_cursor = Vec::new();
+        let mut vec_pull = Vec::new();
+
+        while cursor.valid() {
+            // 查看当前元素不会将其消费
+            assert_eq!(cursor.key(), cursor.key());
+            vec_cursor.push((cursor.key().clone(), cursor.value().clone()));
+            cursor.next()?;
+        }
+        while let Some(item) = iterator.try_next()? {
+            assert_eq!(iterator.cursor().key(), &item.0);
+            vec_pull.push(item);
+        }
+        assert_eq!(vec_cursor, vec_data);
+        assert_eq!(vec_pull, vec_data);
+
+        cursor.seek(Seek::Backward(&vec_data[1024].0))?;
+        iterator.seek(Seek::Backward(&vec_data[1024].0))?;
+        assert_eq!(cursor.key(), &vec_data[1024].0);
+        assert_eq!(iterator.try_next()?, Some(vec_data[1024].clone()));
+
+        cursor.seek(Seek::Last)?;
+        iterator.seek(Seek::Last)?;
+        assert!(!cursor.valid());
+        assert_eq!(iterator.try_next()?, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cursor_with_prefetch() -> KernelResult<()> {
+        let prefetch_blocks = 4;
+        let (ss_table, vec_data) = ss_table_with_data(2333, prefetch_blocks).await?;
+
+        let iterator = CursorIter::new(SSTableCursor::new(&ss_table)?);
+        // 创建时载入IndexBlock与第一个DataBlock，并预取其后的DataBlock
+        let mut wait_times = 0;
+        while ss_table.cache.len() < prefetch_blocks + 2 {
+            assert!(wait_times < 100, "data blocks were not prefetched");
+            wait_times += 1;
+            sleep(Duration::from_millis(10)).await;
+        }
+        drop(iterator);
+
+        // 预取不会影响迭代的结果
+        let mut iterator = CursorIter::new(SSTableCursor::new(&ss_table)?);
+        for kv in vec_data.iter() {
+            assert_eq!(iterator.try_next()?.as_ref(), Some(kv));
+        }
+        assert_eq!(iterator.try_next()?, None);
+
+        iterator.seek(Seek::Backward(&vec_data[1024].0))?;
+        for kv in vec_data[1024..].iter() {
+            assert_eq!(iterator.try_next()?.as_ref(), Some(kv));
+        }
+        assert_eq!(iterator.try_next()?, None);
+
+        Ok(())
+    }
+
+    async fn ss_table_with_data(
+        times: usize,
+        scan_prefetch_blocks: usize,
+    ) -> KernelResult<(SSTable, Vec<KeyValue>)> {
         let temp_dir = TempDir::new().expect("unable to create temporary working directory");
 
-        let config = Config::new(temp_dir.into_path());
+        let config = Config::new(temp_dir.keep()).scan_prefetch_blocks(scan_prefetch_blocks);
 
         let sst_factory = IoFactory::new(
             config.dir_path.join(DEFAULT_SS_TABLE_PATH),
@@ -144,8 +355,6 @@ mod tests {
             Bytes::from_static(b"What you are you do not see, what you see is your shadow.");
         let mut vec_data = Vec::new();
 
-        let times = 2333;
-
         // 默认使用大端序进行序列化，保证顺序正确性
         for i in 0..times {
             let mut key = b"KipDB-".to_vec();
@@ -169,25 +378,6 @@ mod tests {
         )
         .await?;
 
-        let mut iterator = SSTableIter::new(&ss_table)?;
-
-        for kv in vec_data.iter().take(times) {
-            assert_eq!(iterator.try_next()?.unwrap(), kv.clone());
-        }
-
-        for i in (0..times - 1).rev() {
-            assert_eq!(iterator.try_prev()?.unwrap(), vec_data[i]);
-        }
-
-        iterator.seek(Seek::Backward(&vec_data[114].0))?;
-        assert_eq!(iterator.try_next()?.unwrap(), vec_data[114]);
-
-        iterator.seek(Seek::First)?;
-        assert_eq!(iterator.try_next()?.unwrap(), vec_data[0]);
-
-        iterator.seek(Seek::Last)?;
-        assert_eq!(iterator.try_next()?, None);
-
-        Ok(())
+        Ok((ss_table, vec_data))
     }
 }
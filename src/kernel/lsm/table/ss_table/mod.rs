@@ -1,28 +1,45 @@
-use crate::kernel::io::{IoFactory, IoReader, IoType};
-use crate::kernel::lsm::iterator::SeekIter;
+use crate::kernel::io::readahead::ReadAheadReader;
+use crate::kernel::io::{
+    checksum_vectored, IoFactory, IoReader, IoType, IoWriter, PositionedReader,
+};
+use crate::kernel::lsm::compactor::LEVEL_0;
+use crate::kernel::lsm::format::{FORMAT_VERSION, PREFIX_INDEX_VERSION};
+use crate::kernel::lsm::iterator::{CursorIter, Iter, SeekIter};
 use crate::kernel::lsm::mem_table::KeyValue;
-use crate::kernel::lsm::storage::Config;
+use crate::kernel::lsm::stats::BloomStats;
+use crate::kernel::lsm::storage::{Config, DEFAULT_DESIRED_ERROR_PROB};
+use crate::kernel::lsm::table::scope::Scope;
 use crate::kernel::lsm::table::ss_table::block::{
-    Block, BlockBuilder, BlockCache, BlockItem, BlockOptions, BlockType, CompressType, Index,
-    MetaBlock, Value,
+    infer_restart_interval, train_compression_dict, Block, BlockBuilder, BlockCache, BlockItem,
+    BlockOptions, BlockType, BloomPolicy, CompressType, FindResult, Index, MetaBlock, PrefixIndex,
+    ScopeBlock, Value, ValuePointer,
 };
-use crate::kernel::lsm::table::ss_table::footer::{Footer, TABLE_FOOTER_SIZE};
-use crate::kernel::lsm::table::ss_table::iter::SSTableIter;
-use crate::kernel::lsm::table::Table;
+use crate::kernel::lsm::table::ss_table::block_iter::BlockIter;
+use crate::kernel::lsm::table::ss_table::footer::{Footer, TABLE_CHECKSUM_SIZE, TABLE_FOOTER_SIZE};
+use crate::kernel::lsm::table::ss_table::iter::SSTableCursor;
+use crate::kernel::lsm::table::ss_table::value_log::ValueLog;
+use crate::kernel::lsm::table::{load_by_iter, load_by_prefix, Table};
 use crate::kernel::utils::bloom_filter::BloomFilter;
+use crate::kernel::utils::rate_limiter::RateLimiter;
 use crate::kernel::KernelResult;
 use crate::KernelError;
 use bytes::Bytes;
 use core::slice::SlicePattern;
+use integer_encoding::FixedIntWriter;
 use parking_lot::Mutex;
-use std::io::SeekFrom;
-use std::sync::Arc;
-use tracing::info;
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::mem;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use tokio::runtime::Handle;
+use tracing::{info, warn};
 
 pub(crate) mod block;
 pub(crate) mod block_iter;
 mod footer;
 pub(crate) mod iter;
+pub(crate) mod value_log;
 
 /// SSTable
 ///
@@ -30,17 +47,111 @@ pub(crate) mod iter;
 pub(crate) struct SSTable {
     // 表索引信息
     footer: Footer,
-    // 文件IO操作器
-    reader: Mutex<Box<dyn IoReader>>,
+    // 文件IO操作器，通过`IoReader::read_at`支持无锁的并发读取
+    reader: ReaderSource,
     // 该SSTable的唯一编号(时间递增)
     gen: i64,
-    // 统计信息存储Block
-    meta: MetaBlock,
+    // 统计信息存储Block，通过已有文件构建时在首次使用时才载入
+    meta: OnceLock<Arc<MetaBlock>>,
+    // Key范围
+    scope: ScopeBlock,
     // Block缓存(Index/Value)
     cache: Arc<BlockCache>,
+    // 范围扫描时预取的DataBlock数量
+    scan_prefetch_blocks: usize,
+    // 分离存储的大Value
+    value_log: ValueLog,
+    // 布隆过滤器的判定统计，由TableLoader所载入的SSTable共享
+    bloom_counters: Arc<BloomCounters>,
+    // MetaBlock损坏时的重建配置与已重建的MetaBlock，由TableLoader所载入的SSTable共享
+    meta_repair: Arc<MetaRepair>,
+}
+
+/// 重建损坏的MetaBlock所使用的布隆过滤器配置，以及已重建的MetaBlock
+///
+/// 重建的MetaBlock不会写回文件，因此缓存于此，
+/// 使SSTable被驱逐出Table缓存后再次载入时无需重复遍历DataBlock与告警
+pub(crate) struct MetaRepair {
+    bloom_policy: BloomPolicy,
+    desired_error_prob: f64,
+    repaired: Mutex<HashMap<i64, Arc<MetaBlock>>>,
+}
+
+impl Default for MetaRepair {
+    fn default() -> Self {
+        MetaRepair {
+            bloom_policy: BloomPolicy::default(),
+            desired_error_prob: DEFAULT_DESIRED_ERROR_PROB,
+            repaired: Mutex::default(),
+        }
+    }
+}
+
+impl MetaRepair {
+    pub(crate) fn new(config: &Config) -> Self {
+        MetaRepair {
+            bloom_policy: config.bloom_policy.clone(),
+            desired_error_prob: config.desired_error_prob,
+            repaired: Mutex::default(),
+        }
+    }
+
+    /// 移除已删除的SSTable所重建的MetaBlock
+    pub(crate) fn forget(&self, gen: i64) {
+        let _ = self.repaired.lock().remove(&gen);
+    }
+}
+
+/// 查询时布隆过滤器的判定统计
+#[derive(Debug, Default)]
+pub(crate) struct BloomCounters {
+    /// 判定Key不存在而跳过读取DataBlock的次数
+    useful: AtomicU64,
+    /// 判定Key可能存在但DataBlock中并不存在的次数
+    false_positives: AtomicU64,
+}
+
+impl BloomCounters {
+    pub(crate) fn useful(&self) -> u64 {
+        self.useful.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn false_positives(&self) -> u64 {
+        self.false_positives.load(Ordering::Relaxed)
+    }
+
+    /// 实际观测到的误判率，未统计时为0
+    pub(crate) fn false_positive_rate(&self) -> f64 {
+        let false_positives = self.false_positives();
+        let total = false_positives + self.useful();
+        if total == 0 {
+            return 0.0;
+        }
+
+        false_positives as f64 / total as f64
+    }
+}
+
+/// SSTable获取文件读取器的方式
+enum ReaderSource {
+    /// 常驻的文件句柄
+    Fixed(Arc<dyn IoReader>),
+    /// 读取时通过IoFactory获取，由其限制同时打开的文件数量
+    Factory(Arc<IoFactory>, IoType),
+}
+
+impl ReaderSource {
+    fn io_type(&self) -> IoType {
+        match self {
+            ReaderSource::Fixed(reader) => reader.get_type(),
+            ReaderSource::Factory(_, io_type) => *io_type,
+        }
+    }
 }
 
 impl SSTable {
+    /// 依据Config决定压缩类型创建SSTable，仅供测试使用
+    #[cfg(test)]
     pub(crate) async fn new(
         io_factory: &IoFactory,
         config: &Config,
@@ -49,103 +160,537 @@ impl SSTable {
         vec_data: Vec<KeyValue>,
         level: usize,
         io_type: IoType,
+    ) -> KernelResult<SSTable> {
+        Self::new_with_compress_type(
+            io_factory, config, cache, gen, vec_data, level, io_type, None, None,
+        )
+        .await
+    }
+
+    /// 以指定的DataBlock压缩类型创建SSTable，为None时依据Config决定压缩类型
+    ///
+    /// 传入`rate_limiter`时写入文件前依据写入的字节数获取令牌，用于限制后台压缩的写入速率
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn new_with_compress_type(
+        io_factory: &IoFactory,
+        config: &Config,
+        cache: Arc<BlockCache>,
+        gen: i64,
+        vec_data: Vec<KeyValue>,
+        level: usize,
+        io_type: IoType,
+        compress_type: Option<CompressType>,
+        rate_limiter: Option<&RateLimiter>,
     ) -> KernelResult<SSTable> {
         let len = vec_data.len();
+        let tombstones = vec_data.iter().filter(|(_, value)| value.is_none()).count();
+        let scope = match (vec_data.first(), vec_data.last()) {
+            (Some((start, _)), Some((end, _))) => ScopeBlock {
+                start: start.clone(),
+                end: end.clone(),
+            },
+            _ => return Err(KernelError::DataEmpty),
+        };
         let data_restart_interval = config.data_restart_interval;
         let index_restart_interval = config.index_restart_interval;
-        let mut filter = BloomFilter::new(len, config.desired_error_prob);
+        let mut filter = config
+            .bloom_policy
+            .error_prob(level, config.desired_error_prob)
+            .map(|error_prob| BloomFilter::new(len, error_prob));
+        let prefix_bloom_len = config.prefix_bloom_len;
+        let mut prefix_filter =
+            (prefix_bloom_len > 0).then(|| BloomFilter::new(len, config.desired_error_prob));
+        let compress_type = compress_type.unwrap_or_else(|| {
+            let compression_dict = (config.compression_dict > 0)
+                .then(|| train_compression_dict(&vec_data, config.compression_dict))
+                .flatten();
+
+            match (compression_dict, config.zstd_compression_level) {
+                (Some(dict), _) => CompressType::ZstdDict(dict),
+                (None, Some(level)) => CompressType::Zstd { level },
+                (None, None) => CompressType::LZ4,
+            }
+        });
 
         let mut builder = BlockBuilder::new(
             BlockOptions::from(config)
-                .compress_type(CompressType::LZ4)
+                .compress_type(compress_type.clone())
                 .data_restart_interval(data_restart_interval)
                 .index_restart_interval(index_restart_interval),
         );
+        // 内存中的SSTable无需分离Value
+        let separation_threshold = (io_type != IoType::Mem)
+            .then_some(config.value_separation_threshold)
+            .filter(|threshold| *threshold > 0);
+        let mut value_log_bytes = Vec::new();
+
         for data in vec_data {
             let (key, value) = data;
-            filter.insert(key.as_slice());
-            builder.add((key, Value::from(value)));
+            if let Some(filter) = &mut filter {
+                filter.insert(key.as_slice());
+            }
+            // 短于前缀长度的Key不可能以长度不小于前缀长度的前缀开头，无需加入
+            if let Some(prefix_filter) = prefix_filter
+                .as_mut()
+                .filter(|_| key.len() >= prefix_bloom_len)
+            {
+                prefix_filter.insert(&key[..prefix_bloom_len]);
+            }
+            let value = match value {
+                Some(value)
+                    if separation_threshold.is_some_and(|threshold| value.len() >= threshold) =>
+                {
+                    let pointer = ValuePointer {
+                        gen,
+                        offset: value_log_bytes.len() as u64,
+                        len: value.len(),
+                    };
+                    ValueLog::append(&mut value_log_bytes, &value);
+                    Value::from_pointer(pointer)
+                }
+                value => Value::from(value),
+            };
+            builder.add((key, value))?;
+        }
+        let value_log = ValueLog::new(&io_factory.path_with_gen(gen), gen, FORMAT_VERSION);
+        // ValueLog先于SSTable落盘，保证SSTable中的ValuePointer总是有效的
+        if !value_log_bytes.is_empty() {
+            if let Some(rate_limiter) = rate_limiter {
+                rate_limiter.request(value_log_bytes.len() as u64).await;
+            }
+            value_log.write(&value_log_bytes, config.use_fsync)?;
         }
         let meta = MetaBlock {
             filter,
             len,
+            tombstones,
             index_restart_interval,
             data_restart_interval,
+            compress_type,
+            prefix_filter,
+            prefix_bloom_len,
         };
-        let (mut bytes, data_bytes_len, index_bytes_len) = builder.build().await?;
-        meta.to_raw(&mut bytes)?;
+        let (bytes, data_bytes_len, index_bytes_len, data_block_count) = builder.build().await?;
+        // IndexBlock与MetaBlock之间存放PrefixIndex(若有)
+        let meta_offset = bytes.len();
+        let mut meta_bytes = Vec::new();
+        meta.to_raw(&mut meta_bytes)?;
+        let scope_offset = meta_offset + meta_bytes.len();
+        scope.to_raw(&mut meta_bytes)?;
+        let footer_offset = meta_offset + meta_bytes.len();
 
         let footer = Footer {
             level: level as u8,
-            index_offset: data_bytes_len as u32,
-            index_len: index_bytes_len as u32,
-            meta_offset: (data_bytes_len + index_bytes_len) as u32,
-            meta_len: (bytes.len() - data_bytes_len + index_bytes_len) as u32,
-            size_of_disk: (bytes.len() + TABLE_FOOTER_SIZE) as u32,
+            index_offset: data_bytes_len as u64,
+            index_len: index_bytes_len as u64,
+            meta_offset: meta_offset as u64,
+            meta_len: (scope_offset - meta_offset) as u64,
+            scope_offset: scope_offset as u64,
+            scope_len: (footer_offset - scope_offset) as u64,
+            size_of_disk: (footer_offset + TABLE_FOOTER_SIZE) as u64,
+            index_restart_interval: index_restart_interval as u32,
+            data_block_count: data_block_count as u32,
+            format_version: FORMAT_VERSION,
+            checksum: 0,
+        };
+        let mut footer_bytes = Vec::with_capacity(TABLE_FOOTER_SIZE);
+        footer.to_raw(&mut footer_bytes)?;
+        footer_bytes.truncate(TABLE_FOOTER_SIZE - TABLE_CHECKSUM_SIZE);
+
+        let (data_bytes, index_bytes) = bytes.split_at(data_bytes_len);
+        // 校验和覆盖除其自身外的整个文件，包括Footer中的其他字段
+        let footer = Footer {
+            checksum: checksum_vectored(&[
+                data_bytes,
+                index_bytes,
+                meta_bytes.as_slice(),
+                footer_bytes.as_slice(),
+            ]),
+            ..footer
         };
-        footer.to_raw(&mut bytes)?;
+        let mut checksummed_footer = Vec::with_capacity(TABLE_FOOTER_SIZE);
+        checksummed_footer.extend_from_slice(&footer_bytes);
+        checksummed_footer.write_fixedint(footer.checksum)?;
+        // 各段分别写入，避免为整个文件再拼接出一份完整的内存副本
+        let segments = [
+            data_bytes,
+            index_bytes,
+            meta_bytes.as_slice(),
+            checksummed_footer.as_slice(),
+        ];
 
+        if let Some(rate_limiter) = rate_limiter {
+            rate_limiter.request(footer.size_of_disk).await;
+        }
         let mut writer = io_factory.writer(gen, io_type)?;
-        writer.write_all(&bytes)?;
+        Self::write_with_sync(
+            writer.as_mut(),
+            &segments,
+            config.bytes_per_sync,
+            config.use_fsync,
+        )?;
         writer.flush()?;
+        // 先关闭写入的文件句柄，使创建期间至多同时打开一个额外的文件句柄
+        drop(writer);
         info!("[SsTable: {}][create][MetaBlock]: {:?}", gen, meta);
 
-        let reader = Mutex::new(io_factory.reader(gen, io_type)?);
-        Ok(SSTable {
+        let reader = ReaderSource::Fixed(io_factory.shared_reader(gen, io_type)?);
+        let ss_table = SSTable {
             footer,
             reader,
             gen,
-            meta,
+            meta: OnceLock::from(Arc::new(meta)),
+            scope,
             cache,
-        })
+            scan_prefetch_blocks: config.scan_prefetch_blocks,
+            value_log,
+            bloom_counters: Arc::default(),
+            meta_repair: Arc::default(),
+        };
+        ss_table.prefetch_index(config.prefetch_index)?;
+
+        Ok(ss_table)
+    }
+
+    /// 依次写入SSTable的各段数据并同步至硬盘(use_fsync为true时为fsync，否则为fdatasync)
+    ///
+    /// `flush`仅将数据交给OS，停电时仍可能丢失，因此返回前必定进行同步；
+    /// bytes_per_sync大于0时每写入bytes_per_sync字节便进行一次同步，
+    /// 以平滑回写避免一次性大量脏页刷盘导致的延迟抖动，
+    /// 否则通过`IoWriter::write_all_vectored`一次性写入所有段
+    fn write_with_sync(
+        writer: &mut dyn IoWriter,
+        segments: &[&[u8]],
+        bytes_per_sync: usize,
+        use_fsync: bool,
+    ) -> KernelResult<()> {
+        if bytes_per_sync == 0 {
+            IoWriter::write_all_vectored(writer, segments)?;
+            return writer.sync(use_fsync);
+        }
+
+        let mut unsynced = 0;
+        for mut segment in segments.iter().copied() {
+            while !segment.is_empty() {
+                let (chunk, rest) = segment.split_at(segment.len().min(bytes_per_sync - unsynced));
+                writer.write_all(chunk)?;
+                unsynced += chunk.len();
+                segment = rest;
+
+                if unsynced == bytes_per_sync {
+                    writer.sync(use_fsync)?;
+                    unsynced = 0;
+                }
+            }
+        }
+        if unsynced > 0 {
+            writer.sync(use_fsync)?;
+        }
+
+        Ok(())
     }
 
     /// 通过已经存在的文件构建SSTable
     ///
-    /// 使用原有的路径与分区大小恢复出一个有内容的SSTable
+    /// 使用原有的路径与分区大小恢复出一个有内容的SSTable，
+    /// 此时仅读取Footer与ScopeBlock，MetaBlock(如BloomFilter)在首次使用时才载入
+    ///
+    /// prefetch_index为true时，Level 0的SSTable会在加载时预取IndexBlock
     pub(crate) fn load_from_file(
-        mut reader: Box<dyn IoReader>,
+        reader: impl Into<Arc<dyn IoReader>>,
         cache: Arc<BlockCache>,
+        prefetch_index: bool,
     ) -> KernelResult<Self> {
+        let reader = reader.into();
         let gen = reader.get_gen();
-        let footer = Footer::read_to_file(reader.as_mut())?;
+        let footer = Footer::read_to_file(reader.as_ref())?;
         let Footer {
             size_of_disk,
-            meta_offset,
-            meta_len,
+            scope_offset,
+            scope_len,
             ..
         } = &footer;
         info!(
-            "[SsTable: {gen}][load_from_file][MetaBlock]: {footer:?}, Size of Disk: {}, IO Type: {:?}",
-            size_of_disk ,
+            "[SsTable: {gen}][load_from_file][Footer]: {footer:?}, Size of Disk: {}, IO Type: {:?}",
+            size_of_disk,
             reader.get_type()
         );
 
-        let mut buf = vec![0; *meta_len as usize];
-        let _ = reader.seek(SeekFrom::Start(*meta_offset as u64))?;
-        let _ = reader.read(&mut buf)?;
-
-        let meta = MetaBlock::from_raw(&buf);
-        let reader = Mutex::new(reader);
-        Ok(SSTable {
+        let scope = ScopeBlock::from_raw(&reader.read_range(*scope_offset, *scope_len as usize)?)?;
+        let value_log = ValueLog::new(&reader.get_path(), gen, footer.format_version);
+        let ss_table = SSTable {
             footer,
             gen,
-            reader,
-            meta,
+            reader: ReaderSource::Fixed(reader),
+            meta: OnceLock::new(),
+            scope,
             cache,
+            scan_prefetch_blocks: 0,
+            value_log,
+            bloom_counters: Arc::default(),
+            meta_repair: Arc::default(),
+        };
+        ss_table.prefetch_index(prefetch_index)?;
+
+        Ok(ss_table)
+    }
+
+    /// 共享布隆过滤器的判定统计，使多个SSTable的统计汇总于一处
+    pub(crate) fn bloom_counters(mut self, bloom_counters: Arc<BloomCounters>) -> Self {
+        self.bloom_counters = bloom_counters;
+        self
+    }
+
+    /// 共享MetaBlock的重建配置与已重建的MetaBlock
+    pub(crate) fn meta_repair(mut self, meta_repair: Arc<MetaRepair>) -> Self {
+        self.meta_repair = meta_repair;
+        self
+    }
+
+    /// 设置范围扫描时预取的DataBlock数量，为0时不进行预取
+    pub(crate) fn scan_prefetch_blocks(mut self, scan_prefetch_blocks: usize) -> Self {
+        self.scan_prefetch_blocks = scan_prefetch_blocks;
+        self
+    }
+
+    /// 不再常驻文件句柄，而是在读取时通过`factory`获取
+    ///
+    /// 配合`IoFactory::max_open_files`限制同时打开的文件数量
+    pub(crate) fn share_reader(mut self, factory: Arc<IoFactory>) -> Self {
+        let io_type = self.reader.io_type();
+        self.value_log = self.value_log.share_reader(&factory);
+        self.reader = ReaderSource::Factory(factory, io_type);
+        self
+    }
+
+    /// 获取MetaBlock，尚未载入时从文件中读取
+    fn meta(&self) -> KernelResult<&MetaBlock> {
+        if let Some(meta) = self.meta.get() {
+            return Ok(meta);
+        }
+        let Footer {
+            meta_offset,
+            meta_len,
+            ..
+        } = self.footer;
+        let meta = match MetaBlock::from_raw(
+            &self.reader()?.read_range(meta_offset, meta_len as usize)?,
+            self.footer.format_version,
+        ) {
+            Ok(meta) => Arc::new(meta),
+            Err(err) => {
+                let mut repaired = self.meta_repair.repaired.lock();
+
+                match repaired.get(&self.gen) {
+                    Some(meta) => Arc::clone(meta),
+                    None => {
+                        warn!(
+                            "[SsTable: {}][meta][repair with data blocks]: {:?}",
+                            self.gen, err
+                        );
+                        let meta = Arc::new(self.repair_meta()?);
+                        let _ = repaired.insert(self.gen, Arc::clone(&meta));
+                        meta
+                    }
+                }
+            }
+        };
+
+        Ok(self.meta.get_or_init(|| meta))
+    }
+
+    /// MetaBlock损坏而IndexBlock与DataBlock完好时，遍历各DataBlock重建MetaBlock
+    ///
+    /// 重新统计数据数量并依据`Config::bloom_policy`重建布隆过滤器，避免单个MetaBlock损坏便需通过WAL恢复整个SSTable；
+    /// DataBlock的压缩类型依据其头部推断，Restart间隔依据各Entry的共享前缀长度推断，
+    /// 不含任何DataBlock或使用带字典的Zstd压缩(字典随MetaBlock一同丢失)的SSTable无法修复
+    pub(crate) fn repair_meta(&self) -> KernelResult<MetaBlock> {
+        let reader = self.reader()?;
+        let Footer {
+            index_offset,
+            index_len,
+            ..
+        } = self.footer;
+        // 仅使用IndexBlock中各DataBlock的位置，因此无需以正确的Restart间隔还原Key
+        let index_block = Self::loading_block::<Index>(
+            reader.as_ref(),
+            index_offset,
+            index_len as usize,
+            &CompressType::None,
+            1,
+            self.footer.format_version,
+        )?;
+        if index_block.entry_len() == 0 {
+            return Err(KernelError::DataEmpty);
+        }
+        let mut compress_type = None;
+        let mut data_blocks = Vec::with_capacity(index_block.entry_len());
+
+        for i in 0..index_block.entry_len() {
+            let index = index_block.get_entry(i).item;
+            let buf = reader.read_range(index.offset(), index.len())?;
+            let compress_type = compress_type.get_or_insert_with(|| CompressType::detect(&buf));
+
+            data_blocks.push(Block::<Value>::decode(
+                &buf,
+                compress_type,
+                1,
+                self.footer.format_version,
+            )?);
+        }
+        let len = data_blocks.iter().map(Block::entry_len).sum();
+        let tombstones = data_blocks
+            .iter()
+            .flat_map(|block| (0..block.entry_len()).map(|i| &block.get_entry(i).item))
+            .filter(|value| value.bytes.is_none() && value.pointer.is_none())
+            .count();
+        let mut filter = self
+            .meta_repair
+            .bloom_policy
+            .error_prob(self.level(), self.meta_repair.desired_error_prob)
+            .map(|error_prob| BloomFilter::new(len, error_prob));
+        if let Some(filter) = &mut filter {
+            for key in data_blocks.iter().flat_map(Block::sequential_keys) {
+                filter.insert(key.as_slice());
+            }
+        }
+        let index_restart_interval = match self.footer.index_restart_interval {
+            0 => infer_restart_interval(&[&index_block]),
+            interval => interval as usize,
+        };
+
+        Ok(MetaBlock {
+            filter,
+            len,
+            tombstones,
+            index_restart_interval,
+            data_restart_interval: infer_restart_interval(&data_blocks.iter().collect::<Vec<_>>()),
+            compress_type: compress_type.ok_or(KernelError::DataEmpty)?,
+            prefix_filter: None,
+            prefix_bloom_len: 0,
         })
     }
 
+    /// 依据前缀布隆过滤器判断该SSTable是否可能存在以`prefix`为前缀的Key
+    ///
+    /// 返回false时该SSTable必定不存在此前缀，前缀扫描可跳过整个SSTable
+    pub(crate) fn may_contain_prefix(&self, prefix: &[u8]) -> KernelResult<bool> {
+        Ok(self.meta()?.may_contain_prefix(prefix))
+    }
+
+    /// IndexBlock的Restart间隔，旧格式的Footer中未存放时从MetaBlock中获取
+    fn index_restart_interval(&self) -> KernelResult<usize> {
+        match self.footer.index_restart_interval {
+            0 => Ok(self.meta()?.index_restart_interval),
+            interval => Ok(interval as usize),
+        }
+    }
+
+    fn reader(&self) -> KernelResult<Arc<dyn IoReader>> {
+        match &self.reader {
+            ReaderSource::Fixed(reader) => Ok(Arc::clone(reader)),
+            ReaderSource::Factory(factory, io_type) => factory.shared_reader(self.gen, *io_type),
+        }
+    }
+
+    /// 取出Value中的数据，分离存储的Value会从ValueLog中读取
+    pub(crate) fn resolve_value(&self, value: Value) -> KernelResult<Option<Bytes>> {
+        match &value.pointer {
+            Some(pointer) => self.value_log.read(pointer).map(Some),
+            None => Ok(value.bytes),
+        }
+    }
+
+    /// 流式校验整个文件的完整性，可发现Block CRC无法覆盖的损坏(如Footer)
+    #[allow(dead_code)]
+    pub(crate) fn verify_checksum(&self) -> KernelResult<bool> {
+        let reader = self.reader()?;
+        let len = reader.file_size()?;
+
+        Ok(len == self.size_of_disk() && reader.checksum_file()? == self.footer.checksum)
+    }
+
+    /// 预取IndexBlock至缓存
+    ///
+    /// 仅对Level 0这类必定会被查询的热Table生效，其余Level的冷Table保持懒加载
+    fn prefetch_index(&self, prefetch_index: bool) -> KernelResult<()> {
+        if prefetch_index && self.level() == LEVEL_0 {
+            let _ = self.index_block()?;
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn data_block(&self, index: Index) -> KernelResult<BlockType> {
-        Ok(BlockType::Data(Self::loading_block(
-            self.reader.lock().as_mut(),
+        self.data_block_into(index, &mut Vec::new())
+    }
+
+    /// 使用给定的缓冲区读取DataBlock，用于顺序扫描时复用同一缓冲区
+    pub(crate) fn data_block_into(
+        &self,
+        index: Index,
+        buf: &mut Vec<u8>,
+    ) -> KernelResult<BlockType> {
+        Ok(BlockType::Data(Self::loading_block_into(
+            self.reader()?.as_ref(),
+            buf,
             index.offset(),
             index.len(),
-            CompressType::LZ4,
-            self.meta.data_restart_interval,
+            &self.meta()?.data_compress_type(),
+            self.meta()?.data_restart_interval,
+            self.footer.format_version,
         )?))
     }
 
+    /// 在阻塞线程中将DataBlock载入Block缓存
+    ///
+    /// 不处于Tokio运行时中或使用内存IO时不进行预取，预取失败时仅影响性能而不影响读取
+    pub(crate) fn prefetch_data_blocks(&self, vec_index: Vec<Index>) {
+        let Ok(handle) = Handle::try_current() else {
+            return;
+        };
+        if self.reader.io_type() == IoType::Mem {
+            return;
+        }
+        let Ok(meta) = self.meta() else {
+            return;
+        };
+        let gen = self.gen;
+        // 与查询共用同一读取器，使预取同样受`IoFactory::max_open_files`的限制
+        let reader = match self.reader() {
+            Ok(reader) => reader,
+            Err(err) => {
+                warn!("[SsTable: {gen}][prefetch_data_blocks][error happen]: {err:?}");
+                return;
+            }
+        };
+        let cache = Arc::clone(&self.cache);
+        let compress_type = meta.data_compress_type();
+        let restart_interval = meta.data_restart_interval;
+        let format_version = self.footer.format_version;
+
+        // 预取仅影响性能，无需等待其完成
+        drop(handle.spawn_blocking(move || {
+            let mut buf = Vec::new();
+            for index in vec_index {
+                if let Err(err) = cache.get_or_insert((gen, Some(index)), |_| {
+                    Ok(BlockType::Data(Self::loading_block_into(
+                        reader.as_ref(),
+                        &mut buf,
+                        index.offset(),
+                        index.len(),
+                        &compress_type,
+                        restart_interval,
+                        format_version,
+                    )?))
+                }) {
+                    warn!("[SsTable: {gen}][prefetch_data_blocks][error happen]: {err:?}");
+                    return;
+                }
+            }
+        }));
+    }
+
     pub(crate) fn index_block(&self) -> KernelResult<&Block<Index>> {
         self.cache
             .get_or_insert((self.gen(), None), |_| {
@@ -154,13 +699,21 @@ impl SSTable {
                     index_len,
                     ..
                 } = self.footer;
-                Ok(BlockType::Index(Self::loading_block(
-                    self.reader.lock().as_mut(),
+                let index_block = Self::loading_block(
+                    self.reader()?.as_ref(),
                     index_offset,
                     index_len as usize,
-                    CompressType::None,
-                    self.meta.index_restart_interval,
-                )?))
+                    &CompressType::None,
+                    self.index_restart_interval()?,
+                    self.footer.format_version,
+                )?;
+                // 索引数量与Footer中记录的DataBlock数量不一致时说明文件已损坏
+                let data_block_count = self.footer.data_block_count as usize;
+                if data_block_count != 0 && index_block.entry_len() != data_block_count {
+                    return Err(KernelError::CrcMisMatch);
+                }
+
+                Ok(BlockType::Index(index_block))
             })
             .map(|block_type| match block_type {
                 BlockType::Index(data_block) => Some(data_block),
@@ -169,51 +722,163 @@ impl SSTable {
             .ok_or(KernelError::DataEmpty)
     }
 
-    fn loading_block<T>(
-        reader: &mut dyn IoReader,
-        offset: u32,
+    /// 获取位于IndexBlock与MetaBlock之间的PrefixIndex，未设置前缀提取方式时为None
+    pub(crate) fn prefix_index(&self) -> KernelResult<Option<&PrefixIndex>> {
+        let Footer {
+            index_offset,
+            index_len,
+            meta_offset,
+            ..
+        } = self.footer;
+        let offset = index_offset + index_len;
+        if self.footer.format_version < PREFIX_INDEX_VERSION || offset >= meta_offset {
+            return Ok(None);
+        }
+        let index = Index::new(offset, (meta_offset - offset) as usize);
+
+        self.cache
+            .get_or_insert((self.gen(), Some(index)), |_| {
+                let buf = self.reader()?.read_range(offset, index.len())?;
+
+                Ok(BlockType::PrefixIndex(PrefixIndex::decode(
+                    &buf,
+                    self.index_restart_interval()?,
+                    self.footer.format_version,
+                )?))
+            })
+            .map(|block_type| match block_type {
+                BlockType::PrefixIndex(prefix_index) => Some(prefix_index),
+                _ => None,
+            })
+    }
+
+    /// 以定位读取的方式载入Block，无需对Reader加锁
+    fn loading_block<T: BlockItem>(
+        reader: &dyn IoReader,
+        offset: u64,
+        len: usize,
+        compress_type: &CompressType,
+        restart_interval: usize,
+        format_version: u32,
+    ) -> KernelResult<Block<T>> {
+        Block::decode(
+            &reader.read_range(offset, len)?,
+            compress_type,
+            restart_interval,
+            format_version,
+        )
+    }
+
+    /// 以定位读取的方式将Block读入`buf`后载入
+    ///
+    /// `buf`仅在长度不足时扩容，连续载入多个Block时复用同一缓冲区可避免逐个Block的分配与释放
+    fn loading_block_into<T: BlockItem>(
+        reader: &dyn IoReader,
+        buf: &mut Vec<u8>,
+        offset: u64,
+        len: usize,
+        compress_type: &CompressType,
+        restart_interval: usize,
+        format_version: u32,
+    ) -> KernelResult<Block<T>> {
+        buf.resize(len, 0);
+        reader.read_exact_at(buf, offset)?;
+
+        Block::decode(buf, compress_type, restart_interval, format_version)
+    }
+
+    /// 通过独占的文件句柄Seek后将Block读入`buf`并载入，用于预取与压缩时的顺序读取
+    fn read_block_into<T, R>(
+        reader: &mut R,
+        buf: &mut Vec<u8>,
+        offset: u64,
         len: usize,
-        compress_type: CompressType,
+        compress_type: &CompressType,
         restart_interval: usize,
+        format_version: u32,
     ) -> KernelResult<Block<T>>
     where
         T: BlockItem,
+        R: Read + Seek + ?Sized,
     {
-        let mut buf = vec![0; len];
-        let _ = reader.seek(SeekFrom::Start(offset as u64))?;
-        reader.read_exact(&mut buf)?;
+        buf.resize(len, 0);
+        let _ = reader.seek(SeekFrom::Start(offset))?;
+        reader.read_exact(buf)?;
 
-        Block::decode(buf, compress_type, restart_interval)
+        Block::decode(buf, compress_type, restart_interval, format_version)
     }
 }
 
 impl Table for SSTable {
     fn query(&self, key: &[u8]) -> KernelResult<Option<KeyValue>> {
-        if self.meta.filter.contains(key) {
-            let index_block = self.index_block()?;
-
-            if let BlockType::Data(data_block) = self.cache.get_or_insert(
-                (self.gen(), Some(index_block.find_with_upper(key))),
-                |(_, index)| {
-                    let index = (*index).ok_or_else(|| KernelError::DataEmpty)?;
-                    Self::data_block(self, index)
-                },
-            )? {
-                if let (value, true) = data_block.find(key) {
-                    return Ok(Some((Bytes::copy_from_slice(key), value)));
-                }
+        let filter = self.meta()?.filter.as_ref();
+        if filter.is_some_and(|filter| !filter.contains(key)) {
+            let _ = self.bloom_counters.useful.fetch_add(1, Ordering::Relaxed);
+            return Ok(None);
+        }
+        let index_block = self.index_block()?;
+
+        if let BlockType::Data(data_block) = self.cache.get_or_insert(
+            (self.gen(), Some(index_block.find_with_upper(key))),
+            |(_, index)| {
+                let index = (*index).ok_or_else(|| KernelError::DataEmpty)?;
+                Self::data_block(self, index)
+            },
+        )? {
+            let value = match data_block.find(key) {
+                FindResult::Found(bytes) => Some(Some(bytes)),
+                FindResult::Separated(pointer) => Some(Some(self.value_log.read(&pointer)?)),
+                // 删除标记同样作为查询结果返回，使查询止步于此而不再查找更旧的数据
+                FindResult::Deleted => Some(None),
+                FindResult::Absent => None,
+            };
+            if let Some(value) = value {
+                return Ok(Some((Bytes::copy_from_slice(key), value)));
             }
         }
+        if filter.is_some() {
+            let _ = self
+                .bloom_counters
+                .false_positives
+                .fetch_add(1, Ordering::Relaxed);
+        }
 
         Ok(None)
     }
 
+    /// MetaBlock尚未载入时不为统计而载入，此时返回默认值
+    fn bloom_stats(&self) -> BloomStats {
+        self.meta
+            .get()
+            .and_then(|meta| meta.filter.as_ref())
+            .map_or(BloomStats::default(), |filter| BloomStats {
+                bits: filter.bits_count() as u64,
+                hash_fn_count: filter.hash_fn_count(),
+                estimated_fpr: filter.estimated_fpr(),
+            })
+    }
+
+    /// MetaBlock载入失败时返回0
     fn len(&self) -> usize {
-        self.meta.len
+        self.meta().map_or(0, |meta| meta.len)
+    }
+
+    /// MetaBlock载入失败时返回0
+    fn tombstone_count(&self) -> usize {
+        self.meta().map_or(0, |meta| meta.tombstones)
     }
 
     fn size_of_disk(&self) -> u64 {
-        self.footer.size_of_disk as u64
+        self.footer.size_of_disk
+    }
+
+    fn memory_usage(&self) -> usize {
+        let ScopeBlock { start, end } = &self.scope;
+
+        mem::size_of::<SSTable>()
+            + self.meta.get().map_or(0, |meta| meta.memory_usage())
+            + start.len()
+            + end.len()
     }
 
     fn gen(&self) -> i64 {
@@ -224,31 +889,131 @@ impl Table for SSTable {
         self.footer.level as usize
     }
 
+    fn scope(&self) -> KernelResult<Scope> {
+        let ScopeBlock { start, end } = &self.scope;
+
+        Ok(Scope::from_range(self.gen, start.clone(), end.clone())
+            .with_size_of_disk(self.size_of_disk()))
+    }
+
     fn iter<'a>(
         &'a self,
     ) -> KernelResult<Box<dyn SeekIter<'a, Item = KeyValue> + 'a + Send + Sync>> {
-        Ok(SSTableIter::new(self).map(Box::new)?)
+        Ok(Box::new(CursorIter::new(SSTableCursor::new(self)?)))
+    }
+
+    /// 获取所有以`prefix`开头的键值对
+    ///
+    /// `prefix`恰为一个完整的前缀时，通过PrefixIndex仅读取包含该前缀的DataBlock，
+    /// 否则从`prefix`处开始顺序扫描
+    fn prefix_scan(&self, prefix: &[u8]) -> KernelResult<Vec<KeyValue>> {
+        let mut vec_kv = Vec::new();
+        if !self.may_contain_prefix(prefix)? {
+            return Ok(vec_kv);
+        }
+
+        match self.prefix_index()? {
+            Some(prefix_index) if prefix_index.extractor().is_whole_prefix(prefix) => {
+                let Some(range) = prefix_index.find(prefix) else {
+                    return Ok(vec_kv);
+                };
+                for index in self.index_block()?.indexes_within(range) {
+                    if let BlockType::Data(data_block) = self
+                        .cache
+                        .get_or_insert((self.gen(), Some(index)), |_| self.data_block(index))?
+                    {
+                        for (key, value) in data_block.entries() {
+                            if key.starts_with(prefix) {
+                                vec_kv.push((key, self.resolve_value(value)?));
+                            }
+                        }
+                    }
+                }
+            }
+            _ => vec_kv = load_by_prefix(self, prefix)?,
+        }
+
+        Ok(vec_kv)
+    }
+
+    /// 开启预读时使用独立的文件句柄按IndexBlock顺序读取所有DataBlock
+    ///
+    /// 读取的DataBlock不会写入Block缓存，避免压缩时的冷数据挤出热数据
+    fn load_for_compaction(
+        &self,
+        readahead_size: usize,
+        fn_is_filter: &dyn Fn(&Bytes) -> bool,
+    ) -> KernelResult<Vec<KeyValue>> {
+        if readahead_size == 0 || self.reader.io_type() == IoType::Mem {
+            return load_by_iter(self, fn_is_filter);
+        }
+        // 复用SSTable的IoReader进行预读，不额外打开文件句柄
+        let mut reader =
+            ReadAheadReader::new(PositionedReader::new(self.reader()?), readahead_size);
+        let mut index_iter = BlockIter::new(self.index_block()?);
+        let meta = self.meta()?;
+        let compress_type = meta.data_compress_type();
+        let mut vec_cmd = Vec::with_capacity(meta.len);
+        let mut buf = Vec::new();
+
+        while let Some((_, index)) = index_iter.try_next()? {
+            let data_block = Self::read_block_into::<Value, _>(
+                &mut reader,
+                &mut buf,
+                index.offset(),
+                index.len(),
+                &compress_type,
+                meta.data_restart_interval,
+                self.footer.format_version,
+            )?;
+            for (key, value) in data_block.entries() {
+                if fn_is_filter(&key) {
+                    vec_cmd.push((key, self.resolve_value(value)?))
+                }
+            }
+        }
+
+        Ok(vec_cmd)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::kernel::io::{FileExtension, IoFactory, IoType};
+    use crate::kernel::io::{FileExtension, IoFactory, IoType, IoWriter};
+    use crate::kernel::lsm::iterator::Iter;
     use crate::kernel::lsm::log::LogLoader;
     use crate::kernel::lsm::mem_table::DEFAULT_WAL_PATH;
     use crate::kernel::lsm::storage::Config;
     use crate::kernel::lsm::table::loader::TableLoader;
-    use crate::kernel::lsm::table::ss_table::SSTable;
+    use crate::kernel::lsm::table::scope::Scope;
+    use crate::kernel::lsm::table::ss_table::block::{
+        BlockBuilder, BlockCache, BlockOptions, BlockType, BloomPolicy, CompressType,
+        PrefixExtractor, Value,
+    };
+    use crate::kernel::lsm::table::ss_table::block_iter::BlockIter;
+    use crate::kernel::lsm::table::ss_table::footer::Footer;
+    use crate::kernel::lsm::table::ss_table::{MetaRepair, SSTable};
     use crate::kernel::lsm::table::{Table, TableType};
     use crate::kernel::lsm::version::DEFAULT_SS_TABLE_PATH;
+    use crate::kernel::utils::bloom_filter::BloomFilter;
     use crate::kernel::utils::lru_cache::ShardingLruCache;
     use crate::kernel::KernelResult;
     use bincode::Options;
     use bytes::Bytes;
+    use itertools::Itertools;
     use std::collections::hash_map::RandomState;
+    use std::fs::OpenOptions;
+    use std::io;
+    use std::io::{Cursor, Read, Seek, SeekFrom, Write};
     use std::sync::Arc;
     use tempfile::TempDir;
 
+    impl SSTable {
+        pub(crate) fn compress_type(&self) -> KernelResult<&CompressType> {
+            Ok(&self.meta()?.compress_type)
+        }
+    }
+
     #[tokio::test]
     async fn test_ss_table() -> KernelResult<()> {
         let temp_dir = TempDir::new().expect("unable to create temporary working directory");
@@ -256,7 +1021,7 @@ mod tests {
         let value = Bytes::copy_from_slice(
             b"If you shed tears when you miss the sun, you also miss the stars.",
         );
-        let config = Config::new(temp_dir.into_path());
+        let config = Config::new(temp_dir.keep());
         let sst_factory = Arc::new(IoFactory::new(
             config.dir_path.join(DEFAULT_SS_TABLE_PATH),
             FileExtension::SSTable,
@@ -290,12 +1055,800 @@ mod tests {
         for kv in vec_data.iter().take(times) {
             assert_eq!(ss_table.query(&kv.0)?.unwrap().1, Some(value.clone()))
         }
-        let cache = ShardingLruCache::new(config.table_cache_size, 16, RandomState::default())?;
+        for io_type in [IoType::Direct, IoType::Mmap] {
+            let cache = ShardingLruCache::new(config.table_cache_size, 16, RandomState::default())?;
+            let ss_table =
+                SSTable::load_from_file(sst_factory.reader(1, io_type)?, Arc::new(cache), false)?;
+            for kv in vec_data.iter().take(times) {
+                assert_eq!(ss_table.query(&kv.0)?.unwrap().1, Some(value.clone()))
+            }
+            // 载入的Table应与构建时的Scope一致
+            let scope = Scope::from_sorted_vec_data(1, &vec_data)?;
+            let loaded_scope = ss_table.scope()?;
+            assert_eq!(loaded_scope.gen(), scope.gen());
+            assert_eq!(loaded_scope.start, scope.start);
+            assert_eq!(loaded_scope.end, scope.end);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ss_table_checksum() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+
+        let value =
+            Bytes::from_static(b"What you are you do not see, what you see is your shadow.");
+        let config = Config::new(temp_dir.keep());
+        let sst_factory = Arc::new(IoFactory::new(
+            config.dir_path.join(DEFAULT_SS_TABLE_PATH),
+            FileExtension::SSTable,
+        )?);
+        let (log_loader, _) = LogLoader::reload(
+            config.path(),
+            (DEFAULT_WAL_PATH, Some(1)),
+            IoType::Buf,
+            &mut vec![0],
+            |_, _| Ok(()),
+        )?;
+        let sst_loader = TableLoader::new(config.clone(), sst_factory.clone(), log_loader)?;
+
+        let vec_data = (0..1000_u32)
+            .map(|i| (Bytes::from(i.to_be_bytes().to_vec()), Some(value.clone())))
+            .collect_vec();
+        let _ = sst_loader
+            .create(1, vec_data, 1, TableType::SortedString)
+            .await?;
+
+        let load = || -> KernelResult<SSTable> {
+            let cache = ShardingLruCache::new(config.table_cache_size, 16, RandomState::default())?;
+            SSTable::load_from_file(
+                sst_factory.reader(1, IoType::Direct)?,
+                Arc::new(cache),
+                false,
+            )
+        };
+        let ss_table = load()?;
+        assert!(ss_table.verify_checksum()?);
+
+        let reader = sst_factory.reader(1, IoType::Direct)?;
+        let file_checksum = reader.checksum_file()?;
+        assert_eq!(file_checksum, ss_table.footer.checksum);
+
+        // 篡改Data区域中的一个字节后，无需解码Block即可发现损坏
+        let offset = ss_table.size_of_disk() / 4;
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(reader.get_path())?;
+        let mut byte = [0_u8];
+        let _ = file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut byte)?;
+        let _ = file.seek(SeekFrom::Start(offset))?;
+        file.write_all(&[!byte[0]])?;
+        file.sync_all()?;
+
+        assert_ne!(file_checksum, reader.checksum_file()?);
+        assert!(!load()?.verify_checksum()?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ss_table_repair_meta() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+
+        let value =
+            Bytes::from_static(b"What you are you do not see, what you see is your shadow.");
+        let config = Config::new(temp_dir.keep()).desired_error_prob(0.01);
+        let sst_factory = Arc::new(IoFactory::new(
+            config.dir_path.join(DEFAULT_SS_TABLE_PATH),
+            FileExtension::SSTable,
+        )?);
+        let (log_loader, _) = LogLoader::reload(
+            config.path(),
+            (DEFAULT_WAL_PATH, Some(1)),
+            IoType::Buf,
+            &mut vec![0],
+            |_, _| Ok(()),
+        )?;
+        let sst_loader = TableLoader::new(config.clone(), sst_factory.clone(), log_loader)?;
+
+        let vec_data = (0..1000_u32)
+            .map(|i| (Bytes::from(i.to_be_bytes().to_vec()), Some(value.clone())))
+            .collect_vec();
+        let _ = sst_loader
+            .create(1, vec_data.clone(), 1, TableType::SortedString)
+            .await?;
+
+        let meta_repair = Arc::new(MetaRepair::new(&config));
+        let load = || -> KernelResult<SSTable> {
+            let cache = ShardingLruCache::new(config.table_cache_size, 16, RandomState::default())?;
+            Ok(SSTable::load_from_file(
+                sst_factory.reader(1, IoType::Direct)?,
+                Arc::new(cache),
+                false,
+            )?
+            .meta_repair(Arc::clone(&meta_repair)))
+        };
+        let ss_table = load()?;
+        let origin_meta = ss_table.meta()?;
+        let Footer {
+            meta_offset,
+            meta_len,
+            ..
+        } = ss_table.footer;
+
+        // 覆盖整个MetaBlock使其无法解析
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(sst_factory.reader(1, IoType::Direct)?.get_path())?;
+        let _ = file.seek(SeekFrom::Start(meta_offset))?;
+        file.write_all(&vec![u8::MAX; meta_len as usize])?;
+        file.sync_all()?;
+
+        let repaired = load()?;
+        let repaired_meta = repaired.meta()?;
+        assert_eq!(repaired_meta.len, vec_data.len());
+        assert_eq!(
+            repaired_meta.index_restart_interval,
+            origin_meta.index_restart_interval
+        );
+        assert_eq!(
+            repaired_meta.data_restart_interval,
+            origin_meta.data_restart_interval
+        );
+        // 布隆过滤器依据Config中的误判率重建
+        assert_eq!(
+            repaired_meta.filter.as_ref().map(BloomFilter::bits_count),
+            origin_meta.filter.as_ref().map(BloomFilter::bits_count)
+        );
+        // 重新载入时复用已重建的MetaBlock
+        let reloaded = load()?;
+        let _ = reloaded.meta()?;
+        assert!(Arc::ptr_eq(
+            repaired.meta.get().unwrap(),
+            reloaded.meta.get().unwrap()
+        ));
+
+        for (key, value) in vec_data.iter() {
+            assert_eq!(repaired.query(key)?, Some((key.clone(), value.clone())));
+        }
+        assert_eq!(repaired.query(&1001_u32.to_be_bytes())?, None);
+
+        let mut iter = repaired.iter()?;
+        let mut items = Vec::with_capacity(vec_data.len());
+        while let Some(item) = iter.try_next()? {
+            items.push(item);
+        }
+        assert_eq!(items, vec_data);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ss_table_with_compression_dict() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.keep());
+        let sst_factory = IoFactory::new(
+            config.dir_path.join(DEFAULT_SS_TABLE_PATH),
+            FileExtension::SSTable,
+        )?;
+        let new_cache = || -> KernelResult<Arc<BlockCache>> {
+            Ok(Arc::new(ShardingLruCache::new(
+                config.block_cache_size,
+                16,
+                RandomState::default(),
+            )?))
+        };
+        // 大量相似的小Value
+        let vec_data = (0..5000_u32)
+            .map(|i| {
+                let value = format!(
+                    r#"{{"id":{i},"name":"KipDB","email":"user_{i}@kipdata.site","status":"active"}}"#
+                );
+                (
+                    Bytes::from(format!("user_{i:08}")),
+                    Some(Bytes::from(value)),
+                )
+            })
+            .collect_vec();
+
+        let lz4_table = SSTable::new(
+            &sst_factory,
+            &config,
+            new_cache()?,
+            1,
+            vec_data.clone(),
+            1,
+            IoType::Direct,
+        )
+        .await?;
+        let dict_config = config.clone().compression_dict(4 * 1024);
+        let dict_table = SSTable::new(
+            &sst_factory,
+            &dict_config,
+            new_cache()?,
+            2,
+            vec_data.clone(),
+            1,
+            IoType::Direct,
+        )
+        .await?;
+        assert_eq!(*lz4_table.compress_type()?, CompressType::LZ4);
+        assert!(matches!(
+            *dict_table.compress_type()?,
+            CompressType::ZstdDict(_)
+        ));
+        assert!(dict_table.size_of_disk() < lz4_table.size_of_disk());
+
+        let reloaded =
+            SSTable::load_from_file(sst_factory.reader(2, IoType::Direct)?, new_cache()?, false)?;
+        assert_eq!(*reloaded.compress_type()?, *dict_table.compress_type()?);
+        for (key, value) in vec_data.iter() {
+            assert_eq!(dict_table.query(key)?, Some((key.clone(), value.clone())));
+            assert_eq!(reloaded.query(key)?, Some((key.clone(), value.clone())));
+        }
+        assert_eq!(reloaded.load_for_compaction(4096, &|_| true)?, vec_data);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ss_table_with_zstd() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.keep());
+        let sst_factory = IoFactory::new(
+            config.dir_path.join(DEFAULT_SS_TABLE_PATH),
+            FileExtension::SSTable,
+        )?;
+        let new_cache = || -> KernelResult<Arc<BlockCache>> {
+            Ok(Arc::new(ShardingLruCache::new(
+                config.block_cache_size,
+                16,
+                RandomState::default(),
+            )?))
+        };
+        let vec_data = (0..5000_u32)
+            .map(|i| {
+                (
+                    Bytes::from(format!("user_{i:08}")),
+                    Some(Bytes::from(format!("KipDB-{}", i % 100).repeat(8))),
+                )
+            })
+            .collect_vec();
+
+        let lz4_table = SSTable::new(
+            &sst_factory,
+            &config,
+            new_cache()?,
+            1,
+            vec_data.clone(),
+            1,
+            IoType::Direct,
+        )
+        .await?;
+        let zstd_config = config.clone().zstd_compression_level(19);
+        let zstd_table = SSTable::new(
+            &sst_factory,
+            &zstd_config,
+            new_cache()?,
+            2,
+            vec_data.clone(),
+            1,
+            IoType::Direct,
+        )
+        .await?;
+        assert_eq!(
+            *zstd_table.compress_type()?,
+            CompressType::Zstd { level: 19 }
+        );
+        assert!(zstd_table.size_of_disk() < lz4_table.size_of_disk());
+
+        // 重新载入时依据MetaBlock中存储的压缩类型选择解压方式
+        let reloaded =
+            SSTable::load_from_file(sst_factory.reader(2, IoType::Direct)?, new_cache()?, false)?;
+        assert_eq!(*reloaded.compress_type()?, CompressType::Zstd { level: 19 });
+        for (key, value) in vec_data.iter() {
+            assert_eq!(reloaded.query(key)?, Some((key.clone(), value.clone())));
+        }
+        assert_eq!(reloaded.load_for_compaction(4096, &|_| true)?, vec_data);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ss_table_bloom_policy() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.keep()).bloom_policy(BloomPolicy::DisableAbove(1));
+        let sst_factory = IoFactory::new(
+            config.dir_path.join(DEFAULT_SS_TABLE_PATH),
+            FileExtension::SSTable,
+        )?;
+        let vec_data = (0..1000_u32)
+            .map(|i| {
+                (
+                    Bytes::from(format!("user_{i:08}")),
+                    Some(Bytes::from(format!("KipDB-{i}"))),
+                )
+            })
+            .collect_vec();
+
+        for (gen, level) in [(1, 1), (2, 2)] {
+            let cache = Arc::new(ShardingLruCache::new(
+                config.block_cache_size,
+                16,
+                RandomState::default(),
+            )?);
+            let _ = SSTable::new(
+                &sst_factory,
+                &config,
+                Arc::clone(&cache),
+                gen,
+                vec_data.clone(),
+                level,
+                IoType::Direct,
+            )
+            .await?;
+            let reloaded =
+                SSTable::load_from_file(sst_factory.reader(gen, IoType::Direct)?, cache, false)?;
+
+            // 无布隆过滤器时查询仍然正确，只是需要读取DataBlock
+            let (key, value) = &vec_data[233];
+            assert_eq!(reloaded.query(key)?, Some((key.clone(), value.clone())));
+            assert_eq!(reloaded.query(b"user_99999999")?, None);
+            assert_eq!(reloaded.meta()?.filter.is_some(), level <= 1);
+
+            let bloom_stats = reloaded.bloom_stats();
+            assert_eq!(bloom_stats.bits > 0, level <= 1);
+            assert!(bloom_stats.estimated_fpr < config.desired_error_prob * 2.0);
+        }
+
+        let policy = BloomPolicy::PerLevel(vec![Some(0.01), Some(0.1), None]);
+        assert_eq!(policy.error_prob(1, 0.05), Some(0.1));
+        assert_eq!(policy.error_prob(2, 0.05), None);
+        assert_eq!(policy.error_prob(3, 0.05), None);
+        assert!(policy.check().is_ok());
+        for error_prob in [0.0, 1.0, -0.1, f64::NAN] {
+            assert!(BloomPolicy::PerLevel(vec![Some(error_prob)])
+                .check()
+                .is_err());
+        }
+        assert_eq!(BloomPolicy::Uniform.error_prob(3, 0.05), Some(0.05));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ss_table_footer_only_lookup() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let config = Config::new(temp_dir.keep());
+        let sst_factory = IoFactory::new(
+            config.dir_path.join(DEFAULT_SS_TABLE_PATH),
+            FileExtension::SSTable,
+        )?;
+        let cache = Arc::new(ShardingLruCache::new(
+            config.block_cache_size,
+            16,
+            RandomState::default(),
+        )?);
+        let vec_data = (0..3000_u32)
+            .map(|i| {
+                (
+                    Bytes::from(format!("user_{i:08}")),
+                    Some(Bytes::from(format!("KipDB-{i}"))),
+                )
+            })
+            .collect_vec();
+        let ss_table = SSTable::new(
+            &sst_factory,
+            &config,
+            Arc::clone(&cache),
+            1,
+            vec_data.clone(),
+            1,
+            IoType::Direct,
+        )
+        .await?;
+        assert_eq!(
+            ss_table.footer.index_restart_interval as usize,
+            config.index_restart_interval
+        );
+
+        let reloaded =
+            SSTable::load_from_file(sst_factory.reader(1, IoType::Direct)?, cache, false)?;
+        assert_eq!(reloaded.footer, ss_table.footer);
+        assert!(reloaded.meta.get().is_none());
+
+        // 仅依靠Footer即可解析IndexBlock，无需载入MetaBlock
+        let index_block = reloaded.index_block()?;
+        assert_eq!(
+            index_block.entry_len(),
+            reloaded.footer.data_block_count as usize
+        );
+        assert!(reloaded.meta.get().is_none());
+
+        // 查询时才载入MetaBlock以使用其中的布隆过滤器
+        let (key, value) = &vec_data[1234];
+        assert_eq!(reloaded.query(key)?, Some((key.clone(), value.clone())));
+        assert!(reloaded.meta.get().is_some());
+        assert_eq!(reloaded.len(), vec_data.len());
+
+        Ok(())
+    }
+
+    struct SyncCountWriter {
+        inner: Cursor<Vec<u8>>,
+        sync_count: usize,
+        sync_all_count: usize,
+    }
+
+    impl Write for SyncCountWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.inner.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    impl Seek for SyncCountWriter {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    impl IoWriter for SyncCountWriter {
+        fn current_pos(&mut self) -> KernelResult<u64> {
+            Ok(self.inner.position())
+        }
+
+        fn sync_data(&mut self) -> KernelResult<()> {
+            self.sync_count += 1;
+            Ok(())
+        }
+
+        fn sync_all(&mut self) -> KernelResult<()> {
+            self.sync_all_count += 1;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ss_table_bytes_per_sync() -> KernelResult<()> {
+        let value =
+            Bytes::from_static(b"What you are you do not see, what you see is your shadow.");
+        let mut builder = BlockBuilder::new(BlockOptions::new().compress_type(CompressType::LZ4));
+
+        for i in 0..2333 {
+            let key = bincode::options().with_big_endian().serialize(&i)?;
+            builder.add((Bytes::from(key), Value::from(Some(value.clone()))))?;
+        }
+        let (bytes, _, _, _) = builder.build().await?;
+
+        let new_writer = || SyncCountWriter {
+            inner: Cursor::new(Vec::new()),
+            sync_count: 0,
+            sync_all_count: 0,
+        };
+        let mut writer = new_writer();
+        SSTable::write_with_sync(&mut writer, &[&bytes], 1024, false)?;
+
+        assert_eq!(writer.sync_count, (bytes.len() + 1023) / 1024);
+        assert!(writer.sync_count > 1);
+        assert_eq!(writer.sync_all_count, 0);
+        assert_eq!(writer.inner.into_inner(), bytes);
+
+        // 开启use_fsync时同步文件元数据
+        let mut writer = new_writer();
+        SSTable::write_with_sync(&mut writer, &[&bytes], 1024, true)?;
+
+        assert_eq!(writer.sync_all_count, (bytes.len() + 1023) / 1024);
+        assert_eq!(writer.sync_count, 0);
+        assert_eq!(writer.inner.into_inner(), bytes);
+
+        // 分段写入时同步的间隔不受段边界影响
+        let (head, tail) = bytes.split_at(bytes.len() / 3);
+        let (mid, tail) = tail.split_at(tail.len() / 2);
+        let segments = [head, mid, tail];
+        let mut writer = new_writer();
+        SSTable::write_with_sync(&mut writer, &segments, 1024, false)?;
+
+        assert_eq!(writer.sync_count, (bytes.len() + 1023) / 1024);
+        assert_eq!(writer.inner.into_inner(), bytes);
+
+        let mut writer = new_writer();
+        SSTable::write_with_sync(&mut writer, &segments, 0, false)?;
+
+        assert_eq!(writer.sync_count, 1);
+        assert_eq!(writer.inner.into_inner(), bytes);
+
+        // 未设置bytes_per_sync时仅在写入完成后同步一次
+        let mut writer = new_writer();
+        SSTable::write_with_sync(&mut writer, &[&bytes], 0, true)?;
+
+        assert_eq!(writer.sync_count, 0);
+        assert_eq!(writer.sync_all_count, 1);
+        assert_eq!(writer.inner.into_inner(), bytes);
+
+        let mut writer = new_writer();
+        SSTable::write_with_sync(&mut writer, &[&bytes], 0, false)?;
+
+        assert_eq!(writer.sync_count, 1);
+        assert_eq!(writer.sync_all_count, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ss_table_prefix_scan() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let value = Bytes::from(vec![b'v'; 64]);
+        // Key为`tenant_id || key`，两者均为4字节
+        let composite_key =
+            |tenant: u32, key: u32| Bytes::from([tenant.to_be_bytes(), key.to_be_bytes()].concat());
+        let vec_data = (0..8)
+            .flat_map(|tenant| (0..200).map(move |key| (tenant, key)))
+            .map(|(tenant, key)| (composite_key(tenant, key), Some(value.clone())))
+            .collect_vec();
+
+        let config = Config::new(temp_dir.keep()).prefix_extractor(PrefixExtractor::FixedLength(4));
+        let sst_factory = IoFactory::new(
+            config.dir_path.join(DEFAULT_SS_TABLE_PATH),
+            FileExtension::SSTable,
+        )?;
+        let new_cache = || -> KernelResult<Arc<BlockCache>> {
+            Ok(Arc::new(ShardingLruCache::new(
+                config.block_cache_size,
+                16,
+                RandomState::default(),
+            )?))
+        };
+        let _ = SSTable::new(
+            &sst_factory,
+            &config,
+            new_cache()?,
+            1,
+            vec_data.clone(),
+            0,
+            IoType::Direct,
+        )
+        .await?;
+
+        let cache = new_cache()?;
+        let ss_table = SSTable::load_from_file(
+            sst_factory.reader(1, IoType::Direct)?,
+            Arc::clone(&cache),
+            false,
+        )?;
+        let prefix = 3_u32.to_be_bytes();
+        let vec_kv = ss_table.prefix_scan(&prefix)?;
+        assert_eq!(
+            vec_kv,
+            vec_data
+                .iter()
+                .filter(|(key, _)| key.starts_with(&prefix))
+                .cloned()
+                .collect_vec()
+        );
+
+        // 仅读取了包含该前缀的DataBlock
+        let index_block = ss_table.index_block()?;
+        let mut read_blocks = 0;
+        for i in 0..index_block.entry_len() {
+            let index = index_block.get_entry(i).item;
+            let BlockType::Data(data_block) = ss_table.data_block(index)? else {
+                unreachable!()
+            };
+            let mut block_iter = BlockIter::new(&data_block);
+            let mut is_relevant = false;
+            while let Some((key, _)) = block_iter.try_next()? {
+                is_relevant |= key.starts_with(&prefix);
+            }
+            let is_read = cache.get(&(1, Some(index))).is_some();
+            assert_eq!(is_read, is_relevant);
+            read_blocks += usize::from(is_read);
+        }
+        assert!(read_blocks > 1);
+        assert!(read_blocks < index_block.entry_len());
+
+        // 不存在的前缀无需读取任何DataBlock
+        let cache_len = cache.len();
+        assert!(ss_table.prefix_scan(&100_u32.to_be_bytes())?.is_empty());
+        assert_eq!(cache.len(), cache_len);
+
+        // 非完整的前缀退化为顺序扫描
+        assert_eq!(ss_table.prefix_scan(&[0, 0, 0])?, vec_data);
+        assert_eq!(
+            ss_table.prefix_scan(&composite_key(5, 1)[..5])?,
+            vec_data[1000..1200]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ss_table_prefix_bloom() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let composite_key =
+            |tenant: u32, key: u32| Bytes::from([tenant.to_be_bytes(), key.to_be_bytes()].concat());
+        // 仅存在偶数的tenant
+        let vec_data = (0..8)
+            .flat_map(|tenant| (0..100).map(move |key| (tenant * 2, key)))
+            .map(|(tenant, key)| (composite_key(tenant, key), Some(Bytes::from_static(b"v"))))
+            .collect_vec();
+
+        let config = Config::new(temp_dir.keep())
+            .desired_error_prob(0.0001)
+            .prefix_bloom_len(4);
+        let sst_factory = IoFactory::new(
+            config.dir_path.join(DEFAULT_SS_TABLE_PATH),
+            FileExtension::SSTable,
+        )?;
+        let cache = Arc::new(ShardingLruCache::new(
+            config.block_cache_size,
+            16,
+            RandomState::default(),
+        )?);
+        let _ = SSTable::new(
+            &sst_factory,
+            &config,
+            Arc::clone(&cache),
+            1,
+            vec_data.clone(),
+            1,
+            IoType::Direct,
+        )
+        .await?;
         let ss_table =
-            SSTable::load_from_file(sst_factory.reader(1, IoType::Direct)?, Arc::new(cache))?;
-        for kv in vec_data.iter().take(times) {
-            assert_eq!(ss_table.query(&kv.0)?.unwrap().1, Some(value.clone()))
+            SSTable::load_from_file(sst_factory.reader(1, IoType::Direct)?, cache, false)?;
+
+        for tenant in 0..8_u32 {
+            assert!(ss_table.may_contain_prefix(&(tenant * 2).to_be_bytes())?);
+            assert!(ss_table.may_contain_prefix(&composite_key(tenant * 2, 1)[..6])?);
+        }
+        let false_positives = (0..8_u32)
+            .filter(|tenant| {
+                ss_table
+                    .may_contain_prefix(&(tenant * 2 + 1).to_be_bytes())
+                    .unwrap()
+            })
+            .count();
+        assert!(false_positives < 8);
+        // 短于前缀长度的前缀无法判断
+        assert!(ss_table.may_contain_prefix(&[0xFF, 0xFF])?);
+        assert!(ss_table.prefix_scan(&u32::MAX.to_be_bytes())?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ss_table_prefetch_index() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+
+        let config = Config::new(temp_dir.keep());
+        let sst_factory = IoFactory::new(
+            config.dir_path.join(DEFAULT_SS_TABLE_PATH),
+            FileExtension::SSTable,
+        )?;
+        let vec_data = vec![
+            (Bytes::from_static(b"1"), Some(Bytes::from_static(b"1"))),
+            (Bytes::from_static(b"2"), Some(Bytes::from_static(b"2"))),
+        ];
+        let _ = SSTable::new(
+            &sst_factory,
+            &config.clone().prefetch_index(false),
+            Arc::new(ShardingLruCache::new(
+                config.block_cache_size,
+                16,
+                RandomState::default(),
+            )?),
+            1,
+            vec_data,
+            0,
+            IoType::Direct,
+        )
+        .await?;
+
+        let lazy_cache = Arc::new(ShardingLruCache::new(
+            config.block_cache_size,
+            16,
+            RandomState::default(),
+        )?);
+        let _ = SSTable::load_from_file(
+            sst_factory.reader(1, IoType::Direct)?,
+            Arc::clone(&lazy_cache),
+            false,
+        )?;
+        assert!(lazy_cache.get(&(1, None)).is_none());
+
+        let prefetch_cache = Arc::new(ShardingLruCache::new(
+            config.block_cache_size,
+            16,
+            RandomState::default(),
+        )?);
+        let ss_table = SSTable::load_from_file(
+            sst_factory.reader(1, IoType::Direct)?,
+            Arc::clone(&prefetch_cache),
+            true,
+        )?;
+        // 预取后首次query时IndexBlock不应再出现缓存缺失
+        assert!(prefetch_cache.get(&(1, None)).is_some());
+        assert_eq!(
+            ss_table.query(b"1")?,
+            Some((Bytes::from_static(b"1"), Some(Bytes::from_static(b"1"))))
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ss_table_loading_block_into() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+
+        let config = Config::new(temp_dir.keep());
+        let sst_factory = IoFactory::new(
+            config.dir_path.join(DEFAULT_SS_TABLE_PATH),
+            FileExtension::SSTable,
+        )?;
+        // 不同长度的Value使得各个DataBlock的长度不一，空Value会被视为删除标记因此长度至少为1
+        let vec_data = (0..2000_u32)
+            .map(|i| {
+                (
+                    Bytes::from(i.to_be_bytes().to_vec()),
+                    Some(Bytes::from(vec![b'v'; (i % 97 + 1) as usize])),
+                )
+            })
+            .collect_vec();
+        let ss_table = SSTable::new(
+            &sst_factory,
+            &config,
+            Arc::new(ShardingLruCache::new(
+                config.block_cache_size,
+                16,
+                RandomState::default(),
+            )?),
+            1,
+            vec_data.clone(),
+            0,
+            IoType::Direct,
+        )
+        .await?;
+        let index_block = ss_table.index_block()?;
+        let max_len = (0..index_block.entry_len())
+            .map(|i| index_block.get_entry(i).item.len())
+            .max()
+            .unwrap();
+        assert!(index_block.entry_len() > 1);
+
+        let mut buf = Vec::new();
+        for i in 0..index_block.entry_len() {
+            let index = index_block.get_entry(i).item;
+            let (BlockType::Data(block), BlockType::Data(expected)) = (
+                ss_table.data_block_into(index, &mut buf)?,
+                ss_table.data_block(index)?,
+            ) else {
+                unreachable!()
+            };
+            assert_eq!(block, expected);
+            assert_eq!(buf.len(), index.len());
+        }
+        assert!(buf.capacity() >= max_len);
+
+        // 缓冲区扩容至最大的DataBlock长度后，再次遍历时不再重新分配
+        let ptr = buf.as_ptr();
+        for i in 0..index_block.entry_len() {
+            let _ = ss_table.data_block_into(index_block.get_entry(i).item, &mut buf)?;
+        }
+        assert_eq!(buf.as_ptr(), ptr);
+
+        let mut iter = ss_table.iter()?;
+        let mut vec_kv = Vec::with_capacity(vec_data.len());
+        while let Some(item) = iter.try_next()? {
+            vec_kv.push(item);
         }
+        assert_eq!(vec_kv, vec_data);
 
         Ok(())
     }
@@ -0,0 +1,204 @@
+use crate::kernel::io::direct::DirectIoReader;
+use crate::kernel::io::{FileExtension, IoFactory, IoOp, IoReader, IoResultExt, IoType};
+use crate::kernel::lsm::format::VALUE_LOG_CRC_VERSION;
+use crate::kernel::lsm::table::ss_table::block::ValuePointer;
+use crate::kernel::KernelResult;
+use crate::KernelError;
+use bytes::Bytes;
+use std::fs::File;
+use std::io::{self, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+/// 每个Value之后附带的CRC32校验码长度
+const VALUE_CRC_SIZE: usize = mem::size_of::<u32>();
+
+/// SSTable中分离存储的大Value所在的文件
+///
+/// 与SSTable使用相同的Gen并存放于同一目录，随SSTable一同创建与删除，
+/// 因此Compaction时其中仍存活的Value会随新的SSTable一同重写
+pub(crate) struct ValueLog {
+    dir_path: Arc<PathBuf>,
+    gen: i64,
+    // 所属SSTable的格式版本，决定Value之后是否附带校验码
+    format_version: u32,
+    // 首次读取ValuePointer时才打开，未分离Value的SSTable不会占用额外的文件句柄
+    reader: OnceLock<Arc<dyn IoReader>>,
+    // 设置时不再常驻文件句柄，而是在读取时通过IoFactory获取
+    factory: Option<Arc<IoFactory>>,
+}
+
+impl ValueLog {
+    /// `sst_path`对应的SSTable的ValueLog
+    pub(crate) fn new(sst_path: &Path, gen: i64, format_version: u32) -> Self {
+        let dir_path = sst_path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+        ValueLog {
+            dir_path: Arc::new(dir_path),
+            gen,
+            format_version,
+            reader: OnceLock::new(),
+            factory: None,
+        }
+    }
+
+    /// 不再常驻文件句柄，而是在读取时通过`sst_factory`同一目录下的ValueLog文件获取
+    ///
+    /// 与SSTable共享`IoFactory::max_open_files`的限制
+    pub(crate) fn share_reader(mut self, sst_factory: &IoFactory) -> Self {
+        self.factory = Some(Arc::new(
+            sst_factory.with_extension(FileExtension::ValueLog),
+        ));
+        self
+    }
+
+    /// 写入分离的Value时所需追加的数据：Value本身与其CRC32校验码
+    pub(crate) fn append(bytes: &mut Vec<u8>, value: &[u8]) {
+        bytes.extend_from_slice(value);
+        bytes.extend_from_slice(&crc32fast::hash(value).to_le_bytes());
+    }
+
+    pub(crate) fn path_of(sst_path: &Path) -> PathBuf {
+        sst_path.with_extension(FileExtension::ValueLog.extension_str())
+    }
+
+    fn path(&self) -> PathBuf {
+        FileExtension::ValueLog.path_with_gen(&self.dir_path, self.gen)
+    }
+
+    /// 写入所有分离的Value并同步至硬盘(use_fsync为true时为fsync，否则为fdatasync)
+    pub(crate) fn write(&self, bytes: &[u8], use_fsync: bool) -> KernelResult<()> {
+        let path = self.path();
+        let mut file = File::create(&path).with_path(IoOp::Open, &path)?;
+        file.write_all(bytes).with_path(IoOp::Write, &path)?;
+
+        if use_fsync {
+            file.sync_all()
+        } else {
+            file.sync_data()
+        }
+        .with_path(IoOp::Write, &path)
+    }
+
+    fn reader(&self) -> KernelResult<Arc<dyn IoReader>> {
+        if let Some(factory) = &self.factory {
+            return factory.shared_reader(self.gen, IoType::Direct);
+        }
+        if let Some(reader) = self.reader.get() {
+            return Ok(Arc::clone(reader));
+        }
+        // DirectIoReader会在文件不存在时创建文件，因此需先行检查
+        let path = self.path();
+        if !path.exists() {
+            return Err(KernelError::FileNotFound(path));
+        }
+        let reader: Arc<dyn IoReader> = Arc::new(DirectIoReader::new(
+            Arc::clone(&self.dir_path),
+            self.gen,
+            Arc::new(FileExtension::ValueLog),
+        )?);
+
+        Ok(Arc::clone(self.reader.get_or_init(|| reader)))
+    }
+
+    /// 读取ValuePointer所指向的Value，校验码不匹配时返回`KernelError::CrcMisMatch`
+    pub(crate) fn read(&self, pointer: &ValuePointer) -> KernelResult<Bytes> {
+        let has_crc = self.format_version >= VALUE_LOG_CRC_VERSION;
+        let crc_size = if has_crc { VALUE_CRC_SIZE } else { 0 };
+        let mut buf = vec![0; pointer.len + crc_size];
+        self.reader()?.read_exact_at(&mut buf, pointer.offset)?;
+
+        if has_crc {
+            let crc = buf.split_off(pointer.len);
+            if crc32fast::hash(&buf).to_le_bytes() != crc.as_slice() {
+                return Err(KernelError::CrcMisMatch);
+            }
+        }
+
+        Ok(Bytes::from(buf))
+    }
+
+    /// 删除`sst_path`对应的SSTable的ValueLog(若存在)
+    pub(crate) fn remove(sst_path: &Path) -> KernelResult<()> {
+        let path = Self::path_of(sst_path);
+
+        match std::fs::remove_file(&path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                Err(err).with_path(IoOp::Remove, &path)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kernel::io::{FileExtension, IoFactory};
+    use crate::kernel::lsm::format::{FORMAT_VERSION, VALUE_POINTER_VERSION};
+    use crate::kernel::lsm::table::ss_table::block::ValuePointer;
+    use crate::kernel::lsm::table::ss_table::value_log::ValueLog;
+    use crate::kernel::KernelResult;
+    use crate::KernelError;
+    use bytes::Bytes;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_value_log_checksum() -> KernelResult<()> {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+        let factory = IoFactory::new(temp_dir.path(), FileExtension::SSTable)?.max_open_files(4)?;
+        let sst_path = factory.path_with_gen(1);
+        let values = [vec![b'K'; 256], vec![b'V'; 1024]];
+
+        let mut bytes = Vec::new();
+        let mut pointers = Vec::new();
+        for value in &values {
+            pointers.push(ValuePointer {
+                gen: 1,
+                offset: bytes.len() as u64,
+                len: value.len(),
+            });
+            ValueLog::append(&mut bytes, value);
+        }
+        let value_log = ValueLog::new(&sst_path, 1, FORMAT_VERSION);
+        value_log.write(&bytes, false)?;
+
+        // 常驻文件句柄与通过IoFactory获取两种方式均可读取
+        let shared_value_log = ValueLog::new(&sst_path, 1, FORMAT_VERSION).share_reader(&factory);
+        for (pointer, value) in pointers.iter().zip(values.iter()) {
+            assert_eq!(value_log.read(pointer)?, Bytes::from(value.clone()));
+            assert_eq!(shared_value_log.read(pointer)?, Bytes::from(value.clone()));
+        }
+
+        // 损坏的Value无法通过校验
+        let path = ValueLog::path_of(&sst_path);
+        let mut corrupted = fs::read(&path)?;
+        corrupted[pointers[1].offset as usize] ^= 0xFF;
+        fs::write(&path, &corrupted)?;
+        let value_log = ValueLog::new(&sst_path, 1, FORMAT_VERSION);
+        assert_eq!(
+            value_log.read(&pointers[0])?,
+            Bytes::from(values[0].clone())
+        );
+        assert!(matches!(
+            value_log.read(&pointers[1]),
+            Err(KernelError::CrcMisMatch)
+        ));
+
+        // 旧格式的ValueLog中不带校验码
+        fs::write(&path, values.concat())?;
+        let legacy_value_log = ValueLog::new(&sst_path, 1, VALUE_POINTER_VERSION);
+        let pointer = ValuePointer {
+            gen: 1,
+            offset: values[0].len() as u64,
+            len: values[1].len(),
+        };
+        assert_eq!(
+            legacy_value_log.read(&pointer)?,
+            Bytes::from(values[1].clone())
+        );
+
+        Ok(())
+    }
+}
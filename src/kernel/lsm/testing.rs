@@ -0,0 +1,125 @@
+//! 供下游项目测试使用的工具，需开启`testing`特性
+//!
+//! 可直接构造指定的SSTable与Version布局，以便在测试中精确控制数据所在的Level；
+//! 使用时建议开启`Config::disable_auto_compaction`，避免自动压缩与手动构造的布局交错
+use crate::kernel::lsm::storage::{Gen, KipStorage};
+use crate::kernel::lsm::table::meta::TableMeta;
+use crate::kernel::lsm::table::scope::Scope;
+use crate::kernel::lsm::version::edit::VersionEdit;
+use crate::kernel::lsm::{check_sorted, MAX_LEVEL};
+use crate::kernel::KernelResult;
+use crate::KernelError;
+use bytes::Bytes;
+
+/// 以(Level, 数据)描述的Table布局，Value为None时表示删除
+pub type LevelLayout = Vec<(usize, Vec<(Bytes, Option<Bytes>)>)>;
+
+/// 以`data`在Level`level`中新建一个SSTable并写入Version，返回该Table的Gen
+///
+/// `data`需要按Key严格递增，否则返回`KernelError::UnsortedInput`，Value为None时表示删除；
+/// Level 0以外的Level中Table之间不可重叠，与已有的Table重叠时返回`KernelError::NotSupport`。
+/// 读取时较浅的Level优先，因此较深的Level中应当存放较旧的数据
+pub async fn ingest_table(
+    storage: &KipStorage,
+    level: usize,
+    data: Vec<(Bytes, Option<Bytes>)>,
+) -> KernelResult<i64> {
+    if level >= MAX_LEVEL {
+        return Err(KernelError::NotSupport("the level is out of range"));
+    }
+    check_sorted(&data)?;
+    let config = storage.inner.config();
+    let ver_status = &storage.inner.ver_status;
+    let version = ver_status.current().await;
+    let gen = Gen::create();
+
+    let level_scopes = &version.level_slice[level];
+    let data_scope = Scope::from_sorted_vec_data(gen, &data)?;
+    if level > 0 && level_scopes.iter().any(|other| other.meet(&data_scope)) {
+        return Err(KernelError::NotSupport(
+            "the table overlaps with existing tables in this level",
+        ));
+    }
+    // Level 0以外的Level中Table按Key的范围有序排列
+    let index = level_scopes
+        .iter()
+        .filter(|other| other.end < data_scope.start)
+        .count();
+    // 绕过MemTable写入的数据同样需要使未命中缓存失效
+    if let Some(negative_cache) = &storage.inner.mem_table.negative_cache {
+        for (key, _) in &data {
+            negative_cache.invalidate(key);
+        }
+    }
+    let (scope, meta) = ver_status
+        .loader()
+        .create(gen, data, level, config.level_table_type[level])
+        .await?;
+
+    ver_status
+        .log_and_apply(
+            vec![VersionEdit::NewFile((vec![scope], level), index, meta)],
+            config.ver_log_snapshot_threshold,
+        )
+        .await?;
+
+    Ok(gen)
+}
+
+/// 移除Version中所有的Table，并以`layout`中的(Level, 数据)依次新建Table，返回各Table的Gen
+///
+/// 仅替换Version中的Table，MemTable中的数据不受影响
+pub async fn force_layout(storage: &KipStorage, layout: LevelLayout) -> KernelResult<Vec<i64>> {
+    let config = storage.inner.config();
+    let ver_status = &storage.inner.ver_status;
+    let version = ver_status.current().await;
+
+    let mut vec_edit = Vec::new();
+    for (level, scopes) in version.level_slice.iter().enumerate() {
+        if scopes.is_empty() {
+            continue;
+        }
+        let gens = scopes.iter().map(|scope| scope.gen()).collect::<Vec<_>>();
+        let metas = gens
+            .iter()
+            .filter_map(|gen| ver_status.loader().get(*gen).map(TableMeta::from))
+            .collect::<Vec<_>>();
+
+        vec_edit.push(VersionEdit::DeleteFile(
+            (gens, level),
+            TableMeta::fusion(&metas),
+        ));
+    }
+    if !vec_edit.is_empty() {
+        ver_status
+            .log_and_apply(vec_edit, config.ver_log_snapshot_threshold)
+            .await?;
+    }
+
+    let mut gens = Vec::with_capacity(layout.len());
+    for (level, data) in layout {
+        gens.push(ingest_table(storage, level, data).await?);
+    }
+
+    Ok(gens)
+}
+
+/// 获取Level`level`中各Table的Gen，Level 0以外按Key的范围排列
+pub async fn level_gens(storage: &KipStorage, level: usize) -> Vec<i64> {
+    storage
+        .current_version()
+        .await
+        .level_slice
+        .get(level)
+        .map(|scopes| scopes.iter().map(|scope| scope.gen()).collect())
+        .unwrap_or_default()
+}
+
+/// 将MemTable持久化并等待Compactor处理完此前提交的所有任务
+///
+/// Compactor依次处理任务，因此返回时此前触发的Flush与压缩均已完成
+pub async fn wait_for_idle(storage: &KipStorage) -> KernelResult<()> {
+    let _ = storage.flush_with_gens().await?;
+
+    Ok(())
+}
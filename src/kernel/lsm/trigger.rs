@@ -48,7 +48,7 @@ impl Trigger for SizeOfMemTrigger {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
 pub enum TriggerType {
     Count,
     SizeOfMem,
@@ -1,7 +1,18 @@
 use crate::kernel::lsm::table::meta::TableMeta;
 use crate::kernel::lsm::table::scope::Scope;
+use crate::kernel::KernelResult;
+use bytes::Bytes;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::cmp::Ordering;
+use std::sync::atomic::AtomicU32;
+use std::sync::Arc;
+
+/// 带版本标签的VersionEdit记录前缀
+///
+/// 格式版本2之前的记录直接以bincode编码`Vec<VersionEdit>`，其前8字节为Vec的长度，
+/// 不可能为`u64::MAX`，因此可以此区分新旧两种记录
+const VERSIONED_EDITS_MAGIC: [u8; 8] = u64::MAX.to_le_bytes();
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub(crate) enum VersionEdit {
@@ -11,8 +22,86 @@ pub(crate) enum VersionEdit {
     // Level 0则请忽略第二位的index参数，默认会放至最尾
     /// ((Vec(scope), Level), Index, TableMeta)
     NewFile((Vec<Scope>, usize), usize, TableMeta),
-    // // Level and SSTable Gen List
-    // CompactPoint(usize, Vec<i64>),
+    /// (Level, 该Level上一次压缩的Table的最大Key)
+    ///
+    /// 供`CompactionPriority::RoundRobin`在重启后由此继续轮转挑选压缩的Table
+    CompactPoint(usize, Bytes),
+}
+
+/// 以版本标签区分的VersionEdit编码
+///
+/// `Scope`或`VersionEdit`增删字段时，应将当前的结构冻结为旧版本(如`VersionEditV1`)，
+/// 新增一个版本并实现由旧版本至最新版本的转换，以保证升级后仍可读取旧的Version日志
+#[derive(Serialize, Deserialize, Debug)]
+enum VersionedEdits<'a> {
+    V1(Vec<VersionEditV1>),
+    /// `Scope`记录Table的磁盘占用
+    V2(Cow<'a, [VersionEdit]>),
+}
+
+impl VersionedEdits<'_> {
+    fn into_latest(self) -> Vec<VersionEdit> {
+        match self {
+            VersionedEdits::V1(edits) => edits.into_iter().map(VersionEdit::from).collect(),
+            VersionedEdits::V2(edits) => edits.into_owned(),
+        }
+    }
+}
+
+/// 格式版本14之前不带磁盘占用的`Scope`
+#[derive(Serialize, Deserialize, Debug)]
+struct ScopeV1 {
+    start: Bytes,
+    end: Bytes,
+    gen: i64,
+    allowed_seeks: Option<Arc<AtomicU32>>,
+}
+
+/// 格式版本14之前的`VersionEdit`
+#[derive(Serialize, Deserialize, Debug)]
+enum VersionEditV1 {
+    DeleteFile((Vec<i64>, usize), TableMeta),
+    NewFile((Vec<ScopeV1>, usize), usize, TableMeta),
+    CompactPoint(usize, Bytes),
+}
+
+impl From<VersionEditV1> for VersionEdit {
+    /// 旧记录中的Scope不带磁盘占用，由`Version::apply`在载入时补全
+    fn from(edit: VersionEditV1) -> Self {
+        match edit {
+            VersionEditV1::DeleteFile(gens, meta) => VersionEdit::DeleteFile(gens, meta),
+            VersionEditV1::NewFile((scopes, level), index, meta) => {
+                let scopes = scopes
+                    .into_iter()
+                    .map(|scope| Scope::from_range(scope.gen, scope.start, scope.end))
+                    .collect();
+
+                VersionEdit::NewFile((scopes, level), index, meta)
+            }
+            VersionEditV1::CompactPoint(level, key) => VersionEdit::CompactPoint(level, key),
+        }
+    }
+}
+
+impl VersionEdit {
+    /// 以最新的版本标签编码一条Version日志记录
+    pub(crate) fn encode_vec(edits: &[VersionEdit]) -> KernelResult<Vec<u8>> {
+        let mut bytes = VERSIONED_EDITS_MAGIC.to_vec();
+        bincode::serialize_into(&mut bytes, &VersionedEdits::V2(Cow::Borrowed(edits)))?;
+
+        Ok(bytes)
+    }
+
+    /// 解码一条Version日志记录，兼容不带版本标签的旧记录
+    pub(crate) fn decode_vec(bytes: &[u8]) -> KernelResult<Vec<VersionEdit>> {
+        Ok(match bytes.strip_prefix(&VERSIONED_EDITS_MAGIC) {
+            Some(bytes) => bincode::deserialize::<VersionedEdits>(bytes)?.into_latest(),
+            None => bincode::deserialize::<Vec<VersionEditV1>>(bytes)?
+                .into_iter()
+                .map(VersionEdit::from)
+                .collect(),
+        })
+    }
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -41,3 +130,96 @@ impl PartialOrd for EditType {
         Some(self.cmp(other))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::kernel::lsm::table::meta::TableMeta;
+    use crate::kernel::lsm::table::scope::Scope;
+    use crate::kernel::lsm::version::edit::{
+        ScopeV1, VersionEdit, VersionEditV1, VersionedEdits, VERSIONED_EDITS_MAGIC,
+    };
+    use crate::kernel::KernelResult;
+    use bytes::Bytes;
+
+    const META: TableMeta = TableMeta {
+        size_of_disk: 1024,
+        len: 8,
+    };
+
+    fn edits() -> Vec<VersionEdit> {
+        vec![
+            VersionEdit::NewFile(
+                (
+                    vec![
+                        Scope::from_range(1, Bytes::from_static(b"a"), Bytes::from_static(b"z"))
+                            .with_size_of_disk(1024),
+                    ],
+                    0,
+                ),
+                0,
+                META,
+            ),
+            VersionEdit::DeleteFile((vec![2, 3], 1), META),
+            VersionEdit::CompactPoint(1, Bytes::from_static(b"m")),
+        ]
+    }
+
+    fn edits_v1() -> Vec<VersionEditV1> {
+        vec![
+            VersionEditV1::NewFile(
+                (
+                    vec![ScopeV1 {
+                        start: Bytes::from_static(b"a"),
+                        end: Bytes::from_static(b"z"),
+                        gen: 1,
+                        allowed_seeks: None,
+                    }],
+                    0,
+                ),
+                0,
+                META,
+            ),
+            VersionEditV1::DeleteFile((vec![2, 3], 1), META),
+        ]
+    }
+
+    #[test]
+    fn test_versioned_edits() -> KernelResult<()> {
+        let edits = edits();
+
+        let bytes = VersionEdit::encode_vec(&edits)?;
+        assert!(bytes.starts_with(&VERSIONED_EDITS_MAGIC));
+        let decoded = VersionEdit::decode_vec(&bytes)?;
+        assert_eq!(decoded, edits);
+        assert!(matches!(
+            &decoded[0],
+            VersionEdit::NewFile((scopes, 0), 0, _) if scopes[0].size_of_disk == 1024
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_versioned_edits_v1() -> KernelResult<()> {
+        let assert_v1 = |edits: Vec<VersionEdit>| {
+            assert!(matches!(
+                &edits[..],
+                [
+                    VersionEdit::NewFile((scopes, 0), 0, META),
+                    VersionEdit::DeleteFile((gens, 1), META),
+                ] if scopes[0].gen() == 1 && scopes[0].size_of_disk == 0 && gens == &[2, 3]
+            ));
+        };
+
+        // 带版本标签V1的记录中的Scope不带磁盘占用
+        let mut bytes = VERSIONED_EDITS_MAGIC.to_vec();
+        bincode::serialize_into(&mut bytes, &VersionedEdits::V1(edits_v1()))?;
+        assert_v1(VersionEdit::decode_vec(&bytes)?);
+
+        // 不带版本标签的旧记录仍可读取
+        let legacy_bytes = bincode::serialize(&edits_v1())?;
+        assert_v1(VersionEdit::decode_vec(&legacy_bytes)?);
+
+        Ok(())
+    }
+}
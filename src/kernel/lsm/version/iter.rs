@@ -1,3 +1,4 @@
+use crate::kernel::lsm::compactor::LEVEL_0;
 use crate::kernel::lsm::iterator::level_iter::LevelIter;
 use crate::kernel::lsm::iterator::merging_iter::SeekMergingIter;
 use crate::kernel::lsm::iterator::{Iter, Seek, SeekIter};
@@ -5,6 +6,7 @@ use crate::kernel::lsm::mem_table::KeyValue;
 use crate::kernel::lsm::version::Version;
 use crate::kernel::lsm::MAX_LEVEL;
 use crate::kernel::KernelResult;
+use std::collections::Bound;
 
 /// Version键值对迭代器
 pub struct VersionIter<'a> {
@@ -14,22 +16,31 @@ pub struct VersionIter<'a> {
 impl<'a> VersionIter<'a> {
     pub(crate) fn new(version: &'a Version) -> KernelResult<VersionIter<'a>> {
         let mut vec_iter = Vec::new();
-        Self::merging_with_version(version, &mut vec_iter)?;
+        Self::merging_with_version(version, Bound::Unbounded, Bound::Unbounded, &mut vec_iter)?;
 
         Ok(Self {
             merge_iter: SeekMergingIter::new(vec_iter)?,
         })
     }
 
+    /// 收集Version中各Level的迭代器，越新的数据越靠前
+    ///
+    /// Level 0中仅收集与范围`(min, max)`相交的Table
     pub(crate) fn merging_with_version(
         version: &'a Version,
+        min: Bound<&[u8]>,
+        max: Bound<&[u8]>,
         iter_vec: &mut Vec<Box<dyn SeekIter<'a, Item = KeyValue> + 'a + Send + Sync>>,
     ) -> KernelResult<()> {
-        for table in version.tables_by_level_0() {
+        for table in version
+            .get_tables_for_range(LEVEL_0, min, max)
+            .into_iter()
+            .rev()
+        {
             iter_vec.push(table.iter()?);
         }
 
-        for level in 1..MAX_LEVEL - 1 {
+        for level in 1..MAX_LEVEL {
             if let Ok(level_iter) = LevelIter::new(version, level) {
                 iter_vec.push(Box::new(level_iter));
             }
@@ -1,5 +1,5 @@
 use crate::kernel::io::{FileExtension, IoFactory};
-use crate::kernel::lsm::compactor::{SeekScope, LEVEL_0};
+use crate::kernel::lsm::compactor::{CompactionPriority, CompactionStrategy, SeekScope, LEVEL_0};
 use crate::kernel::lsm::mem_table::KeyValue;
 use crate::kernel::lsm::storage::{Config, Gen};
 use crate::kernel::lsm::table::loader::TableLoader;
@@ -9,10 +9,14 @@ use crate::kernel::lsm::table::Table;
 use crate::kernel::lsm::version::cleaner::CleanTag;
 use crate::kernel::lsm::version::edit::{EditType, VersionEdit};
 use crate::kernel::lsm::version::meta::VersionMeta;
+use crate::kernel::lsm::version::summary::{LevelSummaries, LevelSummary};
 use crate::kernel::lsm::MAX_LEVEL;
 use crate::kernel::{sorted_gen_list, KernelResult};
+use bytes::Bytes;
 use itertools::Itertools;
+use std::collections::{Bound, HashSet};
 use std::fmt;
+use std::mem;
 use std::sync::Arc;
 use tokio::sync::mpsc::UnboundedSender;
 use tracing::info;
@@ -22,6 +26,7 @@ pub(crate) mod edit;
 pub(crate) mod iter;
 mod meta;
 pub(crate) mod status;
+pub(crate) mod summary;
 #[cfg(test)]
 mod test;
 
@@ -61,8 +66,12 @@ pub(crate) struct Version {
     /// 以索引0为level-0这样的递推，存储文件的gen值
     /// 每个Version各持有各自的Gen矩阵
     pub(crate) level_slice: LevelSlice,
+    /// 各Level上一次压缩的Table的最大Key，由`VersionEdit::CompactPoint`记录
+    pub(crate) compact_points: [Option<Bytes>; MAX_LEVEL],
     /// 统计数据
     pub(crate) meta_data: VersionMeta,
+    /// 各Level的布隆过滤器摘要，见`Config::level_summary_bits`
+    level_summaries: LevelSummaries,
     /// 清除信号发送器
     /// Drop时通知Cleaner进行删除
     clean_tx: UnboundedSender<CleanTag>,
@@ -85,6 +94,24 @@ impl Version {
         self.meta_data.size_of_disk
     }
 
+    /// 指定Level中所有Table占有的磁盘大小
+    pub(crate) fn level_size(&self, level: usize) -> u64 {
+        self.level_slice[level]
+            .iter()
+            .map(|scope| scope.size_of_disk)
+            .sum()
+    }
+
+    /// 估算等待Major压缩的数据大小
+    ///
+    /// 即Table数量达到压缩阈值的各Level的大小之和，最后一层不会被压缩因此不计入
+    pub(crate) fn pending_compaction_bytes(&self, config: &Config) -> u64 {
+        (0..MAX_LEVEL - 1)
+            .filter(|level| self.is_threshold_exceeded_major(config, *level))
+            .map(|level| self.level_size(level))
+            .sum()
+    }
+
     /// 通过一组VersionEdit载入Version
     pub(crate) fn load_from_log(
         vec_log: Vec<VersionEdit>,
@@ -95,10 +122,12 @@ impl Version {
             version_num: 0,
             table_loader: Arc::clone(ss_table_loader),
             level_slice: Self::level_slice_new(),
+            compact_points: Default::default(),
             meta_data: VersionMeta {
                 size_of_disk: 0,
                 len: 0,
             },
+            level_summaries: Default::default(),
             clean_tx,
         };
 
@@ -118,21 +147,39 @@ impl Version {
     pub(crate) fn apply(&mut self, vec_version_edit: Vec<VersionEdit>) -> KernelResult<()> {
         let mut del_gens = Vec::new();
         let mut vec_statistics_sst_meta = Vec::new();
+        let mut changed_levels = [false; MAX_LEVEL];
+        let mut new_gens: [Vec<i64>; MAX_LEVEL] = Default::default();
 
         for version_edit in vec_version_edit {
             match version_edit {
                 VersionEdit::DeleteFile((mut vec_gen, level), sst_meta) => {
                     vec_statistics_sst_meta.push(EditType::Del(sst_meta));
+                    changed_levels[level] = true;
 
                     self.level_slice[level].retain(|scope| !vec_gen.contains(&scope.gen()));
                     del_gens.append(&mut vec_gen);
                 }
                 VersionEdit::NewFile((vec_scope, level), index, sst_meta) => {
                     vec_statistics_sst_meta.push(EditType::Add(sst_meta));
+                    changed_levels[level] = true;
+                    new_gens[level].extend(vec_scope.iter().map(Scope::gen));
 
                     // Level 0中的Table绝对是以gen为优先级
                     // Level N中则不以gen为顺序，此处对gen排序是因为单次NewFile中的gen肯定是有序的
-                    let scope_iter = vec_scope.into_iter().sorted_by_key(Scope::gen);
+                    // 旧版本的Version日志中的Scope未记录磁盘占用，仅在此处由Table补全一次
+                    let scope_iter = vec_scope
+                        .into_iter()
+                        .map(|scope| match scope.size_of_disk {
+                            0 => {
+                                let size_of_disk = self
+                                    .table_loader
+                                    .get(scope.gen())
+                                    .map_or(0, |table| table.size_of_disk());
+                                scope.with_size_of_disk(size_of_disk)
+                            }
+                            _ => scope,
+                        })
+                        .sorted_by_key(Scope::gen);
                     if level == LEVEL_0 {
                         for scope in scope_iter {
                             self.level_slice[level].push(scope);
@@ -143,9 +190,21 @@ impl Version {
                         }
                     }
                 }
+                VersionEdit::CompactPoint(level, key) => {
+                    self.compact_points[level] = Some(key);
+                }
             }
         }
 
+        for level in (0..MAX_LEVEL).filter(|level| changed_levels[*level]) {
+            self.level_summaries[level] = LevelSummary::update(
+                self.level_summaries[level].as_deref(),
+                &self.level_slice[level],
+                &new_gens[level],
+                &self.table_loader,
+            )
+            .map(Arc::new);
+        }
         self.meta_data
             .statistical_process(vec_statistics_sst_meta)?;
         self.version_num += 1;
@@ -162,28 +221,36 @@ impl Version {
     }
 
     /// 把当前version的leveSlice中的数据转化为一组versionEdit 作为新version_log的base
+    ///
+    /// 统计数据仅附加于第一个NewFile中，避免Level 0为空时统计数据丢失；各Level的压缩点附加于其后
     pub(crate) fn to_vec_edit(&self) -> Vec<VersionEdit> {
-        fn sst_meta_by_level(level: usize, size_of_disk: u64, len: usize) -> TableMeta {
-            if LEVEL_0 == level {
-                TableMeta { size_of_disk, len }
-            } else {
-                TableMeta::default()
-            }
-        }
+        let mut is_meta_attached = false;
+        let compact_points = self
+            .compact_points
+            .iter()
+            .enumerate()
+            .filter_map(|(level, key)| {
+                key.as_ref()
+                    .map(|key| VersionEdit::CompactPoint(level, key.clone()))
+            });
 
-        #[allow(clippy::filter_map_bool_then)]
         self.level_slice
             .iter()
             .enumerate()
-            .filter_map(|(level, vec_scope)| {
-                (!vec_scope.is_empty()).then(|| {
-                    VersionEdit::NewFile(
-                        (vec_scope.clone(), level),
-                        0,
-                        sst_meta_by_level(level, self.size_of_disk(), self.len()),
-                    )
-                })
+            .filter(|(_, vec_scope)| !vec_scope.is_empty())
+            .map(|(level, vec_scope)| {
+                let meta = if mem::replace(&mut is_meta_attached, true) {
+                    TableMeta::default()
+                } else {
+                    TableMeta {
+                        size_of_disk: self.size_of_disk(),
+                        len: self.len(),
+                    }
+                };
+
+                VersionEdit::NewFile((vec_scope.clone(), level), 0, meta)
             })
+            .chain(compact_points)
             .collect_vec()
     }
 
@@ -193,15 +260,36 @@ impl Version {
             .and_then(|scope| self.table_loader.get(scope.gen()))
     }
 
-    /// 只限定获取Level 0的Table
+    /// 读取各Table中以`prefix`开头的键值对，越新的数据越靠前
     ///
-    /// Tips：不鼓励全量获取其他Level
-    pub(crate) fn tables_by_level_0(&self) -> Vec<&dyn Table> {
-        self.level_slice[LEVEL_0]
+    /// 仅读取Key范围可能包含该前缀的Table，各Level除Level 0外的结果合并为一组
+    pub(crate) fn prefix_scan(&self, prefix: &[u8]) -> KernelResult<Vec<Vec<KeyValue>>> {
+        let mut vec_level_kv = Vec::new();
+
+        for scope in self.level_slice[LEVEL_0]
             .iter()
-            .filter_map(|scope| self.table_loader.get(scope.gen()))
             .rev()
-            .collect_vec()
+            .filter(|scope| scope.may_contain_prefix(prefix))
+        {
+            if let Some(table) = self.table_loader.get(scope.gen()) {
+                vec_level_kv.push(table.prefix_scan(prefix)?);
+            }
+        }
+        for level in 1..MAX_LEVEL {
+            let mut vec_kv = Vec::new();
+
+            for scope in self.level_slice[level]
+                .iter()
+                .filter(|scope| scope.may_contain_prefix(prefix))
+            {
+                if let Some(table) = self.table_loader.get(scope.gen()) {
+                    vec_kv.append(&mut table.prefix_scan(prefix)?);
+                }
+            }
+            vec_level_kv.push(vec_kv);
+        }
+
+        Ok(vec_level_kv)
     }
 
     /// 获取指定level中与scope冲突的Tables和Scopes
@@ -226,14 +314,189 @@ impl Version {
             })
             .unzip();
 
-        (tables, scopes, first_index.unwrap_or(0))
+        // 不存在相交的Table时，返回按Key顺序插入`target_scope`的位置
+        let index = first_index.unwrap_or_else(|| {
+            self.level_slice[level].partition_point(|scope| scope.end < target_scope.start)
+        });
+
+        (tables, scopes, index)
+    }
+
+    /// 依据`priority`挑选`level`中优先向下一级压缩的Table的Scope
+    ///
+    /// 为`CompactionPriority::Cascade`或该Level为空时返回None
+    pub(crate) fn pick_compaction_scope(
+        &self,
+        level: usize,
+        priority: CompactionPriority,
+    ) -> Option<Scope> {
+        let mut scopes = self.level_slice[level].iter();
+
+        match priority {
+            CompactionPriority::Cascade => None,
+            CompactionPriority::LargestFirst => scopes.max_by_key(|scope| self.table_size(scope)),
+            CompactionPriority::OldestSmallestSeqFirst => scopes.min_by_key(|scope| scope.gen()),
+            CompactionPriority::MinOverlappingRatio => {
+                scopes.min_by_key(|scope| self.overlapping_ratio(level, scope))
+            }
+            CompactionPriority::RoundRobin => {
+                // 由上次压缩的最大Key之后的Table开始，越过末尾时回到该Level的首个Table
+                let compact_point = self.compact_points[level].as_ref();
+
+                scopes
+                    .find(|scope| compact_point.map_or(true, |key| scope.start > *key))
+                    .or_else(|| self.level_slice[level].first())
+            }
+            CompactionPriority::TombstoneDensity => {
+                scopes.max_by_key(|scope| self.tombstone_score(level, scope))
+            }
+        }
+        .cloned()
+    }
+
+    /// 多个Level同时超出阈值时，依据`priority`排列`levels`，越靠前越优先压缩
+    ///
+    /// 以各Level中`pick_compaction_scope`所挑选的Table的指标进行比较；
+    /// `CompactionPriority::Cascade`与`CompactionPriority::RoundRobin`由上至下逐级压缩
+    pub(crate) fn sort_levels_by_priority(
+        &self,
+        levels: &mut [usize],
+        priority: CompactionPriority,
+    ) {
+        levels.sort_by_cached_key(|level| {
+            let score = self
+                .pick_compaction_scope(*level, priority)
+                .map_or(u128::MAX, |scope| match priority {
+                    CompactionPriority::Cascade | CompactionPriority::RoundRobin => 0,
+                    CompactionPriority::LargestFirst => {
+                        u128::MAX - u128::from(self.table_size(&scope))
+                    }
+                    CompactionPriority::OldestSmallestSeqFirst => scope.gen() as u128,
+                    CompactionPriority::MinOverlappingRatio => {
+                        self.overlapping_ratio(*level, &scope)
+                    }
+                    CompactionPriority::TombstoneDensity => {
+                        u128::MAX - self.tombstone_score(*level, &scope) as u128
+                    }
+                });
+
+            (score, *level)
+        });
+    }
+
+    fn table_size(&self, scope: &Scope) -> u64 {
+        self.table_loader
+            .get(scope.gen())
+            .map_or(0, |table| table.size_of_disk())
+    }
+
+    /// 下一级中与该Table重叠的数据量相对其自身大小的比值
+    fn overlapping_ratio(&self, level: usize, scope: &Scope) -> u128 {
+        let overlapping_size: u64 = self
+            .tables_by_scopes(level + 1, scope)
+            .0
+            .iter()
+            .map(|table| table.size_of_disk())
+            .sum();
+        // 放大后以整数比较，避免浮点数无法全序比较
+        u128::from(overlapping_size) * 1024 / u128::from(self.table_size(scope).max(1))
+    }
+
+    /// 综合删除标记密度与下一级中重叠的Table数量的评分，越大越优先
+    fn tombstone_score(&self, level: usize, scope: &Scope) -> usize {
+        let Some(table) = self.table_loader.get(scope.gen()) else {
+            return 0;
+        };
+        let overlapping_count = self.tables_by_scopes(level + 1, scope).0.len();
+        // 删除标记密度放大后加一，使不含删除标记时退化为优先压缩重叠最少的Table
+        let density = table.tombstone_count() * 1024 / table.len().max(1) + 1;
+
+        density * 1024 / (overlapping_count + 1)
+    }
+
+    /// 依据`max_bytes`截取`level`中参与此次压缩的Table，返回的bool表示是否有Table被截去
+    ///
+    /// Level 0的Table间范围可能重叠，因此按Gen由旧至新截取，使留下的Table均比被压缩的Table新；
+    /// 其余Level按Key顺序截取。输入大小包括截取的Table及下一级与其合并范围重叠的Table，
+    /// 且至少保留一个Table以保证压缩能够推进；`max_bytes`为0时不作限制
+    pub(crate) fn truncate_compaction_input<'a>(
+        &'a self,
+        level: usize,
+        tables: Vec<&'a dyn Table>,
+        scopes: Vec<Scope>,
+        max_bytes: u64,
+    ) -> (Vec<&'a dyn Table>, Vec<Scope>, bool) {
+        if max_bytes == 0 || tables.len() < 2 {
+            return (tables, scopes, false);
+        }
+        let (mut tables, mut scopes): (Vec<&dyn Table>, Vec<Scope>) = if level == LEVEL_0 {
+            tables
+                .into_iter()
+                .zip(scopes)
+                .sorted_by_key(|(_, scope)| scope.gen())
+                .unzip()
+        } else {
+            (tables, scopes)
+        };
+        let input_bytes = |len: usize| -> u64 {
+            let overlapping_bytes: u64 = Scope::fusion(&scopes[..len]).map_or(0, |fusion| {
+                self.tables_by_scopes(level + 1, &fusion)
+                    .0
+                    .iter()
+                    .map(|table| table.size_of_disk())
+                    .sum()
+            });
+            tables[..len]
+                .iter()
+                .map(|table| table.size_of_disk())
+                .sum::<u64>()
+                + overlapping_bytes
+        };
+        let len = (2..=tables.len())
+            .take_while(|len| input_bytes(*len) <= max_bytes)
+            .last()
+            .unwrap_or(1);
+        let is_truncated = len < tables.len();
+
+        tables.truncate(len);
+        scopes.truncate(len);
+
+        (tables, scopes, is_truncated)
+    }
+
+    /// 获取指定level中与范围`(start, end)`相交的Tables
+    pub(crate) fn get_tables_for_range(
+        &self,
+        level: usize,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+    ) -> Vec<&dyn Table> {
+        self.level_slice[level]
+            .iter()
+            .filter(|scope| scope.meet_with_range(start, end))
+            .filter_map(|scope| self.table_loader.get(scope.gen()))
+            .collect_vec()
     }
 
     /// 使用Key从现有Tables中获取对应的数据
     pub(crate) fn query(&self, key: &[u8]) -> KernelResult<(Option<KeyValue>, Option<SeekScope>)> {
         let table_loader = &self.table_loader;
+        // 摘要判定Key不存在时跳过其所覆盖的Table
+        let skippable = |level: usize| {
+            self.level_summaries[level]
+                .as_ref()
+                .and_then(|summary| summary.skippable(key))
+        };
+        let is_skipped = |skippable: Option<&HashSet<i64>>, scope: &Scope| {
+            skippable.is_some_and(|gens| gens.contains(&scope.gen()))
+        };
         // Level 0的Table是无序且Table间的数据是可能重复的,因此需要遍历
-        for scope in self.level_slice[LEVEL_0].iter().rev() {
+        let level_0_skippable = skippable(LEVEL_0);
+        for scope in self.level_slice[LEVEL_0]
+            .iter()
+            .rev()
+            .filter(|scope| !is_skipped(level_0_skippable, scope))
+        {
             if let SeekOption::Hit(key_value) =
                 Self::query_by_scope(key, table_loader, scope, LEVEL_0)?
             {
@@ -246,7 +509,10 @@ impl Version {
         for level in 1..MAX_LEVEL {
             let offset = self.query_meet_index(key, level);
 
-            if let Some(scope) = self.level_slice[level].get(offset) {
+            if let Some(scope) = self.level_slice[level]
+                .get(offset)
+                .filter(|scope| !is_skipped(skippable(level), scope))
+            {
                 match Self::query_by_scope(key, table_loader, scope, level)? {
                     SeekOption::Hit(value) => return Ok((Some(value), miss_seek)),
                     SeekOption::Miss(Some(seek_scope)) => {
@@ -286,10 +552,54 @@ impl Version {
     }
 
     /// 判断是否溢出指定的Table数量
+    /// Level 0设置了`Config::level0_file_num_compaction_trigger`时以其作为阈值
     pub(crate) fn is_threshold_exceeded_major(&self, config: &Config, level: usize) -> bool {
-        self.level_slice[level].len()
-            >= (config.major_threshold_with_sst_size
-                * config.level_sst_magnification.pow(level as u32))
+        let threshold = match config.compaction_strategy {
+            _ if level == LEVEL_0 => config.level_0_compaction_threshold(),
+            CompactionStrategy::Tiered { min_merge_width } => {
+                return self.tiered_run(level, min_merge_width).is_some();
+            }
+            CompactionStrategy::Leveled => {
+                config.major_threshold_with_sst_size
+                    * config.level_sst_magnification.pow(level as u32)
+            }
+        };
+
+        self.level_slice[level].len() >= threshold
+    }
+
+    /// 挑选`level`中磁盘占用处于同一2的幂次区间且数量不少于`min_merge_width`的一组Table
+    ///
+    /// 存在多组时优先挑选磁盘占用最小的一组，使小Table尽早合并；返回的Table保持Key顺序
+    pub(crate) fn pick_tiered_run(
+        &self,
+        level: usize,
+        min_merge_width: usize,
+    ) -> Option<(Vec<&dyn Table>, Vec<Scope>)> {
+        Some(
+            self.tiered_run(level, min_merge_width)?
+                .into_iter()
+                .filter_map(|scope| {
+                    self.table_loader
+                        .get(scope.gen())
+                        .map(|table| (table, scope.clone()))
+                })
+                .unzip(),
+        )
+    }
+
+    /// 以Scope中记录的磁盘占用挑选`pick_tiered_run`的一组Table，无需载入Table
+    fn tiered_run(&self, level: usize, min_merge_width: usize) -> Option<Vec<&Scope>> {
+        let size_class = |scope: &Scope| u64::BITS - scope.size_of_disk.leading_zeros();
+
+        self.level_slice[level]
+            .iter()
+            .map(|scope| (size_class(scope), scope))
+            .into_group_map()
+            .into_iter()
+            .filter(|(_, run)| run.len() >= min_merge_width)
+            .min_by_key(|(class, _)| *class)
+            .map(|(_, run)| run)
     }
 }
 
@@ -1,19 +1,23 @@
 use crate::kernel::io::{FileExtension, IoFactory, IoType, IoWriter};
+use crate::kernel::lsm::compactor::LEVEL_0;
 use crate::kernel::lsm::log::{LogLoader, LogWriter};
 use crate::kernel::lsm::storage::{Config, Gen};
 use crate::kernel::lsm::table::loader::TableLoader;
+use crate::kernel::lsm::table::scope::Scope;
 use crate::kernel::lsm::version::cleaner::Cleaner;
 use crate::kernel::lsm::version::edit::VersionEdit;
 use crate::kernel::lsm::version::{
     snapshot_gen, Version, DEFAULT_SS_TABLE_PATH, DEFAULT_VERSION_PATH,
 };
 use crate::kernel::KernelResult;
+use std::collections::HashSet;
 use std::mem;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use tokio::sync::futures::Notified;
 use tokio::sync::mpsc::unbounded_channel;
-use tokio::sync::RwLock;
-use tracing::info;
+use tokio::sync::{Notify, RwLock};
+use tracing::{info, warn};
 
 /// 用于切换Version的封装Inner
 struct VersionInner {
@@ -26,12 +30,19 @@ pub(crate) struct VersionStatus {
     ss_table_loader: Arc<TableLoader>,
     log_factory: Arc<IoFactory>,
     edit_approximate_count: AtomicUsize,
+    /// 当前Version中Level 0的Table数量，供写入时无需获取Version便可判断是否需要延迟
+    level_0_len: AtomicUsize,
+    /// 新的Version生效时唤醒等待Level 0回落的写入
+    version_notify: Notify,
 }
 
 impl VersionStatus {
     pub(crate) fn load_with_path(config: Config, wal: LogLoader) -> KernelResult<Self> {
         let sst_path = config.path().join(DEFAULT_SS_TABLE_PATH);
-        let sst_factory = Arc::new(IoFactory::new(sst_path, FileExtension::SSTable)?);
+        let sst_factory = Arc::new(
+            IoFactory::new(sst_path, FileExtension::SSTable)?
+                .max_open_files(config.max_open_files)?,
+        );
         let ss_table_loader = Arc::new(TableLoader::new(
             config.clone(),
             Arc::clone(&sst_factory),
@@ -48,7 +59,7 @@ impl VersionStatus {
             IoType::Direct,
             &mut version_logs,
             |bytes, records| {
-                records.append(&mut bincode::deserialize::<Vec<VersionEdit>>(bytes)?);
+                records.append(&mut VersionEdit::decode_vec(bytes)?);
 
                 Ok(())
             },
@@ -60,6 +71,10 @@ impl VersionStatus {
             &ss_table_loader,
             clean_tx,
         )?);
+        if let Some(max_gen) = version.level_slice.iter().flatten().map(Scope::gen).max() {
+            Gen::advance(max_gen);
+        }
+        Self::clean_orphans(&config, &ss_table_loader, &version)?;
         let mut cleaner = Cleaner::new(&ss_table_loader, clean_rx);
 
         let _ignore = tokio::spawn(async move {
@@ -68,6 +83,7 @@ impl VersionStatus {
 
         let mut ver_log_writer = ver_log_loader.writer(log_gen)?;
         let _ = ver_log_writer.seek_end()?;
+        let level_0_len = AtomicUsize::new(version.level_len(LEVEL_0));
 
         Ok(Self {
             inner: RwLock::new(VersionInner {
@@ -77,13 +93,65 @@ impl VersionStatus {
             ss_table_loader,
             log_factory,
             edit_approximate_count,
+            level_0_len,
+            version_notify: Notify::new(),
         })
     }
 
+    /// 清理磁盘中未被Version所引用的Table
+    ///
+    /// Major压缩在新Table写入后、VersionEdit持久化前中断时，这些新Table不会被任何Version引用而一直残留；
+    /// `Config::keep_orphans`为true时仅记录而不删除。
+    /// Version未引用任何Table时视为Version日志已丢失，此时不做清理以免删除全部数据
+    fn clean_orphans(config: &Config, loader: &TableLoader, version: &Version) -> KernelResult<()> {
+        let live_gens: HashSet<i64> = version
+            .level_slice
+            .iter()
+            .flatten()
+            .map(Scope::gen)
+            .collect();
+        let orphan_gens = loader
+            .disk_gens()?
+            .into_iter()
+            .filter(|gen| !live_gens.contains(gen))
+            .collect::<Vec<_>>();
+
+        if orphan_gens.is_empty() {
+            return Ok(());
+        }
+        if config.keep_orphans || live_gens.is_empty() {
+            warn!("[Version Status][orphan tables][keep]: {:?}", orphan_gens);
+            return Ok(());
+        }
+        info!("[Version Status][orphan tables][clean]: {:?}", orphan_gens);
+        for gen in orphan_gens {
+            loader.discard(gen)?;
+        }
+
+        Ok(())
+    }
+
     pub(crate) async fn current(&self) -> Arc<Version> {
         Arc::clone(&self.inner.read().await.version)
     }
 
+    /// 当前Version中Level 0的Table数量
+    pub(crate) fn level_0_len(&self) -> usize {
+        self.level_0_len.load(Ordering::Acquire)
+    }
+
+    /// 等待下一个Version生效或`notify_waiters`被调用
+    ///
+    /// 需在检查`level_0_len`之前获取，避免在两者之间生效的Version错过唤醒
+    pub(crate) fn notified(&self) -> Notified<'_> {
+        self.version_notify.notified()
+    }
+
+    /// 唤醒所有通过`notified`等待的写入
+    pub(crate) fn notify_waiters(&self) {
+        self.version_notify.notify_waiters();
+    }
+
     /// 对一组VersionEdit持久化并应用
     pub(crate) async fn log_and_apply(
         &self,
@@ -103,10 +171,16 @@ impl VersionStatus {
         let _ = inner
             .ver_log_writer
             .0
-            .add_record(&bincode::serialize(&vec_version_edit)?)?;
+            .add_record(&VersionEdit::encode_vec(&vec_version_edit)?)?;
+        // Direct IO的写入会缓存不足一个对齐块的末尾，需flush使每次变更都写入文件
+        inner.ver_log_writer.0.flush()?;
 
         new_version.apply(vec_version_edit)?;
+        self.level_0_len
+            .store(new_version.level_len(LEVEL_0), Ordering::Release);
         inner.version = Arc::new(new_version);
+        drop(inner);
+        self.notify_waiters();
 
         Ok(())
     }
@@ -134,7 +208,8 @@ impl VersionStatus {
         let _ = inner
             .ver_log_writer
             .0
-            .add_record(&bincode::serialize(&snap_shot_version_edits)?)?;
+            .add_record(&VersionEdit::encode_vec(&snap_shot_version_edits)?)?;
+        inner.ver_log_writer.0.flush()?;
 
         // 删除旧的 version log
         log_factory.clean(old_gen)?;
@@ -0,0 +1,116 @@
+use crate::kernel::lsm::mem_table::KeyValue;
+use crate::kernel::lsm::table::loader::TableLoader;
+use crate::kernel::lsm::table::scope::Scope;
+use crate::kernel::lsm::MAX_LEVEL;
+use crate::kernel::utils::bloom_filter::BloomFilter;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// 摘要过滤器所使用的固定哈希种子，使各Table的摘要可合并为Level的摘要
+const SUMMARY_SEEDS: [u64; 2] = [0x4b69_7044_425f_5331, 0x4b69_7044_425f_5332];
+
+/// 单个Table的摘要过滤器，由TableLoader在Table写入时生成
+pub(crate) type TableSummary = Arc<BloomFilter<[u8]>>;
+
+/// 以相同参数为一个Table的所有Key构建摘要过滤器
+pub(crate) fn table_summary(
+    bits_count: usize,
+    err_rate: f64,
+    vec_data: &[KeyValue],
+) -> BloomFilter<[u8]> {
+    let mut filter = BloomFilter::with_seeds(bits_count, err_rate, SUMMARY_SEEDS);
+
+    for (key, _) in vec_data {
+        filter.insert(key.as_ref());
+    }
+    filter
+}
+
+/// 一个Level的布隆过滤器摘要，即该Level中各Table摘要的并集
+///
+/// 摘要判定Key不存在时可直接跳过其所覆盖的所有Table，而无需逐个载入Table的过滤器；
+/// 摘要仅保存于内存中而不会持久化，重启前写入的Table不被覆盖，仍需逐个查询
+pub(crate) struct LevelSummary {
+    filter: BloomFilter<[u8]>,
+    covered: HashSet<i64>,
+    /// 已被删除但其Key仍残留于`filter`中的Table数量
+    stale: usize,
+}
+
+impl LevelSummary {
+    /// 合并`scopes`中各Table的摘要，均不存在摘要时返回None
+    pub(crate) fn build(scopes: &[Scope], loader: &TableLoader) -> Option<Self> {
+        let mut summary: Option<LevelSummary> = None;
+
+        for scope in scopes {
+            let Some(table_summary) = loader.summary(scope.gen()) else {
+                continue;
+            };
+            match &mut summary {
+                Some(summary) => {
+                    if summary.filter.union(&table_summary) {
+                        let _ = summary.covered.insert(scope.gen());
+                    }
+                }
+                None => {
+                    summary = Some(LevelSummary {
+                        filter: BloomFilter::clone(&table_summary),
+                        covered: HashSet::from([scope.gen()]),
+                        stale: 0,
+                    });
+                }
+            }
+        }
+
+        summary
+    }
+
+    /// 依据Level中Table的变化由`prev`得出新的摘要，`new_gens`为新增的Table
+    ///
+    /// 新增Table的摘要直接并入原有的摘要；布隆过滤器无法移除元素，被删除的Table的Key仍残留于摘要中，
+    /// 仅会增加误判而不影响正确性，因此残留的Table多于所覆盖的Table时才以现存的Table重新合并
+    pub(crate) fn update(
+        prev: Option<&LevelSummary>,
+        scopes: &[Scope],
+        new_gens: &[i64],
+        loader: &TableLoader,
+    ) -> Option<Self> {
+        let Some(prev) = prev else {
+            return Self::build(scopes, loader);
+        };
+        let gens = scopes.iter().map(Scope::gen).collect::<HashSet<_>>();
+        let covered = prev
+            .covered
+            .iter()
+            .filter(|gen| gens.contains(gen))
+            .copied()
+            .collect::<HashSet<_>>();
+        let stale = prev.stale + prev.covered.len() - covered.len();
+        if stale > covered.len() {
+            return Self::build(scopes, loader);
+        }
+        let mut summary = LevelSummary {
+            filter: prev.filter.clone(),
+            covered,
+            stale,
+        };
+
+        for gen in new_gens.iter().filter(|gen| gens.contains(gen)) {
+            if let Some(table_summary) = loader.summary(*gen) {
+                if summary.filter.union(&table_summary) {
+                    let _ = summary.covered.insert(*gen);
+                }
+            }
+        }
+
+        Some(summary)
+    }
+
+    /// 查询`key`时可跳过的Table，即摘要判定不存在时其所覆盖的Table
+    pub(crate) fn skippable(&self, key: &[u8]) -> Option<&HashSet<i64>> {
+        (!self.filter.contains(key)).then_some(&self.covered)
+    }
+}
+
+/// 各Level的摘要，Version间共享未发生变化的Level的摘要
+pub(crate) type LevelSummaries = [Option<Arc<LevelSummary>>; MAX_LEVEL];
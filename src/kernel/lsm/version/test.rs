@@ -7,7 +7,9 @@ use crate::kernel::lsm::version::status::VersionStatus;
 use crate::kernel::lsm::version::Version;
 use crate::kernel::lsm::version::DEFAULT_VERSION_PATH;
 use crate::kernel::KernelResult;
+use crate::KernelError;
 use bytes::Bytes;
+use std::collections::{Bound, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 use tempfile::TempDir;
@@ -18,7 +20,7 @@ fn test_version_clean() -> KernelResult<()> {
     let temp_dir = TempDir::new().expect("unable to create temporary working directory");
 
     tokio_test::block_on(async move {
-        let config = Config::new(temp_dir.into_path());
+        let config = Config::new(temp_dir.keep());
 
         let (wal, _) = LogLoader::reload(
             config.path(),
@@ -80,7 +82,7 @@ fn test_version_clean() -> KernelResult<()> {
             IoType::Direct,
             &mut snapshot,
             |bytes, records| {
-                records.append(&mut bincode::deserialize::<Vec<VersionEdit>>(bytes)?);
+                records.append(&mut VersionEdit::decode_vec(bytes)?);
 
                 Ok(())
             },
@@ -123,7 +125,7 @@ fn test_version_apply_and_log() -> KernelResult<()> {
     let temp_dir = TempDir::new().expect("unable to create temporary working directory");
 
     tokio_test::block_on(async move {
-        let config = Config::new(temp_dir.into_path());
+        let config = Config::new(temp_dir.keep());
 
         let (wal, _) = LogLoader::reload(
             config.path(),
@@ -205,3 +207,240 @@ fn test_version_apply_and_log() -> KernelResult<()> {
         Ok(())
     })
 }
+
+#[test]
+fn test_version_get_tables_for_range() -> KernelResult<()> {
+    let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+
+    tokio_test::block_on(async move {
+        let config = Config::new(temp_dir.keep());
+
+        let (wal, _) = LogLoader::reload(
+            config.path(),
+            (DEFAULT_VERSION_PATH, Some(1)),
+            IoType::Direct,
+            &mut vec![0],
+            |_, _| Ok(()),
+        )?;
+        let ver_status = VersionStatus::load_with_path(config, wal)?;
+
+        let mut vec_edit = Vec::new();
+        for (index, (start, end)) in [(b"a", b"c"), (b"d", b"f"), (b"g", b"i")]
+            .into_iter()
+            .enumerate()
+        {
+            let gen = index as i64 + 1;
+            let (scope, meta) = ver_status
+                .loader()
+                .create(
+                    gen,
+                    vec![
+                        (Bytes::from_static(start), None),
+                        (Bytes::from_static(end), None),
+                    ],
+                    1,
+                    TableType::SortedString,
+                )
+                .await?;
+            vec_edit.push(VersionEdit::NewFile((vec![scope], 1), index, meta));
+        }
+        ver_status.log_and_apply(vec_edit, 10).await?;
+
+        let version = ver_status.current().await;
+        let gens_for_range = |start: Bound<&[u8]>, end: Bound<&[u8]>| {
+            version
+                .get_tables_for_range(1, start, end)
+                .into_iter()
+                .map(|table| table.gen())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            gens_for_range(Bound::Unbounded, Bound::Unbounded),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            gens_for_range(Bound::Included(&b"b"[..]), Bound::Excluded(&b"g"[..])),
+            vec![1, 2]
+        );
+        assert_eq!(
+            gens_for_range(Bound::Excluded(&b"c"[..]), Bound::Included(&b"g"[..])),
+            vec![2, 3]
+        );
+        assert_eq!(
+            gens_for_range(Bound::Included(&b"e"[..]), Bound::Included(&b"e"[..])),
+            vec![2]
+        );
+        assert!(gens_for_range(Bound::Excluded(&b"i"[..]), Bound::Unbounded).is_empty());
+        assert!(gens_for_range(Bound::Included(&b"e"[..]), Bound::Excluded(&b"e"[..])).is_empty());
+        assert!(version
+            .get_tables_for_range(0, Bound::Unbounded, Bound::Unbounded)
+            .is_empty());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn test_version_query_stops_at_tombstone() -> KernelResult<()> {
+    let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+
+    tokio_test::block_on(async move {
+        let config = Config::new(temp_dir.keep());
+
+        let (wal, _) = LogLoader::reload(
+            config.path(),
+            (DEFAULT_VERSION_PATH, Some(1)),
+            IoType::Direct,
+            &mut vec![0],
+            |_, _| Ok(()),
+        )?;
+        let ver_status = VersionStatus::load_with_path(config, wal)?;
+        let key = Bytes::from_static(b"k");
+
+        // Level 3中的旧数据被Level 0中的删除标记覆盖
+        let mut vec_edit = Vec::new();
+        for (gen, level, value) in [(1, 3, Some(Bytes::from_static(b"v"))), (2, 0, None)] {
+            let (scope, meta) = ver_status
+                .loader()
+                .create(
+                    gen,
+                    vec![(key.clone(), value)],
+                    level,
+                    TableType::SortedString,
+                )
+                .await?;
+            vec_edit.push(VersionEdit::NewFile((vec![scope], level), 0, meta));
+        }
+        ver_status.log_and_apply(vec_edit, 10).await?;
+
+        let version = ver_status.current().await;
+        assert_eq!(version.query(&key)?.0, Some((key, None)));
+
+        Ok(())
+    })
+}
+
+#[test]
+fn test_version_level_summary_update() -> KernelResult<()> {
+    let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+
+    tokio_test::block_on(async move {
+        let config = Config::new(temp_dir.keep()).level_summary_bits(1 << 12);
+
+        let (wal, _) = LogLoader::reload(
+            config.path(),
+            (DEFAULT_VERSION_PATH, Some(1)),
+            IoType::Direct,
+            &mut vec![0],
+            |_, _| Ok(()),
+        )?;
+        let ver_status = VersionStatus::load_with_path(config, wal)?;
+        let mut metas = Vec::new();
+        for (gen, key) in [(1, b"a"), (2, b"b"), (3, b"c")] {
+            let (scope, meta) = ver_status
+                .loader()
+                .create(
+                    gen,
+                    vec![(Bytes::from_static(key), None)],
+                    0,
+                    TableType::SortedString,
+                )
+                .await?;
+            metas.push((scope, meta));
+        }
+        let new_file = |index: usize| {
+            let (scope, meta) = metas[index].clone();
+            VersionEdit::NewFile((vec![scope], 0), 0, meta)
+        };
+        let delete_file = |index: usize| {
+            let (scope, meta) = metas[index].clone();
+            VersionEdit::DeleteFile((vec![scope.gen()], 0), meta)
+        };
+        let skippable = |version: &Version, key: &[u8]| {
+            version.level_summaries[0]
+                .as_ref()
+                .and_then(|summary| summary.skippable(key).cloned())
+        };
+
+        // 新增的Table并入原有的摘要
+        ver_status.log_and_apply(vec![new_file(0)], 10).await?;
+        ver_status.log_and_apply(vec![new_file(1)], 10).await?;
+        let version = ver_status.current().await;
+        assert_eq!(skippable(&version, b"z"), Some(HashSet::from([1, 2])));
+        assert_eq!(skippable(&version, b"a"), None);
+
+        // 被删除的Table不再被跳过，但其Key仍残留于摘要中
+        ver_status.log_and_apply(vec![delete_file(0)], 10).await?;
+        let version = ver_status.current().await;
+        assert_eq!(skippable(&version, b"z"), Some(HashSet::from([2])));
+        assert_eq!(skippable(&version, b"a"), None);
+
+        // 残留的Table多于所覆盖的Table时重新合并，残留的Key随之清除
+        ver_status
+            .log_and_apply(vec![new_file(2), delete_file(1)], 10)
+            .await?;
+        let version = ver_status.current().await;
+        assert_eq!(skippable(&version, b"z"), Some(HashSet::from([3])));
+        assert_eq!(skippable(&version, b"a"), Some(HashSet::from([3])));
+        assert_eq!(skippable(&version, b"c"), None);
+
+        Ok(())
+    })
+}
+
+#[test]
+fn test_version_clean_orphans() -> KernelResult<()> {
+    for keep_orphans in [false, true] {
+        let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+
+        tokio_test::block_on(async move {
+            let config = Config::new(temp_dir.keep()).keep_orphans(keep_orphans);
+
+            let (wal, _) = LogLoader::reload(
+                config.path(),
+                (DEFAULT_VERSION_PATH, Some(1)),
+                IoType::Direct,
+                &mut vec![0],
+                |_, _| Ok(()),
+            )?;
+            let ver_status_1 = VersionStatus::load_with_path(config.clone(), wal.clone())?;
+
+            let (scope_1, meta_1) = ver_status_1
+                .loader()
+                .create(
+                    1,
+                    vec![(Bytes::from_static(b"test1"), None)],
+                    1,
+                    TableType::SortedString,
+                )
+                .await?;
+            ver_status_1
+                .log_and_apply(
+                    vec![VersionEdit::NewFile((vec![scope_1], 1), 0, meta_1)],
+                    10,
+                )
+                .await?;
+            // 模拟压缩在写入新Table后、持久化VersionEdit前中断
+            let _ = ver_status_1
+                .loader()
+                .create(
+                    2,
+                    vec![(Bytes::from_static(b"test2"), None)],
+                    1,
+                    TableType::SortedString,
+                )
+                .await?;
+            drop(ver_status_1);
+
+            let ver_status_2 = VersionStatus::load_with_path(config, wal)?;
+
+            assert!(ver_status_2.loader().is_table_file_exist(1)?);
+            assert_eq!(ver_status_2.loader().is_table_file_exist(2)?, keep_orphans);
+
+            Ok::<(), KernelError>(())
+        })?;
+    }
+
+    Ok(())
+}
@@ -4,6 +4,7 @@ use itertools::Itertools;
 use rand::random;
 use std::hash::{DefaultHasher, Hash, Hasher};
 use std::marker::PhantomData;
+use std::mem;
 use std::slice;
 
 // https://rust-algo.club/collections/bloom_filter/
@@ -15,6 +16,18 @@ pub struct BloomFilter<T: ?Sized> {
     _phantom: PhantomData<T>,
 }
 
+// 手动实现以避免derive为`T`添加`Clone`约束，使`BloomFilter<[u8]>`同样可被克隆
+impl<T: ?Sized> Clone for BloomFilter<T> {
+    fn clone(&self) -> Self {
+        Self {
+            bits: self.bits.clone(),
+            hash_fn_count: self.hash_fn_count,
+            hashers: self.hashers,
+            _phantom: PhantomData,
+        }
+    }
+}
+
 impl<T: ?Sized> BloomFilter<T> {
     pub fn new(len: usize, err_rate: f64) -> Self {
         let bits_count = Self::optimal_bits_count(len, err_rate);
@@ -29,6 +42,33 @@ impl<T: ?Sized> BloomFilter<T> {
         }
     }
 
+    /// 以固定的位数与哈希种子创建过滤器，参数相同的过滤器可通过`union`合并
+    pub fn with_seeds(bits_count: usize, err_rate: f64, seeds: [u64; 2]) -> Self {
+        Self {
+            bits: BitVector::new(bits_count),
+            hash_fn_count: Self::optimal_hashers_count(err_rate),
+            hashers: seeds.map(FixedHasher::new),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// 将`other`中已置位的位合并至自身，合并后包含两者的元素
+    ///
+    /// 两者的位数、哈希函数数量或种子不同时无法合并，返回false且不做修改
+    pub fn union(&mut self, other: &Self) -> bool {
+        if self.bits.len != other.bits.len
+            || self.hash_fn_count != other.hash_fn_count
+            || self.hashers != other.hashers
+        {
+            return false;
+        }
+        for (bits, other_bits) in self.bits.bit_groups.iter_mut().zip(&other.bits.bit_groups) {
+            *bits |= *other_bits;
+        }
+
+        true
+    }
+
     pub fn insert(&mut self, elem: &T)
     where
         T: Hash,
@@ -41,6 +81,35 @@ impl<T: ?Sized> BloomFilter<T> {
         }
     }
 
+    /// 过滤器的位数
+    pub fn bits_count(&self) -> usize {
+        self.bits.len()
+    }
+
+    pub fn hash_fn_count(&self) -> u64 {
+        self.hash_fn_count
+    }
+
+    /// 依据已置位的比例估算的误判率: (置位数 / 位数) ^ k
+    pub fn estimated_fpr(&self) -> f64 {
+        if self.bits.is_empty() {
+            return 0.0;
+        }
+        let ones: u32 = self
+            .bits
+            .bit_groups
+            .iter()
+            .map(|bits| bits.count_ones())
+            .sum();
+
+        (ones as f64 / self.bits.len() as f64).powi(self.hash_fn_count as i32)
+    }
+
+    /// 过滤器占用的内存大小
+    pub fn memory_usage(&self) -> usize {
+        mem::size_of::<Self>() + self.bits.bit_groups.capacity()
+    }
+
     pub fn contains(&self, elem: &T) -> bool
     where
         T: Hash,
@@ -106,7 +175,7 @@ impl<T: ?Sized> BloomFilter<T> {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct BitVector {
     len: u64,
     bit_groups: Vec<i8>,
@@ -162,7 +231,7 @@ impl BitVector {
     }
 }
 
-#[derive(Debug, Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
 pub struct FixedHasher {
     seed: u64,
 }
@@ -259,6 +328,24 @@ mod tests {
         assert!(!bf.contains(&2));
     }
 
+    #[test]
+    fn bloom_filter_union() {
+        let mut bf_1 = BloomFilter::with_seeds(1024, 0.01, [1, 2]);
+        let mut bf_2 = BloomFilter::with_seeds(1024, 0.01, [1, 2]);
+
+        bf_1.insert(&1);
+        bf_2.insert(&2);
+        assert!(bf_1.union(&bf_2));
+        assert!(bf_1.contains(&1));
+        assert!(bf_1.contains(&2));
+        assert!(!bf_1.contains(&3));
+
+        // 参数不同的过滤器无法合并
+        assert!(!bf_1.union(&BloomFilter::with_seeds(1024, 0.01, [2, 1])));
+        assert!(!bf_1.union(&BloomFilter::with_seeds(2048, 0.01, [1, 2])));
+        assert!(!bf_1.union(&BloomFilter::new(100, 0.01)));
+    }
+
     #[test]
     fn bloom_filter_fpr_test() {
         let cnt = 500000;
@@ -9,6 +9,7 @@ use std::hash::{BuildHasher, Hash, Hasher};
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 use std::ptr::NonNull;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
 use std::sync::Arc;
 
 // 只读Node操作裸指针
@@ -47,6 +48,10 @@ unsafe impl<K: Sync, V: Sync, S: Sync> Sync for ShardingLruCache<K, V, S> {}
 pub struct ShardingLruCache<K, V, S = RandomState> {
     sharding_vec: Vec<Arc<Mutex<LruCache<K, V>>>>,
     hasher: S,
+    /// 命中计数
+    hits: AtomicU64,
+    /// 未命中计数
+    misses: AtomicU64,
 }
 
 struct Node<K, V> {
@@ -99,6 +104,8 @@ pub struct LruCache<K, V> {
     tail: Option<NodeReadPtr<K, V>>,
     inner: HashMap<KeyRef<K, V>, NodeReadPtr<K, V>>,
     cap: usize,
+    /// 驱逐计数
+    evictions: u64,
     marker: PhantomData<Node<K, V>>,
 }
 
@@ -128,15 +135,21 @@ impl<K: Hash + Eq + PartialEq, V, S: BuildHasher> ShardingLruCache<K, V, S> {
         Ok(ShardingLruCache {
             sharding_vec,
             hasher,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
         })
     }
 
     #[inline]
     pub fn get(&self, key: &K) -> Option<&V> {
-        self.shard(key)
+        let option = self
+            .shard(key)
             .lock()
             .get_node(key)
-            .map(|node| unsafe { &node.as_ref().value })
+            .map(|node| unsafe { &node.as_ref().value });
+        self.record(option.is_some());
+
+        option
     }
 
     #[inline]
@@ -149,6 +162,38 @@ impl<K: Hash + Eq + PartialEq, V, S: BuildHasher> ShardingLruCache<K, V, S> {
         self.shard(key).lock().remove(key)
     }
 
+    /// 当前缓存的元素数量
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.sharding_vec.iter().map(|lru| lru.lock().len()).sum()
+    }
+
+    /// 通过fn_weight计算所有缓存元素的权重之和
+    #[inline]
+    pub fn weight<F>(&self, fn_weight: F) -> usize
+    where
+        F: Fn(&K, &V) -> usize,
+    {
+        self.sharding_vec
+            .iter()
+            .map(|lru| {
+                lru.lock()
+                    .iter()
+                    .map(|(key, value)| fn_weight(key, value))
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+
+    /// 因容量不足而被驱逐的元素数量
+    #[inline]
+    pub fn evictions(&self) -> u64 {
+        self.sharding_vec
+            .iter()
+            .map(|lru| lru.lock().evictions())
+            .sum()
+    }
+
     #[inline]
     pub fn is_empty(&self) -> bool {
         for lru in &self.sharding_vec {
@@ -164,10 +209,50 @@ impl<K: Hash + Eq + PartialEq, V, S: BuildHasher> ShardingLruCache<K, V, S> {
     where
         F: FnOnce(&K) -> KernelResult<V>,
     {
-        self.shard(&key)
+        let mut is_hit = true;
+        let result = self
+            .shard(&key)
             .lock()
-            .get_or_insert_node(key, fn_once)
-            .map(|node| unsafe { &node.as_ref().value })
+            .get_or_insert_node(key, |key| {
+                is_hit = false;
+                fn_once(key)
+            })
+            .map(|node| unsafe { &node.as_ref().value });
+        self.record(is_hit);
+
+        result
+    }
+
+    /// 缓存命中率
+    ///
+    /// 未发生过查询时返回0
+    #[inline]
+    pub fn hit_rate(&self) -> f64 {
+        let hits = self.hits.load(AtomicOrdering::Relaxed);
+        let total = hits + self.misses.load(AtomicOrdering::Relaxed);
+
+        if total == 0 {
+            0.0
+        } else {
+            hits as f64 / total as f64
+        }
+    }
+
+    /// 命中次数
+    #[inline]
+    pub fn hits(&self) -> u64 {
+        self.hits.load(AtomicOrdering::Relaxed)
+    }
+
+    /// 未命中次数
+    #[inline]
+    pub fn misses(&self) -> u64 {
+        self.misses.load(AtomicOrdering::Relaxed)
+    }
+
+    fn record(&self, is_hit: bool) {
+        let counter = if is_hit { &self.hits } else { &self.misses };
+        let _ = counter.fetch_add(1, AtomicOrdering::Relaxed);
     }
 
     fn sharding_size(&self) -> usize {
@@ -195,6 +280,7 @@ impl<K: Hash + Eq + PartialEq, V> LruCache<K, V> {
             tail: None,
             inner: HashMap::new(),
             cap,
+            evictions: 0,
             marker: PhantomData,
         })
     }
@@ -252,6 +338,7 @@ impl<K: Hash + Eq + PartialEq, V> LruCache<K, V> {
             if self.inner.len() >= self.cap {
                 self.detach(tail);
                 let _ignore = self.inner.remove(&KeyRef(tail));
+                self.evictions += 1;
             }
         }
     }
@@ -339,11 +426,36 @@ impl<K: Hash + Eq + PartialEq, V> LruCache<K, V> {
             .map(|node| unsafe { &node.as_ref().value })
     }
 
+    /// 移除并返回最久未使用的元素
+    ///
+    /// 与容量不足时的驱逐不同，被移除的元素会被释放
+    #[inline]
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        self.tail.map(|tail| {
+            self.detach(tail);
+            let _ignore = self.inner.remove(&KeyRef(tail));
+            let node: Box<Node<K, V>> = unsafe { Box::from_raw(tail.as_ptr()) };
+            (node.key, node.value)
+        })
+    }
+
+    /// 元素数量是否已达到容量，此时插入新的元素会驱逐最久未使用的元素
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.inner.len() >= self.cap
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.inner.len()
     }
 
+    /// 因容量不足而被驱逐的元素数量
+    #[inline]
+    pub fn evictions(&self) -> u64 {
+        self.evictions
+    }
+
     #[inline]
     pub fn is_empty(&self) -> bool {
         self.inner.is_empty()
@@ -406,6 +518,7 @@ mod tests {
         assert_eq!(lru.get_or_insert(9, |_| Ok(9)).unwrap(), &9);
 
         assert_eq!(lru.len(), 3);
+        assert_eq!(lru.evictions(), 2);
         assert!(!lru.is_empty());
 
         let mut set = HashSet::from([(&9, &9), (&2, &200), (&4, &40)]);
@@ -423,5 +536,8 @@ mod tests {
         assert_eq!(lru.get(&1), Some(&10));
         assert!(!lru.is_empty());
         assert_eq!(lru.get_or_insert(9, |_| Ok(9)).unwrap(), &9);
+        assert_eq!(lru.get_or_insert(9, |_| Ok(9)).unwrap(), &9);
+        assert_eq!(lru.hit_rate(), 2.0 / 3.0);
+        assert_eq!(lru.weight(|_, value| *value), 19);
     }
 }
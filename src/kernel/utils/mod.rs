@@ -1,2 +1,3 @@
 pub mod bloom_filter;
 pub mod lru_cache;
+pub mod rate_limiter;
@@ -0,0 +1,111 @@
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// 令牌桶限速器
+///
+/// 以每秒`bytes_per_sec`个令牌的速率补充令牌，桶的容量为一秒的令牌数；
+/// 令牌不足时允许预支，由预支者等待至令牌补足，因此并发的请求者会依次排队而不会同时越过限速；
+/// 速率可在运行时修改，并于下一次获取令牌时生效
+pub(crate) struct RateLimiter {
+    /// 每秒补充的令牌数，为0时不作限制
+    bytes_per_sec: AtomicU64,
+    /// 当前可用的令牌数(可能为负数，即已被预支)与上次补充令牌的时刻
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(bytes_per_sec: u64) -> Self {
+        RateLimiter {
+            bytes_per_sec: AtomicU64::new(bytes_per_sec),
+            bucket: Mutex::new((bytes_per_sec as f64, Instant::now())),
+        }
+    }
+
+    pub(crate) fn bytes_per_sec(&self) -> u64 {
+        self.bytes_per_sec.load(Ordering::Relaxed)
+    }
+
+    /// 修改补充令牌的速率，桶中超出新容量的令牌将在下一次获取令牌时被舍弃
+    ///
+    /// 由不限速改为限速时与新建时一致，桶中存有一秒的令牌
+    pub(crate) fn set_bytes_per_sec(&self, bytes_per_sec: u64) {
+        if self.bytes_per_sec.swap(bytes_per_sec, Ordering::Relaxed) == 0 {
+            *self.bucket.lock() = (bytes_per_sec as f64, Instant::now());
+        }
+    }
+
+    /// 获取`bytes`个令牌，令牌不足时异步等待至令牌补足
+    pub(crate) async fn request(&self, bytes: u64) {
+        if let Some(wait) = self.acquire(bytes) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// 扣除`bytes`个令牌，返回需要等待的时长
+    fn acquire(&self, bytes: u64) -> Option<Duration> {
+        let bytes_per_sec = self.bytes_per_sec();
+        if bytes_per_sec == 0 || bytes == 0 {
+            return None;
+        }
+        let rate = bytes_per_sec as f64;
+        let mut bucket = self.bucket.lock();
+        let (tokens, last_refill) = &mut *bucket;
+        let now = Instant::now();
+
+        *tokens = (*tokens + now.duration_since(*last_refill).as_secs_f64() * rate).min(rate);
+        *last_refill = now;
+        *tokens -= bytes as f64;
+
+        (*tokens < 0.0).then(|| Duration::from_secs_f64(-*tokens / rate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kernel::utils::rate_limiter::RateLimiter;
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn test_rate_limiter() {
+        let limiter = RateLimiter::new(100 * 1024);
+
+        // 桶中初始时存有一秒的令牌
+        let start = Instant::now();
+        limiter.request(100 * 1024).await;
+        assert!(start.elapsed() < Duration::from_millis(100));
+
+        // 令牌耗尽后需等待补充
+        let start = Instant::now();
+        limiter.request(50 * 1024).await;
+        assert!(start.elapsed() >= Duration::from_millis(400));
+
+        // 预支令牌后，后续的请求需一同等待预支的部分
+        assert!(limiter.acquire(100 * 1024).unwrap() >= Duration::from_millis(900));
+        assert!(limiter.acquire(100 * 1024).unwrap() >= Duration::from_millis(1900));
+    }
+
+    #[test]
+    fn test_rate_limiter_unlimited() {
+        let limiter = RateLimiter::new(0);
+
+        assert!(limiter.acquire(u64::MAX).is_none());
+    }
+
+    #[test]
+    fn test_rate_limiter_set_bytes_per_sec() {
+        let limiter = RateLimiter::new(0);
+
+        // 由不限速改为限速时桶中存有一秒的令牌
+        limiter.set_bytes_per_sec(100 * 1024);
+        assert!(limiter.acquire(100 * 1024).is_none());
+        assert!(limiter.acquire(50 * 1024).unwrap() >= Duration::from_millis(400));
+
+        // 降低速率后按新的速率计算等待时长
+        limiter.set_bytes_per_sec(10 * 1024);
+        assert!(limiter.acquire(10 * 1024).unwrap() >= Duration::from_secs(5));
+
+        limiter.set_bytes_per_sec(0);
+        assert!(limiter.acquire(u64::MAX).is_none());
+    }
+}
@@ -16,6 +16,9 @@ pub mod server;
 
 pub use error::KernelError;
 
+#[cfg(feature = "testing")]
+pub use kernel::lsm::testing;
+
 pub const DEFAULT_PORT: u16 = 6333;
 
 pub const LOCAL_IP: &str = "127.0.0.1";
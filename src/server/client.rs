@@ -1,8 +1,13 @@
 use crate::error::ConnectionError;
+use crate::kernel::lsm::stats::KipStats;
 use crate::proto::kipdb_rpc_client::KipdbRpcClient;
 use crate::proto::{
-    BatchGetReq, BatchRemoveReq, BatchSetReq, Empty, GetReq, Kv, RemoveReq, SetReq,
+    BatchGetReq, BatchRemoveReq, BatchSetReq, CloseIterReq, Empty, GetReq, Kv, NextReq,
+    OpenIterReq, RemoveReq, SetReq,
 };
+use crate::server::compression::Compression;
+use crate::KernelError;
+use std::collections::VecDeque;
 use tonic::transport::Channel;
 
 pub type ConnectionResult<T> = Result<T, ConnectionError>;
@@ -12,12 +17,72 @@ type KV = (Key, Value);
 
 pub struct KipdbClient {
     conn: KipdbRpcClient<Channel>,
+    compression: Option<Compression>,
 }
 
 impl KipdbClient {
     pub async fn connect(addr: String) -> ConnectionResult<Self> {
         let conn = KipdbRpcClient::connect(addr).await?;
-        Ok(Self { conn })
+        Ok(Self {
+            conn,
+            compression: None,
+        })
+    }
+
+    /// 通过Unix domain socket连接至同一主机上的服务端
+    #[cfg(unix)]
+    pub async fn connect_unix(path: impl Into<std::path::PathBuf>) -> ConnectionResult<Self> {
+        use std::sync::Arc;
+        use tokio::net::UnixStream;
+        use tonic::transport::{Endpoint, Uri};
+        use tower::service_fn;
+
+        let path = Arc::new(path.into());
+        // 该地址仅用于满足Endpoint的格式，实际连接由connector通过socket建立
+        let channel = Endpoint::try_from("http://[::]:6333")?
+            .connect_with_connector(service_fn(move |_: Uri| {
+                let path = Arc::clone(&path);
+                async move { UnixStream::connect(path.as_ref()).await }
+            }))
+            .await?;
+
+        Ok(Self::with_channel(channel))
+    }
+
+    /// 基于已建立的Channel创建客户端，便于自定义连接方式
+    #[inline]
+    pub fn with_channel(channel: Channel) -> Self {
+        Self {
+            conn: KipdbRpcClient::new(channel),
+            compression: None,
+        }
+    }
+
+    /// 开启响应中Value的压缩
+    ///
+    /// 压缩算法随每个请求声明给服务端，仅长度不小于`COMPRESSION_THRESHOLD`的Value会被压缩，
+    /// 适用于批量读取大Value等响应较大的场景
+    #[inline]
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// 构建请求并附带所协商的压缩算法
+    fn request<T>(&self, message: T) -> tonic::Request<T> {
+        let mut req = tonic::Request::new(message);
+        if let Some(compression) = &self.compression {
+            compression.insert_header(req.metadata_mut());
+        }
+        req
+    }
+
+    /// 还原经服务端压缩的Value，未开启压缩时原样返回
+    fn decode_value(&self, value: Value) -> ConnectionResult<Value> {
+        match &self.compression {
+            Some(compression) => compression.decode(value),
+            None => Ok(value),
+        }
     }
 
     #[inline]
@@ -44,9 +109,12 @@ impl KipdbClient {
 
     #[inline]
     pub async fn get(&mut self, key: Key) -> ConnectionResult<Option<Value>> {
-        let req = tonic::Request::new(GetReq { key });
+        let req = self.request(GetReq { key });
         let resp = self.conn.get(req).await?;
-        Ok(resp.into_inner().value)
+        resp.into_inner()
+            .value
+            .map(|value| self.decode_value(value))
+            .transpose()
     }
 
     #[inline]
@@ -75,9 +143,13 @@ impl KipdbClient {
 
     #[inline]
     pub async fn batch_get(&mut self, keys: Vec<Key>) -> ConnectionResult<Vec<Value>> {
-        let req = tonic::Request::new(BatchGetReq { keys });
+        let req = self.request(BatchGetReq { keys });
         let resp = self.conn.batch_get(req).await?;
-        Ok(resp.into_inner().values)
+        resp.into_inner()
+            .values
+            .into_iter()
+            .map(|value| self.decode_value(value))
+            .collect()
     }
 
     #[inline]
@@ -104,4 +176,76 @@ impl KipdbClient {
         let resp = self.conn.len(req).await?;
         Ok(resp.into_inner().len as usize)
     }
+
+    #[inline]
+    pub async fn stats(&mut self) -> ConnectionResult<KipStats> {
+        let req = tonic::Request::new(Empty {});
+        let resp = self.conn.stats(req).await?;
+        Ok(bincode::deserialize(&resp.into_inner().stats).map_err(KernelError::from)?)
+    }
+
+    /// 通过服务端游标以每批`batch`个的方式扫描`[start, end)`内的键值对，为None时表示该侧无界
+    ///
+    /// 服务端会将`batch`限制于其所允许的批次上限之内；
+    /// 游标固定了打开时的数据快照，之后的写入不会被扫描到；
+    /// 未遍历完便不再使用时需调用`RemoteIter::close`，否则服务端将在游标闲置超时后才将其释放
+    #[inline]
+    pub async fn iter(
+        &mut self,
+        start: Option<Key>,
+        end: Option<Key>,
+        batch: u32,
+    ) -> ConnectionResult<RemoteIter<'_>> {
+        let req = tonic::Request::new(OpenIterReq { start, end });
+        let id = self.conn.open_iter(req).await?.into_inner().id;
+
+        Ok(RemoteIter {
+            client: self,
+            id,
+            batch,
+            buf: VecDeque::new(),
+            is_done: false,
+        })
+    }
+}
+
+/// 服务端游标的客户端迭代器，按批次向服务端拉取键值对
+pub struct RemoteIter<'a> {
+    client: &'a mut KipdbClient,
+    id: u64,
+    batch: u32,
+    buf: VecDeque<KV>,
+    is_done: bool,
+}
+
+impl RemoteIter<'_> {
+    #[inline]
+    pub async fn try_next(&mut self) -> ConnectionResult<Option<KV>> {
+        if self.buf.is_empty() && !self.is_done {
+            let req = self.client.request(NextReq {
+                id: self.id,
+                batch: self.batch,
+            });
+            let resp = self.client.conn.next(req).await?.into_inner();
+
+            for kv in resp.kvs {
+                self.buf
+                    .push_back((kv.key, self.client.decode_value(kv.value)?));
+            }
+            self.is_done = resp.done;
+        }
+
+        Ok(self.buf.pop_front())
+    }
+
+    /// 释放服务端游标，已遍历完毕的游标已由服务端释放
+    #[inline]
+    pub async fn close(self) -> ConnectionResult<()> {
+        if !self.is_done {
+            let req = tonic::Request::new(CloseIterReq { id: self.id });
+            let _ = self.client.conn.close_iter(req).await?;
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,80 @@
+use crate::error::ConnectionError;
+use crate::kernel::lsm::CompressType;
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::Status;
+
+/// 客户端声明所接受的Value压缩算法的请求头，未携带时服务端不进行压缩
+const COMPRESSION_HEADER: &str = "kipdb-compression";
+
+/// 小于该长度的Value保持原样传输，其压缩收益不足以抵消压缩的开销
+pub const COMPRESSION_THRESHOLD: usize = 512;
+
+/// 经协商后Value的首个字节为该标记，表示其余字节未被压缩
+const RAW_TAG: u8 = 0;
+/// 经协商后Value的首个字节为该标记，表示其余字节以协商的算法压缩
+const COMPRESSED_TAG: u8 = 1;
+
+/// 响应中Value的压缩算法，由客户端在每个请求中声明
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    LZ4,
+    Zstd,
+}
+
+impl Compression {
+    fn name(&self) -> &'static str {
+        match self {
+            Compression::LZ4 => "lz4",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    fn compress_type(&self) -> CompressType {
+        match self {
+            Compression::LZ4 => CompressType::LZ4,
+            Compression::Zstd => CompressType::Zstd { level: 3 },
+        }
+    }
+
+    /// 在请求头中声明所接受的压缩算法
+    pub(crate) fn insert_header(&self, metadata: &mut MetadataMap) {
+        let _ = metadata.insert(COMPRESSION_HEADER, MetadataValue::from_static(self.name()));
+    }
+
+    /// 解析请求头中客户端所声明的压缩算法，未声明时返回None
+    pub(crate) fn from_header(metadata: &MetadataMap) -> Result<Option<Self>, Status> {
+        let Some(value) = metadata.get(COMPRESSION_HEADER) else {
+            return Ok(None);
+        };
+        match value.to_str() {
+            Ok("lz4") => Ok(Some(Compression::LZ4)),
+            Ok("zstd") => Ok(Some(Compression::Zstd)),
+            _ => Err(Status::invalid_argument("Unsupported compression")),
+        }
+    }
+
+    /// 为Value添加压缩标记，长度不小于`COMPRESSION_THRESHOLD`时进行压缩
+    pub(crate) fn encode(&self, value: Vec<u8>) -> Result<Vec<u8>, ConnectionError> {
+        let mut bytes = Vec::with_capacity(value.len().min(COMPRESSION_THRESHOLD) + 1);
+        if value.len() < COMPRESSION_THRESHOLD {
+            bytes.push(RAW_TAG);
+            bytes.extend_from_slice(&value);
+        } else {
+            bytes.push(COMPRESSED_TAG);
+            self.compress_type().compress(&value, &mut bytes)?;
+        }
+
+        Ok(bytes)
+    }
+
+    /// 与encode对应，移除压缩标记并解压
+    pub(crate) fn decode(&self, bytes: Vec<u8>) -> Result<Vec<u8>, ConnectionError> {
+        match bytes.split_first() {
+            Some((&RAW_TAG, raw)) => Ok(raw.to_vec()),
+            Some((&COMPRESSED_TAG, compressed)) => {
+                Ok(self.compress_type().decompress(compressed)?)
+            }
+            _ => Err(ConnectionError::DecodeErr),
+        }
+    }
+}
@@ -1,3 +1,4 @@
 pub mod client;
+pub mod compression;
 #[allow(clippy::module_inception)]
 pub mod server;
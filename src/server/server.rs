@@ -1,33 +1,206 @@
 use crate::error::ConnectionError;
+use crate::kernel::lsm::iterator::Iter;
+use crate::kernel::lsm::mvcc::{CheckType, Transaction};
 use crate::kernel::lsm::storage::KipStorage;
-use crate::kernel::Storage;
+use crate::kernel::{KernelResult, Storage};
 use crate::proto::kipdb_rpc_server::{KipdbRpc, KipdbRpcServer};
 use crate::proto::{
-    BatchGetReq, BatchGetResp, BatchRemoveReq, BatchRemoveResp, BatchSetReq, BatchSetResp, Empty,
-    FlushResp, GetReq, GetResp, LenResp, RemoveReq, RemoveResp, SetReq, SetResp, SizeOfDiskResp,
+    BatchGetReq, BatchGetResp, BatchRemoveReq, BatchRemoveResp, BatchSetReq, BatchSetResp,
+    CloseIterReq, CloseIterResp, Empty, FlushResp, GetReq, GetResp, Kv, LenResp, NextReq, NextResp,
+    OpenIterReq, OpenIterResp, RemoveReq, RemoveResp, SetReq, SetResp, SizeOfDiskResp, StatsResp,
 };
+use crate::server::compression::Compression;
 use bytes::Bytes;
-use std::sync::Arc;
+use parking_lot::Mutex;
+use std::collections::{Bound, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 use tonic::transport::Server;
 use tonic::{Request, Response, Status};
+use tracing::info;
+
+const DEFAULT_DATA_PATH: &str = "./data";
+
+/// 游标闲置超过该时长后将被释放
+///
+/// 游标持有事务以固定其打开时的Version，客户端未关闭游标时依靠该超时避免Version泄漏
+const ITER_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 游标每批返回的键值对数量上限，避免客户端指定过大的批次使服务端预先分配过多内存
+const MAX_ITER_BATCH: u32 = 1024;
 
 pub async fn serve(ip: &String, port: u16) -> Result<(), ConnectionError> {
+    let kv_store = Arc::new(KipStorage::open(DEFAULT_DATA_PATH).await?);
+
+    serve_tcp(ip, port, kv_store).await
+}
+
+/// 同时通过TCP与Unix domain socket提供服务
+#[cfg(unix)]
+pub async fn serve_with_uds(
+    ip: &String,
+    port: u16,
+    uds_path: impl AsRef<std::path::Path>,
+) -> Result<(), ConnectionError> {
+    let kv_store = Arc::new(KipStorage::open(DEFAULT_DATA_PATH).await?);
+    let _ = tokio::try_join!(
+        serve_tcp(ip, port, Arc::clone(&kv_store)),
+        serve_uds(uds_path, kv_store)
+    )?;
+
+    Ok(())
+}
+
+async fn serve_tcp(
+    ip: &String,
+    port: u16,
+    kv_store: Arc<KipStorage>,
+) -> Result<(), ConnectionError> {
     let addr = format!("{}:{}", ip, port).parse()?;
-    let kv_store = Arc::new(KipStorage::open("./data").await?);
-    let kipdb_server = KipdbServer::new(kv_store);
     Server::builder()
-        .add_service(KipdbRpcServer::new(kipdb_server))
+        .add_service(rpc_service(kv_store))
         .serve(addr)
         .await?;
     Ok(())
 }
 
+/// 通过Unix domain socket提供服务
+///
+/// 适用于客户端与服务端位于同一主机的场景，避免TCP的开销且不对外暴露端口
+/// Tips: `path`上已存在的socket文件(如上次运行所遗留)将被移除
+#[cfg(unix)]
+pub async fn serve_uds(
+    path: impl AsRef<std::path::Path>,
+    kv_store: Arc<KipStorage>,
+) -> Result<(), ConnectionError> {
+    use std::os::unix::fs::FileTypeExt;
+    use tokio::net::UnixListener;
+    use tokio_stream::wrappers::UnixListenerStream;
+
+    let path = path.as_ref();
+    if std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    Server::builder()
+        .add_service(rpc_service(kv_store))
+        .serve_with_incoming(UnixListenerStream::new(listener))
+        .await?;
+    Ok(())
+}
+
+fn rpc_service(kv_store: Arc<KipStorage>) -> KipdbRpcServer<KipdbServer> {
+    KipdbRpcServer::new(KipdbServer::new(kv_store))
+}
+
+/// 依据客户端声明的压缩算法编码响应中的Value，未声明时原样返回
+///
+/// 因此未开启压缩的客户端不受影响
+fn encode_value(compression: Option<Compression>, value: Vec<u8>) -> Result<Vec<u8>, Status> {
+    match compression {
+        Some(compression) => compression
+            .encode(value)
+            .map_err(|_| Status::internal("Failed to compress value")),
+        None => Ok(value),
+    }
+}
+
 struct KipdbServer {
     kv_store: Arc<KipStorage>,
+    cursors: Arc<IterCursors>,
 }
 impl KipdbServer {
     pub fn new(kv_store: Arc<KipStorage>) -> Self {
-        Self { kv_store }
+        let cursors = Arc::new(IterCursors::default());
+        IterCursors::spawn_idle_cleaner(Arc::downgrade(&cursors), ITER_IDLE_TIMEOUT);
+
+        Self { kv_store, cursors }
+    }
+}
+
+/// 服务端游标，以事务固定打开时的数据快照，使跨请求的分批扫描结果保持一致
+struct IterCursor {
+    tx: Transaction,
+    /// 下一批次的起始位置，每批次后更新为不包含该批次最后一个Key
+    start: Bound<Bytes>,
+    end: Bound<Bytes>,
+    last_access: Instant,
+}
+
+impl IterCursor {
+    /// 读取至多`batch`个未被删除的键值对，返回的bool表示游标是否已遍历完毕
+    fn next_batch(&mut self, batch: usize) -> KernelResult<(Vec<Kv>, bool)> {
+        let mut iter = self
+            .tx
+            .iter(
+                self.start.as_ref().map(|key| &key[..]),
+                self.end.as_ref().map(|key| &key[..]),
+            )?
+            .skip_tombstones(true);
+        let mut kvs = Vec::with_capacity(batch);
+        let mut last_key = None;
+
+        while kvs.len() < batch {
+            let Some((key, value)) = iter.try_next()? else {
+                return Ok((kvs, true));
+            };
+            kvs.push(Kv {
+                key: key.to_vec(),
+                value: value.map_or(vec![], |value| value.to_vec()),
+            });
+            last_key = Some(key);
+        }
+        if let Some(key) = last_key {
+            self.start = Bound::Excluded(key);
+        }
+        self.last_access = Instant::now();
+
+        Ok((kvs, false))
+    }
+}
+
+#[derive(Default)]
+struct IterCursors {
+    next_id: AtomicU64,
+    inner: Mutex<HashMap<u64, IterCursor>>,
+}
+
+impl IterCursors {
+    /// 定期释放闲置超时的游标，服务停止后随之退出
+    fn spawn_idle_cleaner(cursors: Weak<IterCursors>, idle_timeout: Duration) {
+        let _ = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(idle_timeout / 2).await;
+                let Some(cursors) = cursors.upgrade() else {
+                    break;
+                };
+                let mut inner = cursors.inner.lock();
+                let len = inner.len();
+                inner.retain(|_, cursor| cursor.last_access.elapsed() < idle_timeout);
+                if inner.len() < len {
+                    info!(
+                        "[IterCursors][released idle cursors]: {}",
+                        len - inner.len()
+                    );
+                }
+            }
+        });
+    }
+
+    fn insert(&self, cursor: IterCursor) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let _ = self.inner.lock().insert(id, cursor);
+        id
+    }
+
+    /// 读取期间游标被取出，因此同一游标的Next请求需要依次发出
+    fn take(&self, id: u64) -> Option<IterCursor> {
+        self.inner.lock().remove(&id)
+    }
+
+    fn put_back(&self, id: u64, cursor: IterCursor) {
+        let _ = self.inner.lock().insert(id, cursor);
     }
 }
 
@@ -54,6 +227,7 @@ impl KipdbRpc for KipdbServer {
     }
 
     async fn get(&self, request: Request<GetReq>) -> Result<Response<GetResp>, Status> {
+        let compression = Compression::from_header(request.metadata())?;
         let req = request.into_inner();
         let value = self
             .kv_store
@@ -61,7 +235,9 @@ impl KipdbRpc for KipdbServer {
             .await
             .map_or(None, |v| v);
         Ok(Response::new(GetResp {
-            value: value.map(|v| v.to_vec()),
+            value: value
+                .map(|v| encode_value(compression, v.to_vec()))
+                .transpose()?,
         }))
     }
 
@@ -109,19 +285,18 @@ impl KipdbRpc for KipdbServer {
         &self,
         request: Request<BatchGetReq>,
     ) -> Result<Response<BatchGetResp>, Status> {
+        let compression = Compression::from_header(request.metadata())?;
         let req = request.into_inner();
-        let mut values = Vec::new();
-        // TODO change kv_store.get return type for parallel processing
-        for key in req.keys {
-            values.push(
-                self.kv_store
-                    .get(key.as_slice())
-                    .await
-                    .map_or(None, |v| v)
-                    .map_or(vec![], |v| v.to_vec()),
-            );
+        // 所有Key共享同一快照，避免为每个Key重复获取Version
+        match self.kv_store.multi_get(&req.keys).await {
+            Ok(values) => Ok(Response::new(BatchGetResp {
+                values: values
+                    .into_iter()
+                    .map(|value| encode_value(compression, value.map_or(vec![], |v| v.to_vec())))
+                    .collect::<Result<_, Status>>()?,
+            })),
+            Err(_) => Err(Status::internal("Failed to batch get")),
         }
-        Ok(Response::new(BatchGetResp { values }))
     }
 
     async fn size_of_disk(
@@ -145,4 +320,74 @@ impl KipdbRpc for KipdbServer {
         let success = self.kv_store.flush().await.map_or(false, |_| true);
         Ok(Response::new(FlushResp { success }))
     }
+
+    async fn stats(&self, _request: Request<Empty>) -> Result<Response<StatsResp>, Status> {
+        match self
+            .kv_store
+            .stats()
+            .await
+            .and_then(|stats| Ok(bincode::serialize(&stats)?))
+        {
+            Ok(stats) => Ok(Response::new(StatsResp { stats })),
+            Err(_) => Err(Status::internal("Failed to get stats")),
+        }
+    }
+
+    async fn open_iter(
+        &self,
+        request: Request<OpenIterReq>,
+    ) -> Result<Response<OpenIterResp>, Status> {
+        let req = request.into_inner();
+        let cursor = IterCursor {
+            tx: self.kv_store.new_transaction(CheckType::Optimistic).await,
+            start: req
+                .start
+                .map_or(Bound::Unbounded, |key| Bound::Included(Bytes::from(key))),
+            end: req
+                .end
+                .map_or(Bound::Unbounded, |key| Bound::Excluded(Bytes::from(key))),
+            last_access: Instant::now(),
+        };
+
+        Ok(Response::new(OpenIterResp {
+            id: self.cursors.insert(cursor),
+        }))
+    }
+
+    async fn next(&self, request: Request<NextReq>) -> Result<Response<NextResp>, Status> {
+        let compression = Compression::from_header(request.metadata())?;
+        let req = request.into_inner();
+        let mut cursor = self
+            .cursors
+            .take(req.id)
+            .ok_or_else(|| Status::not_found("Cursor not found or expired"))?;
+
+        match cursor.next_batch(req.batch.clamp(1, MAX_ITER_BATCH) as usize) {
+            Ok((kvs, done)) => {
+                // 遍历完毕的游标直接释放，无需客户端再关闭
+                if !done {
+                    self.cursors.put_back(req.id, cursor);
+                }
+                let kvs = kvs
+                    .into_iter()
+                    .map(|kv| {
+                        Ok(Kv {
+                            key: kv.key,
+                            value: encode_value(compression, kv.value)?,
+                        })
+                    })
+                    .collect::<Result<_, Status>>()?;
+                Ok(Response::new(NextResp { kvs, done }))
+            }
+            Err(_) => Err(Status::internal("Failed to iterate cursor")),
+        }
+    }
+
+    async fn close_iter(
+        &self,
+        request: Request<CloseIterReq>,
+    ) -> Result<Response<CloseIterResp>, Status> {
+        let success = self.cursors.take(request.into_inner().id).is_some();
+        Ok(Response::new(CloseIterResp { success }))
+    }
 }
@@ -0,0 +1,59 @@
+#![cfg(feature = "net")]
+
+use std::process::{Child, Command, Output};
+use std::thread::sleep;
+use std::time::Duration;
+use tempfile::TempDir;
+
+const TEST_PORT: &str = "16333";
+
+struct ServerGuard(Child);
+
+impl Drop for ServerGuard {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn cli(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cli"))
+        .args(["--port", TEST_PORT])
+        .args(args)
+        .output()
+        .expect("failed to run cli")
+}
+
+#[test]
+fn cli_stats() {
+    let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+    let _server = ServerGuard(
+        Command::new(env!("CARGO_BIN_EXE_server"))
+            .args(["--port", TEST_PORT])
+            .current_dir(temp_dir.path())
+            .spawn()
+            .expect("failed to start server"),
+    );
+
+    // 等待服务端启动
+    let mut retry = 0;
+    while !cli(&["len"]).status.success() {
+        retry += 1;
+        assert!(retry < 50, "server start time out");
+        sleep(Duration::from_millis(100));
+    }
+
+    assert!(cli(&["set", "key_1", "value_1"]).status.success());
+    assert!(cli(&["flush"]).status.success());
+
+    let output = cli(&["stats"]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let level_0_count = stdout
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Level 0: "))
+        .and_then(|count| count.trim().parse::<usize>().ok())
+        .expect("level 0 count not found");
+    assert!(level_0_count > 0);
+}
@@ -0,0 +1,144 @@
+#![cfg(all(unix, feature = "net"))]
+
+use kip_db::kernel::lsm::storage::KipStorage;
+use kip_db::server::client::{ConnectionResult, KipdbClient};
+use kip_db::server::compression::{Compression, COMPRESSION_THRESHOLD};
+use kip_db::server::server::serve_uds;
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::UnixStream;
+use tonic::transport::{Endpoint, Uri};
+use tower::service_fn;
+
+/// 统计客户端从socket中读取的字节数
+struct CountingStream {
+    inner: UnixStream,
+    read_bytes: Arc<AtomicUsize>,
+}
+
+impl AsyncRead for CountingStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let _ = self
+            .read_bytes
+            .fetch_add(buf.filled().len() - filled, Ordering::Relaxed);
+        poll
+    }
+}
+
+impl AsyncWrite for CountingStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+async fn connect_counting(
+    socket_path: PathBuf,
+    read_bytes: Arc<AtomicUsize>,
+) -> ConnectionResult<KipdbClient> {
+    let socket_path = Arc::new(socket_path);
+    let channel = Endpoint::try_from("http://[::]:6333")?
+        .connect_with_connector(service_fn(move |_: Uri| {
+            let socket_path = Arc::clone(&socket_path);
+            let read_bytes = Arc::clone(&read_bytes);
+            async move {
+                Ok::<_, io::Error>(CountingStream {
+                    inner: UnixStream::connect(socket_path.as_ref()).await?,
+                    read_bytes,
+                })
+            }
+        }))
+        .await?;
+
+    Ok(KipdbClient::with_channel(channel))
+}
+
+#[tokio::test]
+async fn batch_get_with_compression() -> ConnectionResult<()> {
+    let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+    let socket_path = temp_dir.path().join("kipdb.sock");
+    let kv_store = Arc::new(KipStorage::open(temp_dir.path().join("data")).await?);
+    let server = tokio::spawn(serve_uds(socket_path.clone(), kv_store));
+
+    // 等待服务端完成socket的绑定
+    let mut retries = 0;
+    let mut client = loop {
+        match KipdbClient::connect_unix(socket_path.clone()).await {
+            Ok(client) => break client,
+            Err(err) if retries >= 50 => return Err(err),
+            Err(_) => {
+                retries += 1;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+    };
+
+    let kvs: Vec<(Vec<u8>, Vec<u8>)> = (0..256_u32)
+        .map(|i| {
+            let key = i.to_be_bytes().to_vec();
+            let value = format!("value_{i}_").repeat(512).into_bytes();
+            (key, value)
+        })
+        .collect();
+    assert!(client.batch_set(kvs.clone()).await?.is_empty());
+    let keys: Vec<Vec<u8>> = kvs.iter().map(|(key, _)| key.clone()).collect();
+    let values: Vec<Vec<u8>> = kvs.into_iter().map(|(_, value)| value).collect();
+
+    let plain_bytes = Arc::new(AtomicUsize::new(0));
+    let mut plain_client = connect_counting(socket_path.clone(), Arc::clone(&plain_bytes)).await?;
+    assert_eq!(plain_client.batch_get(keys.clone()).await?, values);
+
+    let compressed_bytes = Arc::new(AtomicUsize::new(0));
+    let mut compressed_client =
+        connect_counting(socket_path.clone(), Arc::clone(&compressed_bytes))
+            .await?
+            .with_compression(Compression::Zstd);
+    assert_eq!(compressed_client.batch_get(keys.clone()).await?, values);
+
+    let plain_bytes = plain_bytes.load(Ordering::Relaxed);
+    let compressed_bytes = compressed_bytes.load(Ordering::Relaxed);
+    assert!(
+        compressed_bytes * 4 < plain_bytes,
+        "compressed: {compressed_bytes} bytes, plain: {plain_bytes} bytes"
+    );
+
+    let mut lz4_client = connect_counting(socket_path.clone(), Arc::new(AtomicUsize::new(0)))
+        .await?
+        .with_compression(Compression::LZ4);
+    assert_eq!(lz4_client.batch_get(keys).await?, values);
+
+    // 小于阈值的Value不被压缩，但仍需被正确还原
+    let small_value = vec![b'k'; COMPRESSION_THRESHOLD - 1];
+    lz4_client
+        .set(b"small".to_vec(), small_value.clone())
+        .await?;
+    assert_eq!(lz4_client.get(b"small".to_vec()).await?, Some(small_value));
+
+    server.abort();
+
+    Ok(())
+}
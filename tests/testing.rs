@@ -0,0 +1,85 @@
+#![cfg(feature = "testing")]
+
+use bytes::Bytes;
+use kip_db::kernel::lsm::storage::{Config, KipStorage};
+use kip_db::kernel::{KernelResult, Storage};
+use kip_db::testing::{force_layout, ingest_table, level_gens, wait_for_idle};
+use kip_db::KernelError;
+use tempfile::TempDir;
+
+fn key_values(range: std::ops::Range<u32>, value: &'static [u8]) -> Vec<(Bytes, Option<Bytes>)> {
+    range
+        .map(|i| {
+            (
+                Bytes::from(i.to_be_bytes().to_vec()),
+                Some(Bytes::from_static(value)),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn query_two_level_tree() -> KernelResult<()> {
+    let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+    let config = Config::new(temp_dir.path()).disable_auto_compaction(true);
+    let kv_store = KipStorage::open_with_config(config).await?;
+
+    // Level 2存放较旧的数据，Level 1中的两个Table覆盖其中的部分Key
+    let gens = force_layout(
+        &kv_store,
+        vec![
+            (2, key_values(0..100, b"old")),
+            (1, key_values(50..60, b"new")),
+            (1, key_values(10..20, b"new")),
+        ],
+    )
+    .await?;
+    assert_eq!(level_gens(&kv_store, 2).await, vec![gens[0]]);
+    // Level 1中的Table按Key的范围排列
+    assert_eq!(level_gens(&kv_store, 1).await, vec![gens[2], gens[1]]);
+
+    for i in 0..100_u32 {
+        let expect: &[u8] = if (10..20).contains(&i) || (50..60).contains(&i) {
+            b"new"
+        } else {
+            b"old"
+        };
+        assert_eq!(
+            kv_store.get(&i.to_be_bytes()).await?,
+            Some(Bytes::from_static(expect))
+        );
+    }
+
+    // Level 1中不允许出现重叠的Table
+    assert!(matches!(
+        ingest_table(&kv_store, 1, key_values(15..30, b"overlap")).await,
+        Err(KernelError::NotSupport(_))
+    ));
+    // 删除同样可以通过Table构造
+    let _ = ingest_table(
+        &kv_store,
+        0,
+        vec![(Bytes::from(0_u32.to_be_bytes().to_vec()), None)],
+    )
+    .await?;
+    assert_eq!(kv_store.get(&0_u32.to_be_bytes()).await?, None);
+
+    // 再次构造布局时移除已有的Table
+    let _ = force_layout(&kv_store, vec![(3, key_values(0..10, b"only"))]).await?;
+    for level in 0..3 {
+        assert!(level_gens(&kv_store, level).await.is_empty());
+    }
+    assert_eq!(kv_store.get(&50_u32.to_be_bytes()).await?, None);
+
+    kv_store
+        .set(Bytes::from_static(b"mem"), Bytes::from_static(b"table"))
+        .await?;
+    wait_for_idle(&kv_store).await?;
+    assert_eq!(level_gens(&kv_store, 0).await.len(), 1);
+    assert_eq!(
+        kv_store.get(b"mem").await?,
+        Some(Bytes::from_static(b"table"))
+    );
+
+    Ok(())
+}
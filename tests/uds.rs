@@ -0,0 +1,154 @@
+#![cfg(all(unix, feature = "net"))]
+
+use kip_db::kernel::lsm::storage::KipStorage;
+use kip_db::kernel::Storage;
+use kip_db::server::client::{ConnectionResult, KipdbClient};
+use kip_db::server::server::serve_uds;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn set_and_get_over_uds() -> ConnectionResult<()> {
+    let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+    let socket_path = temp_dir.path().join("kipdb.sock");
+    let kv_store = Arc::new(KipStorage::open(temp_dir.path().join("data")).await?);
+    let server = tokio::spawn(serve_uds(socket_path.clone(), kv_store));
+
+    // 等待服务端完成socket的绑定
+    let mut retries = 0;
+    let mut client = loop {
+        match KipdbClient::connect_unix(socket_path.clone()).await {
+            Ok(client) => break client,
+            Err(err) if retries >= 50 => return Err(err),
+            Err(_) => {
+                retries += 1;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+    };
+
+    client.set(b"KipDB".to_vec(), b"over uds".to_vec()).await?;
+    assert_eq!(
+        client.get(b"KipDB".to_vec()).await?,
+        Some(b"over uds".to_vec())
+    );
+    client.remove(b"KipDB".to_vec()).await?;
+    assert_eq!(client.get(b"KipDB".to_vec()).await?, None);
+
+    server.abort();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_get_over_uds() -> ConnectionResult<()> {
+    let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+    let socket_path = temp_dir.path().join("kipdb.sock");
+    let kv_store = Arc::new(KipStorage::open(temp_dir.path().join("data")).await?);
+    let server = tokio::spawn(serve_uds(socket_path.clone(), Arc::clone(&kv_store)));
+
+    // 等待服务端完成socket的绑定
+    let mut retries = 0;
+    let mut client = loop {
+        match KipdbClient::connect_unix(socket_path.clone()).await {
+            Ok(client) => break client,
+            Err(err) if retries >= 50 => return Err(err),
+            Err(_) => {
+                retries += 1;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+    };
+
+    let kvs: Vec<(Vec<u8>, Vec<u8>)> = (0..5000_u32)
+        .map(|i| (i.to_be_bytes().to_vec(), format!("value_{i}").into_bytes()))
+        .collect();
+    assert!(client.batch_set(kvs[..2500].to_vec()).await?.is_empty());
+    client.flush().await?;
+    assert!(client.batch_set(kvs[2500..].to_vec()).await?.is_empty());
+
+    // 仅由Get组成的批量请求，同时覆盖SSTable、MemTable与不存在的Key
+    let keys: Vec<Vec<u8>> = (0..6000_u32).map(|i| i.to_be_bytes().to_vec()).collect();
+    let values = client.batch_get(keys).await?;
+    assert_eq!(values.len(), 6000);
+    for (i, value) in values.into_iter().enumerate() {
+        let expect = kvs.get(i).map_or(vec![], |(_, value)| value.clone());
+        assert_eq!(value, expect);
+    }
+    assert_eq!(kv_store.len().await?, 5000);
+
+    server.abort();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn scan_over_uds_with_cursor() -> ConnectionResult<()> {
+    let temp_dir = TempDir::new().expect("unable to create temporary working directory");
+    let socket_path = temp_dir.path().join("kipdb.sock");
+    let kv_store = Arc::new(KipStorage::open(temp_dir.path().join("data")).await?);
+    let server = tokio::spawn(serve_uds(socket_path.clone(), kv_store));
+
+    // 等待服务端完成socket的绑定
+    let mut retries = 0;
+    let mut client = loop {
+        match KipdbClient::connect_unix(socket_path.clone()).await {
+            Ok(client) => break client,
+            Err(err) if retries >= 50 => return Err(err),
+            Err(_) => {
+                retries += 1;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+    };
+
+    let kvs: Vec<(Vec<u8>, Vec<u8>)> = (0..20_000_u32)
+        .map(|i| (i.to_be_bytes().to_vec(), format!("value_{i}").into_bytes()))
+        .collect();
+    assert!(client.batch_set(kvs[..10_000].to_vec()).await?.is_empty());
+    client.flush().await?;
+    assert!(client.batch_set(kvs[10_000..].to_vec()).await?.is_empty());
+    // 已删除的Key不会被扫描到
+    let removed: Vec<Vec<u8>> = (0..20_000_u32)
+        .step_by(7)
+        .map(|i| i.to_be_bytes().to_vec())
+        .collect();
+    assert!(client.batch_remove(removed).await?.is_empty());
+
+    let expected: Vec<(Vec<u8>, Vec<u8>)> = kvs[1000..19_000]
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| (i + 1000) % 7 != 0)
+        .map(|(_, kv)| kv.clone())
+        .collect();
+
+    let mut writer = KipdbClient::connect_unix(socket_path.clone()).await?;
+    let mut iter = client
+        .iter(
+            Some(1000_u32.to_be_bytes().to_vec()),
+            Some(19_000_u32.to_be_bytes().to_vec()),
+            512,
+        )
+        .await?;
+    // 游标打开后的写入对其不可见
+    writer
+        .set(1500_u32.to_be_bytes().to_vec(), b"overwritten".to_vec())
+        .await?;
+    let mut scanned = Vec::new();
+    while let Some(kv) = iter.try_next().await? {
+        scanned.push(kv);
+    }
+    iter.close().await?;
+    assert_eq!(scanned, expected);
+
+    // 未遍历完时提前关闭游标，Key 0已被删除
+    let mut iter = client.iter(None, None, 100).await?;
+    assert_eq!(iter.try_next().await?, Some(kvs[1].clone()));
+    assert_eq!(iter.try_next().await?, Some(kvs[2].clone()));
+    iter.close().await?;
+
+    server.abort();
+
+    Ok(())
+}